@@ -0,0 +1,427 @@
+//! Run bundles for `oracle-omen export` and `import`.
+//!
+//! A bundle is one JSON file holding a run's files by their path under the
+//! data directory, a manifest with the BLAKE3 hash of every file, and an
+//! optional Ed25519 signature over the manifest hash. Config inputs are
+//! stored under `runs/<run_id>/config/`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use oracle_omen_core::{
+    event::{EventLog, EventPayload},
+    hash::Hash,
+};
+use oracle_omen_patches::signature::{KeyPair, Signature, SignerId};
+use oracle_omen_runtime::{MemoryBackend, RunConfig, ToolKind};
+use serde::{Deserialize, Serialize};
+
+/// Bundle format version this build reads and writes
+pub const BUNDLE_VERSION: u64 = 1;
+
+/// A tool manifest as bundled
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledTool {
+    /// Tool name
+    pub name: String,
+
+    /// Tool version
+    pub version: String,
+
+    /// `native` or `wasm`
+    pub kind: String,
+
+    /// Capabilities the tool needs
+    pub capabilities: BTreeSet<String>,
+
+    /// Bundle path of the WASM module
+    pub module: Option<String>,
+
+    /// BLAKE3 hash of the WASM module
+    pub module_hash: Option<Hash>,
+}
+
+/// What a bundle contains
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle format version
+    pub version: u64,
+
+    /// Bundled run
+    pub run_id: u64,
+
+    /// Canonical hash of the bundled run config
+    pub config_hash: Option<Hash>,
+
+    /// BLAKE3 hash of each file, by bundle path
+    pub files: BTreeMap<String, Hash>,
+
+    /// Tools of the bundled run config
+    pub tools: Vec<BundledTool>,
+}
+
+impl BundleManifest {
+    /// Hash the signature covers
+    pub fn hash(&self) -> Hash {
+        Hash::from_canonical(self)
+    }
+}
+
+/// Signature over a manifest hash
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleSignature {
+    /// Signing key
+    pub signer: SignerId,
+
+    /// Ed25519 signature of the hex manifest hash
+    pub signature: Signature,
+}
+
+/// A run packaged for handover
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunBundle {
+    /// File hashes and tools
+    pub manifest: BundleManifest,
+
+    /// Optional signature
+    pub signature: Option<BundleSignature>,
+
+    /// Hex-encoded file contents, by bundle path
+    files: BTreeMap<String, String>,
+}
+
+impl RunBundle {
+    /// Create an empty bundle for a run
+    pub fn new(run_id: u64) -> Self {
+        Self {
+            manifest: BundleManifest {
+                version: BUNDLE_VERSION,
+                run_id,
+                config_hash: None,
+                files: BTreeMap::new(),
+                tools: Vec::new(),
+            },
+            signature: None,
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Add a file at a path under the data directory
+    pub fn add_file(&mut self, path: impl Into<String>, bytes: &[u8]) {
+        let path = path.into();
+        self.manifest
+            .files
+            .insert(path.clone(), Hash::from_bytes(bytes));
+        self.files.insert(path, to_hex(bytes));
+    }
+
+    /// Add a run config with its policy files, memory file and WASM modules
+    pub fn add_config(&mut self, config: &RunConfig, source: &[u8]) -> Result<(), BundleError> {
+        let dir = format!("runs/{}/config", self.manifest.run_id);
+        self.add_file(format!("{}/config.toml", dir), source);
+
+        for (i, file) in config.policy_files.iter().enumerate() {
+            let bytes = read(&config.resolve(file))?;
+            self.add_file(
+                format!("{}/policies/{}-{}", dir, i, file_name(file)),
+                &bytes,
+            );
+        }
+        if let MemoryBackend::File { path } = &config.memory {
+            let path = config.resolve(path);
+            if path.exists() {
+                self.add_file(
+                    format!("{}/memory/{}", dir, file_name(&path)),
+                    &read(&path)?,
+                );
+            }
+        }
+        for tool in &config.tools {
+            let (module, module_hash) = match &tool.module {
+                Some(file) => {
+                    let bytes = read(&config.resolve(file))?;
+                    let path = format!("{}/tools/{}-{}.wasm", dir, tool.name, tool.version);
+                    self.add_file(path.clone(), &bytes);
+                    (Some(path), Some(Hash::from_bytes(&bytes)))
+                }
+                None => (None, None),
+            };
+            self.manifest.tools.push(BundledTool {
+                name: tool.name.clone(),
+                version: tool.version.clone(),
+                kind: match tool.kind {
+                    ToolKind::Native => "native".to_string(),
+                    ToolKind::Wasm => "wasm".to_string(),
+                },
+                capabilities: tool.capabilities.clone(),
+                module,
+                module_hash,
+            });
+        }
+        self.manifest.config_hash = Some(config.hash());
+        Ok(())
+    }
+
+    /// Sign the manifest
+    pub fn sign(&mut self, key: &KeyPair) {
+        self.signature = Some(BundleSignature {
+            signer: key.signer_id(),
+            signature: key.sign(self.manifest.hash().to_hex().as_bytes()),
+        });
+    }
+
+    /// Contents of a file
+    pub fn file(&self, path: &str) -> Result<Vec<u8>, BundleError> {
+        let hex = self
+            .files
+            .get(path)
+            .ok_or_else(|| BundleError::MissingFile(path.to_string()))?;
+        from_hex(hex).ok_or_else(|| BundleError::Format(format!("{}: invalid hex", path)))
+    }
+
+    /// Check the format, paths, file hashes, module hashes and signature
+    ///
+    /// With `trusted` keys, the bundle must be signed by one of them.
+    pub fn verify(&self, trusted: &[SignerId]) -> Result<(), BundleError> {
+        if self.manifest.version != BUNDLE_VERSION {
+            return Err(BundleError::Format(format!(
+                "bundle version {}, expected {}",
+                self.manifest.version, BUNDLE_VERSION
+            )));
+        }
+        if let Some(path) = self
+            .files
+            .keys()
+            .find(|p| !self.manifest.files.contains_key(*p))
+        {
+            return Err(BundleError::UnlistedFile(path.clone()));
+        }
+        for (path, expected) in &self.manifest.files {
+            if !is_safe_path(path, self.manifest.run_id) {
+                return Err(BundleError::UnsafePath(path.clone()));
+            }
+            let actual = Hash::from_bytes(&self.file(path)?);
+            if actual != *expected {
+                return Err(BundleError::HashMismatch(path.clone()));
+            }
+        }
+        for tool in &self.manifest.tools {
+            if let (Some(module), Some(hash)) = (&tool.module, tool.module_hash) {
+                if self.manifest.files.get(module) != Some(&hash) {
+                    return Err(BundleError::HashMismatch(module.clone()));
+                }
+            }
+        }
+
+        match &self.signature {
+            Some(sig) => {
+                let message = self.manifest.hash().to_hex();
+                if !sig.signature.verify(message.as_bytes(), &sig.signer) {
+                    return Err(BundleError::Signature(
+                        "signature does not match manifest".to_string(),
+                    ));
+                }
+                if !trusted.is_empty() && !trusted.contains(&sig.signer) {
+                    return Err(BundleError::Untrusted(sig.signer.to_hex()));
+                }
+            }
+            None if !trusted.is_empty() => {
+                return Err(BundleError::Signature("bundle is not signed".to_string()));
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Write every file under `dir`
+    pub fn unpack(&self, dir: &Path) -> Result<(), BundleError> {
+        for path in self.files.keys() {
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, self.file(path)?)?;
+        }
+        Ok(())
+    }
+
+    /// Read a bundle file
+    pub fn load(path: &Path) -> Result<Self, BundleError> {
+        let bytes = read(path)?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| BundleError::Format(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Write the bundle to a file
+    ///
+    /// The file is written next to the target and renamed into place.
+    pub fn save(&self, path: &Path) -> Result<(), BundleError> {
+        let json =
+            serde_json::to_vec_pretty(self).map_err(|e| BundleError::Format(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Config hash recorded in a run's `AgentInit` event
+pub fn recorded_config_hash(log: &EventLog) -> Option<Hash> {
+    log.events().iter().find_map(|event| match &event.payload {
+        EventPayload::AgentInit(init) => init
+            .config
+            .get("config_hash")
+            .and_then(|hex| Hash::from_hex(hex).ok()),
+        _ => None,
+    })
+}
+
+/// Whether a bundle path stays within the run or names the patch store
+fn is_safe_path(path: &str, run_id: u64) -> bool {
+    if path == "patches.json" {
+        return true;
+    }
+    let Some(rest) = path.strip_prefix(&format!("runs/{}/", run_id)) else {
+        return false;
+    };
+    !path.contains('\\')
+        && rest
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| "file".to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, BundleError> {
+    fs::read(path).map_err(|e| BundleError::Io(format!("{}: {}", path.display(), e)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Bundle errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BundleError {
+    /// IO error
+    Io(String),
+
+    /// Malformed bundle
+    Format(String),
+
+    /// A file the manifest lists is missing
+    MissingFile(String),
+
+    /// A file the manifest does not list
+    UnlistedFile(String),
+
+    /// A file does not match its manifest hash
+    HashMismatch(String),
+
+    /// A path outside the run's directory
+    UnsafePath(String),
+
+    /// Missing or invalid signature
+    Signature(String),
+
+    /// Signed by a key that is not trusted
+    Untrusted(String),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Io(msg) => write!(f, "IO error: {}", msg),
+            BundleError::Format(msg) => write!(f, "Invalid bundle: {}", msg),
+            BundleError::MissingFile(path) => write!(f, "Missing file: {}", path),
+            BundleError::UnlistedFile(path) => write!(f, "File not in manifest: {}", path),
+            BundleError::HashMismatch(path) => write!(f, "Hash mismatch: {}", path),
+            BundleError::UnsafePath(path) => write!(f, "Path outside the run: {}", path),
+            BundleError::Signature(msg) => write!(f, "Signature error: {}", msg),
+            BundleError::Untrusted(signer) => write!(f, "Untrusted signer: {}", signer),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<std::io::Error> for BundleError {
+    fn from(e: std::io::Error) -> Self {
+        BundleError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RunBundle {
+        let mut bundle = RunBundle::new(3);
+        bundle.add_file("runs/3/events.jsonl", b"{}\n");
+        bundle.add_file("runs/3/snapshots/2.json", b"{}");
+        bundle.add_file("patches.json", b"{}");
+        bundle
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let key = KeyPair::from_secret([7u8; 32]);
+        let mut bundle = sample();
+        bundle.sign(&key);
+        assert_eq!(bundle.verify(&[]), Ok(()));
+        assert_eq!(bundle.verify(&[key.signer_id()]), Ok(()));
+        assert_eq!(
+            bundle.verify(&[KeyPair::from_secret([8u8; 32]).signer_id()]),
+            Err(BundleError::Untrusted(key.signer_id().to_hex()))
+        );
+
+        let mut edited = bundle.clone();
+        edited
+            .files
+            .insert("runs/3/events.jsonl".to_string(), to_hex(b"[]\n"));
+        assert_eq!(
+            edited.verify(&[]),
+            Err(BundleError::HashMismatch("runs/3/events.jsonl".to_string()))
+        );
+
+        // Rehashing the edit breaks the signature instead
+        edited.add_file("runs/3/events.jsonl", b"[]\n");
+        assert!(matches!(edited.verify(&[]), Err(BundleError::Signature(_))));
+
+        let mut unsigned = sample();
+        unsigned.add_file("runs/4/events.jsonl", b"");
+        assert_eq!(
+            unsigned.verify(&[]),
+            Err(BundleError::UnsafePath("runs/4/events.jsonl".to_string()))
+        );
+        assert!(matches!(
+            sample().verify(&[key.signer_id()]),
+            Err(BundleError::Signature(_))
+        ));
+    }
+
+    #[test]
+    fn test_paths() {
+        assert!(is_safe_path("runs/3/config/policies/0-default.policy", 3));
+        assert!(!is_safe_path("runs/3/../../etc/passwd", 3));
+        assert!(!is_safe_path("runs/3//x", 3));
+        assert!(!is_safe_path("runs/31/events.jsonl", 3));
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255])), Some(vec![0, 15, 255]));
+        assert_eq!(from_hex("0g"), None);
+    }
+}
@@ -1,7 +1,9 @@
 //! CLI commands for oracle-omen.
 
-use std::path::PathBuf;
 use crate::output::Output;
+use crate::patch::PatchCommand;
+use crate::trace::{TraceFilter, TraceFormat};
+use std::path::{Path, PathBuf};
 
 /// CLI commands
 #[derive(Debug, clap::Subcommand)]
@@ -16,12 +18,36 @@ pub enum Command {
     Replay {
         /// Run ID to replay
         run_id: String,
+
+        /// Stop after the event with this sequence number
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Start from the latest snapshot before the end point
+        #[arg(long)]
+        from_snapshot: bool,
+
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Trace a run
     Trace {
         /// Run ID to trace
         run_id: String,
+
+        /// Event filters
+        #[command(flatten)]
+        filter: TraceFilter,
+
+        /// Render parent links as an indented tree
+        #[arg(long)]
+        tree: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = TraceFormat::Table)]
+        format: TraceFormat,
     },
 
     /// Diff two runs
@@ -30,6 +56,10 @@ pub enum Command {
         run_a: String,
         /// Second run ID
         run_b: String,
+
+        /// Events to show on each side of the first divergence
+        #[arg(long, default_value_t = 3)]
+        context: u64,
     },
 
     /// Inspect a run
@@ -38,11 +68,62 @@ pub enum Command {
         run_id: String,
     },
 
+    /// Step through a run interactively
+    Debug {
+        /// Run ID to debug
+        run_id: String,
+    },
+
+    /// Write a run's trace as OTLP-JSON
+    Otlp {
+        /// Run ID to export
+        run_id: String,
+
+        /// Output file (default: `run-<run_id>.otlp.json`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Package a run and its inputs into a bundle file
+    Export {
+        /// Run ID to export
+        run_id: String,
+
+        /// Run config whose policies, memory file and tools to include
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Key file to sign the bundle with
+        #[arg(long)]
+        key: Option<PathBuf>,
+
+        /// Output file (default: `run-<run_id>.bundle.json`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Verify a bundle and add its run to the data directory
+    Import {
+        /// Bundle file
+        bundle: PathBuf,
+
+        /// Hex public key the bundle must be signed by (repeatable)
+        #[arg(long = "trust")]
+        trusted: Vec<String>,
+    },
+
     /// List capabilities
     Capabilities {
         /// Run ID
         run_id: String,
     },
+
+    /// Propose, gate, apply and roll back patches
+    Patch {
+        /// Patch subcommand
+        #[command(subcommand)]
+        action: PatchCommand,
+    },
 }
 
 /// Main CLI struct
@@ -51,6 +132,7 @@ pub enum Command {
 #[command(about = "Deterministic, auditable autonomous agents", long_about = None)]
 #[command(version)]
 pub struct Cli {
+    /// Command to run
     #[command(subcommand)]
     pub command: Command,
 
@@ -68,11 +150,41 @@ impl Cli {
     pub fn run(&self) -> Result<(), CliError> {
         match &self.command {
             Command::Run { config } => commands::run(self, config),
-            Command::Replay { run_id } => commands::replay(self, run_id),
-            Command::Trace { run_id } => commands::trace(self, run_id),
-            Command::Diff { run_a, run_b } => commands::diff(self, run_a, run_b),
+            Command::Replay {
+                run_id,
+                to,
+                from_snapshot,
+                json,
+            } => commands::replay(self, run_id, *to, *from_snapshot, *json),
+            Command::Trace {
+                run_id,
+                filter,
+                tree,
+                format,
+            } => commands::trace(self, run_id, filter, *tree, *format),
+            Command::Diff {
+                run_a,
+                run_b,
+                context,
+            } => commands::diff(self, run_a, run_b, *context),
             Command::Inspect { run_id } => commands::inspect(self, run_id),
+            Command::Debug { run_id } => commands::debug(self, run_id),
+            Command::Otlp { run_id, output } => commands::otlp(self, run_id, output.as_deref()),
+            Command::Export {
+                run_id,
+                config,
+                key,
+                output,
+            } => commands::export(
+                self,
+                run_id,
+                config.as_deref(),
+                key.as_deref(),
+                output.as_deref(),
+            ),
+            Command::Import { bundle, trusted } => commands::import(self, bundle, trusted),
             Command::Capabilities { run_id } => commands::capabilities(self, run_id),
+            Command::Patch { action } => commands::patch(self, action),
         }
     }
 }
@@ -91,6 +203,25 @@ pub enum CliError {
 
     /// Not found
     NotFound(String),
+
+    /// A run failed verification
+    Verification(String),
+
+    /// Two runs diverged
+    Diverged(String),
+}
+
+impl CliError {
+    /// Process exit code, as listed in `docs/CLI.md`
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Io(_) | CliError::Runtime(_) => 1,
+            CliError::Config(_) => 2,
+            CliError::NotFound(_) => 3,
+            CliError::Verification(_) => 4,
+            CliError::Diverged(_) => 5,
+        }
+    }
 }
 
 impl std::fmt::Display for CliError {
@@ -100,6 +231,8 @@ impl std::fmt::Display for CliError {
             CliError::Config(msg) => write!(f, "Config error: {}", msg),
             CliError::Runtime(msg) => write!(f, "Runtime error: {}", msg),
             CliError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            CliError::Verification(msg) => write!(f, "Verification failed: {}", msg),
+            CliError::Diverged(msg) => write!(f, "Runs diverged: {}", msg),
         }
     }
 }
@@ -115,14 +248,51 @@ impl From<std::io::Error> for CliError {
 /// Command implementations
 mod commands {
     use super::*;
+    use crate::bundle::{recorded_config_hash, BundleError, RunBundle};
+    use crate::debug::{DebugCommand, Debugger};
+    use crate::patch::{
+        self, gate_reason, passed_data, patch_payload, patches_path, rejected_payload,
+        RunTestRunner, PATCH_APPROVED, PATCH_AUDITED, PATCH_TESTED,
+    };
+    use crate::{diff, inspect::Inspection, output::Table, trace};
+    use oracle_omen_core::{
+        event::{Event, EventKind, EventLog, EventPayload},
+        hash::Hash,
+        replay::{ReplayEngine, Snapshot, VerificationReport},
+        serde_utils::StableMap,
+        state::AgentState,
+    };
+    use oracle_omen_patches::{
+        apply::{status_name, ApplyError, PatchEngine},
+        gate::GateResult,
+        patch::{Patch, PatchStatus},
+        signature::{load_approvers, KeyPair, SignerId},
+        store::{PatchStore, StoreError},
+    };
+    use oracle_omen_policy::{
+        compiler::PolicyCompiler,
+        engine::{EvalContext, PolicyEngine},
+        lang::Policy,
+    };
+    use oracle_omen_runtime::{OtlpExporter, PersistError, RunConfig, RunStore};
 
     pub fn run(cli: &Cli, config: &PathBuf) -> Result<(), CliError> {
+        let run_config = oracle_omen_runtime::RunConfig::load(config).map_err(|e| match e {
+            oracle_omen_runtime::ConfigError::Io { .. } => CliError::Config(e.to_string()),
+            _ => CliError::Config(format!("{}:{}", config.display(), e)),
+        })?;
+
         let output = Output::new()
             .header("oracle-omen run")
             .kv("config", config.display())
+            .kv("config_hash", run_config.hash())
+            .kv(
+                "agent",
+                format!("{}@{}", run_config.agent_type, run_config.agent_version),
+            )
+            .kv("run_id", run_config.run_id)
             .kv("data_dir", cli.data_dir.display())
             .line("")
-            .line("Loading agent configuration...")
             .line("Initializing event log...")
             .line("Starting agent execution...");
 
@@ -131,110 +301,947 @@ mod commands {
         }
 
         // TODO: Actual implementation
-        // 1. Load config from file
-        // 2. Initialize event log
-        // 3. Create agent instance
-        // 4. Run agent loop
-        // 5. Log all events
-        // 6. Save final state
+        // 1. Initialize event log
+        // 2. Create agent instance
+        // 3. Run agent loop
+        // 4. Log all events
+        // 5. Save final state
 
         Ok(())
     }
 
-    pub fn replay(cli: &Cli, run_id: &str) -> Result<(), CliError> {
-        let output = Output::new()
-            .header("oracle-omen replay")
-            .kv("run_id", run_id)
-            .line("")
-            .line("Loading event log...")
-            .line("Replaying events...")
-            .line("Verifying state reconstruction...");
+    pub fn replay(
+        cli: &Cli,
+        run_id: &str,
+        to: Option<u64>,
+        from_snapshot: bool,
+        json: bool,
+    ) -> Result<(), CliError> {
+        let outcome = replay_run(cli, parse_run_id(run_id)?, to, from_snapshot)?;
 
-        if cli.verbose {
-            output.print();
+        if json {
+            println!("{}", outcome.to_json());
+        } else {
+            let snapshot = match &outcome.snapshot {
+                Some((id, position)) => format!("{} at {}", id, position),
+                None => "none".to_string(),
+            };
+            let recorded = outcome
+                .recorded_state_hash
+                .map_or_else(|| "none".to_string(), |h| h.to_string());
+            Output::new()
+                .header("oracle-omen replay")
+                .kv("run_id", outcome.run_id)
+                .kv("events", outcome.report.total_events)
+                .kv("snapshot", snapshot)
+                .line("")
+                .line(&outcome.report)
+                .kv("final_state_hash", outcome.final_state_hash)
+                .kv("recorded_state_hash", recorded)
+                .print();
         }
 
-        // TODO: Actual implementation
-        // 1. Load event log for run_id
-        // 2. Load snapshot if available
-        // 3. Replay from snapshot or beginning
-        // 4. Verify each event hash
-        // 5. Compare final state hash
+        if outcome.report.is_valid() {
+            Ok(())
+        } else {
+            Err(CliError::Verification(outcome.report.to_string()))
+        }
+    }
 
-        Ok(())
+    /// Result of replaying a persisted run
+    pub struct ReplayOutcome {
+        /// Replayed run
+        pub run_id: u64,
+        /// Hash chain and state hash verification
+        pub report: VerificationReport,
+        /// ID and position of the snapshot replay started from
+        pub snapshot: Option<(String, u64)>,
+        /// Hash of the replayed state
+        pub final_state_hash: Hash,
+        /// `state_hash_after` of the last event that records one
+        pub recorded_state_hash: Option<Hash>,
     }
 
-    pub fn trace(cli: &Cli, run_id: &str) -> Result<(), CliError> {
-        let output = Output::new()
-            .header("oracle-omen trace")
-            .kv("run_id", run_id)
-            .line("")
-            .section("Events")
-            .line("Loading event log...");
+    impl ReplayOutcome {
+        pub fn to_json(&self) -> serde_json::Value {
+            serde_json::json!({
+                "run_id": self.run_id,
+                "total_events": self.report.total_events,
+                "verified_events": self.report.verified_events,
+                "hash_failures": self.report.hash_failures,
+                "state_mismatches": self.report.state_mismatches,
+                "valid": self.report.is_valid(),
+                "snapshot": self.snapshot.as_ref().map(|(id, position)| {
+                    serde_json::json!({ "id": id, "position": position })
+                }),
+                "final_state_hash": self.final_state_hash.to_hex(),
+                "recorded_state_hash": self.recorded_state_hash.map(|h| h.to_hex()),
+            })
+        }
+    }
 
-        if cli.verbose {
-            output.print();
+    /// Replay a run up to and including event `to`
+    pub fn replay_run(
+        cli: &Cli,
+        run_id: u64,
+        to: Option<u64>,
+        from_snapshot: bool,
+    ) -> Result<ReplayOutcome, CliError> {
+        let store = RunStore::new(&cli.data_dir);
+        let mut log = load_run(&store, run_id)?;
+        if let Some(to) = to {
+            log = truncate(&log, to)?;
         }
 
-        // TODO: Actual implementation
-        // 1. Load event log
-        // 2. Print each event with:
-        //    - Event ID
-        //    - Kind
-        //    - Timestamp
-        //    - Hashes
-        //    - Payload summary
+        let snapshot = if from_snapshot {
+            latest_snapshot(&store, &log)?
+        } else {
+            None
+        };
+        let mut engine = match &snapshot {
+            Some(snapshot) => ReplayEngine::with_state(log.clone(), snapshot.state.clone()),
+            None => ReplayEngine::new(log.clone()),
+        };
+        let state = engine
+            .replay_from(snapshot.as_ref().map_or(0, |s| s.position))
+            .map_err(|e| CliError::Runtime(e.to_string()))?;
+        let report = engine
+            .verify()
+            .map_err(|e| CliError::Runtime(e.to_string()))?;
 
+        Ok(ReplayOutcome {
+            run_id,
+            report,
+            snapshot: snapshot.map(|s| (s.id, s.position)),
+            final_state_hash: state.hash(),
+            recorded_state_hash: log.events().iter().rev().find_map(|e| e.state_hash_after),
+        })
+    }
+
+    /// Parse a run ID argument
+    pub fn parse_run_id(run_id: &str) -> Result<u64, CliError> {
+        run_id
+            .parse()
+            .map_err(|_| CliError::Config(format!("invalid run ID '{}'", run_id)))
+    }
+
+    /// Load a persisted run
+    ///
+    /// A log whose events fail their hash or sequence checks is a
+    /// verification failure rather than a read error.
+    pub fn load_run(store: &RunStore, run_id: u64) -> Result<EventLog, CliError> {
+        store.load_log(run_id).map_err(|e| match e {
+            PersistError::RunNotFound(_) => {
+                CliError::NotFound(format!("run {} in {}", run_id, store.root().display()))
+            }
+            PersistError::Corrupted { .. } => CliError::Verification(e.to_string()),
+            _ => CliError::Io(e.to_string()),
+        })
+    }
+
+    /// Copy of `log` holding events up to and including sequence `to`
+    fn truncate(log: &EventLog, to: u64) -> Result<EventLog, CliError> {
+        if to >= log.len() as u64 {
+            return Err(CliError::Config(format!(
+                "--to {} is past the end of run {} ({} events)",
+                to,
+                log.run_id,
+                log.len()
+            )));
+        }
+        let mut truncated = EventLog::new(log.run_id);
+        for event in log.events().iter().take(to as usize + 1) {
+            truncated
+                .append(event.clone())
+                .map_err(|e| CliError::Runtime(e.to_string()))?;
+        }
+        Ok(truncated)
+    }
+
+    /// Latest snapshot at or before the end of `log`, checked against its hash
+    fn latest_snapshot(store: &RunStore, log: &EventLog) -> Result<Option<Snapshot>, CliError> {
+        let snapshots = store
+            .load_snapshots(log.run_id)
+            .map_err(|e| CliError::Io(e.to_string()))?;
+        match snapshots.get_snapshot_before(log.len() as u64) {
+            Some(snapshot) if !snapshot.verify() => Err(CliError::Verification(format!(
+                "snapshot {} does not match its state hash",
+                snapshot.id
+            ))),
+            snapshot => Ok(snapshot.cloned()),
+        }
+    }
+
+    pub fn trace(
+        cli: &Cli,
+        run_id: &str,
+        filter: &TraceFilter,
+        tree: bool,
+        format: TraceFormat,
+    ) -> Result<(), CliError> {
+        let store = RunStore::new(&cli.data_dir);
+        let log = load_run(&store, parse_run_id(run_id)?)?;
+        let events = filter.apply(&log);
+        let depths = tree.then(|| trace::depths(&log, &events));
+        let depth = |event: &Event| depths.as_ref().map(|d| d[&event.id.sequence]);
+
+        match format {
+            TraceFormat::Table => println!("{}", trace::render_table(&events, depths.as_ref())),
+            TraceFormat::Json => {
+                let records: Vec<_> = events.iter().map(|e| trace::to_json(e, depth(e))).collect();
+                println!("{}", serde_json::Value::Array(records));
+            }
+            TraceFormat::Ndjson => {
+                for event in &events {
+                    println!("{}", trace::to_json(event, depth(event)));
+                }
+            }
+        }
         Ok(())
     }
 
-    pub fn diff(cli: &Cli, run_a: &str, run_b: &str) -> Result<(), CliError> {
-        let output = Output::new()
+    pub fn diff(cli: &Cli, run_a: &str, run_b: &str, context: u64) -> Result<(), CliError> {
+        let store = RunStore::new(&cli.data_dir);
+        let (id_a, id_b) = (parse_run_id(run_a)?, parse_run_id(run_b)?);
+        let log_a = load_run(&store, id_a)?;
+        let log_b = load_run(&store, id_b)?;
+
+        let engine_a = ReplayEngine::new(log_a.clone());
+        let engine_b = ReplayEngine::new(log_b.clone());
+        let divergences = engine_a.detect_divergence(&engine_b);
+        let snapshot_a = latest_snapshot(&store, &log_a)?;
+        let snapshot_b = latest_snapshot(&store, &log_b)?;
+
+        let mut output = Output::new()
             .header("oracle-omen diff")
-            .kv("run_a", run_a)
-            .kv("run_b", run_b)
-            .line("")
-            .section("Divergence Analysis")
-            .line("Comparing event logs...");
+            .kv("run_a", format!("{} ({} events)", id_a, log_a.len()))
+            .kv("run_b", format!("{} ({} events)", id_b, log_b.len()));
 
-        output.print();
+        let Some(first) = divergences.first() else {
+            output.line("").line("Runs are identical").print();
+            return Ok(());
+        };
 
-        // TODO: Actual implementation
-        // 1. Load both event logs
-        // 2. Find first divergence point
-        // 3. Show detailed diff
-        // 4. Compare final states
+        output = output
+            .section("Divergence")
+            .kv("first", first)
+            .kv("divergent_events", divergences.len())
+            .section("Context")
+            .line(diff::context_table(&log_a, &log_b, first.position, context))
+            .section("Field Diff");
+        let event_a = log_a.get_by_sequence(first.position);
+        let event_b = log_b.get_by_sequence(first.position);
+        for field in diff::event_diff(event_a, event_b) {
+            output = output.line(field);
+        }
 
-        Ok(())
+        let recorded = |snapshot: &Option<Snapshot>| {
+            snapshot.as_ref().map_or("no snapshot".to_string(), |s| {
+                format!("{} (snapshot at {})", s.state_hash, s.position)
+            })
+        };
+        output = output
+            .section("Recorded State")
+            .kv("run_a", recorded(&snapshot_a))
+            .kv("run_b", recorded(&snapshot_b));
+        if let (Some(a), Some(b)) = (&snapshot_a, &snapshot_b) {
+            for field in diff::state_diff(&a.state, &b.state) {
+                output = output.line(field);
+            }
+        }
+        output.print();
+
+        Err(CliError::Diverged(format!(
+            "runs {} and {} first differ at event {}",
+            id_a, id_b, first.position
+        )))
     }
 
     pub fn inspect(cli: &Cli, run_id: &str) -> Result<(), CliError> {
-        let output = Output::new()
+        let store = RunStore::new(&cli.data_dir);
+        let run_id = parse_run_id(run_id)?;
+        let log = load_run(&store, run_id)?;
+        let snapshots = store
+            .load_snapshots(run_id)
+            .map_err(|e| CliError::Io(e.to_string()))?;
+        let report = ReplayEngine::new(log.clone())
+            .verify()
+            .map_err(|e| CliError::Runtime(e.to_string()))?;
+        let latest = latest_snapshot(&store, &log)?;
+        let inspection = Inspection::of(&log);
+
+        let mut output = Output::new()
             .header("oracle-omen inspect")
             .kv("run_id", run_id)
-            .line("")
-            .section("Run Information")
-            .line("Loading run data...")
-            .line("")
-            .section("Event Summary")
-            .line("Loading events...")
-            .line("")
-            .section("State")
-            .line("Loading final state...");
+            .kv("events", log.len())
+            .line(&report)
+            .section("Run Information");
+        match &inspection.agent {
+            Some(agent) => {
+                output = output.kv(
+                    "agent",
+                    format!("{}@{}", agent.agent_type, agent.agent_version),
+                );
+                for (key, value) in &agent.config {
+                    output = output.kv(format!("config.{}", key), value);
+                }
+            }
+            None => output = output.line("No agent_init event"),
+        }
 
-        if cli.verbose {
-            output.print();
+        let mut kinds = Table::new(vec!["kind".to_string(), "count".to_string()]);
+        for (kind, count) in &inspection.kinds {
+            kinds = kinds.row(vec![kind.clone(), count.to_string()]);
         }
+        output = output.section("Event Summary").line(kinds.format());
 
-        // TODO: Actual implementation
-        // 1. Load run metadata
-        // 2. Show event count and types
-        // 3. Show final state
-        // 4. Show capability usage
+        output = output.section("Tool Calls");
+        if inspection.tools.is_empty() {
+            output = output.line("none");
+        } else {
+            let headers = ["tool", "calls", "responses", "success", "duration_ms"];
+            let mut tools = Table::new(headers.iter().map(|h| h.to_string()).collect());
+            for (name, stats) in &inspection.tools {
+                tools = tools.row(vec![
+                    name.clone(),
+                    stats.calls.to_string(),
+                    stats.responses.to_string(),
+                    format!("{:.1}%", stats.success_rate()),
+                    stats.duration_ms.to_string(),
+                ]);
+            }
+            output = output.line(tools.format());
+        }
+
+        output = output.section("Capability Denials");
+        if inspection.denials.is_empty() {
+            output = output.line("none");
+        }
+        for denial in &inspection.denials {
+            output = output.line(format!(
+                "{} {} lacked {}: {}",
+                denial.sequence, denial.tool, denial.capability, denial.reason
+            ));
+        }
+
+        let positions = snapshots.positions();
+        let list = |seqs: &[u64]| match seqs {
+            [] => "none".to_string(),
+            _ => seqs
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        output = output
+            .section("Patches")
+            .kv("proposed", inspection.patches_proposed)
+            .kv("applied", inspection.patches_applied)
+            .kv("rejected", inspection.patches_rejected)
+            .kv("rolled_back", inspection.patches_rolled_back)
+            .section("Snapshots")
+            .kv("stored", list(&positions))
+            .kv("events", list(&inspection.snapshot_events))
+            .section("State");
+        match &latest {
+            Some(snapshot) => {
+                output = output
+                    .kv(
+                        "snapshot",
+                        format!("{} at {}", snapshot.id, snapshot.position),
+                    )
+                    .kv("state_hash", snapshot.state_hash)
+                    .kv("domains", snapshot.state.data.len());
+                for (domain, data) in &snapshot.state.data {
+                    let value = serde_json::to_string(data).unwrap_or_default();
+                    output = output.kv(format!("  {}", domain), value);
+                }
+            }
+            None => output = output.line("No snapshot recorded"),
+        }
+        output.print();
+
+        if report.is_valid() {
+            Ok(())
+        } else {
+            Err(CliError::Verification(report.to_string()))
+        }
+    }
+
+    pub fn debug(cli: &Cli, run_id: &str) -> Result<(), CliError> {
+        use std::io::Write;
+
+        let store = RunStore::new(&cli.data_dir);
+        let run_id = parse_run_id(run_id)?;
+        let log = load_run(&store, run_id)?;
+        let snapshots = store
+            .load_snapshots(run_id)
+            .map_err(|e| CliError::Io(e.to_string()))?;
+        Output::new()
+            .header("oracle-omen debug")
+            .kv("run_id", run_id)
+            .kv("events", log.len())
+            .kv("snapshots", snapshots.positions().len())
+            .line("Type `help` for commands.")
+            .print();
+
+        let mut debugger = Debugger::new(log, snapshots);
+        let stdin = std::io::stdin();
+        loop {
+            print!("{} ", debugger.prompt());
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            if stdin.read_line(&mut line)? == 0 {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            match DebugCommand::parse(&line) {
+                Ok(DebugCommand::Quit) => break,
+                Ok(command) => println!("{}", debugger.execute(&command)),
+                Err(e) => println!("{}", e),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn otlp(cli: &Cli, run_id: &str, output: Option<&Path>) -> Result<(), CliError> {
+        let store = RunStore::new(&cli.data_dir);
+        let run_id = parse_run_id(run_id)?;
+        let log = load_run(&store, run_id)?;
+        let path = output.map_or_else(
+            || PathBuf::from(format!("run-{}.otlp.json", run_id)),
+            Path::to_path_buf,
+        );
+        let exporter = OtlpExporter::new();
+        exporter
+            .write(&log, &path)
+            .map_err(|e| CliError::Io(e.to_string()))?;
+
+        Output::new()
+            .header("oracle-omen otlp")
+            .kv("run_id", run_id)
+            .kv("events", log.len())
+            .kv("spans", exporter.spans(&log).len())
+            .kv("output", path.display())
+            .print();
+        Ok(())
+    }
+
+    pub fn export(
+        cli: &Cli,
+        run_id: &str,
+        config: Option<&Path>,
+        key: Option<&Path>,
+        output: Option<&Path>,
+    ) -> Result<(), CliError> {
+        let store = RunStore::new(&cli.data_dir);
+        let run_id = parse_run_id(run_id)?;
+        let log = load_run(&store, run_id)?;
+        let report = ReplayEngine::new(log.clone())
+            .verify()
+            .map_err(|e| CliError::Runtime(e.to_string()))?;
+        if !report.is_valid() {
+            return Err(CliError::Verification(report.to_string()));
+        }
+
+        let mut bundle = RunBundle::new(run_id);
+        let mut files = Vec::new();
+        list_files(
+            &store.run_dir(run_id),
+            &format!("runs/{}", run_id),
+            &mut files,
+        )?;
+        for (path, file) in files {
+            bundle.add_file(path, &std::fs::read(file)?);
+        }
+        let patches = PatchStore::load(patches_path(&cli.data_dir))
+            .map_err(store_error)?
+            .for_run(run_id);
+        if !patches.is_empty() {
+            let json = serde_json::to_vec_pretty(&patches)
+                .map_err(|e| CliError::Runtime(e.to_string()))?;
+            bundle.add_file("patches.json", &json);
+        }
+
+        if let Some(path) = config {
+            let run_config = RunConfig::load(path).map_err(|e| CliError::Config(e.to_string()))?;
+            let recorded = recorded_config_hash(&log);
+            if recorded.is_some_and(|hash| hash != run_config.hash()) {
+                return Err(CliError::Verification(format!(
+                    "{} does not match the config_hash recorded by run {}",
+                    path.display(),
+                    run_id
+                )));
+            }
+            bundle
+                .add_config(&run_config, &std::fs::read(path)?)
+                .map_err(bundle_error)?;
+        }
+        if let Some(key) = key {
+            let key = KeyPair::load(key).map_err(|e| CliError::Config(e.to_string()))?;
+            bundle.sign(&key);
+        }
 
+        let path = output.map_or_else(
+            || PathBuf::from(format!("run-{}.bundle.json", run_id)),
+            Path::to_path_buf,
+        );
+        bundle.save(&path).map_err(bundle_error)?;
+
+        let signer = bundle
+            .signature
+            .as_ref()
+            .map_or_else(|| "unsigned".to_string(), |s| s.signer.to_hex());
+        Output::new()
+            .header("oracle-omen export")
+            .kv("run_id", run_id)
+            .kv("files", bundle.manifest.files.len())
+            .kv("tools", bundle.manifest.tools.len())
+            .kv("manifest_hash", bundle.manifest.hash())
+            .kv("signer", signer)
+            .kv("output", path.display())
+            .print();
         Ok(())
     }
 
+    pub fn import(cli: &Cli, bundle: &Path, trusted: &[String]) -> Result<(), CliError> {
+        let bundle = RunBundle::load(bundle).map_err(bundle_error)?;
+        let trusted = signer_ids(trusted)?;
+        bundle.verify(&trusted).map_err(bundle_error)?;
+
+        let run_id = bundle.manifest.run_id;
+        let store = RunStore::new(&cli.data_dir);
+        if store.exists(run_id) {
+            return Err(CliError::Runtime(format!(
+                "run {} already exists in {}",
+                run_id,
+                store.root().display()
+            )));
+        }
+
+        // Unpack next to the data directory and check with the normal loaders
+        let staging = cli.data_dir.join(format!(".import-{}", run_id));
+        let _ = std::fs::remove_dir_all(&staging);
+        let checked = bundle
+            .unpack(&staging)
+            .map_err(bundle_error)
+            .and_then(|()| check_import(&cli.data_dir, &staging, &bundle));
+        let result = checked.and_then(|(report, patches)| {
+            std::fs::create_dir_all(store.root().join("runs"))?;
+            std::fs::rename(
+                RunStore::new(&staging).run_dir(run_id),
+                store.run_dir(run_id),
+            )?;
+            if let Some(patches) = patches {
+                patches
+                    .save(patches_path(&cli.data_dir))
+                    .map_err(store_error)?;
+            }
+            Ok(report)
+        });
+        let _ = std::fs::remove_dir_all(&staging);
+        let report = result?;
+
+        let signer = bundle
+            .signature
+            .as_ref()
+            .map_or_else(|| "unsigned".to_string(), |s| s.signer.to_hex());
+        Output::new()
+            .header("oracle-omen import")
+            .kv("run_id", run_id)
+            .kv("files", bundle.manifest.files.len())
+            .kv("manifest_hash", bundle.manifest.hash())
+            .kv("signer", signer)
+            .line(&report)
+            .print();
+        Ok(())
+    }
+
+    /// Verify an unpacked bundle, returning its report and the merged patch store
+    fn check_import(
+        data_dir: &Path,
+        staging: &Path,
+        bundle: &RunBundle,
+    ) -> Result<(VerificationReport, Option<PatchStore>), CliError> {
+        let run_id = bundle.manifest.run_id;
+        let staged = RunStore::new(staging);
+        let log = staged.load_log(run_id).map_err(|e| match e {
+            PersistError::Io(_) => CliError::Io(e.to_string()),
+            _ => CliError::Verification(e.to_string()),
+        })?;
+        let report = ReplayEngine::new(log.clone())
+            .verify()
+            .map_err(|e| CliError::Verification(e.to_string()))?;
+        if !report.is_valid() {
+            return Err(CliError::Verification(report.to_string()));
+        }
+        staged
+            .load_snapshots(run_id)
+            .map_err(|e| CliError::Verification(e.to_string()))?;
+
+        if let Some(expected) = bundle.manifest.config_hash {
+            let path = format!("runs/{}/config/config.toml", run_id);
+            let source = String::from_utf8(bundle.file(&path).map_err(bundle_error)?)
+                .map_err(|e| CliError::Verification(format!("{}: {}", path, e)))?;
+            let config = RunConfig::parse(&source)
+                .map_err(|e| CliError::Verification(format!("{}:{}", path, e)))?;
+            let recorded = recorded_config_hash(&log);
+            if config.hash() != expected || recorded.is_some_and(|hash| hash != expected) {
+                return Err(CliError::Verification(format!(
+                    "{} does not match the recorded config_hash",
+                    path
+                )));
+            }
+        }
+
+        let path = patches_path(staging);
+        if !path.exists() {
+            return Ok((report, None));
+        }
+        let patches = PatchStore::load(&path).map_err(|e| CliError::Verification(e.to_string()))?;
+        let foreign = patches.list_patches().len() - patches.for_run(run_id).list_patches().len();
+        if foreign > 0 {
+            return Err(CliError::Verification(format!(
+                "patches.json holds {} patches of other runs",
+                foreign
+            )));
+        }
+        let mut merged = PatchStore::load(patches_path(data_dir)).map_err(store_error)?;
+        merged.merge(patches).map_err(store_error)?;
+        Ok((report, Some(merged)))
+    }
+
+    /// Files under `dir`, sorted, with paths as `prefix/<relative>`
+    fn list_files(
+        dir: &Path,
+        prefix: &str,
+        out: &mut Vec<(String, PathBuf)>,
+    ) -> Result<(), CliError> {
+        let mut entries = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for path in entries {
+            let name = format!(
+                "{}/{}",
+                prefix,
+                path.file_name().unwrap_or_default().to_string_lossy()
+            );
+            if path.is_dir() {
+                list_files(&path, &name, out)?;
+            } else {
+                out.push((name, path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse hex public keys given with `--trust`
+    fn signer_ids(keys: &[String]) -> Result<Vec<SignerId>, CliError> {
+        keys.iter()
+            .map(|hex| SignerId::from_hex(hex).map_err(|e| CliError::Config(e.to_string())))
+            .collect()
+    }
+
+    fn bundle_error(e: BundleError) -> CliError {
+        match e {
+            BundleError::Io(_) => CliError::Io(e.to_string()),
+            _ => CliError::Verification(e.to_string()),
+        }
+    }
+
+    pub fn patch(cli: &Cli, action: &PatchCommand) -> Result<(), CliError> {
+        let path = patches_path(&cli.data_dir);
+        let store = PatchStore::load(&path).map_err(store_error)?;
+        match action {
+            PatchCommand::List => patch_list(&store),
+            PatchCommand::Show { id } => patch_show(&store, id),
+            PatchCommand::Propose { file } => {
+                let patch: Patch = serde_json::from_slice(&std::fs::read(file)?)
+                    .map_err(|e| CliError::Config(format!("{}: {}", file.display(), e)))?;
+                let mut engine = PatchEngine::new(store);
+                let mut log = load_run(&RunStore::new(&cli.data_dir), patch.id.run_id)?;
+                engine.submit(patch.clone()).map_err(apply_error)?;
+                engine.into_store().save(&path).map_err(store_error)?;
+                let payload = EventPayload::PatchProposal(patch_payload(&patch));
+                append_to_run(cli, &mut log, payload)?;
+                Output::new()
+                    .kv("proposed", &patch.id)
+                    .kv("patch_hash", patch.hash())
+                    .print();
+                Ok(())
+            }
+            PatchCommand::Test { id } => {
+                let (patch, _) = get_patch(&store, id)?;
+                let mut log = load_run(&RunStore::new(&cli.data_dir), patch.id.run_id)?;
+                let report = ReplayEngine::new(log.clone())
+                    .verify()
+                    .map_err(|e| CliError::Runtime(e.to_string()))?;
+                let mut engine = PatchEngine::new(store);
+                let result = engine
+                    .test_gate(id, &RunTestRunner { report })
+                    .map_err(apply_error)?;
+                engine.into_store().save(&path).map_err(store_error)?;
+                gate_outcome(cli, &mut log, &patch, "test", PATCH_TESTED, &result)
+            }
+            PatchCommand::Audit { id, policies } => {
+                let (patch, _) = get_patch(&store, id)?;
+                let mut log = load_run(&RunStore::new(&cli.data_dir), patch.id.run_id)?;
+                let mut policy = PolicyEngine::new();
+                for file in policies {
+                    let doc: Policy = serde_json::from_slice(&std::fs::read(file)?)
+                        .map_err(|e| CliError::Config(format!("{}: {}", file.display(), e)))?;
+                    let compiled = PolicyCompiler::compile(&doc)
+                        .map_err(|e| CliError::Config(format!("{}: {}", file.display(), e)))?;
+                    policy.add_policy(compiled);
+                }
+                let mut engine = PatchEngine::new(store).with_policy(policy);
+                let result = engine
+                    .audit_gate(id, &EvalContext::default())
+                    .map_err(apply_error)?;
+                engine.into_store().save(&path).map_err(store_error)?;
+                gate_outcome(cli, &mut log, &patch, "audit", PATCH_AUDITED, &result)
+            }
+            PatchCommand::Approve { id, key } => {
+                let (patch, _) = get_patch(&store, id)?;
+                let mut log = load_run(&RunStore::new(&cli.data_dir), patch.id.run_id)?;
+                let keypair = KeyPair::load(key)
+                    .map_err(|e| CliError::Config(format!("{}: {}", key.display(), e)))?;
+                let signature = keypair.sign(patch.hash().to_hex().as_bytes());
+                let mut engine = PatchEngine::new(store).with_approvers(trusted_approvers(cli)?);
+                engine
+                    .approve(id, signature, keypair.signer_id())
+                    .map_err(apply_error)?;
+                engine.into_store().save(&path).map_err(store_error)?;
+                let mut data = passed_data(&patch, "approve");
+                data.insert("signer".to_string(), keypair.signer_id().to_hex());
+                append_kind_to_run(cli, &mut log, PATCH_APPROVED, data)?;
+                Output::new()
+                    .kv("approved", id)
+                    .kv("signer", keypair.signer_id().to_hex())
+                    .print();
+                Ok(())
+            }
+            PatchCommand::Apply { id } => {
+                let (patch, _) = get_patch(&store, id)?;
+                let run_id = patch.id.run_id;
+                let mut log = load_run(&RunStore::new(&cli.data_dir), run_id)?;
+                let mut state = patched_state(&store, &log)?;
+                let mut engine = PatchEngine::new(store).with_approvers(trusted_approvers(cli)?);
+                let result = engine.apply(id, &mut state).map_err(apply_error)?;
+                let mut store = engine.into_store();
+                store.set_state(run_id, state);
+                store.save(&path).map_err(store_error)?;
+                let payload = EventPayload::PatchApplied(patch_payload(&patch));
+                append_to_run(cli, &mut log, payload)?;
+                let mut output = Output::new().kv("applied", id);
+                if let Some(applied) = store.applied(id) {
+                    output = output
+                        .kv("state_before", applied.before_hash)
+                        .kv("state_after", applied.after_hash);
+                }
+                output.kv("changes", result.changes_made.join(", ")).print();
+                Ok(())
+            }
+            PatchCommand::Rollback { id, reason } => {
+                let (patch, _) = get_patch(&store, id)?;
+                let run_id = patch.id.run_id;
+                let mut log = load_run(&RunStore::new(&cli.data_dir), run_id)?;
+                let mut state = patched_state(&store, &log)?;
+                let mut engine = PatchEngine::new(store);
+                let result = engine
+                    .rollback(id, reason, &mut state)
+                    .map_err(apply_error)?;
+                let mut store = engine.into_store();
+                store.set_state(run_id, state);
+                store.save(&path).map_err(store_error)?;
+                let payload =
+                    EventPayload::PatchRejected(rejected_payload(&patch, "rollback", reason));
+                append_to_run(cli, &mut log, payload)?;
+                Output::new()
+                    .kv("rolled_back", id)
+                    .kv("restored_to", result.restored_to)
+                    .print();
+                Ok(())
+            }
+        }
+    }
+
+    fn patch_list(store: &PatchStore) -> Result<(), CliError> {
+        let headers = ["id", "type", "target", "status"];
+        let mut table = Table::new(headers.iter().map(|h| h.to_string()).collect());
+        for (id, patch, status) in store.list_patches() {
+            table = table.row(vec![
+                id,
+                patch::type_name(&patch.patch_type),
+                patch::target_name(&patch.target),
+                status_name(&status).to_string(),
+            ]);
+        }
+        Output::new()
+            .header("oracle-omen patch list")
+            .line(table.format())
+            .print();
+        Ok(())
+    }
+
+    fn patch_show(store: &PatchStore, id: &str) -> Result<(), CliError> {
+        let (patch, status) = get_patch(store, id)?;
+        let mut output = Output::new()
+            .header("oracle-omen patch show")
+            .kv("id", &patch.id)
+            .kv("type", patch::type_name(&patch.patch_type))
+            .kv("target", patch::target_name(&patch.target))
+            .kv("status", status_name(&status))
+            .kv("patch_hash", patch.hash())
+            .kv("reasoning", &patch.reasoning);
+        if let PatchStatus::Rejected { reason } | PatchStatus::RolledBack { reason } = &status {
+            output = output.kv("reason", reason);
+        }
+        output = output.section("Data");
+        for (key, value) in &patch.data {
+            output = output.kv(key, value);
+        }
+        output = output.section("Tests");
+        for test in &patch.tests {
+            output = output.kv(
+                &test.name,
+                format!("{:?}, expect {:?}", test.test_type, test.expected),
+            );
+        }
+        output = output.section("Approval");
+        output = match store.approval(id) {
+            Some(signed) => {
+                let valid = if signed.verify() { "valid" } else { "INVALID" };
+                output
+                    .kv("signer", signed.signer.to_hex())
+                    .kv("signature", valid)
+            }
+            None => output.line("none"),
+        };
+        if let Some(applied) = store.applied(id) {
+            output = output
+                .section("Application")
+                .kv("state_before", applied.before_hash)
+                .kv("state_after", applied.after_hash);
+        }
+        output.print();
+        Ok(())
+    }
+
+    fn get_patch(store: &PatchStore, id: &str) -> Result<(Patch, PatchStatus), CliError> {
+        store
+            .get_patch(id)
+            .ok_or_else(|| CliError::NotFound(format!("patch {}", id)))
+    }
+
+    /// Report a gate result and log it to the owning run
+    ///
+    /// A pass is logged as the `passed` event kind and a failure as
+    /// `patch_rejected`.
+    fn gate_outcome(
+        cli: &Cli,
+        log: &mut EventLog,
+        patch: &Patch,
+        stage: &str,
+        passed: &str,
+        result: &GateResult,
+    ) -> Result<(), CliError> {
+        match gate_reason(result) {
+            None => {
+                append_kind_to_run(cli, log, passed, passed_data(patch, stage))?;
+                Output::new()
+                    .kv(format!("{} gate", stage), "passed")
+                    .print();
+                Ok(())
+            }
+            Some(reason) => {
+                let payload = EventPayload::PatchRejected(rejected_payload(patch, stage, reason));
+                append_to_run(cli, log, payload)?;
+                Err(CliError::Verification(format!(
+                    "patch {} failed {} gate: {}",
+                    patch.id, stage, reason
+                )))
+            }
+        }
+    }
+
+    /// Append an event to a persisted run, caused by its last event
+    fn append_to_run(cli: &Cli, log: &mut EventLog, payload: EventPayload) -> Result<(), CliError> {
+        append_event_to_run(cli, log, payload.kind(), payload)
+    }
+
+    /// Append a custom event with raw data to a persisted run
+    fn append_kind_to_run(
+        cli: &Cli,
+        log: &mut EventLog,
+        kind: &str,
+        data: StableMap<String, String>,
+    ) -> Result<(), CliError> {
+        let kind = EventKind::Custom(kind.to_string());
+        append_event_to_run(cli, log, kind, EventPayload::Raw(data))
+    }
+
+    fn append_event_to_run(
+        cli: &Cli,
+        log: &mut EventLog,
+        kind: EventKind,
+        payload: EventPayload,
+    ) -> Result<(), CliError> {
+        let parent = log.last().map(|e| e.id);
+        let id = log
+            .append_next(kind, payload, parent)
+            .map_err(|e| CliError::Runtime(e.to_string()))?;
+        let event = log
+            .get(id)
+            .cloned()
+            .ok_or_else(|| CliError::Runtime(format!("event {} missing", id)))?;
+        RunStore::new(&cli.data_dir)
+            .append_event(&event)
+            .map_err(|e| CliError::Io(e.to_string()))
+    }
+
+    /// State patches apply to: the run's state after its latest apply or
+    /// rollback, else its state at the end of the log
+    fn patched_state(store: &PatchStore, log: &EventLog) -> Result<AgentState, CliError> {
+        if let Some(state) = store.state(log.run_id) {
+            return Ok(state.clone());
+        }
+        ReplayEngine::new(log.clone())
+            .replay_all()
+            .map_err(|e| CliError::Runtime(e.to_string()))
+    }
+
+    /// Approvers listed in the data directory's trust store
+    fn trusted_approvers(cli: &Cli) -> Result<Vec<SignerId>, CliError> {
+        let path = patch::trusted_path(&cli.data_dir);
+        load_approvers(&path).map_err(|e| CliError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    fn store_error(e: StoreError) -> CliError {
+        match e {
+            StoreError::NotFound(_) => CliError::NotFound(e.to_string()),
+            StoreError::Corrupted(_) => CliError::Verification(e.to_string()),
+            _ => CliError::Io(e.to_string()),
+        }
+    }
+
+    fn apply_error(e: ApplyError) -> CliError {
+        match e {
+            ApplyError::NotFound(id) => CliError::NotFound(format!("patch {}", id)),
+            ApplyError::NotApproved => {
+                CliError::Verification("patch has no valid approval".to_string())
+            }
+            ApplyError::UntrustedSigner(_) => CliError::Verification(e.to_string()),
+            ApplyError::TestFailed(_) | ApplyError::AuditFailed(_) => {
+                CliError::Verification(e.to_string())
+            }
+            _ => CliError::Runtime(e.to_string()),
+        }
+    }
+
     pub fn capabilities(cli: &Cli, run_id: &str) -> Result<(), CliError> {
         let output = Output::new()
             .header("oracle-omen capabilities")
@@ -257,3 +1264,395 @@ mod commands {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::commands::*;
+    use super::*;
+    use oracle_omen_core::{
+        event::{Event, EventKind, EventLog, EventPayload, StateTransitionPayload},
+        hash::Hash,
+        replay::{ReplayEngine, Snapshot},
+        state::{AgentState, StateData, StateValue},
+    };
+    use oracle_omen_runtime::RunStore;
+
+    fn temp_cli(name: &str) -> Cli {
+        let dir =
+            std::env::temp_dir().join(format!("oracle-omen-cli-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Cli {
+            command: Command::Inspect {
+                run_id: "0".to_string(),
+            },
+            verbose: false,
+            data_dir: dir,
+        }
+    }
+
+    /// A run whose transitions move through `states`
+    fn transition_log(run_id: u64, states: &[&str]) -> EventLog {
+        let mut log = EventLog::new(run_id);
+        for pair in states.windows(2) {
+            let (before, after) = (Hash::from_str(pair[0]), Hash::from_str(pair[1]));
+            let id = log.next_id();
+            let payload = EventPayload::StateTransition(StateTransitionPayload {
+                from_hash: before,
+                to_hash: after,
+                transition_type: "test".to_string(),
+            });
+            let event = Event::new(
+                id,
+                EventKind::StateTransition,
+                id.to_logical_time(),
+                payload,
+            )
+            .with_state_hashes(before, after);
+            log.append(event).unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn test_replay_run() {
+        let cli = temp_cli("replay");
+        let store = RunStore::new(&cli.data_dir);
+        let log = transition_log(1, &["a", "b", "c", "d"]);
+        store.save_log(&log).unwrap();
+
+        let full = replay_run(&cli, 1, None, false).unwrap();
+        assert!(full.report.is_valid());
+        assert_eq!(full.report.total_events, 3);
+        assert_eq!(full.recorded_state_hash, Some(Hash::from_str("d")));
+
+        let partial = replay_run(&cli, 1, Some(1), false).unwrap();
+        assert_eq!(partial.report.total_events, 2);
+        assert_eq!(partial.recorded_state_hash, Some(Hash::from_str("c")));
+        assert!(replay_run(&cli, 1, Some(3), false).is_err());
+
+        let mut engine = ReplayEngine::new(log);
+        engine.step();
+        engine.step();
+        let snapshot = Snapshot::new("s2", 1, 2, engine.current_state().clone());
+        store.save_snapshot(&snapshot).unwrap();
+        let resumed = replay_run(&cli, 1, None, true).unwrap();
+        assert_eq!(resumed.snapshot, Some(("s2".to_string(), 2)));
+        assert_eq!(resumed.final_state_hash, full.final_state_hash);
+    }
+
+    #[test]
+    fn test_replay_failures() {
+        let cli = temp_cli("replay-fail");
+        let store = RunStore::new(&cli.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        let mut broken = transition_log(2, &["a", "b"]);
+        let id = broken.next_id();
+        let payload = EventPayload::StateTransition(StateTransitionPayload {
+            from_hash: Hash::from_str("x"),
+            to_hash: Hash::from_str("y"),
+            transition_type: "test".to_string(),
+        });
+        broken
+            .append(
+                Event::new(
+                    id,
+                    EventKind::StateTransition,
+                    id.to_logical_time(),
+                    payload,
+                )
+                .with_state_hashes(Hash::from_str("x"), Hash::from_str("y")),
+            )
+            .unwrap();
+        store.save_log(&broken).unwrap();
+
+        let outcome = replay_run(&cli, 2, None, false).unwrap();
+        assert_eq!(outcome.report.state_mismatches, 1);
+        assert_eq!(outcome.to_json()["valid"], false);
+
+        let err = replay_run(&cli, 9, None, false).err().unwrap();
+        assert_eq!(err.exit_code(), 3);
+
+        let path = store.events_path(1);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replacen("\"test\"", "\"edited\"", 1)).unwrap();
+        let err = replay_run(&cli, 1, None, false).err().unwrap();
+        assert!(matches!(err, CliError::Verification(_)));
+        assert_eq!(err.exit_code(), 4);
+    }
+
+    #[test]
+    fn test_diff_exit_codes() {
+        let cli = temp_cli("diff");
+        let store = RunStore::new(&cli.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        store
+            .save_log(&transition_log(2, &["a", "b", "c"]))
+            .unwrap();
+        store
+            .save_log(&transition_log(3, &["a", "b", "x"]))
+            .unwrap();
+
+        assert!(commands::diff(&cli, "1", "2", 3).is_ok());
+        let err = commands::diff(&cli, "1", "3", 3).err().unwrap();
+        assert!(matches!(&err, CliError::Diverged(msg) if msg.ends_with("event 1")));
+        assert_eq!(err.exit_code(), 5);
+        assert_eq!(
+            commands::diff(&cli, "1", "9", 3).err().unwrap().exit_code(),
+            3
+        );
+
+        let mut state = AgentState::with_run_id(3);
+        state.set("count", StateData::Value(StateValue::U64(1)));
+        store
+            .save_snapshot(&Snapshot::new("s1", 3, 1, state.clone()))
+            .unwrap();
+        let err = commands::diff(&cli, "1", "3", 3).err().unwrap();
+        assert_eq!(err.exit_code(), 5);
+
+        let mut tampered = Snapshot::new("s1", 3, 1, state);
+        tampered
+            .state
+            .set("count", StateData::Value(StateValue::U64(2)));
+        store.save_snapshot(&tampered).unwrap();
+        let err = commands::diff(&cli, "1", "3", 3).err().unwrap();
+        assert!(matches!(err, CliError::Verification(_)));
+    }
+
+    #[test]
+    fn test_inspect_verifies_log() {
+        let cli = temp_cli("inspect");
+        let store = RunStore::new(&cli.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        assert!(commands::inspect(&cli, "1").is_ok());
+        assert_eq!(commands::inspect(&cli, "9").err().unwrap().exit_code(), 3);
+
+        let mut state = AgentState::with_run_id(1);
+        state.set("count", StateData::Value(StateValue::U64(1)));
+        let mut snapshot = Snapshot::new("s2", 1, 2, state);
+        store.save_snapshot(&snapshot).unwrap();
+        assert!(commands::inspect(&cli, "1").is_ok());
+        snapshot
+            .state
+            .set("count", StateData::Value(StateValue::U64(2)));
+        store.save_snapshot(&snapshot).unwrap();
+        assert_eq!(commands::inspect(&cli, "1").err().unwrap().exit_code(), 4);
+        store
+            .save_snapshot(&Snapshot::new("s2", 1, 2, AgentState::with_run_id(1)))
+            .unwrap();
+
+        let path = store.events_path(1);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replacen("\"test\"", "\"edited\"", 1)).unwrap();
+        let err = commands::inspect(&cli, "1").err().unwrap();
+        assert!(matches!(err, CliError::Verification(_)));
+        assert_eq!(err.exit_code(), 4);
+    }
+
+    #[test]
+    fn test_otlp_writes_trace() {
+        let cli = temp_cli("otlp");
+        let store = RunStore::new(&cli.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        let path = cli.data_dir.join("trace.json");
+        commands::otlp(&cli, "1", Some(&path)).unwrap();
+
+        let doc: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let spans = &doc["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["name"], "run 1");
+        assert_eq!(spans[0]["events"].as_array().map(Vec::len), Some(2));
+        assert_eq!(
+            commands::otlp(&cli, "9", Some(&path))
+                .unwrap_err()
+                .exit_code(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        use crate::bundle::RunBundle;
+        use oracle_omen_patches::patch::{Patch, PatchId, PatchStatus, PatchTarget, PatchType};
+        use oracle_omen_patches::{signature::KeyPair, store::PatchStore};
+
+        let source = temp_cli("export");
+        let store = RunStore::new(&source.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        let mut patches = PatchStore::new();
+        for run_id in [1, 2] {
+            let patch = Patch::new(
+                PatchId::new(run_id, 0),
+                PatchType::Prompt,
+                PatchTarget::SystemPrompt,
+                "test".to_string(),
+            );
+            patches
+                .add_patch(patch.id.to_string(), patch, PatchStatus::Proposed)
+                .unwrap();
+        }
+        patches
+            .save(crate::patch::patches_path(&source.data_dir))
+            .unwrap();
+        let key = source.data_dir.join("signer.key");
+        std::fs::write(&key, "07".repeat(32)).unwrap();
+        let bundle = source.data_dir.join("run-1.bundle.json");
+        commands::export(&source, "1", None, Some(&key), Some(&bundle)).unwrap();
+
+        let target = temp_cli("import");
+        let signer = KeyPair::from_secret([7u8; 32]).signer_id().to_hex();
+        commands::import(&target, &bundle, std::slice::from_ref(&signer)).unwrap();
+        let imported = RunStore::new(&target.data_dir).load_log(1).unwrap();
+        assert_eq!(imported.events(), store.load_log(1).unwrap().events());
+        let imported = PatchStore::load(crate::patch::patches_path(&target.data_dir)).unwrap();
+        assert_eq!(imported.list_patches().len(), 1);
+        assert!(commands::import(&target, &bundle, &[]).is_err());
+
+        // Any edit to the bundle is caught before the run is accepted
+        let fresh = temp_cli("import-tampered");
+        let text = std::fs::read_to_string(&bundle).unwrap();
+        let events = RunBundle::load(&bundle)
+            .unwrap()
+            .file("runs/1/events.jsonl")
+            .unwrap();
+        let hex: String = events.iter().map(|b| format!("{:02x}", b)).collect();
+        let edited = hex.replacen(&format!("{:02x}", b'{'), &format!("{:02x}", b' '), 1);
+        std::fs::write(&bundle, text.replacen(&hex, &edited, 1)).unwrap();
+        let err = commands::import(&fresh, &bundle, &[signer]).unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert!(!RunStore::new(&fresh.data_dir).exists(1));
+    }
+
+    #[test]
+    fn test_patch_lifecycle() {
+        use crate::patch::{patches_path, PatchCommand};
+        use oracle_omen_patches::patch::{Patch, PatchId, PatchTarget, PatchType};
+        use oracle_omen_patches::{signature::KeyPair, store::PatchStore};
+        use oracle_omen_policy::lang::{Action, Condition, Policy, Rule, RuleKind};
+
+        let cli = temp_cli("patch");
+        let store = RunStore::new(&cli.data_dir);
+        store
+            .save_log(&transition_log(1, &["a", "b", "c"]))
+            .unwrap();
+        let write = |name: &str, contents: String| {
+            let path = cli.data_dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path
+        };
+        let run = |action: PatchCommand| commands::patch(&cli, &action);
+
+        for sequence in [0, 1] {
+            let patch = Patch::new(
+                PatchId::new(1, sequence),
+                PatchType::Config,
+                PatchTarget::Config("max_iterations".to_string()),
+                "raise limit".to_string(),
+            )
+            .with_data("value", "200");
+            let file = write("patch.json", serde_json::to_string(&patch).unwrap());
+            run(PatchCommand::Propose { file }).unwrap();
+        }
+        let id = |s: &str| s.to_string();
+        let key = write("approver.key", "07".repeat(32));
+        let trusted = KeyPair::from_secret([7u8; 32]).signer_id().to_hex();
+        let approve = |key: &PathBuf| PatchCommand::Approve {
+            id: id("1:1"),
+            key: key.clone(),
+        };
+
+        // Gates run in order, and a failed gate is logged to the run
+        let early = PatchCommand::Approve {
+            id: id("1:0"),
+            key: key.clone(),
+        };
+        let err = run(early).unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        run(PatchCommand::Test { id: id("1:0") }).unwrap();
+        let err = run(PatchCommand::Audit {
+            id: id("1:0"),
+            policies: Vec::new(),
+        })
+        .unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        let log = load_run(&store, 1).unwrap();
+        assert!(matches!(
+            &log.last().unwrap().payload,
+            EventPayload::PatchRejected(p) if p.stage == "audit"
+        ));
+
+        let mut policy = Policy::new("patches", "1.0.0");
+        policy.add_rule(Rule {
+            name: "allow_patches".to_string(),
+            kind: RuleKind::Patch,
+            condition: Condition::True,
+            action: Action::Allow,
+        });
+        let policies = vec![write(
+            "patches.policy.json",
+            serde_json::to_string(&policy).unwrap(),
+        )];
+        run(PatchCommand::Test { id: id("1:1") }).unwrap();
+        run(PatchCommand::Audit {
+            id: id("1:1"),
+            policies,
+        })
+        .unwrap();
+
+        // Only a key in the trust store approves, and apply checks it again
+        let stranger = write("stranger.key", "08".repeat(32));
+        assert_eq!(run(approve(&key)).unwrap_err().exit_code(), 4);
+        write("trusted_approvers", format!("# approvers\n{}\n", trusted));
+        assert_eq!(run(approve(&stranger)).unwrap_err().exit_code(), 4);
+        run(approve(&key)).unwrap();
+        let apply = || PatchCommand::Apply { id: id("1:1") };
+        write("trusted_approvers", String::new());
+        assert_eq!(run(apply()).unwrap_err().exit_code(), 4);
+        write("trusted_approvers", trusted);
+        run(apply()).unwrap();
+        let log = load_run(&store, 1).unwrap();
+        assert_eq!(log.last().unwrap().kind, EventKind::PatchApplied);
+
+        // The patched state is kept, and rollback restores it
+        let patches = PatchStore::load(patches_path(&cli.data_dir)).unwrap();
+        let applied = patches.applied("1:1").unwrap().clone();
+        let state = patches.state(1).unwrap();
+        assert_eq!(state.hash(), applied.after_hash);
+        assert!(state.get("config.max_iterations").is_some());
+
+        run(PatchCommand::Rollback {
+            id: id("1:1"),
+            reason: id("regressed"),
+        })
+        .unwrap();
+        let patches = PatchStore::load(patches_path(&cli.data_dir)).unwrap();
+        let state = patches.state(1).unwrap();
+        assert_eq!(state.hash(), applied.before_hash);
+        assert!(state.get("config.max_iterations").is_none());
+        let log = load_run(&store, 1).unwrap();
+        assert!(matches!(
+            &log.last().unwrap().payload,
+            EventPayload::PatchRejected(p) if p.stage == "rollback" && p.reason == "regressed"
+        ));
+        let kinds: Vec<&str> = log.events().iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds.iter().filter(|k| **k == "patch_proposal").count(), 2);
+        assert_eq!(kinds.iter().filter(|k| **k == "patch_tested").count(), 2);
+        assert_eq!(kinds.iter().filter(|k| **k == "patch_audited").count(), 1);
+        assert_eq!(kinds.iter().filter(|k| **k == "patch_approved").count(), 1);
+        assert_eq!(
+            run(PatchCommand::Show { id: id("9:9") })
+                .unwrap_err()
+                .exit_code(),
+            3
+        );
+    }
+}
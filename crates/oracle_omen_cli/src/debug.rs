@@ -0,0 +1,438 @@
+//! Interactive stepping through a persisted run.
+//!
+//! The position is the number of events applied; the current event is the
+//! last one applied.
+
+use oracle_omen_core::{
+    event::{Event, EventLog},
+    replay::{ReplayEngine, SnapshotManager},
+    state::StateData,
+};
+
+use crate::trace::{summarize, tool_name};
+
+const HELP: &str = "\
+next [n]            apply the next n events (alias n)
+back [n]            go back n events, restoring from a snapshot (alias b)
+goto <seq>          make event <seq> the current event (alias g)
+continue            run until a breakpoint or the end (alias c)
+break kind <kind>   stop at events of a kind, e.g. tool_request
+break tool <name>   stop at events about a tool
+break key <domain>  stop when a state domain changes
+delete <n>          remove breakpoint n
+breakpoints         list breakpoints
+state [domain]      print state domains at the current position (alias s)
+event               print the current event (alias e)
+parents             print the causal parents of the current event (alias p)
+quit                leave the debugger (alias q)";
+
+/// Condition that stops `next` and `continue`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// An event of this kind, e.g. `tool_request`
+    Kind(String),
+
+    /// An event about this tool
+    Tool(String),
+
+    /// A change to this state domain
+    StateKey(String),
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Kind(kind) => write!(f, "kind {}", kind),
+            Breakpoint::Tool(tool) => write!(f, "tool {}", tool),
+            Breakpoint::StateKey(key) => write!(f, "key {}", key),
+        }
+    }
+}
+
+/// A debugger command
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Apply up to n events
+    Next(u64),
+
+    /// Go back n events
+    Back(u64),
+
+    /// Make the event with this sequence current
+    Goto(u64),
+
+    /// Run until a breakpoint or the end
+    Continue,
+
+    /// Add a breakpoint
+    Break(Breakpoint),
+
+    /// Remove a breakpoint by its number
+    Delete(usize),
+
+    /// List breakpoints
+    Breakpoints,
+
+    /// Print one state domain, or all of them
+    State(Option<String>),
+
+    /// Print the current event
+    Event,
+
+    /// Print the causal parents of the current event
+    Parents,
+
+    /// Print the command list
+    Help,
+
+    /// Leave the debugger
+    Quit,
+}
+
+impl DebugCommand {
+    /// Parse a command line
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |word: Option<&&str>, default: Option<u64>| match word {
+            Some(word) => word
+                .parse::<u64>()
+                .map_err(|_| format!("expected a number, got '{}'", word)),
+            None => default.ok_or_else(|| "expected a number".to_string()),
+        };
+        let command = match words.as_slice() {
+            ["next" | "n", rest @ ..] if rest.len() <= 1 => {
+                DebugCommand::Next(number(rest.first(), Some(1))?)
+            }
+            ["back" | "b", rest @ ..] if rest.len() <= 1 => {
+                DebugCommand::Back(number(rest.first(), Some(1))?)
+            }
+            ["goto" | "g", seq] => DebugCommand::Goto(number(Some(seq), None)?),
+            ["continue" | "c"] => DebugCommand::Continue,
+            ["break", "kind", kind] => DebugCommand::Break(Breakpoint::Kind(kind.to_string())),
+            ["break", "tool", tool] => DebugCommand::Break(Breakpoint::Tool(tool.to_string())),
+            ["break", "key", key] => DebugCommand::Break(Breakpoint::StateKey(key.to_string())),
+            ["delete", n] => DebugCommand::Delete(number(Some(n), None)? as usize),
+            ["breakpoints"] => DebugCommand::Breakpoints,
+            ["state" | "s"] => DebugCommand::State(None),
+            ["state" | "s", domain] => DebugCommand::State(Some(domain.to_string())),
+            ["event" | "e"] => DebugCommand::Event,
+            ["parents" | "p"] => DebugCommand::Parents,
+            ["help" | "h" | "?"] => DebugCommand::Help,
+            ["quit" | "q" | "exit"] => DebugCommand::Quit,
+            _ => return Err(format!("unknown command '{}', try help", line.trim())),
+        };
+        Ok(command)
+    }
+}
+
+/// Stepping state over one run
+pub struct Debugger {
+    log: EventLog,
+    snapshots: SnapshotManager,
+    engine: ReplayEngine,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    /// Start at position 0, before the first event
+    pub fn new(log: EventLog, snapshots: SnapshotManager) -> Self {
+        let engine = ReplayEngine::new(log.clone());
+        Self {
+            log,
+            snapshots,
+            engine,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Number of events applied
+    pub fn position(&self) -> u64 {
+        self.engine.position()
+    }
+
+    /// Last event applied
+    pub fn current(&self) -> Option<&Event> {
+        let position = self.position().checked_sub(1)?;
+        self.log.get_by_sequence(position)
+    }
+
+    /// Prompt showing the position and current event
+    pub fn prompt(&self) -> String {
+        let current = self
+            .current()
+            .map_or("start".to_string(), |e| format!("{} {}", e.id, e.kind));
+        format!("[{}/{} {}]>", self.position(), self.log.len(), current)
+    }
+
+    /// Run a command and return its output
+    pub fn execute(&mut self, command: &DebugCommand) -> String {
+        match command {
+            DebugCommand::Next(n) => self.advance(Some(*n)),
+            DebugCommand::Continue => self.advance(None),
+            DebugCommand::Back(n) => {
+                let note = self.seek(self.position().saturating_sub(*n));
+                format!("{}{}", note, self.describe())
+            }
+            DebugCommand::Goto(seq) => {
+                if *seq >= self.log.len() as u64 {
+                    return format!("No event {} (run has {} events)", seq, self.log.len());
+                }
+                let note = self.seek(seq + 1);
+                format!("{}{}", note, self.describe())
+            }
+            DebugCommand::Break(breakpoint) => {
+                self.breakpoints.push(breakpoint.clone());
+                format!("Breakpoint {}: {}", self.breakpoints.len(), breakpoint)
+            }
+            DebugCommand::Delete(n) => {
+                if *n == 0 || *n > self.breakpoints.len() {
+                    return format!("No breakpoint {}", n);
+                }
+                format!("Deleted breakpoint {}", self.breakpoints.remove(n - 1))
+            }
+            DebugCommand::Breakpoints if self.breakpoints.is_empty() => {
+                "No breakpoints".to_string()
+            }
+            DebugCommand::Breakpoints => self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(i, b)| format!("{}: {}", i + 1, b))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            DebugCommand::State(domain) => self.state(domain.as_deref()),
+            DebugCommand::Event => self.describe(),
+            DebugCommand::Parents => self.parents(),
+            DebugCommand::Help => HELP.to_string(),
+            DebugCommand::Quit => String::new(),
+        }
+    }
+
+    /// Step until `limit` events are applied, a breakpoint hits, or the end
+    fn advance(&mut self, limit: Option<u64>) -> String {
+        let mut steps = 0;
+        while limit.map_or(true, |limit| steps < limit) {
+            let before: Vec<Option<StateData>> = self
+                .breakpoints
+                .iter()
+                .map(|b| match b {
+                    Breakpoint::StateKey(key) => self.engine.current_state().get(key).cloned(),
+                    _ => None,
+                })
+                .collect();
+            let Some(event) = self.engine.step() else {
+                return format!("End of run\n{}", self.describe());
+            };
+            steps += 1;
+
+            let hit = self
+                .breakpoints
+                .iter()
+                .zip(before)
+                .position(|(b, before)| match b {
+                    Breakpoint::Kind(kind) => event.kind.as_str() == kind,
+                    Breakpoint::Tool(tool) => tool_name(&event.payload) == Some(tool.as_str()),
+                    Breakpoint::StateKey(key) => {
+                        self.engine.current_state().get(key) != before.as_ref()
+                    }
+                });
+            if let Some(i) = hit {
+                return format!(
+                    "Breakpoint {} ({})\n{}",
+                    i + 1,
+                    self.breakpoints[i],
+                    self.describe()
+                );
+            }
+        }
+        self.describe()
+    }
+
+    /// Move to `target` applied events
+    ///
+    /// Going back restarts from the latest snapshot at or before the target,
+    /// or from the start of the run.
+    fn seek(&mut self, target: u64) -> String {
+        let mut note = String::new();
+        if target < self.position() {
+            let snapshot = self
+                .snapshots
+                .get_snapshot_before(target)
+                .filter(|s| s.verify());
+            self.engine = match snapshot {
+                Some(snapshot) => {
+                    note = format!(
+                        "Restored snapshot {} at {}\n",
+                        snapshot.id, snapshot.position
+                    );
+                    ReplayEngine::from_snapshot(self.log.clone(), snapshot)
+                }
+                None => ReplayEngine::new(self.log.clone()),
+            };
+        }
+        while self.position() < target && self.engine.step().is_some() {}
+        note
+    }
+
+    /// Current event on one line
+    fn describe(&self) -> String {
+        match self.current() {
+            Some(event) => format!("{} {} {}", event.id, event.kind, summarize(&event.payload)),
+            None => "At start of run, no events applied".to_string(),
+        }
+    }
+
+    fn state(&self, domain: Option<&str>) -> String {
+        let state = self.engine.current_state();
+        let show = |data: &StateData| serde_json::to_string(data).unwrap_or_default();
+        match domain {
+            Some(domain) => match state.get(domain) {
+                Some(data) => format!("{} = {}", domain, show(data)),
+                None => format!("No domain '{}'", domain),
+            },
+            None => {
+                let mut lines = vec![format!("state_hash: {}", state.hash())];
+                lines.extend(
+                    state
+                        .data
+                        .iter()
+                        .map(|(k, v)| format!("{} = {}", k, show(v))),
+                );
+                lines.join("\n")
+            }
+        }
+    }
+
+    /// Parent chain of the current event, nearest first
+    fn parents(&self) -> String {
+        let Some(event) = self.current() else {
+            return "No current event".to_string();
+        };
+        let mut lines = Vec::new();
+        let mut parent = event.parent_id;
+        while let Some(id) = parent {
+            let Some(event) = self.log.get(id) else {
+                lines.push(format!("{} (missing)", id));
+                break;
+            };
+            lines.push(format!(
+                "{} {} {}",
+                event.id,
+                event.kind,
+                summarize(&event.payload)
+            ));
+            parent = event.parent_id;
+        }
+        if lines.is_empty() {
+            format!("{} has no parent", event.id)
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::{
+        event::{EventKind, EventPayload, ObservationPayload, ToolRequestPayload},
+        hash::Hash,
+        replay::Snapshot,
+    };
+    use std::collections::BTreeMap;
+
+    /// observation -> request(echo) -> request(hash), then a second observation
+    fn sample_log() -> EventLog {
+        let mut log = EventLog::new(1);
+        let observe = |log: &mut EventLog| {
+            let payload = EventPayload::Observation(ObservationPayload {
+                obs_type: "ask".to_string(),
+                data: BTreeMap::new(),
+                source: "user".to_string(),
+            });
+            log.append_next(EventKind::Observation, payload, None)
+                .unwrap()
+        };
+        let mut parent = observe(&mut log);
+        for tool in ["echo", "hash"] {
+            let payload = EventPayload::ToolRequest(ToolRequestPayload {
+                tool_name: tool.to_string(),
+                tool_version: "1.0.0".to_string(),
+                request_hash: Hash::from_str(tool),
+                capabilities: Vec::new(),
+                input: "{}".to_string(),
+            });
+            parent = log
+                .append_next(EventKind::ToolRequest, payload, Some(parent))
+                .unwrap();
+        }
+        observe(&mut log);
+        log
+    }
+
+    fn run(debugger: &mut Debugger, line: &str) -> String {
+        debugger.execute(&DebugCommand::parse(line).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(DebugCommand::parse("n"), Ok(DebugCommand::Next(1)));
+        assert_eq!(DebugCommand::parse("back 3"), Ok(DebugCommand::Back(3)));
+        assert_eq!(
+            DebugCommand::parse("break tool echo"),
+            Ok(DebugCommand::Break(Breakpoint::Tool("echo".to_string())))
+        );
+        assert!(DebugCommand::parse("goto").is_err());
+        assert!(DebugCommand::parse("next x").is_err());
+        assert!(DebugCommand::parse("jump").is_err());
+    }
+
+    #[test]
+    fn test_stepping_and_breakpoints() {
+        let mut debugger = Debugger::new(sample_log(), SnapshotManager::new());
+        assert_eq!(debugger.prompt(), "[0/4 start]>");
+
+        assert!(run(&mut debugger, "next").starts_with("E(1:0) observation"));
+        run(&mut debugger, "break tool hash");
+        let out = run(&mut debugger, "continue");
+        assert!(out.starts_with("Breakpoint 1 (tool hash)"));
+        assert_eq!(debugger.position(), 3);
+
+        let parents = run(&mut debugger, "parents");
+        assert_eq!(parents.lines().count(), 2);
+        assert!(parents.starts_with("E(1:1) tool_request echo@1.0.0"));
+
+        run(&mut debugger, "delete 1");
+        run(&mut debugger, "break key event_3");
+        run(&mut debugger, "goto 0");
+        assert!(run(&mut debugger, "c").starts_with("Breakpoint 1 (key event_3)"));
+        assert!(run(&mut debugger, "c").starts_with("End of run"));
+        assert_eq!(
+            run(&mut debugger, "goto 9"),
+            "No event 9 (run has 4 events)"
+        );
+    }
+
+    #[test]
+    fn test_back_uses_snapshots() {
+        let log = sample_log();
+        let mut engine = ReplayEngine::new(log.clone());
+        engine.step();
+        engine.step();
+        let mut snapshots = SnapshotManager::new();
+        snapshots.add(Snapshot::new("s2", 1, 2, engine.current_state().clone()));
+
+        let mut debugger = Debugger::new(log, snapshots);
+        run(&mut debugger, "next 4");
+        let full = run(&mut debugger, "state");
+        let out = run(&mut debugger, "back");
+        assert!(out.starts_with("Restored snapshot s2 at 2\nE(1:2)"));
+        assert_eq!(debugger.position(), 3);
+        assert_eq!(run(&mut debugger, "state event_3"), "No domain 'event_3'");
+        assert!(!run(&mut debugger, "b 3").contains("Restored"));
+        assert_eq!(debugger.position(), 0);
+
+        run(&mut debugger, "goto 3");
+        assert_eq!(run(&mut debugger, "state"), full);
+    }
+}
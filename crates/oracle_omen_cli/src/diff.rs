@@ -0,0 +1,198 @@
+//! Field-level comparison of events and states from two runs.
+
+use std::collections::BTreeSet;
+
+use oracle_omen_core::{
+    event::{Event, EventLog},
+    state::AgentState,
+};
+use serde_json::Value;
+
+use crate::output::Table;
+use crate::trace::summarize;
+
+/// A field that differs between two runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// Dotted path to the field
+    pub path: String,
+
+    /// Value in the first run
+    pub a: Option<Value>,
+
+    /// Value in the second run
+    pub b: Option<Value>,
+}
+
+impl std::fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |v: &Option<Value>| v.as_ref().map_or("<missing>".to_string(), Value::to_string);
+        write!(f, "{}: {} -> {}", self.path, show(&self.a), show(&self.b))
+    }
+}
+
+/// Fields that differ between the events at the same position
+///
+/// Event IDs and timestamps carry the run ID, so parents are compared by
+/// sequence and timestamps are skipped.
+pub fn event_diff(a: Option<&Event>, b: Option<&Event>) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+    let a = a.map(comparable);
+    let b = b.map(comparable);
+    diff_values(String::new(), a.as_ref(), b.as_ref(), &mut diffs);
+    diffs
+}
+
+/// Domains whose values differ between two states
+pub fn state_diff(a: &AgentState, b: &AgentState) -> Vec<FieldDiff> {
+    let domains: BTreeSet<&String> = a.data.keys().chain(b.data.keys()).collect();
+    domains
+        .into_iter()
+        .filter_map(|domain| {
+            let va = a
+                .data
+                .get(domain)
+                .and_then(|d| serde_json::to_value(d).ok());
+            let vb = b
+                .data
+                .get(domain)
+                .and_then(|d| serde_json::to_value(d).ok());
+            (va != vb).then(|| FieldDiff {
+                path: domain.clone(),
+                a: va,
+                b: vb,
+            })
+        })
+        .collect()
+}
+
+/// Events around `position` in both runs, divergent row marked with `>`
+pub fn context_table(a: &EventLog, b: &EventLog, position: u64, context: u64) -> String {
+    let headers = ["", "seq", "run a", "run b"];
+    let mut table = Table::new(headers.iter().map(|h| h.to_string()).collect());
+    let end = position.saturating_add(context);
+    for seq in position.saturating_sub(context)..=end {
+        let (ea, eb) = (a.get_by_sequence(seq), b.get_by_sequence(seq));
+        if ea.is_none() && eb.is_none() {
+            break;
+        }
+        let cell = |e: Option<&Event>| {
+            e.map_or("<none>".to_string(), |e| {
+                format!("{} {}", e.kind, summarize(&e.payload))
+            })
+        };
+        let diverged = ea.map(Event::content_hash) != eb.map(Event::content_hash);
+        table = table.row(vec![
+            if diverged { ">" } else { "" }.to_string(),
+            seq.to_string(),
+            cell(ea),
+            cell(eb),
+        ]);
+    }
+    table.format()
+}
+
+/// Run-independent view of an event
+fn comparable(event: &Event) -> Value {
+    serde_json::json!({
+        "kind": event.kind,
+        "parent": event.parent_id.map(|p| p.sequence),
+        "payload": event.payload,
+        "state_hash_before": event.state_hash_before,
+        "state_hash_after": event.state_hash_after,
+    })
+}
+
+fn diff_values(path: String, a: Option<&Value>, b: Option<&Value>, out: &mut Vec<FieldDiff>) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (a, b) {
+        (Some(Value::Object(ma)), Some(Value::Object(mb))) => {
+            let keys: BTreeSet<&String> = ma.keys().chain(mb.keys()).collect();
+            for key in keys {
+                diff_values(join(key), ma.get(key), mb.get(key), out);
+            }
+        }
+        (Some(Value::Array(va)), Some(Value::Array(vb))) => {
+            for i in 0..va.len().max(vb.len()) {
+                diff_values(format!("{}[{}]", path, i), va.get(i), vb.get(i), out);
+            }
+        }
+        (a, b) if a != b => out.push(FieldDiff {
+            path,
+            a: a.cloned(),
+            b: b.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::{
+        event::{EventKind, EventPayload, ObservationPayload},
+        state::{StateData, StateValue},
+    };
+
+    fn observation_log(run_id: u64, source: &str) -> EventLog {
+        let mut log = EventLog::new(run_id);
+        for obs_type in ["start", "ask"] {
+            let parent = log.last().map(|e| e.id);
+            log.append_next(
+                EventKind::Observation,
+                EventPayload::Observation(ObservationPayload {
+                    obs_type: obs_type.to_string(),
+                    data: Default::default(),
+                    source: source.to_string(),
+                }),
+                parent,
+            )
+            .unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn test_event_diff() {
+        let (a, b) = (observation_log(1, "user"), observation_log(2, "user"));
+        assert!(event_diff(a.get_by_sequence(1), b.get_by_sequence(1)).is_empty());
+
+        let c = observation_log(3, "cron");
+        let diffs = event_diff(a.get_by_sequence(1), c.get_by_sequence(1));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "payload.Observation.source");
+        assert_eq!(
+            diffs[0].to_string(),
+            "payload.Observation.source: \"user\" -> \"cron\""
+        );
+
+        let missing = event_diff(a.get_by_sequence(1), None);
+        assert_eq!(missing[0].b, None);
+
+        let table = context_table(&a, &c, 1, 3);
+        assert!(table.contains("> | 1"));
+        assert!(table.contains("ask from cron"));
+    }
+
+    #[test]
+    fn test_state_diff() {
+        let mut a = AgentState::initial();
+        let mut b = AgentState::initial();
+        a.set("same", StateData::Value(StateValue::U64(1)));
+        b.set("same", StateData::Value(StateValue::U64(1)));
+        a.set("count", StateData::Value(StateValue::U64(1)));
+        b.set("count", StateData::Value(StateValue::U64(2)));
+        b.set("extra", StateData::Value(StateValue::U64(3)));
+
+        let diffs = state_diff(&a, &b);
+        let paths: Vec<&str> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["count", "extra"]);
+        assert_eq!(diffs[1].a, None);
+    }
+}
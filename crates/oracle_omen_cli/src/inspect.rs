@@ -0,0 +1,239 @@
+//! Run summaries for `oracle-omen inspect`.
+
+use std::collections::BTreeMap;
+
+use oracle_omen_core::event::{AgentInitPayload, EventLog, EventPayload};
+
+/// Tool call statistics
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ToolStats {
+    /// Requests logged
+    pub calls: usize,
+
+    /// Responses logged
+    pub responses: usize,
+
+    /// Successful responses
+    pub successes: usize,
+
+    /// Sum of response `duration_ms`
+    pub duration_ms: u64,
+}
+
+impl ToolStats {
+    /// Successful responses as a percentage of all responses
+    pub fn success_rate(&self) -> f64 {
+        if self.responses == 0 {
+            0.0
+        } else {
+            self.successes as f64 * 100.0 / self.responses as f64
+        }
+    }
+}
+
+/// A logged capability denial
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Denial {
+    /// Sequence of the denial event
+    pub sequence: u64,
+
+    /// Tool that was denied
+    pub tool: String,
+
+    /// Capability it lacked
+    pub capability: String,
+
+    /// Reason given
+    pub reason: String,
+}
+
+/// Summary of a run's event log
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inspection {
+    /// First `AgentInit` payload
+    pub agent: Option<AgentInitPayload>,
+
+    /// Events per kind
+    pub kinds: BTreeMap<String, usize>,
+
+    /// Statistics per tool name
+    pub tools: BTreeMap<String, ToolStats>,
+
+    /// Capability denials in log order
+    pub denials: Vec<Denial>,
+
+    /// Patches proposed
+    pub patches_proposed: usize,
+
+    /// Patches applied
+    pub patches_applied: usize,
+
+    /// Patches rejected at a gate
+    pub patches_rejected: usize,
+
+    /// Applied patches rolled back
+    pub patches_rolled_back: usize,
+
+    /// Sequences of `Snapshot` events
+    pub snapshot_events: Vec<u64>,
+}
+
+impl Inspection {
+    /// Summarize a log
+    pub fn of(log: &EventLog) -> Self {
+        let mut inspection = Self::default();
+        for event in log.events() {
+            *inspection
+                .kinds
+                .entry(event.kind.as_str().to_string())
+                .or_default() += 1;
+            match &event.payload {
+                EventPayload::AgentInit(p) if inspection.agent.is_none() => {
+                    inspection.agent = Some(p.clone());
+                }
+                EventPayload::ToolRequest(p) => inspection.tool(&p.tool_name).calls += 1,
+                EventPayload::ToolResponse(p) => {
+                    let stats = inspection.tool(&p.tool_name);
+                    stats.responses += 1;
+                    stats.successes += usize::from(p.success);
+                    stats.duration_ms += p.duration_ms;
+                }
+                EventPayload::CapabilityDenied(p) => inspection.denials.push(Denial {
+                    sequence: event.id.sequence,
+                    tool: p.tool_name.clone(),
+                    capability: p.capability.to_string(),
+                    reason: p.reason.clone(),
+                }),
+                EventPayload::PatchProposal(_) => inspection.patches_proposed += 1,
+                EventPayload::PatchApplied(_) => inspection.patches_applied += 1,
+                EventPayload::PatchRejected(p) if p.stage == "rollback" => {
+                    inspection.patches_rolled_back += 1
+                }
+                EventPayload::PatchRejected(_) => inspection.patches_rejected += 1,
+                EventPayload::Snapshot(_) => inspection.snapshot_events.push(event.id.sequence),
+                _ => {}
+            }
+        }
+        inspection
+    }
+
+    fn tool(&mut self, name: &str) -> &mut ToolStats {
+        self.tools.entry(name.to_string()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::{
+        capability::Capability,
+        event::{
+            CapabilityDeniedPayload, EventKind, PatchPayload, PatchRejectedPayload,
+            ToolRequestPayload, ToolResponsePayload,
+        },
+        hash::Hash,
+        tool::ResponseSource,
+    };
+
+    fn sample_log() -> EventLog {
+        let mut log = EventLog::new(1);
+        let mut append = |kind, payload| {
+            let parent = log.last().map(|e| e.id);
+            log.append_next(kind, payload, parent).unwrap();
+        };
+        append(
+            EventKind::AgentInit,
+            EventPayload::AgentInit(AgentInitPayload {
+                agent_type: "echo_agent".to_string(),
+                agent_version: "1.0.0".to_string(),
+                config: BTreeMap::new(),
+            }),
+        );
+        for (success, duration_ms) in [(true, 5), (false, 7)] {
+            append(
+                EventKind::ToolRequest,
+                EventPayload::ToolRequest(ToolRequestPayload {
+                    tool_name: "echo".to_string(),
+                    tool_version: "1.0.0".to_string(),
+                    request_hash: Hash::from_str("req"),
+                    capabilities: Vec::new(),
+                    input: "{}".to_string(),
+                }),
+            );
+            append(
+                EventKind::ToolResponse,
+                EventPayload::ToolResponse(ToolResponsePayload {
+                    tool_name: "echo".to_string(),
+                    request_hash: Hash::from_str("req"),
+                    response_hash: Hash::from_str("out"),
+                    output: "{}".to_string(),
+                    success,
+                    error: (!success).then(|| "boom".to_string()),
+                    duration_ms,
+                    source: ResponseSource::Tool,
+                    raw_hash: None,
+                }),
+            );
+        }
+        append(
+            EventKind::CapabilityDenied,
+            EventPayload::CapabilityDenied(CapabilityDeniedPayload {
+                capability: Capability::new("fs:write:/etc"),
+                tool_name: "fs".to_string(),
+                reason: "not granted".to_string(),
+            }),
+        );
+        append(
+            EventKind::PatchProposal,
+            EventPayload::PatchProposal(PatchPayload {
+                patch_type: "prompt".to_string(),
+                target: "system".to_string(),
+                patch_hash: Hash::from_str("patch"),
+                reasoning: "shorter".to_string(),
+            }),
+        );
+        append(
+            EventKind::PatchRejected,
+            EventPayload::PatchRejected(PatchRejectedPayload {
+                patch_hash: Hash::from_str("patch"),
+                reason: "tests failed".to_string(),
+                stage: "test".to_string(),
+            }),
+        );
+        append(
+            EventKind::PatchRejected,
+            EventPayload::PatchRejected(PatchRejectedPayload {
+                patch_hash: Hash::from_str("applied"),
+                reason: "raised error rate".to_string(),
+                stage: "rollback".to_string(),
+            }),
+        );
+        log
+    }
+
+    #[test]
+    fn test_inspection() {
+        let inspection = Inspection::of(&sample_log());
+        assert_eq!(inspection.agent.unwrap().agent_type, "echo_agent");
+        assert_eq!(inspection.kinds["tool_request"], 2);
+        assert_eq!(inspection.kinds["agent_init"], 1);
+
+        let echo = &inspection.tools["echo"];
+        assert_eq!((echo.calls, echo.responses, echo.successes), (2, 2, 1));
+        assert_eq!(echo.duration_ms, 12);
+        assert_eq!(echo.success_rate(), 50.0);
+
+        assert_eq!(inspection.denials.len(), 1);
+        assert_eq!(inspection.denials[0].capability, "fs:write:/etc");
+        assert_eq!(inspection.denials[0].sequence, 5);
+        assert_eq!(
+            (
+                inspection.patches_proposed,
+                inspection.patches_applied,
+                inspection.patches_rejected,
+                inspection.patches_rolled_back
+            ),
+            (1, 0, 1, 1)
+        );
+    }
+}
@@ -5,8 +5,20 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+mod bundle;
 mod commands;
+mod debug;
+mod diff;
+mod inspect;
 mod output;
+mod patch;
+mod trace;
 
+pub use bundle::*;
 pub use commands::*;
+pub use debug::*;
+pub use diff::*;
+pub use inspect::*;
 pub use output::*;
+pub use patch::*;
+pub use trace::*;
@@ -5,10 +5,16 @@
 use clap::Parser;
 use std::process::ExitCode;
 
+mod bundle;
 mod commands;
+mod debug;
+mod diff;
+mod inspect;
 mod output;
+mod patch;
+mod trace;
 
-use commands::{Cli, CliError};
+use commands::Cli;
 
 fn main() -> ExitCode {
     let cli = Cli::parse();
@@ -16,7 +22,7 @@ fn main() -> ExitCode {
         Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("Error: {}", e);
-            ExitCode::FAILURE
+            ExitCode::from(e.exit_code())
         }
     }
 }
@@ -0,0 +1,253 @@
+//! Patch subcommands and their event payloads.
+
+use std::path::{Path, PathBuf};
+
+use oracle_omen_core::{
+    event::{PatchPayload, PatchRejectedPayload},
+    replay::VerificationReport,
+    serde_utils::StableMap,
+};
+use oracle_omen_patches::{
+    gate::{GateResult, TestResult, TestRunner},
+    patch::{Patch, PatchTarget, PatchType, TestRequirement, TestType},
+};
+
+/// Event kind logged when a patch passes the test gate
+pub const PATCH_TESTED: &str = "patch_tested";
+
+/// Event kind logged when a patch passes the audit gate
+pub const PATCH_AUDITED: &str = "patch_audited";
+
+/// Event kind logged when a patch is approved
+pub const PATCH_APPROVED: &str = "patch_approved";
+
+/// Patch subcommands
+#[derive(Debug, clap::Subcommand)]
+pub enum PatchCommand {
+    /// List stored patches
+    List,
+
+    /// Show a patch, its status and approval
+    Show {
+        /// Patch ID, e.g. `1:0`
+        id: String,
+    },
+
+    /// Submit a patch from a JSON file
+    Propose {
+        /// Patch JSON file
+        file: PathBuf,
+    },
+
+    /// Run the test gate
+    Test {
+        /// Patch ID
+        id: String,
+    },
+
+    /// Run the audit gate
+    Audit {
+        /// Patch ID
+        id: String,
+
+        /// Policy JSON file with patch rules (repeatable)
+        #[arg(long = "policy")]
+        policies: Vec<PathBuf>,
+    },
+
+    /// Sign an audited patch with a local key
+    ///
+    /// The key must be listed in the data directory's `trusted_approvers`.
+    Approve {
+        /// Patch ID
+        id: String,
+
+        /// Key file holding a hex-encoded Ed25519 secret key
+        #[arg(long)]
+        key: PathBuf,
+    },
+
+    /// Apply an approved patch
+    ///
+    /// The approval must still be from a key in `trusted_approvers`.
+    Apply {
+        /// Patch ID
+        id: String,
+    },
+
+    /// Roll back an applied patch
+    Rollback {
+        /// Patch ID
+        id: String,
+
+        /// Reason recorded in the run log
+        #[arg(long, default_value = "manual rollback")]
+        reason: String,
+    },
+}
+
+/// Path of the patch store under a data directory
+pub fn patches_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("patches.json")
+}
+
+/// Path of the trusted approver keys under a data directory
+///
+/// One hex public key per line. Patch commands only read it, so approving
+/// or applying a patch cannot widen who is trusted.
+pub fn trusted_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("trusted_approvers")
+}
+
+/// Test runner that checks replay tests against the owning run
+///
+/// Other test types cannot run from the CLI, so the test gate defers
+/// patches that require them.
+pub struct RunTestRunner {
+    /// Verification of the owning run's log
+    pub report: VerificationReport,
+}
+
+impl TestRunner for RunTestRunner {
+    fn run_test(&self, _patch: &Patch, test: &TestRequirement) -> TestResult {
+        match test.test_type {
+            TestType::Replay => TestResult {
+                passed: self.report.is_valid(),
+                reason: self.report.to_string(),
+                duration_ms: 0,
+            },
+            _ => TestResult {
+                passed: false,
+                reason: format!("{:?} tests cannot run here", test.test_type),
+                duration_ms: 0,
+            },
+        }
+    }
+
+    fn supports(&self, test_type: &TestType) -> bool {
+        *test_type == TestType::Replay
+    }
+}
+
+/// Reason a gate did not pass
+pub fn gate_reason(result: &GateResult) -> Option<&str> {
+    match result {
+        GateResult::Passed => None,
+        GateResult::Failed { reason, .. } | GateResult::Deferred { reason, .. } => Some(reason),
+    }
+}
+
+/// Event payload describing a patch
+pub fn patch_payload(patch: &Patch) -> PatchPayload {
+    PatchPayload {
+        patch_type: type_name(&patch.patch_type),
+        target: target_name(&patch.target),
+        patch_hash: patch.hash(),
+        reasoning: patch.reasoning.clone(),
+    }
+}
+
+/// Event payload for a patch rejected at `stage`
+pub fn rejected_payload(patch: &Patch, stage: &str, reason: &str) -> PatchRejectedPayload {
+    PatchRejectedPayload {
+        patch_hash: patch.hash(),
+        reason: reason.to_string(),
+        stage: stage.to_string(),
+    }
+}
+
+/// Event data for a patch that passed `stage`
+pub fn passed_data(patch: &Patch, stage: &str) -> StableMap<String, String> {
+    let mut data = StableMap::new();
+    data.insert("patch_id".to_string(), patch.id.to_string());
+    data.insert("patch_hash".to_string(), patch.hash().to_hex());
+    data.insert("stage".to_string(), stage.to_string());
+    data
+}
+
+/// Name of a patch type as logged by agents
+pub fn type_name(patch_type: &PatchType) -> String {
+    match patch_type {
+        PatchType::Prompt => "prompt".to_string(),
+        PatchType::Policy => "policy".to_string(),
+        PatchType::Routing => "routing".to_string(),
+        PatchType::Config => "config".to_string(),
+        PatchType::Tools => "tools".to_string(),
+        PatchType::MemorySchema => "memory_schema".to_string(),
+        PatchType::Planning => "planning".to_string(),
+        PatchType::Custom(name) => name.clone(),
+    }
+}
+
+/// Name of a patch target, e.g. `config:max_iterations`
+pub fn target_name(target: &PatchTarget) -> String {
+    match target {
+        PatchTarget::SystemPrompt => "system_prompt".to_string(),
+        PatchTarget::Policy(name) => format!("policy:{}", name),
+        PatchTarget::Route(name) => format!("route:{}", name),
+        PatchTarget::Config(key) => format!("config:{}", key),
+        PatchTarget::Tool(name) => format!("tool:{}", name),
+        PatchTarget::MemorySchema(key) => format!("memory_schema:{}", key),
+        PatchTarget::Custom(name) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_patches::{
+        gate::TestGate,
+        patch::{PatchId, TestOutcome},
+    };
+
+    #[test]
+    fn test_run_test_runner() {
+        let patch = Patch::new(
+            PatchId::new(1, 0),
+            PatchType::Config,
+            PatchTarget::Config("max_iterations".to_string()),
+            "raise limit".to_string(),
+        )
+        .with_test(TestRequirement {
+            name: "replay".to_string(),
+            test_type: TestType::Replay,
+            expected: TestOutcome::Pass,
+        });
+
+        let valid = RunTestRunner {
+            report: VerificationReport {
+                total_events: 3,
+                verified_events: 3,
+                hash_failures: 0,
+                state_mismatches: 0,
+            },
+        };
+        assert_eq!(gate_reason(&TestGate::evaluate(&patch, &valid)), None);
+
+        let broken = RunTestRunner {
+            report: VerificationReport {
+                state_mismatches: 1,
+                ..valid.report
+            },
+        };
+        let result = TestGate::evaluate(&patch, &broken);
+        assert!(gate_reason(&result).is_some_and(|r| r.starts_with("Test 'replay' failed")));
+
+        for test_type in [TestType::Determinism, TestType::Unit] {
+            let patch = patch.clone().with_test(TestRequirement {
+                name: "other".to_string(),
+                test_type,
+                expected: TestOutcome::Fail,
+            });
+            let result = TestGate::evaluate(&patch, &valid);
+            assert!(!result.is_passed());
+            assert!(matches!(result, GateResult::Deferred { needs, .. } if needs == ["other"]));
+        }
+
+        let payload = patch_payload(&patch);
+        assert_eq!(
+            (payload.patch_type.as_str(), payload.target.as_str()),
+            ("config", "config:max_iterations")
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Event trace filtering and rendering.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use oracle_omen_core::{
+    event::{Event, EventLog, EventPayload},
+    hash::Hash,
+};
+
+use crate::output::Table;
+
+/// Trace output format
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TraceFormat {
+    /// Aligned table
+    #[default]
+    Table,
+
+    /// One JSON array
+    Json,
+
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// Which events a trace shows
+#[derive(Clone, Debug, Default, PartialEq, Eq, clap::Args)]
+pub struct TraceFilter {
+    /// Only events of this kind, e.g. `tool_request` (repeatable)
+    #[arg(long = "kind")]
+    pub kinds: Vec<String>,
+
+    /// Only events about this tool
+    #[arg(long)]
+    pub tool: Option<String>,
+
+    /// First sequence number to show
+    #[arg(long)]
+    pub from: Option<u64>,
+
+    /// Last sequence number to show
+    #[arg(long)]
+    pub to: Option<u64>,
+
+    /// Only this event and the events caused by it
+    #[arg(long)]
+    pub ancestor: Option<u64>,
+}
+
+impl TraceFilter {
+    /// Events in `log` that pass the filter, in log order
+    pub fn apply<'a>(&self, log: &'a EventLog) -> Vec<&'a Event> {
+        let descendants = self.ancestor.map(|root| descendants(log, root));
+        log.events()
+            .iter()
+            .filter(|e| self.from.map_or(true, |from| e.id.sequence >= from))
+            .filter(|e| self.to.map_or(true, |to| e.id.sequence <= to))
+            .filter(|e| self.kinds.is_empty() || self.kinds.iter().any(|k| k == e.kind.as_str()))
+            .filter(|e| {
+                self.tool
+                    .as_deref()
+                    .map_or(true, |tool| tool_name(&e.payload) == Some(tool))
+            })
+            .filter(|e| {
+                descendants
+                    .as_ref()
+                    .map_or(true, |set| set.contains(&e.id.sequence))
+            })
+            .collect()
+    }
+}
+
+/// Sequence numbers of `root` and every event whose parent chain reaches it
+fn descendants(log: &EventLog, root: u64) -> BTreeSet<u64> {
+    let mut set = BTreeSet::new();
+    for event in log.events() {
+        let caused = event
+            .parent_id
+            .is_some_and(|parent| set.contains(&parent.sequence));
+        if event.id.sequence == root || caused {
+            set.insert(event.id.sequence);
+        }
+    }
+    set
+}
+
+/// Tool an event refers to, if any
+pub fn tool_name(payload: &EventPayload) -> Option<&str> {
+    match payload {
+        EventPayload::ToolRequest(p) => Some(&p.tool_name),
+        EventPayload::ToolResponse(p) => Some(&p.tool_name),
+        EventPayload::CapabilityDenied(p) => Some(&p.tool_name),
+        EventPayload::Decision(p) => p.data.get("tool").and_then(|t| t.split('@').next()),
+        EventPayload::Error(p) => p.component.strip_prefix("tool:"),
+        _ => None,
+    }
+}
+
+/// One-line description of a payload
+pub fn summarize(payload: &EventPayload) -> String {
+    match payload {
+        EventPayload::AgentInit(p) => format!("{}@{}", p.agent_type, p.agent_version),
+        EventPayload::StateTransition(p) => format!(
+            "{} {} -> {}",
+            p.transition_type,
+            short(&p.from_hash),
+            short(&p.to_hash)
+        ),
+        EventPayload::ToolRequest(p) => {
+            format!(
+                "{}@{} {}",
+                p.tool_name,
+                p.tool_version,
+                short(&p.request_hash)
+            )
+        }
+        EventPayload::ToolResponse(p) => match &p.error {
+            Some(error) => format!("{} error: {}", p.tool_name, error),
+            None => format!(
+                "{} ok {} ({}ms)",
+                p.tool_name,
+                short(&p.response_hash),
+                p.duration_ms
+            ),
+        },
+        EventPayload::CapabilityDenied(p) => {
+            format!("{} denied {}: {}", p.tool_name, p.capability, p.reason)
+        }
+        EventPayload::Observation(p) => format!("{} from {}", p.obs_type, p.source),
+        EventPayload::Decision(p) if p.data.is_empty() => p.decision_type.clone(),
+        EventPayload::Decision(p) => format!("{} {}", p.decision_type, pairs(&p.data)),
+        EventPayload::MemoryWrite(p) | EventPayload::MemoryRead(p) => {
+            format!("{} {}", p.operation, p.key)
+        }
+        EventPayload::PatchProposal(p) | EventPayload::PatchApplied(p) => {
+            format!("{} {} {}", p.patch_type, p.target, short(&p.patch_hash))
+        }
+        EventPayload::PatchRejected(p) => format!("{}: {}", p.stage, p.reason),
+        EventPayload::Error(p) => format!("{} {}: {}", p.component, p.error_type, p.message),
+        EventPayload::Snapshot(p) => format!("{} at {}", p.snapshot_id, p.at_sequence),
+        EventPayload::Raw(data) => pairs(data),
+    }
+}
+
+fn pairs(data: &BTreeMap<String, String>) -> String {
+    data.iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// First 12 hex digits of a hash
+fn short(hash: &Hash) -> String {
+    hash.to_hex()[..12].to_string()
+}
+
+/// Depth of each shown event below its nearest shown ancestor
+pub fn depths(log: &EventLog, events: &[&Event]) -> BTreeMap<u64, usize> {
+    let mut depths = BTreeMap::new();
+    for event in events {
+        let mut parent = event.parent_id;
+        let mut depth = 0;
+        while let Some(id) = parent {
+            if let Some(d) = depths.get(&id.sequence) {
+                depth = d + 1;
+                break;
+            }
+            parent = log.get(id).and_then(|e| e.parent_id);
+        }
+        depths.insert(event.id.sequence, depth);
+    }
+    depths
+}
+
+/// JSON record for an event
+pub fn to_json(event: &Event, depth: Option<usize>) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "id": event.id.to_string(),
+        "sequence": event.id.sequence,
+        "parent": event.parent_id.map(|p| p.sequence),
+        "kind": event.kind.as_str(),
+        "logical_time": event.timestamp.sequence,
+        "event_hash": event.event_hash().to_hex(),
+        "payload_hash": event.payload_hash.to_hex(),
+        "state_hash_before": event.state_hash_before.map(|h| h.to_hex()),
+        "state_hash_after": event.state_hash_after.map(|h| h.to_hex()),
+        "summary": summarize(&event.payload),
+        "payload": event.payload,
+    });
+    if let Some(depth) = depth {
+        record["depth"] = depth.into();
+    }
+    record
+}
+
+/// Render events as a table, or as an indented tree when `depths` is set
+pub fn render_table(events: &[&Event], depths: Option<&BTreeMap<u64, usize>>) -> String {
+    let headers = ["id", "parent", "kind", "time", "hash", "state", "summary"];
+    let mut table = Table::new(headers.iter().map(|h| h.to_string()).collect());
+    for event in events {
+        let indent = depths
+            .and_then(|d| d.get(&event.id.sequence))
+            .map_or(String::new(), |depth| "  ".repeat(*depth));
+        let state = match (event.state_hash_before, event.state_hash_after) {
+            (Some(before), Some(after)) => format!("{} -> {}", short(&before), short(&after)),
+            _ => String::new(),
+        };
+        table = table.row(vec![
+            format!("{}{}", indent, event.id),
+            event.parent_id.map_or(String::new(), |p| p.to_string()),
+            event.kind.to_string(),
+            event.timestamp.to_string(),
+            short(&event.event_hash()),
+            state,
+            summarize(&event.payload),
+        ]);
+    }
+    table.format()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::{
+        event::{EventKind, ObservationPayload, ToolRequestPayload, ToolResponsePayload},
+        tool::ResponseSource,
+    };
+
+    /// observation -> request(echo) -> response(echo); observation -> request(hash)
+    fn sample_log() -> EventLog {
+        let mut log = EventLog::new(1);
+        for (tool, parent) in [("echo", None), ("hash", None)] {
+            let obs = log
+                .append_next(
+                    EventKind::Observation,
+                    EventPayload::Observation(ObservationPayload {
+                        obs_type: "ask".to_string(),
+                        data: BTreeMap::new(),
+                        source: "test".to_string(),
+                    }),
+                    parent,
+                )
+                .unwrap();
+            let request = log
+                .append_next(
+                    EventKind::ToolRequest,
+                    EventPayload::ToolRequest(ToolRequestPayload {
+                        tool_name: tool.to_string(),
+                        tool_version: "1.0.0".to_string(),
+                        request_hash: Hash::from_str(tool),
+                        capabilities: Vec::new(),
+                        input: "{}".to_string(),
+                    }),
+                    Some(obs),
+                )
+                .unwrap();
+            if tool == "echo" {
+                log.append_next(
+                    EventKind::ToolResponse,
+                    EventPayload::ToolResponse(ToolResponsePayload {
+                        tool_name: tool.to_string(),
+                        request_hash: Hash::from_str(tool),
+                        response_hash: Hash::from_str("out"),
+                        output: "{}".to_string(),
+                        success: true,
+                        error: None,
+                        duration_ms: 0,
+                        source: ResponseSource::Tool,
+                        raw_hash: None,
+                    }),
+                    Some(request),
+                )
+                .unwrap();
+            }
+        }
+        log
+    }
+
+    fn sequences(events: &[&Event]) -> Vec<u64> {
+        events.iter().map(|e| e.id.sequence).collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        let log = sample_log();
+        let all = TraceFilter::default();
+        assert_eq!(sequences(&all.apply(&log)), vec![0, 1, 2, 3, 4]);
+
+        let kinds = TraceFilter {
+            kinds: vec!["tool_request".to_string()],
+            ..TraceFilter::default()
+        };
+        assert_eq!(sequences(&kinds.apply(&log)), vec![1, 4]);
+
+        let tool = TraceFilter {
+            tool: Some("echo".to_string()),
+            ..TraceFilter::default()
+        };
+        assert_eq!(sequences(&tool.apply(&log)), vec![1, 2]);
+
+        let range = TraceFilter {
+            from: Some(2),
+            to: Some(3),
+            ..TraceFilter::default()
+        };
+        assert_eq!(sequences(&range.apply(&log)), vec![2, 3]);
+
+        let ancestor = TraceFilter {
+            ancestor: Some(1),
+            ..TraceFilter::default()
+        };
+        assert_eq!(sequences(&ancestor.apply(&log)), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_tree_and_json() {
+        let log = sample_log();
+        let events = TraceFilter::default().apply(&log);
+        let tree_depths = depths(&log, &events);
+        assert_eq!(
+            tree_depths.values().copied().collect::<Vec<_>>(),
+            vec![0, 1, 2, 0, 1]
+        );
+
+        let filtered = TraceFilter {
+            kinds: vec!["observation".to_string(), "tool_response".to_string()],
+            ..TraceFilter::default()
+        }
+        .apply(&log);
+        assert_eq!(depths(&log, &filtered)[&2], 1);
+
+        let tree = render_table(&events, Some(&tree_depths));
+        assert!(tree.contains("    E(1:2)"));
+        assert!(tree.contains("echo@1.0.0"));
+
+        let record = to_json(events[2], Some(2));
+        assert_eq!(record["kind"], "tool_response");
+        assert_eq!(record["parent"], 1);
+        assert_eq!(record["depth"], 2);
+    }
+}
@@ -47,6 +47,31 @@ impl Capability {
         }
         true
     }
+
+    /// Check if this grant covers a required capability
+    ///
+    /// A `*` counts only in the grant: it matches one segment, or every
+    /// remaining segment when it comes last. So `network:*` covers
+    /// `network:https:*`, but `network:https:*` does not cover `network:*`.
+    #[must_use]
+    pub fn covers(&self, required: &Capability) -> bool {
+        let grant: Vec<&str> = self.0.split(':').collect();
+        let wanted: Vec<&str> = required.0.split(':').collect();
+        let open = grant.last() == Some(&"*");
+        if wanted.len() < grant.len() || (!open && wanted.len() != grant.len()) {
+            return false;
+        }
+        grant.iter().zip(&wanted).all(|(g, w)| *g == "*" || g == w)
+    }
+
+    /// Scope of a `domain:action:scope` capability with the given prefix
+    ///
+    /// `Capability::new("fs:read:/tmp").scope("fs:read")` is `Some("/tmp")`.
+    /// The scope may itself contain `:`.
+    #[must_use]
+    pub fn scope(&self, prefix: &str) -> Option<&str> {
+        self.0.strip_prefix(prefix)?.strip_prefix(':')
+    }
 }
 
 impl fmt::Display for Capability {
@@ -99,9 +124,13 @@ impl CapabilitySet {
     }
 
     /// Check if a capability pattern is granted
+    ///
+    /// True if a granted capability covers the pattern. A `*` in the pattern
+    /// is literal, so only a grant at least as broad satisfies it.
     #[must_use]
     pub fn has_pattern(&self, pattern: &str) -> bool {
-        self.inner.iter().any(|c: &Capability| c.matches(pattern))
+        let required = Capability::new(pattern);
+        self.inner.iter().any(|c| c.covers(&required))
     }
 
     /// Check if any of the required capabilities are granted
@@ -191,6 +220,19 @@ mod tests {
         assert!(cap.matches("fs:read:/tmp"));
         assert!(cap.matches("fs:read:*"));
         assert!(!cap.matches("fs:write:*"));
+
+        let wildcard = Capability::new("fs:read:*");
+        assert!(!wildcard.matches("fs:read:/tmp"));
+        assert!(wildcard.covers(&cap));
+        assert!(!wildcard.covers(&Capability::new("fs:write:/tmp")));
+        assert!(!wildcard.covers(&Capability::new("fs:read")));
+
+        // A narrow grant never covers a broad requirement
+        let narrow = Capability::new("network:https:*");
+        assert!(!narrow.covers(&Capability::new("network:*")));
+        assert!(Capability::new("network:*").covers(&narrow));
+        assert_eq!(cap.scope("fs:read"), Some("/tmp"));
+        assert_eq!(cap.scope("fs:write"), None);
     }
 
     #[test]
@@ -211,5 +253,9 @@ mod tests {
         assert!(set.has_pattern("fs:read:*"));
         assert!(set.has_pattern("fs:read:/tmp"));
         assert!(!set.has_pattern("fs:write:*"));
+
+        let narrow = CapabilitySet::new([Capability::new("fs:read:/tmp")]);
+        assert!(narrow.has_pattern("fs:read:/tmp"));
+        assert!(!narrow.has_pattern("fs:read:*"));
     }
 }
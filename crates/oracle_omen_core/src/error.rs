@@ -141,40 +141,8 @@ impl fmt::Display for CapabilityError {
     }
 }
 
-/// Tool errors
-#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
-pub enum ToolError {
-    /// Tool not found
-    NotFound(String),
-    /// Timeout
-    Timeout { tool: String, duration_ms: u64 },
-    /// Execution failed
-    ExecutionFailed { tool: String, reason: String },
-    /// Invalid input
-    InvalidInput { tool: String, reason: String },
-    /// Output serialization failed
-    SerializationFailed { tool: String, reason: String },
-}
-
-impl fmt::Display for ToolError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ToolError::NotFound(name) => write!(f, "Tool not found: {}", name),
-            ToolError::Timeout { tool, duration_ms } => {
-                write!(f, "Tool {} timed out after {}ms", tool, duration_ms)
-            }
-            ToolError::ExecutionFailed { tool, reason } => {
-                write!(f, "Tool {} execution failed: {}", tool, reason)
-            }
-            ToolError::InvalidInput { tool, reason } => {
-                write!(f, "Tool {} invalid input: {}", tool, reason)
-            }
-            ToolError::SerializationFailed { tool, reason } => {
-                write!(f, "Tool {} serialization failed: {}", tool, reason)
-            }
-        }
-    }
-}
+/// Tool errors, shared with [`crate::tool`]
+pub use crate::tool::ToolError;
 
 /// Serialization errors
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
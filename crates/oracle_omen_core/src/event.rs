@@ -16,6 +16,7 @@ use crate::{
     hash::Hash,
     serde_utils::StableMap,
     time::LogicalTime,
+    tool::ResponseSource,
 };
 
 /// Unique event identifier
@@ -261,6 +262,22 @@ impl Event {
         Hash::from_canonical(self)
     }
 
+    /// Hash of the event without its run ID
+    ///
+    /// Covers the sequence, parent sequence, kind, payload hash and state
+    /// hashes, so matching events in two runs hash the same.
+    #[must_use]
+    pub fn content_hash(&self) -> Hash {
+        Hash::from_canonical(&(
+            self.id.sequence,
+            self.parent_id.map(|p| p.sequence),
+            &self.kind,
+            self.payload_hash,
+            self.state_hash_before,
+            self.state_hash_after,
+        ))
+    }
+
     /// Verify payload hash matches
     #[must_use]
     pub fn verify_payload_hash(&self) -> bool {
@@ -423,6 +440,14 @@ pub struct ToolResponsePayload {
 
     /// Duration in milliseconds
     pub duration_ms: u64,
+
+    /// What served the response
+    #[serde(default)]
+    pub source: ResponseSource,
+
+    /// Hash of the output before normalization, for live responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_hash: Option<Hash>,
 }
 
 /// Capability denied payload
@@ -609,6 +634,28 @@ impl EventLog {
         Ok(())
     }
 
+    /// ID the next appended event must carry
+    #[must_use]
+    pub fn next_id(&self) -> EventId {
+        EventId::new(self.run_id, self.events.len() as u64)
+    }
+
+    /// Build and append the next event in sequence
+    ///
+    /// The event timestamp is the logical time of its ID.
+    pub fn append_next(
+        &mut self,
+        kind: EventKind,
+        payload: EventPayload,
+        parent_id: Option<EventId>,
+    ) -> Result<EventId, EventLogError> {
+        let id = self.next_id();
+        let mut event = Event::new(id, kind, id.to_logical_time(), payload);
+        event.parent_id = parent_id;
+        self.append(event)?;
+        Ok(id)
+    }
+
     /// Get event by ID
     #[must_use]
     pub fn get(&self, id: EventId) -> Option<&Event> {
@@ -787,4 +834,28 @@ mod tests {
         assert!(log.append(child).is_ok());
         assert_eq!(log.len(), 2);
     }
+
+    #[test]
+    fn test_event_log_append_next() {
+        let mut log = EventLog::new(7);
+        let first = log
+            .append_next(
+                EventKind::Custom("node_started".to_string()),
+                EventPayload::Raw(StableMap::new()),
+                None,
+            )
+            .unwrap();
+        let second = log
+            .append_next(
+                EventKind::Custom("node_completed".to_string()),
+                EventPayload::Raw(StableMap::new()),
+                Some(first),
+            )
+            .unwrap();
+
+        assert_eq!(second, EventId::new(7, 1));
+        assert_eq!(log.next_id(), EventId::new(7, 2));
+        assert_eq!(log.get(second).unwrap().parent_id, Some(first));
+        assert_eq!(log.get(second).unwrap().timestamp, LogicalTime::new(7, 1));
+    }
 }
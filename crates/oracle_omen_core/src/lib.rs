@@ -5,6 +5,7 @@
 // - Stable hashing
 // - State machine definitions
 // - Capability types
+// - Tool schema validation, versioning and response normalization
 // - Error types
 
 #![warn(missing_docs)]
@@ -21,6 +22,9 @@ pub mod error;
 pub mod time;
 pub mod serde_utils;
 pub mod replay;
+pub mod schema;
+pub mod version;
+pub mod normalize;
 
 pub use event::*;
 pub use hash::*;
@@ -31,3 +35,6 @@ pub use error::*;
 pub use time::*;
 pub use serde_utils::*;
 pub use replay::*;
+pub use schema::*;
+pub use version::*;
+pub use normalize::*;
@@ -0,0 +1,352 @@
+//! Tool response normalization.
+//!
+//! Runs before a response is hashed, so responses that differ only in
+//! formatting or volatile fields hash the same. Steps, in order:
+//! 1. Normalize line endings to `\n` (opt-in, as it changes file contents
+//!    and bodies returned to callers)
+//! 2. Strip volatile fields (JSON only)
+//! 3. Truncate long strings
+//! 4. Write JSON canonically (sorted keys, no whitespace)
+//! 5. Truncate the whole output
+//!
+//! Output that is not JSON goes through steps 1, 3 and 5 as plain text.
+
+use std::collections::BTreeSet;
+use std::string::String;
+use std::vec::Vec;
+
+use serde_json::Value;
+
+use crate::hash::Hash;
+
+/// Result of normalizing one response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalized {
+    /// Normalized output
+    pub output: String,
+
+    /// Hash of the output before normalization
+    pub raw_hash: Hash,
+
+    /// Hash of the normalized output
+    pub normalized_hash: Hash,
+
+    /// JSON pointers of stripped fields, in document order
+    pub stripped: Vec<String>,
+
+    /// Whether anything was truncated
+    pub truncated: bool,
+}
+
+impl Normalized {
+    /// Check whether normalization changed the output
+    #[must_use]
+    pub fn changed(&self) -> bool {
+        self.raw_hash != self.normalized_hash
+    }
+}
+
+/// Configurable normalization stage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalizer {
+    /// Rewrite JSON with sorted keys and no whitespace
+    canonical_json: bool,
+
+    /// Rewrite `\r\n` and `\r` as `\n`
+    line_endings: bool,
+
+    /// Field names stripped at any depth, or JSON pointers if they start with `/`
+    volatile_fields: BTreeSet<String>,
+
+    /// Longest string kept, in characters
+    max_string_chars: Option<usize>,
+
+    /// Longest output kept, in bytes
+    max_bytes: Option<usize>,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self {
+            canonical_json: true,
+            line_endings: false,
+            volatile_fields: BTreeSet::new(),
+            max_string_chars: None,
+            max_bytes: None,
+        }
+    }
+}
+
+impl Normalizer {
+    /// Canonical JSON only, nothing stripped or truncated
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Normalizer that leaves output unchanged
+    #[must_use]
+    pub fn identity() -> Self {
+        Self {
+            canonical_json: false,
+            line_endings: false,
+            ..Self::default()
+        }
+    }
+
+    /// Strip a volatile field
+    ///
+    /// A name such as `timestamp` is stripped wherever it appears; a JSON
+    /// pointer such as `/meta/request_id` only at that path.
+    #[must_use]
+    pub fn with_volatile_field(mut self, field: impl Into<String>) -> Self {
+        self.volatile_fields.insert(field.into());
+        self
+    }
+
+    /// Truncate strings longer than `max` characters
+    #[must_use]
+    pub fn with_max_string_chars(mut self, max: usize) -> Self {
+        self.max_string_chars = Some(max);
+        self
+    }
+
+    /// Truncate output longer than `max` bytes
+    ///
+    /// Truncated JSON is no longer valid JSON; prefer string limits.
+    #[must_use]
+    pub fn with_max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = Some(max);
+        self
+    }
+
+    /// Enable or disable canonical JSON
+    #[must_use]
+    pub fn with_canonical_json(mut self, enabled: bool) -> Self {
+        self.canonical_json = enabled;
+        self
+    }
+
+    /// Enable or disable line ending normalization
+    #[must_use]
+    pub fn with_line_endings(mut self, enabled: bool) -> Self {
+        self.line_endings = enabled;
+        self
+    }
+
+    /// Hash of the configuration
+    ///
+    /// Outputs normalized under different configurations differ, so caches of
+    /// normalized output include this in their key.
+    #[must_use]
+    pub fn config_hash(&self) -> Hash {
+        Hash::from_canonical(&(
+            self.canonical_json,
+            self.line_endings,
+            &self.volatile_fields,
+            self.max_string_chars,
+            self.max_bytes,
+        ))
+    }
+
+    /// Normalize an output
+    #[must_use]
+    pub fn normalize(&self, raw: &str) -> Normalized {
+        let mut stripped = Vec::new();
+        let mut truncated = false;
+
+        let json = if self.needs_json() {
+            serde_json::from_str::<Value>(raw).ok()
+        } else {
+            None
+        };
+
+        let output = match json {
+            Some(value) => {
+                let mut normalized = value.clone();
+                self.normalize_value(&mut normalized, "", &mut stripped, &mut truncated);
+                if self.canonical_json || normalized != value {
+                    normalized.to_string()
+                } else {
+                    raw.to_string()
+                }
+            }
+            None => self.normalize_text(raw, &mut truncated),
+        };
+
+        let output = match self.max_bytes {
+            Some(max) if output.len() > max => {
+                truncated = true;
+                truncate_bytes(&output, max).to_string()
+            }
+            _ => output,
+        };
+
+        Normalized {
+            raw_hash: Hash::from_canonical(&raw),
+            normalized_hash: Hash::from_canonical(&output),
+            output,
+            stripped,
+            truncated,
+        }
+    }
+
+    /// Whether any step needs the output parsed as JSON
+    fn needs_json(&self) -> bool {
+        self.canonical_json
+            || self.line_endings
+            || !self.volatile_fields.is_empty()
+            || self.max_string_chars.is_some()
+    }
+
+    /// Normalize plain text
+    fn normalize_text(&self, text: &str, truncated: &mut bool) -> String {
+        let text = if self.line_endings {
+            normalize_line_endings(text)
+        } else {
+            text.to_string()
+        };
+        self.truncate_string(text, truncated)
+    }
+
+    /// Normalize a JSON value in place
+    fn normalize_value(
+        &self,
+        value: &mut Value,
+        path: &str,
+        stripped: &mut Vec<String>,
+        truncated: &mut bool,
+    ) {
+        match value {
+            Value::String(s) => {
+                let text = std::mem::take(s);
+                *s = self.normalize_text(&text, truncated);
+            }
+            Value::Array(items) => {
+                for (idx, item) in items.iter_mut().enumerate() {
+                    let item_path = format!("{}/{}", path, idx);
+                    self.normalize_value(item, &item_path, stripped, truncated);
+                }
+            }
+            Value::Object(obj) => {
+                let keys: Vec<String> = obj.keys().cloned().collect();
+                for key in keys {
+                    let field_path = format!("{}/{}", path, escape_pointer(&key));
+                    if self.is_volatile(&key, &field_path) {
+                        obj.remove(&key);
+                        stripped.push(field_path);
+                    } else if let Some(field) = obj.get_mut(&key) {
+                        self.normalize_value(field, &field_path, stripped, truncated);
+                    }
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+
+    /// Check whether a field is volatile
+    fn is_volatile(&self, key: &str, path: &str) -> bool {
+        self.volatile_fields.contains(key) || self.volatile_fields.contains(path)
+    }
+
+    /// Truncate a string to the character limit
+    fn truncate_string(&self, text: String, truncated: &mut bool) -> String {
+        match self.max_string_chars {
+            Some(max) if text.chars().count() > max => {
+                *truncated = true;
+                text.chars().take(max).collect()
+            }
+            _ => text,
+        }
+    }
+}
+
+/// Rewrite `\r\n` and lone `\r` as `\n`
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Cut a string to at most `max` bytes on a character boundary
+fn truncate_bytes(text: &str, max: usize) -> &str {
+    let mut end = max.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Escape a key for use in a JSON pointer
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_json() {
+        let normalizer = Normalizer::new();
+        let a = normalizer.normalize("{ \"b\": 1,\n  \"a\": [true, null] }");
+        let b = normalizer.normalize(r#"{"a":[true,null],"b":1}"#);
+        assert_eq!(a.output, r#"{"a":[true,null],"b":1}"#);
+        assert_eq!(a.normalized_hash, b.normalized_hash);
+        assert!(a.changed());
+        assert!(!b.changed());
+    }
+
+    #[test]
+    fn test_strip_volatile_fields() {
+        let normalizer = Normalizer::new()
+            .with_volatile_field("timestamp")
+            .with_volatile_field("/meta/request_id");
+        let out = normalizer.normalize(
+            r#"{"data": {"timestamp": 1, "v": 2}, "meta": {"request_id": "x", "timestamp": 3}, "request_id": "kept"}"#,
+        );
+        assert_eq!(
+            out.output,
+            r#"{"data":{"v":2},"meta":{},"request_id":"kept"}"#
+        );
+        assert_eq!(
+            out.stripped,
+            vec!["/data/timestamp", "/meta/request_id", "/meta/timestamp"]
+        );
+        assert_ne!(out.raw_hash, out.normalized_hash);
+    }
+
+    #[test]
+    fn test_config_hash() {
+        let base = Normalizer::new();
+        assert_eq!(base.config_hash(), Normalizer::default().config_hash());
+        assert_ne!(
+            base.config_hash(),
+            base.clone().with_volatile_field("timestamp").config_hash()
+        );
+        assert_ne!(base.config_hash(), Normalizer::identity().config_hash());
+    }
+
+    #[test]
+    fn test_line_endings_and_truncation() {
+        let out = Normalizer::new()
+            .with_line_endings(true)
+            .with_max_string_chars(5)
+            .normalize(r#"{"text": "ab\r\ncd\refgh"}"#);
+        assert_eq!(out.output, r#"{"text":"ab\ncd"}"#);
+        assert!(out.truncated);
+
+        let out = Normalizer::new()
+            .with_line_endings(true)
+            .with_max_bytes(4)
+            .normalize("añb\r\nc");
+        assert_eq!(out.output, "añb");
+        assert!(out.truncated);
+
+        let out = Normalizer::new().normalize(r#"{"text": "a\r\nb"}"#);
+        assert_eq!(out.output, r#"{"text":"a\r\nb"}"#);
+
+        let raw = "{ \"a\": 1 }";
+        let out = Normalizer::identity().normalize(raw);
+        assert_eq!(out.output, raw);
+        assert!(!out.changed());
+    }
+}
@@ -0,0 +1,526 @@
+//! JSON Schema validation for tool inputs and outputs.
+//!
+//! A deterministic subset of JSON Schema:
+//! - `type` (a name or a list of names)
+//! - `properties`, `required`, `additionalProperties` (boolean only)
+//! - `items` (a single schema)
+//! - `enum`
+//! - `minimum`, `maximum`
+//! - `minLength`, `maxLength`, `pattern`
+//! - `minItems`, `maxItems`
+//!
+//! The boolean schemas `true` and `false` accept and reject every value.
+//! Other keywords, such as `description`, are ignored. Object properties are
+//! checked in key order, so the first reported violation is stable.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use regex::Regex;
+use serde_json::Value;
+
+/// Schema errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    /// The schema itself is not valid
+    Invalid {
+        /// JSON pointer to the offending schema keyword
+        path: String,
+        /// What is wrong with the schema
+        message: String,
+    },
+
+    /// A value does not match the schema
+    Mismatch {
+        /// JSON pointer to the offending value
+        path: String,
+        /// Which constraint the value violates
+        message: String,
+    },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Invalid { path, message } => {
+                write!(f, "Invalid schema at {}: {}", display_path(path), message)
+            }
+            SchemaError::Mismatch { path, message } => {
+                write!(f, "Schema mismatch at {}: {}", display_path(path), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// JSON value types
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchemaType {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Boolean,
+    /// Number without a fractional part
+    Integer,
+    /// Any number
+    Number,
+    /// String
+    String,
+    /// Array
+    Array,
+    /// Object
+    Object,
+}
+
+impl SchemaType {
+    /// Parse a type name
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(SchemaType::Null),
+            "boolean" => Some(SchemaType::Boolean),
+            "integer" => Some(SchemaType::Integer),
+            "number" => Some(SchemaType::Number),
+            "string" => Some(SchemaType::String),
+            "array" => Some(SchemaType::Array),
+            "object" => Some(SchemaType::Object),
+            _ => None,
+        }
+    }
+
+    /// Get the type name
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaType::Null => "null",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Integer => "integer",
+            SchemaType::Number => "number",
+            SchemaType::String => "string",
+            SchemaType::Array => "array",
+            SchemaType::Object => "object",
+        }
+    }
+
+    /// Check whether a value has this type
+    #[must_use]
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (SchemaType::Null, Value::Null) => true,
+            (SchemaType::Boolean, Value::Bool(_)) => true,
+            (SchemaType::Integer, Value::Number(n)) => is_integer(n),
+            (SchemaType::Number, Value::Number(_)) => true,
+            (SchemaType::String, Value::String(_)) => true,
+            (SchemaType::Array, Value::Array(_)) => true,
+            (SchemaType::Object, Value::Object(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A parsed schema
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    reject_all: bool,
+    types: Option<Vec<SchemaType>>,
+    properties: BTreeMap<String, Schema>,
+    required: Vec<String>,
+    additional_properties: bool,
+    items: Option<Box<Schema>>,
+    enum_values: Option<Vec<Value>>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+}
+
+impl Schema {
+    /// Schema accepting any value
+    #[must_use]
+    pub fn any() -> Self {
+        Self {
+            additional_properties: true,
+            ..Self::default()
+        }
+    }
+
+    /// Schema rejecting every value
+    #[must_use]
+    pub fn nothing() -> Self {
+        Self {
+            reject_all: true,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a schema from a JSON string
+    pub fn parse(json: &str) -> Result<Self, SchemaError> {
+        let value: Value = serde_json::from_str(json).map_err(|e| SchemaError::Invalid {
+            path: String::new(),
+            message: e.to_string(),
+        })?;
+        Self::from_value(&value, "")
+    }
+
+    /// Parse a schema from a JSON value
+    fn from_value(value: &Value, path: &str) -> Result<Self, SchemaError> {
+        let invalid = |key: &str, message: &str| SchemaError::Invalid {
+            path: format!("{}/{}", path, key),
+            message: message.to_string(),
+        };
+        let obj = match value {
+            Value::Object(obj) => obj,
+            Value::Bool(true) => return Ok(Self::any()),
+            Value::Bool(false) => return Ok(Self::nothing()),
+            _ => {
+                return Err(SchemaError::Invalid {
+                    path: path.to_string(),
+                    message: "schema must be an object".to_string(),
+                })
+            }
+        };
+
+        let mut schema = Self::any();
+
+        if let Some(types) = obj.get("type") {
+            let names: Vec<&Value> = match types {
+                Value::Array(names) => names.iter().collect(),
+                name => vec![name],
+            };
+            let mut parsed = Vec::with_capacity(names.len());
+            for name in names {
+                let ty = name
+                    .as_str()
+                    .and_then(SchemaType::from_name)
+                    .ok_or_else(|| invalid("type", &format!("unknown type {}", name)))?;
+                parsed.push(ty);
+            }
+            schema.types = Some(parsed);
+        }
+
+        if let Some(properties) = obj.get("properties") {
+            let properties = properties
+                .as_object()
+                .ok_or_else(|| invalid("properties", "expected an object"))?;
+            for (key, sub) in properties {
+                let sub_path = format!("{}/properties/{}", path, key);
+                schema
+                    .properties
+                    .insert(key.clone(), Self::from_value(sub, &sub_path)?);
+            }
+        }
+
+        if let Some(required) = obj.get("required") {
+            let required = required
+                .as_array()
+                .ok_or_else(|| invalid("required", "expected an array"))?;
+            for key in required {
+                let key = key
+                    .as_str()
+                    .ok_or_else(|| invalid("required", "expected property names"))?;
+                schema.required.push(key.to_string());
+            }
+        }
+
+        if let Some(additional) = obj.get("additionalProperties") {
+            schema.additional_properties = additional
+                .as_bool()
+                .ok_or_else(|| invalid("additionalProperties", "expected a boolean"))?;
+        }
+
+        if let Some(items) = obj.get("items") {
+            let items_path = format!("{}/items", path);
+            schema.items = Some(Box::new(Self::from_value(items, &items_path)?));
+        }
+
+        if let Some(values) = obj.get("enum") {
+            let values = values
+                .as_array()
+                .ok_or_else(|| invalid("enum", "expected an array"))?;
+            schema.enum_values = Some(values.clone());
+        }
+
+        let number = |key: &str| -> Result<Option<f64>, SchemaError> {
+            obj.get(key)
+                .map(|v| v.as_f64().ok_or_else(|| invalid(key, "expected a number")))
+                .transpose()
+        };
+        schema.minimum = number("minimum")?;
+        schema.maximum = number("maximum")?;
+
+        let count = |key: &str| -> Result<Option<usize>, SchemaError> {
+            obj.get(key)
+                .map(|v| {
+                    v.as_u64()
+                        .map(|n| n as usize)
+                        .ok_or_else(|| invalid(key, "expected a non-negative integer"))
+                })
+                .transpose()
+        };
+        schema.min_length = count("minLength")?;
+        schema.max_length = count("maxLength")?;
+        schema.min_items = count("minItems")?;
+        schema.max_items = count("maxItems")?;
+
+        if let Some(pattern) = obj.get("pattern") {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| invalid("pattern", "expected a string"))?;
+            let regex = Regex::new(pattern).map_err(|e| invalid("pattern", &e.to_string()))?;
+            schema.pattern = Some(regex);
+        }
+
+        Ok(schema)
+    }
+
+    /// Parse a JSON string and validate it
+    pub fn validate_str(&self, json: &str) -> Result<Value, SchemaError> {
+        let value: Value = serde_json::from_str(json).map_err(|e| SchemaError::Mismatch {
+            path: String::new(),
+            message: format!("invalid JSON: {}", e),
+        })?;
+        self.validate(&value)?;
+        Ok(value)
+    }
+
+    /// Validate a value, returning the first violation
+    pub fn validate(&self, value: &Value) -> Result<(), SchemaError> {
+        self.check(value, "")
+    }
+
+    /// Validate a value found at `path`
+    fn check(&self, value: &Value, path: &str) -> Result<(), SchemaError> {
+        let mismatch = |message: String| SchemaError::Mismatch {
+            path: path.to_string(),
+            message,
+        };
+
+        if self.reject_all {
+            return Err(mismatch("schema rejects every value".to_string()));
+        }
+
+        if let Some(types) = &self.types {
+            if !types.iter().any(|ty| ty.matches(value)) {
+                let expected: Vec<&str> = types.iter().map(SchemaType::name).collect();
+                return Err(mismatch(format!(
+                    "expected {}, found {}",
+                    expected.join(" or "),
+                    value_type(value)
+                )));
+            }
+        }
+
+        if let Some(values) = &self.enum_values {
+            if !values.contains(value) {
+                return Err(mismatch(format!(
+                    "{} is not one of the allowed values",
+                    value
+                )));
+            }
+        }
+
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or(f64::NAN);
+                if let Some(min) = self.minimum.filter(|min| n < *min) {
+                    return Err(mismatch(format!("{} is less than minimum {}", n, min)));
+                }
+                if let Some(max) = self.maximum.filter(|max| n > *max) {
+                    return Err(mismatch(format!("{} is greater than maximum {}", n, max)));
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count();
+                if let Some(min) = self.min_length.filter(|min| len < *min) {
+                    return Err(mismatch(format!("length {} is less than {}", len, min)));
+                }
+                if let Some(max) = self.max_length.filter(|max| len > *max) {
+                    return Err(mismatch(format!("length {} is greater than {}", len, max)));
+                }
+                if let Some(pattern) = self.pattern.as_ref().filter(|p| !p.is_match(s)) {
+                    return Err(mismatch(format!("does not match pattern {}", pattern)));
+                }
+            }
+            Value::Array(items) => {
+                if let Some(min) = self.min_items.filter(|min| items.len() < *min) {
+                    return Err(mismatch(format!(
+                        "{} items, expected at least {}",
+                        items.len(),
+                        min
+                    )));
+                }
+                if let Some(max) = self.max_items.filter(|max| items.len() > *max) {
+                    return Err(mismatch(format!(
+                        "{} items, expected at most {}",
+                        items.len(),
+                        max
+                    )));
+                }
+                if let Some(schema) = &self.items {
+                    for (idx, item) in items.iter().enumerate() {
+                        schema.check(item, &format!("{}/{}", path, idx))?;
+                    }
+                }
+            }
+            Value::Object(obj) => {
+                for key in &self.required {
+                    if !obj.contains_key(key) {
+                        return Err(mismatch(format!("missing required property '{}'", key)));
+                    }
+                }
+                let keys: BTreeMap<&String, &Value> = obj.iter().collect();
+                for (key, field) in keys {
+                    let field_path = format!("{}/{}", path, key);
+                    match self.properties.get(key) {
+                        Some(schema) => schema.check(field, &field_path)?,
+                        None if !self.additional_properties => {
+                            return Err(SchemaError::Mismatch {
+                                path: field_path,
+                                message: "additional property not allowed".to_string(),
+                            })
+                        }
+                        None => {}
+                    }
+                }
+            }
+            Value::Null | Value::Bool(_) => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Type name of a value, for messages
+fn value_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if is_integer(n) => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Check for a number without a fractional part
+fn is_integer(n: &serde_json::Number) -> bool {
+    n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+}
+
+/// Show the root path as `/`
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(json: &str) -> Schema {
+        Schema::parse(json).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    fn mismatch_path(result: Result<Value, SchemaError>) -> String {
+        match result {
+            Err(SchemaError::Mismatch { path, .. }) => path,
+            other => panic!("expected mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_types_and_bounds() {
+        let s = schema(r#"{"type": "integer", "minimum": 1, "maximum": 10}"#);
+        assert!(s.validate_str("5").is_ok());
+        assert!(s.validate_str("0").is_err());
+        assert!(s.validate_str("11").is_err());
+        assert!(s.validate_str("2.5").is_err());
+        assert!(s.validate_str("\"5\"").is_err());
+
+        let s = schema(r#"{"type": ["string", "null"], "minLength": 2, "pattern": "^[a-z]+$"}"#);
+        assert!(s.validate_str("null").is_ok());
+        assert!(s.validate_str("\"ab\"").is_ok());
+        assert!(s.validate_str("\"a\"").is_err());
+        assert!(s.validate_str("\"AB\"").is_err());
+        assert!(s.validate_str("not json").is_err());
+    }
+
+    #[test]
+    fn test_objects_and_arrays() {
+        let s = schema(
+            r#"{
+                "type": "object",
+                "required": ["name"],
+                "properties": {
+                    "name": {"type": "string"},
+                    "mode": {"enum": ["fast", "slow"]},
+                    "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2}
+                },
+                "additionalProperties": false
+            }"#,
+        );
+        assert!(s
+            .validate_str(r#"{"name": "a", "mode": "fast", "tags": ["x"]}"#)
+            .is_ok());
+        assert_eq!(mismatch_path(s.validate_str(r#"{"mode": "fast"}"#)), "");
+        assert_eq!(
+            mismatch_path(s.validate_str(r#"{"name": "a", "mode": "other"}"#)),
+            "/mode"
+        );
+        assert_eq!(
+            mismatch_path(s.validate_str(r#"{"name": "a", "tags": ["x", 1]}"#)),
+            "/tags/1"
+        );
+        assert_eq!(
+            mismatch_path(s.validate_str(r#"{"name": "a", "tags": ["x", "y", "z"]}"#)),
+            "/tags"
+        );
+        assert_eq!(
+            mismatch_path(s.validate_str(r#"{"name": "a", "extra": 1}"#)),
+            "/extra"
+        );
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(matches!(
+            Schema::parse("[]"),
+            Err(SchemaError::Invalid { .. })
+        ));
+        let err = Schema::parse(r#"{"properties": {"a": {"type": "text"}}}"#).err();
+        assert_eq!(
+            err,
+            Some(SchemaError::Invalid {
+                path: "/properties/a/type".to_string(),
+                message: "unknown type \"text\"".to_string(),
+            })
+        );
+        assert!(Schema::parse(r#"{"pattern": "("}"#).is_err());
+        assert!(Schema::parse("1").is_err());
+
+        // Unknown keywords are ignored
+        assert!(schema(r#"{"type": "string", "description": "x"}"#)
+            .validate_str("\"a\"")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_boolean_schemas() {
+        assert!(schema("true").validate_str(r#"{"a": 1}"#).is_ok());
+        assert_eq!(mismatch_path(schema("false").validate_str("null")), "");
+
+        let s = schema(r#"{"properties": {"secret": false}}"#);
+        assert!(s.validate_str(r#"{"other": 1}"#).is_ok());
+        assert_eq!(mismatch_path(s.validate_str(r#"{"secret": 1}"#)), "/secret");
+    }
+}
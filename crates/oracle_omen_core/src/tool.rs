@@ -14,6 +14,7 @@ use std::{fmt, time::Duration};
 
 use crate::capability::Capability;
 use crate::hash::Hash;
+use crate::version::Version;
 
 /// Unique tool identifier
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
@@ -40,6 +41,22 @@ impl ToolId {
     pub fn as_str(&self) -> String {
         format!("{}@{}", self.name, self.version)
     }
+
+    /// Parse the version as semver
+    pub fn semver(&self) -> ToolResult<Version> {
+        Version::parse(&self.version).map_err(|e| ToolError::InvalidVersion {
+            tool: self.name.clone(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Hash identifying a call of this tool with `input`
+    ///
+    /// Recorded responses are looked up by this hash during replay.
+    #[must_use]
+    pub fn request_hash(&self, input: &str) -> Hash {
+        Hash::from_canonical(&(&self.name, &self.version, input))
+    }
 }
 
 impl fmt::Display for ToolId {
@@ -233,9 +250,10 @@ impl ToolResponseMetadata {
 }
 
 /// Source of a tool response
-#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ResponseSource {
     /// Direct tool execution
+    #[default]
     Tool,
 
     /// Cached response
@@ -316,6 +334,17 @@ pub enum ToolError {
     /// Resource limit exceeded
     ResourceExceeded { tool: String, limit: String },
 
+    /// A tool with the same ID is already registered
+    AlreadyRegistered(String),
+
+    /// Version or version requirement is not valid semver
+    InvalidVersion {
+        /// Name of the tool being registered or resolved
+        tool: String,
+        /// Parser error for the version or requirement
+        reason: String,
+    },
+
     /// Other error
     Other(String),
 }
@@ -342,11 +371,17 @@ impl fmt::Display for ToolError {
             ToolError::ResourceExceeded { tool, limit } => {
                 write!(f, "Tool {} exceeded resource limit: {}", tool, limit)
             }
+            ToolError::AlreadyRegistered(id) => write!(f, "Tool already registered: {}", id),
+            ToolError::InvalidVersion { tool, reason } => {
+                write!(f, "Tool {} has an invalid version: {}", tool, reason)
+            }
             ToolError::Other(msg) => write!(f, "Tool error: {}", msg),
         }
     }
 }
 
+impl std::error::Error for ToolError {}
+
 /// Execution context provided to tools
 ///
 /// Contains deterministic time and other context needed for reproducible execution.
@@ -0,0 +1,57 @@
+//! Semantic versions and version requirements for tool lookup.
+//!
+//! Versions and requirements are the [`semver`] crate's types, so parsing,
+//! precedence and matching follow SemVer 2.0 and Cargo's requirement syntax:
+//! - `1.2.3` or `^1.2.3`: compatible with 1.2.3 (same leftmost non-zero part)
+//! - `~1.2.3`: same major and minor, at least the given patch
+//! - `=1.2.3`: exactly 1.2.3
+//! - `>=1.2`, `>1.2`, `<2`, `<=2.1`: comparisons
+//! - `*`: any release
+//!
+//! Comparators can be combined with commas, e.g. `>=1.2, <1.5`. A pre-release
+//! version only matches a requirement that names a pre-release of the same
+//! `MAJOR.MINOR.PATCH`.
+
+pub use semver::{Error as VersionError, Version, VersionReq};
+
+use semver::{Comparator, Op};
+
+/// Requirement compatible with `version` (caret semantics)
+#[must_use]
+pub fn compatible_with(version: &Version) -> VersionReq {
+    VersionReq {
+        comparators: vec![Comparator {
+            op: Op::Caret,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+            pre: version.pre.clone(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    #[test]
+    fn test_compatible_with() {
+        let req = compatible_with(&v("1.2.3"));
+        assert!(req.matches(&v("1.4.0")));
+        assert!(!req.matches(&v("1.2.2")));
+        assert!(!req.matches(&v("2.0.0")));
+
+        let req = compatible_with(&v("0.2.3"));
+        assert!(req.matches(&v("0.2.9")));
+        assert!(!req.matches(&v("0.3.0")));
+
+        let req = compatible_with(&v("1.1.0-beta.2"));
+        assert!(req.matches(&v("1.1.0-beta.3")));
+        assert!(req.matches(&v("1.3.0")));
+        assert!(!req.matches(&v("1.1.0-beta.1")));
+    }
+}
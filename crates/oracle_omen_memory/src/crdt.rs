@@ -0,0 +1,637 @@
+//! Structural CRDTs for document values.
+//!
+//! Keys declared in a [`MemorySchema`] hold one of these next to their value,
+//! so concurrent writes merge by counter, element or field instead of
+//! replacing the whole value.
+
+use crate::document::{DocumentValue, VectorClock};
+use serde_with::serde_as;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// CRDT a key's value merges with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrdtKind {
+    /// Multi-value register holding the whole value
+    #[default]
+    Register,
+
+    /// PN-counter over an `Integer`
+    Counter,
+
+    /// Add-wins OR-set over the elements of a `Vec`
+    Set,
+
+    /// OR-map over the fields of a `Map`, each field a register
+    Map,
+
+    /// RGA sequence over the elements of a `Vec`
+    Sequence,
+}
+
+impl std::fmt::Display for CrdtKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CrdtKind::Register => "register",
+            CrdtKind::Counter => "counter",
+            CrdtKind::Set => "set",
+            CrdtKind::Map => "map",
+            CrdtKind::Sequence => "sequence",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Which CRDT applies to which keys
+///
+/// Rules match by key prefix and the longest matching prefix wins; other
+/// keys are registers. Replicas that merge must use the same schema.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct MemorySchema(BTreeMap<String, CrdtKind>);
+
+impl MemorySchema {
+    /// Create an empty schema
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a CRDT for keys starting with `prefix`
+    pub fn with_prefix(mut self, prefix: impl Into<String>, kind: CrdtKind) -> Self {
+        self.0.insert(prefix.into(), kind);
+        self
+    }
+
+    /// CRDT for a key
+    pub fn kind_for(&self, key: &str) -> CrdtKind {
+        self.0
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(CrdtKind::Register, |(_, kind)| *kind)
+    }
+}
+
+/// Value that does not fit a key's CRDT
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// CRDT declared for the key
+    pub kind: CrdtKind,
+
+    /// Type name of the value written
+    pub found: String,
+}
+
+impl std::fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "A {} cannot hold a {} value", self.kind, self.found)
+    }
+}
+
+impl std::error::Error for TypeMismatch {}
+
+/// CRDT state of a structured document
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrdtState {
+    /// PN-counter
+    Counter(PnCounter),
+
+    /// OR-set
+    Set(OrSet),
+
+    /// OR-map
+    Map(OrMap),
+
+    /// RGA sequence
+    Sequence(Rga),
+}
+
+impl CrdtState {
+    /// Empty state for a kind, or `None` for registers
+    pub fn new(kind: CrdtKind) -> Option<Self> {
+        match kind {
+            CrdtKind::Register => None,
+            CrdtKind::Counter => Some(CrdtState::Counter(PnCounter::new())),
+            CrdtKind::Set => Some(CrdtState::Set(OrSet::new())),
+            CrdtKind::Map => Some(CrdtState::Map(OrMap::new())),
+            CrdtKind::Sequence => Some(CrdtState::Sequence(Rga::new())),
+        }
+    }
+
+    /// Kind of this state
+    pub fn kind(&self) -> CrdtKind {
+        match self {
+            CrdtState::Counter(_) => CrdtKind::Counter,
+            CrdtState::Set(_) => CrdtKind::Set,
+            CrdtState::Map(_) => CrdtKind::Map,
+            CrdtState::Sequence(_) => CrdtKind::Sequence,
+        }
+    }
+
+    /// Current value
+    pub fn value(&self) -> DocumentValue {
+        match self {
+            CrdtState::Counter(counter) => DocumentValue::Integer(counter.value()),
+            CrdtState::Set(set) => DocumentValue::Vec(set.values()),
+            CrdtState::Map(map) => DocumentValue::Map(map.values()),
+            CrdtState::Sequence(seq) => DocumentValue::Vec(seq.values()),
+        }
+    }
+
+    /// Record the operations that turn the current value into `value`
+    ///
+    /// Only what changed becomes an operation, so a replica that wrote a
+    /// whole value still merges with concurrent changes to other parts.
+    pub fn update(&mut self, value: &DocumentValue, replica: &str) -> Result<(), TypeMismatch> {
+        match (&mut *self, value) {
+            (CrdtState::Counter(counter), DocumentValue::Integer(n)) => {
+                let delta = i128::from(*n) - i128::from(counter.value());
+                counter.add(
+                    delta.clamp(i64::MIN.into(), i64::MAX.into()) as i64,
+                    replica,
+                );
+            }
+            (CrdtState::Set(set), DocumentValue::Vec(values)) => set.update(values, replica),
+            (CrdtState::Map(map), DocumentValue::Map(fields)) => map.update(fields, replica),
+            (CrdtState::Sequence(seq), DocumentValue::Vec(values)) => seq.update(values, replica),
+            (state, value) => {
+                return Err(TypeMismatch {
+                    kind: state.kind(),
+                    found: value.type_name().to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge another state of the same kind; other kinds are ignored
+    pub fn merge(&mut self, other: &CrdtState) {
+        match (self, other) {
+            (CrdtState::Counter(a), CrdtState::Counter(b)) => a.merge(b),
+            (CrdtState::Set(a), CrdtState::Set(b)) => a.merge(b),
+            (CrdtState::Map(a), CrdtState::Map(b)) => a.merge(b),
+            (CrdtState::Sequence(a), CrdtState::Sequence(b)) => a.merge(b),
+            _ => {}
+        }
+    }
+}
+
+/// PN-counter: increments and decrements per replica
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PnCounter {
+    increments: BTreeMap<String, u64>,
+    decrements: BTreeMap<String, u64>,
+}
+
+impl PnCounter {
+    /// Create a counter at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `delta` on behalf of a replica
+    pub fn add(&mut self, delta: i64, replica: &str) {
+        let side = if delta >= 0 {
+            &mut self.increments
+        } else {
+            &mut self.decrements
+        };
+        let total = side.entry(replica.to_string()).or_insert(0);
+        *total = total.saturating_add(delta.unsigned_abs());
+    }
+
+    /// Current value, saturating at the `i64` range
+    pub fn value(&self) -> i64 {
+        let sum =
+            |side: &BTreeMap<String, u64>| side.values().map(|&n| i128::from(n)).sum::<i128>();
+        let value = sum(&self.increments) - sum(&self.decrements);
+        value.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+    }
+
+    /// Take the per-replica maximum of both counters
+    pub fn merge(&mut self, other: &PnCounter) {
+        for (ours, theirs) in [
+            (&mut self.increments, &other.increments),
+            (&mut self.decrements, &other.decrements),
+        ] {
+            for (replica, &count) in theirs {
+                let entry = ours.entry(replica.clone()).or_insert(0);
+                *entry = (*entry).max(count);
+            }
+        }
+    }
+}
+
+/// ID of one add to an OR-map or OR-set: a replica's n-th add
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct Dot {
+    /// Add number within the replica, from 1
+    pub counter: u64,
+
+    /// Replica that made the add
+    pub replica: String,
+}
+
+/// OR-map: fields added and removed by observed dots
+///
+/// A field removed on one replica and concurrently written on another is
+/// kept. Concurrent writes to one field are all held and the value with the
+/// highest dot wins.
+#[serde_as]
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct OrMap {
+    /// Live writes per field
+    #[serde_as(as = "BTreeMap<_, Vec<(_, _)>>")]
+    entries: BTreeMap<String, BTreeMap<Dot, DocumentValue>>,
+
+    /// Every dot seen, including removed ones
+    context: VectorClock,
+}
+
+impl OrMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value of a field
+    pub fn get(&self, field: &str) -> Option<&DocumentValue> {
+        self.entries
+            .get(field)
+            .and_then(|writes| writes.values().next_back())
+    }
+
+    /// Whether a field is present
+    pub fn contains(&self, field: &str) -> bool {
+        self.entries.contains_key(field)
+    }
+
+    /// Set a field, replacing every write to it seen here
+    pub fn put(&mut self, field: impl Into<String>, value: DocumentValue, replica: &str) {
+        self.context.increment(replica);
+        let dot = Dot {
+            counter: self.context.get(replica),
+            replica: replica.to_string(),
+        };
+        self.entries
+            .insert(field.into(), BTreeMap::from([(dot, value)]));
+    }
+
+    /// Remove a field
+    pub fn remove(&mut self, field: &str) {
+        self.entries.remove(field);
+    }
+
+    /// Fields and their values, in field order
+    pub fn values(&self) -> BTreeMap<String, DocumentValue> {
+        self.entries
+            .keys()
+            .filter_map(|field| Some((field.clone(), self.get(field)?.clone())))
+            .collect()
+    }
+
+    /// Make the fields equal to `fields`
+    fn update(&mut self, fields: &BTreeMap<String, DocumentValue>, replica: &str) {
+        let removed: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|field| !fields.contains_key(*field))
+            .cloned()
+            .collect();
+        for field in removed {
+            self.remove(&field);
+        }
+        for (field, value) in fields {
+            if self.get(field) != Some(value) {
+                self.put(field.clone(), value.clone(), replica);
+            }
+        }
+    }
+
+    /// Keep writes both maps hold, and writes the other map has not seen
+    pub fn merge(&mut self, other: &OrMap) {
+        let fields: BTreeSet<&String> = self.entries.keys().chain(other.entries.keys()).collect();
+        let mut entries = BTreeMap::new();
+        for field in fields {
+            let mut writes = survivors(
+                self.entries.get(field),
+                other.entries.get(field),
+                &other.context,
+            );
+            writes.extend(survivors(
+                other.entries.get(field),
+                self.entries.get(field),
+                &self.context,
+            ));
+            if !writes.is_empty() {
+                entries.insert(field.clone(), writes);
+            }
+        }
+        self.entries = entries;
+        self.context.join(&other.context);
+    }
+}
+
+/// Writes in `ours` that `theirs` holds too or has not seen
+fn survivors(
+    ours: Option<&BTreeMap<Dot, DocumentValue>>,
+    theirs: Option<&BTreeMap<Dot, DocumentValue>>,
+    their_context: &VectorClock,
+) -> BTreeMap<Dot, DocumentValue> {
+    ours.into_iter()
+        .flatten()
+        .filter(|(dot, _)| {
+            theirs.is_some_and(|writes| writes.contains_key(*dot))
+                || their_context.get(&dot.replica) < dot.counter
+        })
+        .map(|(dot, value)| (dot.clone(), value.clone()))
+        .collect()
+}
+
+/// Add-wins OR-set
+///
+/// Elements are held in an [`OrMap`] keyed by their canonical JSON, and
+/// listed in that order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct OrSet(OrMap);
+
+impl OrSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an element is present
+    pub fn contains(&self, value: &DocumentValue) -> bool {
+        self.0.contains(&element_key(value))
+    }
+
+    /// Add an element
+    pub fn insert(&mut self, value: DocumentValue, replica: &str) {
+        self.0.put(element_key(&value), value, replica);
+    }
+
+    /// Remove an element
+    pub fn remove(&mut self, value: &DocumentValue) {
+        self.0.remove(&element_key(value));
+    }
+
+    /// Elements in canonical order
+    pub fn values(&self) -> Vec<DocumentValue> {
+        self.0.values().into_values().collect()
+    }
+
+    /// Make the elements equal to `values`
+    fn update(&mut self, values: &[DocumentValue], replica: &str) {
+        let wanted: BTreeMap<String, DocumentValue> = values
+            .iter()
+            .map(|value| (element_key(value), value.clone()))
+            .collect();
+        self.0.update(&wanted, replica);
+    }
+
+    /// Merge another set
+    pub fn merge(&mut self, other: &OrSet) {
+        self.0.merge(&other.0);
+    }
+}
+
+/// Canonical JSON of a set element
+fn element_key(value: &DocumentValue) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// ID of an RGA element: Lamport time, then replica
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct SeqId {
+    /// Lamport time of the insert
+    pub lamport: u64,
+
+    /// Replica that made the insert
+    pub replica: String,
+}
+
+/// RGA element; removed elements stay as tombstones
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct RgaNode {
+    origin: Option<SeqId>,
+    value: DocumentValue,
+    deleted: bool,
+}
+
+/// RGA sequence
+///
+/// Each element is inserted after an origin element. Elements with the same
+/// origin are ordered newest first, so concurrent inserts at one position
+/// are all kept in the same order on every replica.
+#[serde_as]
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Rga {
+    #[serde_as(as = "Vec<(_, _)>")]
+    nodes: BTreeMap<SeqId, RgaNode>,
+}
+
+impl Rga {
+    /// Create an empty sequence
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.nodes.values().filter(|node| !node.deleted).count()
+    }
+
+    /// Whether the sequence has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Elements in order
+    pub fn values(&self) -> Vec<DocumentValue> {
+        self.visible()
+            .into_iter()
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    /// Insert an element at `index`, or at the end if past it
+    pub fn insert(&mut self, index: usize, value: DocumentValue, replica: &str) {
+        let visible = self.visible();
+        let origin = index
+            .min(visible.len())
+            .checked_sub(1)
+            .map(|at| visible[at].0.clone());
+        self.insert_after(origin, value, replica);
+    }
+
+    /// Remove the element at `index`
+    pub fn remove(&mut self, index: usize) -> Option<DocumentValue> {
+        let id = self.visible().get(index)?.0.clone();
+        let node = self.nodes.get_mut(&id)?;
+        node.deleted = true;
+        Some(node.value.clone())
+    }
+
+    /// Make the elements equal to `values`, keeping the common prefix and suffix
+    fn update(&mut self, values: &[DocumentValue], replica: &str) {
+        let current: Vec<(SeqId, DocumentValue)> = self
+            .visible()
+            .into_iter()
+            .map(|(id, value)| (id.clone(), value.clone()))
+            .collect();
+        let prefix = current
+            .iter()
+            .zip(values)
+            .take_while(|((_, old), new)| old == *new)
+            .count();
+        let suffix = current[prefix..]
+            .iter()
+            .rev()
+            .zip(values[prefix..].iter().rev())
+            .take_while(|((_, old), new)| old == *new)
+            .count();
+
+        for (id, _) in &current[prefix..current.len() - suffix] {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.deleted = true;
+            }
+        }
+        let mut origin = prefix.checked_sub(1).map(|at| current[at].0.clone());
+        for value in &values[prefix..values.len() - suffix] {
+            origin = Some(self.insert_after(origin, value.clone(), replica));
+        }
+    }
+
+    /// Insert directly after `origin`, or at the start
+    fn insert_after(
+        &mut self,
+        origin: Option<SeqId>,
+        value: DocumentValue,
+        replica: &str,
+    ) -> SeqId {
+        let lamport = self.nodes.keys().map(|id| id.lamport).max().unwrap_or(0) + 1;
+        let id = SeqId {
+            lamport,
+            replica: replica.to_string(),
+        };
+        let node = RgaNode {
+            origin,
+            value,
+            deleted: false,
+        };
+        self.nodes.insert(id.clone(), node);
+        id
+    }
+
+    /// Live elements in sequence order
+    fn visible(&self) -> Vec<(&SeqId, &DocumentValue)> {
+        let mut children: BTreeMap<Option<&SeqId>, Vec<&SeqId>> = BTreeMap::new();
+        for (id, node) in &self.nodes {
+            children.entry(node.origin.as_ref()).or_default().push(id);
+        }
+
+        // Children are pushed oldest first so the newest is visited first
+        let mut stack = children.get(&None).cloned().unwrap_or_default();
+        let mut order = Vec::new();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.deleted {
+                order.push((id, &node.value));
+            }
+            if let Some(next) = children.get(&Some(id)) {
+                stack.extend(next.iter().copied());
+            }
+        }
+        order
+    }
+
+    /// Union the elements of both sequences
+    pub fn merge(&mut self, other: &Rga) {
+        for (id, node) in &other.nodes {
+            self.nodes
+                .entry(id.clone())
+                .and_modify(|ours| ours.deleted |= node.deleted)
+                .or_insert_with(|| node.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> DocumentValue {
+        DocumentValue::Vec(values.iter().map(|&n| DocumentValue::Integer(n)).collect())
+    }
+
+    #[test]
+    fn test_schema_longest_prefix() {
+        let schema = MemorySchema::new()
+            .with_prefix("plan/", CrdtKind::Map)
+            .with_prefix("plan/steps", CrdtKind::Sequence);
+        assert_eq!(schema.kind_for("plan/steps"), CrdtKind::Sequence);
+        assert_eq!(schema.kind_for("plan/owner"), CrdtKind::Map);
+        assert_eq!(schema.kind_for("notes"), CrdtKind::Register);
+    }
+
+    #[test]
+    fn test_concurrent_updates_merge() {
+        let mut base = Rga::new();
+        base.update(&[DocumentValue::Integer(1)], "a");
+
+        // Two replicas append after the same element; both appends survive
+        let (mut a, mut b) = (base.clone(), base.clone());
+        a.update(&[DocumentValue::Integer(1), DocumentValue::Integer(2)], "a");
+        b.update(&[DocumentValue::Integer(1), DocumentValue::Integer(3)], "b");
+        let mut ab = a.clone();
+        ab.merge(&b);
+        b.merge(&a);
+        assert_eq!(ab, b);
+        assert_eq!(DocumentValue::Vec(ab.values()), ints(&[1, 3, 2]));
+
+        // Increments from both replicas add up
+        let mut a = CrdtState::new(CrdtKind::Counter).unwrap();
+        let mut b = a.clone();
+        a.update(&DocumentValue::Integer(5), "a").unwrap();
+        b.update(&DocumentValue::Integer(-2), "b").unwrap();
+        a.merge(&b);
+        assert_eq!(a.value(), DocumentValue::Integer(3));
+
+        // A concurrent add wins over a remove
+        let mut a = OrSet::new();
+        a.insert(DocumentValue::Integer(1), "a");
+        let mut b = a.clone();
+        a.remove(&DocumentValue::Integer(1));
+        b.insert(DocumentValue::Integer(1), "b");
+        b.insert(DocumentValue::Integer(2), "b");
+        a.merge(&b);
+        assert_eq!(DocumentValue::Vec(a.values()), ints(&[1, 2]));
+        b.remove(&DocumentValue::Integer(2));
+        a.merge(&b);
+        assert_eq!(DocumentValue::Vec(a.values()), ints(&[1]));
+    }
+
+    #[test]
+    fn test_map_fields_merge() {
+        let mut a = OrMap::new();
+        a.put("owner", "planner".into(), "a");
+        a.put("stale", DocumentValue::Bool(true), "a");
+        let mut b = a.clone();
+
+        a.put("status", "running".into(), "a");
+        b.remove("stale");
+        b.put("owner", "executor".into(), "b");
+        a.merge(&b);
+        assert_eq!(a.get("owner"), Some(&"executor".into()));
+        assert_eq!(a.get("status"), Some(&"running".into()));
+        assert!(!a.contains("stale"));
+
+        let state = CrdtState::Map(a);
+        let err = state.clone().update(&ints(&[1]), "a").unwrap_err();
+        assert_eq!(err.kind, CrdtKind::Map);
+        assert_eq!(err.found, "vec");
+    }
+}
@@ -1,9 +1,88 @@
 //! CRDT document types for memory store.
 
+use crate::crdt::{CrdtKind, CrdtState};
 use oracle_omen_core::hash::Hash;
-use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Vector clock: number of writes seen from each replica
+///
+/// Replicas that are absent count as zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+impl VectorClock {
+    /// Create an empty clock
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes seen from a replica
+    pub fn get(&self, replica: &str) -> u64 {
+        self.0.get(replica).copied().unwrap_or(0)
+    }
+
+    /// Count one more write from a replica
+    pub fn increment(&mut self, replica: &str) {
+        *self.0.entry(replica.to_string()).or_insert(0) += 1;
+    }
+
+    /// Take the per-replica maximum of both clocks
+    pub fn join(&mut self, other: &VectorClock) {
+        for (replica, &count) in &other.0 {
+            let entry = self.0.entry(replica.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+
+    /// Causal order of two clocks
+    pub fn compare(&self, other: &VectorClock) -> ClockOrder {
+        let replicas: BTreeSet<&String> = self.0.keys().chain(other.0.keys()).collect();
+        let (mut less, mut greater) = (false, false);
+        for replica in replicas {
+            match self.get(replica).cmp(&other.get(replica)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => ClockOrder::Equal,
+            (true, false) => ClockOrder::Before,
+            (false, true) => ClockOrder::After,
+            (true, true) => ClockOrder::Concurrent,
+        }
+    }
+
+    /// Replicas and their counts, in replica order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &u64)> {
+        self.0.iter()
+    }
+}
+
+/// Causal order of two vector clocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockOrder {
+    /// Same writes seen
+    Equal,
+
+    /// Happened before the other
+    Before,
+
+    /// Happened after the other
+    After,
+
+    /// Neither saw the other's writes
+    Concurrent,
+}
 
 /// CRDT document
+///
+/// A multi-value register: writes whose clocks are concurrent are all kept,
+/// and `value` is the one that sorts last by `(causal_event, replica, hash)`.
+/// The others are in `concurrent`. Documents with a `crdt` state merge that
+/// state instead, and `value` is read from it.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Document {
     /// Document key
@@ -13,11 +92,27 @@ pub struct Document {
     pub value: DocumentValue,
 
     /// Version vector for causality
-    pub version: BTreeMap<String, u64>,
+    pub version: VectorClock,
 
     /// Causal event ID
     pub causal_event: u64,
 
+    /// Replica that made the write
+    #[serde(default)]
+    pub replica: String,
+
+    /// Whether the write is a delete
+    #[serde(default)]
+    pub deleted: bool,
+
+    /// Concurrent writes that lost the tie-break, latest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub concurrent: Vec<Document>,
+
+    /// CRDT state of a key declared in a [`crate::crdt::MemorySchema`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crdt: Option<CrdtState>,
+
     /// Document hash
     pub hash: Hash,
 }
@@ -29,34 +124,159 @@ impl Document {
         let mut doc = Self {
             key,
             value,
-            version: BTreeMap::new(),
+            version: VectorClock::new(),
             causal_event,
+            replica: String::new(),
+            deleted: false,
+            concurrent: Vec::new(),
+            crdt: None,
             hash: Hash::zero(),
         };
         doc.rehash();
         doc
     }
 
+    /// Create a delete marker
+    pub fn tombstone(key: impl Into<String>, causal_event: u64) -> Self {
+        let mut doc = Self::new(key, DocumentValue::Null, causal_event);
+        doc.deleted = true;
+        doc.rehash();
+        doc
+    }
+
     /// Recompute document hash
-    fn rehash(&mut self) {
-        self.hash = Hash::from_canonical(&(&self.key, &self.value, &self.version, self.causal_event));
+    pub(crate) fn rehash(&mut self) {
+        let write = Hash::from_canonical(&(
+            &self.key,
+            &self.value,
+            &self.version,
+            self.causal_event,
+            &self.replica,
+            self.deleted,
+            &self.crdt,
+        ));
+        self.hash = if self.concurrent.is_empty() {
+            write
+        } else {
+            let mut hashes = vec![write];
+            hashes.extend(self.concurrent.iter().map(|d| d.hash));
+            oracle_omen_core::hash::combine_hashes(&hashes)
+        };
     }
 
-    /// Merge with another document (LWW-register semantics)
+    /// Merge with another document
+    ///
+    /// Writes whose clock is before another write's are dropped; writes with
+    /// equal clocks keep the one that wins the tie-break. Structured
+    /// documents merge their CRDT states. Merging is commutative, associative
+    /// and idempotent.
     pub fn merge(&mut self, other: &Document) -> MergeResult {
         if self.key != other.key {
             return MergeResult::KeyMismatch;
         }
+        if self.crdt_kind() != other.crdt_kind() {
+            return MergeResult::TypeMismatch;
+        }
+        if self.crdt.is_some() {
+            return self.merge_state(other);
+        }
+
+        let mut writes = self.writes();
+        writes.extend(other.writes());
+        let merged = Self::from_writes(writes);
+        if merged == *self {
+            MergeResult::Unchanged
+        } else {
+            *self = merged;
+            if self.is_concurrent() {
+                MergeResult::Concurrent
+            } else {
+                MergeResult::Merged
+            }
+        }
+    }
 
-        // Later causal event wins (LWW)
-        if other.causal_event > self.causal_event {
-            self.value = other.value.clone();
-            self.causal_event = other.causal_event;
-            self.rehash();
-            return MergeResult::Merged;
+    /// Join the CRDT states, clocks and latest causal event of two documents
+    fn merge_state(&mut self, other: &Document) -> MergeResult {
+        let mut merged = self.clone();
+        if let (Some(state), Some(theirs)) = (&mut merged.crdt, &other.crdt) {
+            state.merge(theirs);
+            merged.value = state.value();
+        }
+        merged.version.join(&other.version);
+        if (other.causal_event, &other.replica) > (self.causal_event, &self.replica) {
+            merged.causal_event = other.causal_event;
+            merged.replica = other.replica.clone();
         }
+        merged.rehash();
 
-        MergeResult::Unchanged
+        if merged == *self {
+            MergeResult::Unchanged
+        } else {
+            *self = merged;
+            MergeResult::Merged
+        }
+    }
+
+    /// CRDT the document merges with
+    pub fn crdt_kind(&self) -> CrdtKind {
+        self.crdt
+            .as_ref()
+            .map_or(CrdtKind::Register, CrdtState::kind)
+    }
+
+    /// Whether concurrent writes are held
+    pub fn is_concurrent(&self) -> bool {
+        !self.concurrent.is_empty()
+    }
+
+    /// Clock covering every write held
+    pub fn seen(&self) -> VectorClock {
+        let mut clock = self.version.clone();
+        for doc in &self.concurrent {
+            clock.join(&doc.version);
+        }
+        clock
+    }
+
+    /// The winning write and the concurrent ones, each on its own
+    fn writes(&self) -> Vec<Document> {
+        let mut head = self.clone();
+        head.concurrent.clear();
+        head.rehash();
+        let mut writes = vec![head];
+        writes.extend(self.concurrent.iter().cloned());
+        writes
+    }
+
+    /// Document holding the writes no other write supersedes
+    fn from_writes(writes: Vec<Document>) -> Self {
+        let mut kept: Vec<Document> = writes
+            .iter()
+            .filter(|w| !writes.iter().any(|other| other.supersedes(w)))
+            .cloned()
+            .collect();
+        kept.sort_by(|a, b| b.tie_break().cmp(&a.tie_break()));
+        kept.dedup_by(|a, b| a.hash == b.hash);
+
+        let mut head = kept.remove(0);
+        head.concurrent = kept;
+        head.rehash();
+        head
+    }
+
+    /// Whether this write replaces `other`
+    fn supersedes(&self, other: &Document) -> bool {
+        match self.version.compare(&other.version) {
+            ClockOrder::After => true,
+            ClockOrder::Equal => self.tie_break() > other.tie_break(),
+            ClockOrder::Before | ClockOrder::Concurrent => false,
+        }
+    }
+
+    /// Order used to pick among writes
+    fn tie_break(&self) -> (u64, &str, Hash) {
+        (self.causal_event, &self.replica, self.hash)
     }
 
     /// Get document hash
@@ -71,11 +291,17 @@ pub enum MergeResult {
     /// Documents merged
     Merged,
 
+    /// Documents merged and hold concurrent writes
+    Concurrent,
+
     /// No changes made
     Unchanged,
 
     /// Key mismatch - cannot merge
     KeyMismatch,
+
+    /// Different CRDT kinds, or a value that does not fit the key's CRDT
+    TypeMismatch,
 }
 
 /// Document value type
@@ -189,6 +415,36 @@ mod tests {
         assert_eq!(doc1.value, DocumentValue::Integer(20));
     }
 
+    #[test]
+    fn test_concurrent_writes() {
+        let write = |value: i64, replica: &str, event: u64| {
+            let mut doc = Document::new("key", DocumentValue::Integer(value), event);
+            doc.version.increment(replica);
+            doc.replica = replica.to_string();
+            doc.rehash();
+            doc
+        };
+        let (a, b) = (write(1, "a", 5), write(2, "b", 5));
+        assert_eq!(a.version.compare(&b.version), ClockOrder::Concurrent);
+
+        // Both writes survive, and replica id breaks the tie either way round
+        let mut ab = a.clone();
+        assert_eq!(ab.merge(&b), MergeResult::Concurrent);
+        let mut ba = b.clone();
+        assert_eq!(ba.merge(&a), MergeResult::Concurrent);
+        assert_eq!(ab, ba);
+        assert_eq!(ab.value, DocumentValue::Integer(2));
+        assert_eq!(ab.concurrent, vec![a.clone()]);
+
+        // A write that saw both replaces them, even with an older event
+        let mut c = write(3, "a", 1);
+        c.version = ab.seen();
+        c.version.increment("a");
+        c.rehash();
+        assert_eq!(ab.merge(&c), MergeResult::Merged);
+        assert_eq!(ab, c);
+    }
+
     #[test]
     fn test_document_merge_key_mismatch() {
         let mut doc1 = Document::new("key1", DocumentValue::Integer(10), 1);
@@ -2,6 +2,7 @@
 //
 // Provides:
 // - CRDT document store
+// - Structural CRDTs for counters, sets, maps and sequences
 // - Causal event linkage
 // - Temporal queries
 // - Deterministic retrieval
@@ -10,11 +11,13 @@
 #![warn(clippy::all)]
 
 pub mod store;
+pub mod crdt;
 pub mod document;
 pub mod provenance;
 pub mod query;
 
 pub use store::*;
+pub use crdt::*;
 pub use document::*;
 pub use provenance::*;
 pub use query::*;
@@ -1,49 +1,99 @@
 //! CRDT document store.
 
+use crate::crdt::{CrdtState, MemorySchema, TypeMismatch};
 use crate::document::{Document, DocumentValue, MergeResult};
 use oracle_omen_core::hash::Hash;
+use oracle_omen_policy::engine::{EvalContext, MemoryOperation, PolicyEngine};
 use std::collections::BTreeMap;
 
+/// Replica ID of a store created with [`MemoryStore::new`]
+pub const DEFAULT_REPLICA: &str = "local";
+
 /// Memory store using CRDT documents
+///
+/// Each store is one replica. Local writes and deletes advance the replica's
+/// entry in the document's vector clock, and [`MemoryStore::merge`] combines
+/// replicas. Deletes are kept as tombstones so a merge cannot resurrect them.
+/// Merges from outside the crate go through [`MemoryStore::merge_checked`].
+///
+/// Keys the [`MemorySchema`] declares as counters, sets, maps or sequences
+/// hold a CRDT state: a write records only what changed, so concurrent
+/// writes merge without losing either side.
+///
+/// Outside this crate, documents are only read, written and deleted through
+/// the `*_checked` methods, so every access passes memory policy.
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct MemoryStore {
-    /// Documents indexed by key
+    /// Replica ID stamped on local writes
+    #[serde(default = "default_replica")]
+    replica: String,
+
+    /// CRDTs declared per key prefix
+    #[serde(default)]
+    schema: MemorySchema,
+
+    /// Documents and tombstones indexed by key
     documents: BTreeMap<String, Document>,
 
-    /// Provenance index: event_id -> affected keys
+    /// Provenance index: event_id -> affected keys, sorted
     provenance: BTreeMap<u64, Vec<String>>,
 
     /// Store hash for verification
     store_hash: Hash,
 }
 
+fn default_replica() -> String {
+    DEFAULT_REPLICA.to_string()
+}
+
 impl MemoryStore {
     /// Create a new memory store
     pub fn new() -> Self {
         Self {
+            replica: default_replica(),
+            schema: MemorySchema::new(),
             documents: BTreeMap::new(),
             provenance: BTreeMap::new(),
             store_hash: Hash::zero(),
         }
     }
 
-    /// Write a document
-    pub fn write(&mut self, doc: Document) -> WriteResult {
-        let key = doc.key.clone();
-        let causal_event = doc.causal_event;
+    /// Set the replica ID stamped on local writes
+    pub fn with_replica(mut self, replica: impl Into<String>) -> Self {
+        self.replica = replica.into();
+        self
+    }
 
-        // Record provenance
-        self.provenance
-            .entry(causal_event)
-            .or_insert_with(Vec::new)
-            .push(key.clone());
+    /// Replica ID of this store
+    pub fn replica(&self) -> &str {
+        &self.replica
+    }
 
-        // Insert or merge document
-        let result = if let Some(existing) = self.documents.get_mut(&key) {
-            existing.merge(&doc)
-        } else {
-            self.documents.insert(key.clone(), doc);
-            MergeResult::Merged
+    /// Set the CRDTs used for key prefixes
+    pub fn with_schema(mut self, schema: MemorySchema) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// CRDTs used for key prefixes
+    pub fn schema(&self) -> &MemorySchema {
+        &self.schema
+    }
+
+    /// Write a document
+    ///
+    /// The write supersedes every version of the key this replica has seen.
+    /// On a structured key it updates the key's CRDT, and a value of the
+    /// wrong type is refused with `MergeResult::TypeMismatch`. Unchecked;
+    /// callers outside the crate use [`MemoryStore::write_checked`].
+    pub(crate) fn write(&mut self, doc: Document) -> WriteResult {
+        let key = doc.key.clone();
+        let result = match self.structure(doc) {
+            Ok(doc) => {
+                self.record(doc.causal_event, key.clone());
+                self.apply(doc)
+            }
+            Err(_) => MergeResult::TypeMismatch,
         };
 
         self.rehash();
@@ -54,38 +104,230 @@ impl MemoryStore {
         }
     }
 
-    /// Read a document by key
-    pub fn read(&self, key: &str) -> Option<&Document> {
-        self.documents.get(key)
+    /// Read a document by key, without checking memory policy
+    pub(crate) fn read(&self, key: &str) -> Option<&Document> {
+        self.documents.get(key).filter(|doc| !doc.deleted)
     }
 
     /// Delete a document
-    pub fn delete(&mut self, key: &str, causal_event: u64) -> DeleteResult {
-        if self.documents.remove(key).is_some() {
-            self.provenance
-                .entry(causal_event)
-                .or_insert_with(Vec::new)
-            .push(format!("!{}", key)); // Prefix with ! to indicate deletion
-            self.rehash();
-            DeleteResult::Deleted
+    ///
+    /// Structured keys are emptied rather than removed, so the delete merges
+    /// with concurrent updates like any other write. Unchecked; callers
+    /// outside the crate use [`MemoryStore::delete_checked`].
+    pub(crate) fn delete(&mut self, key: &str, causal_event: u64) -> DeleteResult {
+        if self.read(key).is_none() {
+            return DeleteResult::NotFound;
+        }
+        self.record(causal_event, format!("!{}", key)); // Prefix with ! to indicate deletion
+        let doc = match CrdtState::new(self.schema.kind_for(key)) {
+            Some(empty) => Document::new(key, empty.value(), causal_event),
+            None => Document::tombstone(key, causal_event),
+        };
+        // An empty value always fits its CRDT
+        if let Ok(doc) = self.structure(doc) {
+            self.apply(doc);
+        }
+        self.rehash();
+        DeleteResult::Deleted
+    }
+
+    /// Merge another replica into this one
+    ///
+    /// Merging is commutative, associative and idempotent: replicas that
+    /// have merged the same writes hold the same documents, in any order.
+    /// Unchecked; callers outside the crate use
+    /// [`MemoryStore::merge_checked`].
+    pub(crate) fn merge(&mut self, other: &MemoryStore) -> MergeReport {
+        let mut report = MergeReport::default();
+        for (key, doc) in &other.documents {
+            let result = match self.documents.get_mut(key) {
+                Some(existing) => existing.merge(doc),
+                None => {
+                    self.documents.insert(key.clone(), doc.clone());
+                    if doc.is_concurrent() {
+                        MergeResult::Concurrent
+                    } else {
+                        MergeResult::Merged
+                    }
+                }
+            };
+            match result {
+                MergeResult::Merged => report.changed.push(key.clone()),
+                MergeResult::Concurrent => {
+                    report.changed.push(key.clone());
+                    report.concurrent.push(key.clone());
+                }
+                MergeResult::TypeMismatch => report.mismatched.push(key.clone()),
+                MergeResult::Unchanged | MergeResult::KeyMismatch => {}
+            }
+        }
+        for (&event, keys) in &other.provenance {
+            for key in keys {
+                self.record(event, key.clone());
+            }
+        }
+        self.rehash();
+        report
+    }
+
+    /// Keys holding concurrent writes, in key order
+    pub fn concurrent_keys(&self) -> Vec<String> {
+        self.documents
+            .iter()
+            .filter(|(_, doc)| doc.is_concurrent())
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Turn a write to a structured key into an update of its CRDT state
+    fn structure(&self, mut doc: Document) -> Result<Document, TypeMismatch> {
+        doc.crdt = match CrdtState::new(self.schema.kind_for(&doc.key)) {
+            None => None,
+            Some(empty) => {
+                let mut state = self
+                    .documents
+                    .get(&doc.key)
+                    .and_then(|existing| existing.crdt.clone())
+                    .filter(|state| state.kind() == empty.kind())
+                    .unwrap_or(empty);
+                state.update(&doc.value, &self.replica)?;
+                doc.value = state.value();
+                doc.deleted = false;
+                Some(state)
+            }
+        };
+        Ok(doc)
+    }
+
+    /// Stamp a local write and merge it over the key's current document
+    ///
+    /// A document of another CRDT kind, left from before a schema change, is
+    /// replaced.
+    fn apply(&mut self, doc: Document) -> MergeResult {
+        let doc = self.stamp(doc);
+        match self.documents.get_mut(&doc.key) {
+            Some(existing) if existing.crdt_kind() == doc.crdt_kind() => existing.merge(&doc),
+            _ => {
+                self.documents.insert(doc.key.clone(), doc);
+                MergeResult::Merged
+            }
+        }
+    }
+
+    /// Give a local write a clock after every version of its key seen here
+    fn stamp(&self, mut doc: Document) -> Document {
+        doc.version = self
+            .documents
+            .get(&doc.key)
+            .map(Document::seen)
+            .unwrap_or_default();
+        doc.version.increment(&self.replica);
+        doc.replica = self.replica.clone();
+        doc.concurrent.clear();
+        doc.rehash();
+        doc
+    }
+
+    /// Add a key to an event's provenance
+    fn record(&mut self, event: u64, key: String) {
+        let keys = self.provenance.entry(event).or_default();
+        if let Err(at) = keys.binary_search(&key) {
+            keys.insert(at, key);
+        }
+    }
+
+    /// Write a document after checking memory policy
+    ///
+    /// The store is left untouched if the policy denies the write.
+    pub fn write_checked(
+        &mut self,
+        doc: Document,
+        policy: &PolicyEngine,
+        ctx: &EvalContext,
+    ) -> Result<WriteResult, AccessDenied> {
+        Self::check(policy, ctx, MemoryOperation::Write, &doc.key)?;
+        Ok(self.write(doc))
+    }
+
+    /// Read a document after checking memory policy
+    pub fn read_checked(
+        &self,
+        key: &str,
+        policy: &PolicyEngine,
+        ctx: &EvalContext,
+    ) -> Result<Option<&Document>, AccessDenied> {
+        Self::check(policy, ctx, MemoryOperation::Read, key)?;
+        Ok(self.read(key))
+    }
+
+    /// Delete a document after checking memory policy
+    pub fn delete_checked(
+        &mut self,
+        key: &str,
+        causal_event: u64,
+        policy: &PolicyEngine,
+        ctx: &EvalContext,
+    ) -> Result<DeleteResult, AccessDenied> {
+        Self::check(policy, ctx, MemoryOperation::Delete, key)?;
+        Ok(self.delete(key, causal_event))
+    }
+
+    /// Merge another replica after checking memory policy
+    ///
+    /// Every key the merge would change, deletes included, must pass a
+    /// `Write` check. The store is left untouched if any is denied.
+    pub fn merge_checked(
+        &mut self,
+        other: &MemoryStore,
+        policy: &PolicyEngine,
+        ctx: &EvalContext,
+    ) -> Result<MergeReport, AccessDenied> {
+        let mut merged = self.clone();
+        let report = merged.merge(other);
+        for key in &report.changed {
+            Self::check(policy, ctx, MemoryOperation::Write, key)?;
+        }
+        *self = merged;
+        Ok(report)
+    }
+
+    /// Evaluate memory policy for an operation on a key
+    fn check(
+        policy: &PolicyEngine,
+        ctx: &EvalContext,
+        operation: MemoryOperation,
+        key: &str,
+    ) -> Result<(), AccessDenied> {
+        let result = policy.evaluate_memory(operation, key, ctx);
+        if result.allowed {
+            Ok(())
         } else {
-            DeleteResult::NotFound
+            Err(AccessDenied {
+                operation,
+                key: key.to_string(),
+                reason: result.reason,
+            })
         }
     }
 
     /// Get all keys (deterministic order)
     pub fn keys(&self) -> Vec<String> {
-        self.documents.keys().cloned().collect()
+        self.live().map(|(key, _)| key.clone()).collect()
     }
 
     /// Get document count
     pub fn len(&self) -> usize {
-        self.documents.len()
+        self.live().count()
     }
 
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.documents.is_empty()
+        self.live().next().is_none()
+    }
+
+    /// Documents that are not deleted
+    fn live(&self) -> impl Iterator<Item = (&String, &Document)> {
+        self.documents.iter().filter(|(_, doc)| !doc.deleted)
     }
 
     /// Get store hash
@@ -107,11 +349,7 @@ impl MemoryStore {
     /// Create a snapshot at current state
     pub fn snapshot(&self) -> StoreSnapshot {
         StoreSnapshot {
-            document_hashes: self
-                .documents
-                .iter()
-                .map(|(k, d)| (k.clone(), d.hash()))
-                .collect(),
+            document_hashes: self.live().map(|(k, d)| (k.clone(), d.hash())).collect(),
             store_hash: self.store_hash,
         }
     }
@@ -143,6 +381,19 @@ pub struct WriteResult {
     pub store_hash: Hash,
 }
 
+/// Keys a merge changed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Keys whose document changed, in key order
+    pub changed: Vec<String>,
+
+    /// Changed keys that now hold concurrent writes
+    pub concurrent: Vec<String>,
+
+    /// Keys left unchanged because the replicas use different CRDTs for them
+    pub mismatched: Vec<String>,
+}
+
 /// Result of a delete operation
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DeleteResult {
@@ -153,6 +404,27 @@ pub enum DeleteResult {
     NotFound,
 }
 
+/// Memory access denied by policy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessDenied {
+    /// Operation that was denied
+    pub operation: MemoryOperation,
+
+    /// Key that was accessed
+    pub key: String,
+
+    /// Denial reason from the policy engine
+    pub reason: String,
+}
+
+impl std::fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Memory {} denied for '{}': {}", self.operation, self.key, self.reason)
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
 /// Store snapshot for replay/checkpointing
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct StoreSnapshot {
@@ -200,6 +472,137 @@ mod tests {
         assert_eq!(store.len(), 0);
     }
 
+    #[test]
+    fn test_memory_store_write_checked() {
+        use oracle_omen_policy::{
+            compiler::PolicyCompiler,
+            lang::{Action, Condition, Policy, Rule, RuleKind},
+        };
+
+        let mut policy = Policy::new("memory", "1.0.0");
+        policy.add_rule(Rule {
+            name: "allow_notes".to_string(),
+            kind: RuleKind::Memory,
+            condition: Condition::KeyMatches("notes/*".to_string()),
+            action: Action::Allow,
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        let ctx = EvalContext::new();
+
+        let mut store = MemoryStore::new();
+        let allowed = Document::new("notes/a", DocumentValue::Integer(1), 1);
+        assert!(store.write_checked(allowed, &engine, &ctx).is_ok());
+
+        let denied = Document::new("config/a", DocumentValue::Integer(2), 2);
+        let err = store.write_checked(denied, &engine, &ctx).unwrap_err();
+        assert_eq!(err.operation, MemoryOperation::Write);
+        assert_eq!(store.keys(), vec!["notes/a".to_string()]);
+        assert!(store.keys_for_event(2).is_empty());
+    }
+
+    #[test]
+    fn test_merge_checked() {
+        use oracle_omen_policy::{
+            compiler::PolicyCompiler,
+            lang::{Action, Condition, Policy, Rule, RuleKind},
+        };
+
+        let mut policy = Policy::new("memory", "1.0.0");
+        policy.add_rule(Rule {
+            name: "allow_notes".to_string(),
+            kind: RuleKind::Memory,
+            condition: Condition::KeyMatches("notes/*".to_string()),
+            action: Action::Allow,
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        let ctx = EvalContext::new();
+
+        let mut other = MemoryStore::new().with_replica("b");
+        other.write(Document::new("notes/a", DocumentValue::Integer(1), 1));
+        let mut store = MemoryStore::new();
+        let report = store.merge_checked(&other, &engine, &ctx).unwrap();
+        assert_eq!(report.changed, vec!["notes/a".to_string()]);
+
+        other.write(Document::new("config/a", DocumentValue::Integer(2), 2));
+        other.write(Document::new("notes/b", DocumentValue::Integer(3), 2));
+        let before = store.clone();
+        let err = store.merge_checked(&other, &engine, &ctx).unwrap_err();
+        assert_eq!(err.key, "config/a");
+        assert_eq!(store, before);
+    }
+
+    #[test]
+    fn test_merge_replicas() {
+        let mut a = MemoryStore::new().with_replica("a");
+        a.write(Document::new("shared", DocumentValue::Integer(1), 1));
+        a.write(Document::new("gone", DocumentValue::Integer(1), 1));
+        let mut b = MemoryStore::new().with_replica("b");
+        b.merge(&a);
+
+        // Concurrent writes to one key are both kept and detected
+        a.write(Document::new("shared", DocumentValue::Integer(2), 2));
+        b.write(Document::new("shared", DocumentValue::Integer(3), 2));
+        b.delete("gone", 3);
+        let report = a.merge(&b);
+        assert_eq!(
+            report.changed,
+            vec!["gone".to_string(), "shared".to_string()]
+        );
+        assert_eq!(report.concurrent, vec!["shared".to_string()]);
+        assert_eq!(a.concurrent_keys(), vec!["shared".to_string()]);
+        assert_eq!(a.read("shared").unwrap().value, DocumentValue::Integer(3));
+        assert!(a.read("gone").is_none());
+        assert_eq!(a.keys_for_event(3), &["!gone".to_string()]);
+
+        // A later write resolves the conflict on every replica
+        a.write(Document::new("shared", DocumentValue::Integer(4), 4));
+        b.merge(&a);
+        assert!(b.concurrent_keys().is_empty());
+        assert_eq!(b.read("shared").unwrap().value, DocumentValue::Integer(4));
+        assert_eq!(b.hash(), a.hash());
+    }
+
+    #[test]
+    fn test_schema_keys_merge_without_loss() {
+        use crate::crdt::CrdtKind;
+
+        let ints = |values: &[i64]| {
+            DocumentValue::Vec(values.iter().map(|&n| DocumentValue::Integer(n)).collect())
+        };
+        let schema = MemorySchema::new()
+            .with_prefix("steps", CrdtKind::Sequence)
+            .with_prefix("calls", CrdtKind::Counter);
+        let mut a = MemoryStore::new()
+            .with_replica("a")
+            .with_schema(schema.clone());
+        a.write(Document::new("steps", ints(&[1]), 1));
+        a.write(Document::new("calls", DocumentValue::Integer(1), 1));
+        let mut b = MemoryStore::new().with_replica("b").with_schema(schema);
+        b.merge(&a);
+
+        // Parallel branches each append a step and count a call
+        a.write(Document::new("steps", ints(&[1, 2]), 2));
+        a.write(Document::new("calls", DocumentValue::Integer(2), 2));
+        b.write(Document::new("steps", ints(&[1, 3]), 2));
+        b.write(Document::new("calls", DocumentValue::Integer(2), 2));
+        let report = a.merge(&b);
+        assert!(report.concurrent.is_empty());
+        assert_eq!(a.read("steps").unwrap().value, ints(&[1, 3, 2]));
+        assert_eq!(a.read("calls").unwrap().value, DocumentValue::Integer(3));
+
+        let wrong = a.write(Document::new("calls", "three".into(), 3));
+        assert_eq!(wrong.result, MergeResult::TypeMismatch);
+        assert!(a.keys_for_event(3).is_empty());
+
+        // Deleting empties the sequence; b's concurrent append survives
+        a.delete("steps", 4);
+        b.write(Document::new("steps", ints(&[1, 3, 4]), 4));
+        a.merge(&b);
+        assert_eq!(a.read("steps").unwrap().value, ints(&[4]));
+    }
+
     #[test]
     fn test_store_hash_changes() {
         let mut store = MemoryStore::new();
@@ -211,4 +614,107 @@ mod tests {
         assert_ne!(hash1, hash2);
         assert!(!hash2.is_zero());
     }
+
+    mod laws {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Write (`Some`) or delete (`None`) by a replica, or a sync between two
+        #[derive(Clone, Debug)]
+        enum Op {
+            Write(usize, usize, Option<Vec<i64>>, u64),
+            Sync(usize, usize),
+        }
+
+        /// `k0` is a register, `k1` to `k4` a counter, set, map and sequence
+        fn schema() -> MemorySchema {
+            use crate::crdt::CrdtKind;
+            MemorySchema::new()
+                .with_prefix("k1", CrdtKind::Counter)
+                .with_prefix("k2", CrdtKind::Set)
+                .with_prefix("k3", CrdtKind::Map)
+                .with_prefix("k4", CrdtKind::Sequence)
+        }
+
+        /// A value of the right type for key `k`
+        fn value(k: usize, v: Vec<i64>) -> DocumentValue {
+            let ints = v.iter().map(|&n| DocumentValue::Integer(n));
+            match k {
+                0 | 1 => DocumentValue::Integer(v.iter().sum()),
+                3 => DocumentValue::Map(
+                    v.iter()
+                        .enumerate()
+                        .map(|(i, &n)| (format!("f{}", n), DocumentValue::Integer(i as i64)))
+                        .collect(),
+                ),
+                _ => DocumentValue::Vec(ints.collect()),
+            }
+        }
+
+        fn op() -> impl Strategy<Value = Op> {
+            let values = proptest::collection::vec(0..4i64, 0..4);
+            prop_oneof![
+                (0..3usize, 0..5usize, proptest::option::of(values), 0..4u64)
+                    .prop_map(|(r, k, v, e)| Op::Write(r, k, v, e)),
+                (0..3usize, 0..3usize).prop_map(|(from, to)| Op::Sync(from, to)),
+            ]
+        }
+
+        /// Three replicas after running `ops` on them
+        ///
+        /// The replicas share one history: replica IDs are unique, so two
+        /// unrelated stores would reuse the same IDs for different writes.
+        fn replicas() -> impl Strategy<Value = Vec<MemoryStore>> {
+            proptest::collection::vec(op(), 0..24).prop_map(|ops| {
+                let mut replicas: Vec<MemoryStore> = ["a", "b", "c"]
+                    .iter()
+                    .map(|id| MemoryStore::new().with_replica(*id).with_schema(schema()))
+                    .collect();
+                for op in ops {
+                    match op {
+                        Op::Write(r, k, Some(v), e) => {
+                            let doc = Document::new(format!("k{}", k), value(k, v), e);
+                            replicas[r].write(doc);
+                        }
+                        Op::Write(r, k, None, e) => {
+                            replicas[r].delete(&format!("k{}", k), e);
+                        }
+                        Op::Sync(from, to) => {
+                            let source = replicas[from].clone();
+                            replicas[to].merge(&source);
+                        }
+                    }
+                }
+                replicas
+            })
+        }
+
+        fn merged(a: &MemoryStore, b: &MemoryStore) -> MemoryStore {
+            let mut out = a.clone();
+            out.merge(b);
+            out
+        }
+
+        proptest! {
+            #[test]
+            fn merge_is_commutative(r in replicas()) {
+                let (a, b) = (&r[0], &r[1]);
+                let ba = merged(&b.clone().with_replica(a.replica()), a);
+                prop_assert_eq!(merged(a, b), ba);
+            }
+
+            #[test]
+            fn merge_is_associative(r in replicas()) {
+                let (a, b, c) = (&r[0], &r[1], &r[2]);
+                prop_assert_eq!(merged(&merged(a, b), c), merged(a, &merged(b, c)));
+            }
+
+            #[test]
+            fn merge_is_idempotent(r in replicas()) {
+                let ab = merged(&r[0], &r[1]);
+                prop_assert_eq!(merged(&ab, &r[1]), ab.clone());
+                prop_assert_eq!(merged(&ab, &ab), ab);
+            }
+        }
+    }
 }
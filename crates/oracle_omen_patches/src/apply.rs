@@ -2,17 +2,18 @@
 
 use crate::{
     gate::GateResult,
-    patch::{Patch, PatchId, PatchStatus, PatchTarget},
+    patch::{Patch, PatchStatus, PatchTarget, SignedPatch},
     signature::Signature,
     signature::SignerId,
     store::PatchStore,
 };
 use oracle_omen_core::{
-    event::{Event, EventId, EventKind, EventPayload},
     hash::Hash,
-    state::AgentState,
+    state::{AgentState, StateData, StateValue},
     time::LogicalTime,
 };
+use oracle_omen_policy::engine::PolicyEngine;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 /// Patch application error
@@ -30,11 +31,22 @@ pub enum ApplyError {
     /// Patch not approved
     NotApproved,
 
+    /// Approval signed by a key that is not a trusted approver
+    UntrustedSigner(String),
+
     /// Application failed
     ApplicationFailed(String),
 
     /// Rollback failed
     RollbackFailed(String),
+
+    /// Patch is not at the lifecycle step the action needs
+    WrongStatus {
+        /// Status the action needs
+        expected: String,
+        /// Status the patch has
+        found: String,
+    },
 }
 
 impl std::fmt::Display for ApplyError {
@@ -44,8 +56,12 @@ impl std::fmt::Display for ApplyError {
             ApplyError::TestFailed(reason) => write!(f, "Test failed: {}", reason),
             ApplyError::AuditFailed(reason) => write!(f, "Audit failed: {}", reason),
             ApplyError::NotApproved => write!(f, "Patch not approved"),
+            ApplyError::UntrustedSigner(signer) => write!(f, "Untrusted approver: {}", signer),
             ApplyError::ApplicationFailed(reason) => write!(f, "Application failed: {}", reason),
             ApplyError::RollbackFailed(reason) => write!(f, "Rollback failed: {}", reason),
+            ApplyError::WrongStatus { expected, found } => {
+                write!(f, "Patch is {}, expected {}", found, expected)
+            }
         }
     }
 }
@@ -53,9 +69,14 @@ impl std::fmt::Display for ApplyError {
 impl std::error::Error for ApplyError {}
 
 /// Patch application engine
+///
+/// Patches move through `Proposed -> Tested -> Audited -> Approved ->
+/// Applied`; each step requires the one before it. Only the configured
+/// approvers can approve a patch, and none are trusted by default.
 pub struct PatchEngine {
     store: PatchStore,
-    applied: BTreeMap<String, AppliedPatch>,
+    policy: PolicyEngine,
+    approvers: Vec<SignerId>,
 }
 
 impl PatchEngine {
@@ -63,62 +84,99 @@ impl PatchEngine {
     pub fn new(store: PatchStore) -> Self {
         Self {
             store,
-            applied: BTreeMap::new(),
+            policy: PolicyEngine::new(),
+            approvers: Vec::new(),
         }
     }
 
+    /// Set the policies checked by the audit gate
+    pub fn with_policy(mut self, policy: PolicyEngine) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the signers trusted to approve patches
+    pub fn with_approvers(mut self, approvers: Vec<SignerId>) -> Self {
+        self.approvers = approvers;
+        self
+    }
+
+    /// Get the patch store
+    pub fn store(&self) -> &PatchStore {
+        &self.store
+    }
+
+    /// Take back the patch store
+    pub fn into_store(self) -> PatchStore {
+        self.store
+    }
+
     /// Submit a patch proposal
     pub fn submit(&mut self, patch: Patch) -> Result<(), ApplyError> {
         let id = patch.id.to_string();
         let status = PatchStatus::Proposed;
 
-        self.store.add_patch(id, patch, status).map_err(|e| {
-            ApplyError::ApplicationFailed(format!("Store error: {}", e))
-        })
+        self.store
+            .add_patch(id, patch, status)
+            .map_err(|e| ApplyError::ApplicationFailed(format!("Store error: {}", e)))
     }
 
-    /// Run test gate on a patch
+    /// Run test gate on a proposed patch
+    ///
+    /// The patch becomes `Tested` if it passes and `Rejected` otherwise.
     pub fn test_gate(
-        &self,
+        &mut self,
         patch_id: &str,
         runner: &dyn crate::gate::TestRunner,
     ) -> Result<GateResult, ApplyError> {
-        let (patch, _) = self.store.get_patch(patch_id)
-            .ok_or_else(|| ApplyError::NotFound(patch_id.to_string()))?;
-
-        Ok(crate::gate::TestGate::evaluate(&patch, runner))
+        let patch = self.expect_status(patch_id, PatchStatus::Proposed)?;
+        let result = crate::gate::TestGate::evaluate(&patch, runner);
+        self.record_gate(patch_id, &result, PatchStatus::Tested)?;
+        Ok(result)
     }
 
-    /// Run audit gate on a patch
+    /// Run audit gate on a tested patch
+    ///
+    /// The patch becomes `Audited` if it passes and `Rejected` otherwise.
     pub fn audit_gate(
-        &self,
+        &mut self,
         patch_id: &str,
         policy_ctx: &oracle_omen_policy::engine::EvalContext,
     ) -> Result<GateResult, ApplyError> {
-        let (patch, _) = self.store.get_patch(patch_id)
-            .ok_or_else(|| ApplyError::NotFound(patch_id.to_string()))?;
-
-        Ok(crate::gate::AuditGate::evaluate(&patch, policy_ctx))
+        let patch = self.expect_status(patch_id, PatchStatus::Tested)?;
+        let result = crate::gate::AuditGate::evaluate_with(&patch, &self.policy, policy_ctx);
+        self.record_gate(patch_id, &result, PatchStatus::Audited)?;
+        Ok(result)
     }
 
-    /// Approve a patch
+    /// Approve an audited patch
+    ///
+    /// `signature` must be a trusted approver's Ed25519 signature over the
+    /// hex patch hash. It is kept in the store alongside the patch.
     pub fn approve(
         &mut self,
         patch_id: &str,
         signature: Signature,
         signer: SignerId,
     ) -> Result<(), ApplyError> {
-        let gate = crate::gate::ApprovalGate::new(vec![signer.clone()]);
+        let patch = self.expect_status(patch_id, PatchStatus::Audited)?;
+        if !self.approvers.contains(&signer) {
+            return Err(ApplyError::UntrustedSigner(signer.to_hex()));
+        }
+        let gate = crate::gate::ApprovalGate::new(self.approvers.clone());
 
         let result = gate.evaluate(&signature, &signer);
-        if !result.is_passed() {
+        let signed = SignedPatch::new(patch, signature, signer);
+        if !result.is_passed() || !signed.verify() {
             return Err(ApplyError::NotApproved);
         }
 
-        self.store.update_status(
-            patch_id,
-            PatchStatus::Approved,
-        ).map_err(|e| ApplyError::ApplicationFailed(e.to_string()))
+        self.store
+            .add_approval(patch_id, signed.signature, signed.signer)
+            .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))?;
+        self.store
+            .update_status(patch_id, PatchStatus::Approved)
+            .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))
     }
 
     /// Apply a patch
@@ -127,13 +185,22 @@ impl PatchEngine {
         patch_id: &str,
         current_state: &mut AgentState,
     ) -> Result<ApplyResult, ApplyError> {
-        let (patch, status) = self.store.get_patch(patch_id)
+        let (patch, status) = self
+            .store
+            .get_patch(patch_id)
             .ok_or_else(|| ApplyError::NotFound(patch_id.to_string()))?;
 
-        // Check patch is approved
-        if !matches!(status, PatchStatus::Approved | PatchStatus::Tested) {
+        // Check patch is approved by a valid signature
+        let signed = self
+            .store
+            .approval(patch_id)
+            .ok_or(ApplyError::NotApproved)?;
+        if status != PatchStatus::Approved || !signed.verify() {
             return Err(ApplyError::NotApproved);
         }
+        if !self.approvers.contains(&signed.signer) {
+            return Err(ApplyError::UntrustedSigner(signed.signer.to_hex()));
+        }
 
         // Apply the patch
         let before_hash = current_state.hash();
@@ -142,152 +209,276 @@ impl PatchEngine {
 
         // Record application
         let rollback_data = result.rollback_data.clone();
+        let mut applied_data = BTreeMap::new();
+        for key in rollback_data.keys() {
+            let written = serde_json::to_string(&current_state.get(key))
+                .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))?;
+            applied_data.insert(key.clone(), written);
+        }
         let applied = AppliedPatch {
             patch_id: patch_id.to_string(),
             patch_hash: patch.hash(),
-            applied_at: LogicalTime::new(0, self.applied.len() as u64),
+            applied_at: LogicalTime::new(patch.id.run_id, patch.id.sequence),
             before_hash,
             after_hash,
             rollback_data,
+            applied_data,
         };
 
-        self.store.update_status(
-            patch_id,
-            PatchStatus::Applied,
-        ).map_err(|e| ApplyError::ApplicationFailed(e.to_string()))?;
+        self.store
+            .update_status(patch_id, PatchStatus::Applied)
+            .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))?;
 
-        self.applied.insert(patch_id.to_string(), applied);
+        self.store.record_applied(applied);
 
         Ok(result)
     }
 
-    /// Rollback a patch
+    /// Rollback an applied patch
+    ///
+    /// Every key the patch changed gets back the value it had before, or is
+    /// removed if the patch created it. The rollback is refused if a key no
+    /// longer holds the value the patch wrote, e.g. because a later patch
+    /// changed it; roll that patch back first.
     pub fn rollback(
         &mut self,
         patch_id: &str,
+        reason: &str,
         current_state: &mut AgentState,
     ) -> Result<RollbackResult, ApplyError> {
-        let applied = self.applied.get(patch_id)
+        let applied = self
+            .store
+            .applied(patch_id)
             .ok_or_else(|| ApplyError::NotFound(patch_id.to_string()))?;
 
-        // Restore previous state
-        current_state.set(
-            "_rollback",
-            oracle_omen_core::state::StateData::Value(
-                oracle_omen_core::state::StateValue::Hash(applied.before_hash),
-            ),
-        );
+        let parse = |key: &str, json: &str| -> Result<Option<StateData>, ApplyError> {
+            serde_json::from_str(json)
+                .map_err(|e| ApplyError::RollbackFailed(format!("{}: {}", key, e)))
+        };
+        let mut previous = Vec::new();
+        for (key, data) in &applied.rollback_data {
+            if let Some(written) = applied.applied_data.get(key) {
+                if parse(key, written)?.as_ref() != current_state.get(key) {
+                    return Err(ApplyError::RollbackFailed(format!(
+                        "{} changed after patch {} was applied",
+                        key, patch_id
+                    )));
+                }
+            }
+            previous.push((key.clone(), parse(key, data)?));
+        }
 
-        self.store.update_status(
-            patch_id,
-            PatchStatus::RolledBack { reason: "Manual rollback".to_string() },
-        ).map_err(|e| ApplyError::RollbackFailed(e.to_string()))?;
+        self.store
+            .update_status(
+                patch_id,
+                PatchStatus::RolledBack {
+                    reason: reason.to_string(),
+                },
+            )
+            .map_err(|e| ApplyError::RollbackFailed(e.to_string()))?;
+        self.store.take_applied(patch_id);
+
+        for (key, data) in previous {
+            match data {
+                Some(data) => current_state.set(key, data),
+                None => {
+                    current_state.remove(&key);
+                }
+            }
+        }
 
         Ok(RollbackResult {
             patch_id: patch_id.to_string(),
-            restored_to: applied.before_hash,
+            restored_to: current_state.hash(),
         })
     }
 
+    /// Get a patch, checking it is at the expected lifecycle step
+    fn expect_status(&self, patch_id: &str, expected: PatchStatus) -> Result<Patch, ApplyError> {
+        let (patch, status) = self
+            .store
+            .get_patch(patch_id)
+            .ok_or_else(|| ApplyError::NotFound(patch_id.to_string()))?;
+        if status != expected {
+            return Err(ApplyError::WrongStatus {
+                expected: status_name(&expected).to_string(),
+                found: status_name(&status).to_string(),
+            });
+        }
+        Ok(patch)
+    }
+
+    /// Move a patch on after a gate, or reject it
+    fn record_gate(
+        &mut self,
+        patch_id: &str,
+        result: &GateResult,
+        passed: PatchStatus,
+    ) -> Result<(), ApplyError> {
+        let status = match result {
+            GateResult::Passed => passed,
+            GateResult::Failed { reason, .. } | GateResult::Deferred { reason, .. } => {
+                PatchStatus::Rejected {
+                    reason: reason.clone(),
+                }
+            }
+        };
+        self.store
+            .update_status(patch_id, status)
+            .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))
+    }
+
     /// Apply a patch to state
+    ///
+    /// The previous value of each changed key is kept in `rollback_data` as
+    /// JSON, `null` if the key did not exist.
     fn apply_patch(
         &self,
         patch: &Patch,
         state: &mut AgentState,
     ) -> Result<ApplyResult, ApplyError> {
-        match &patch.target {
-            PatchTarget::SystemPrompt => {
-                // Update system prompt
-                state.set(
-                    "system_prompt",
-                    oracle_omen_core::state::StateData::Value(
-                        oracle_omen_core::state::StateValue::String(
-                            patch.data.get("prompt").cloned().unwrap_or_default()
-                        ),
-                    ),
-                );
-
-                Ok(ApplyResult {
-                    patch_id: patch.id.to_string(),
-                    changes_made: vec!["system_prompt".to_string()],
-                    rollback_data: BTreeMap::new(),
-                })
-            }
+        let (key, value) = match &patch.target {
+            PatchTarget::SystemPrompt => (
+                "system_prompt".to_string(),
+                patch.data.get("prompt").cloned().unwrap_or_default(),
+            ),
             PatchTarget::Config(key) => {
-                if let Some(value) = patch.data.get("value") {
-                    state.set(
-                        format!("config.{}", key),
-                        oracle_omen_core::state::StateData::Value(
-                            oracle_omen_core::state::StateValue::String(value.clone())
-                        ),
-                    );
-                }
-
-                Ok(ApplyResult {
-                    patch_id: patch.id.to_string(),
-                    changes_made: vec![format!("config.{}", key)],
-                    rollback_data: BTreeMap::new(),
-                })
+                let value = patch.data.get("value").cloned().ok_or_else(|| {
+                    ApplyError::ApplicationFailed("config patch missing 'value'".to_string())
+                })?;
+                (format!("config.{}", key), value)
             }
-            PatchTarget::Policy(name) => {
-                // Update policy
-                state.set(
-                    format!("policy.{}", name),
-                    oracle_omen_core::state::StateData::Value(
-                        oracle_omen_core::state::StateValue::String(
-                            patch.data.get("policy").cloned().unwrap_or_default()
-                        ),
-                    ),
-                );
-
-                Ok(ApplyResult {
-                    patch_id: patch.id.to_string(),
-                    changes_made: vec![format!("policy.{}", name)],
-                    rollback_data: BTreeMap::new(),
-                })
+            PatchTarget::Policy(name) => (
+                format!("policy.{}", name),
+                patch.data.get("policy").cloned().unwrap_or_default(),
+            ),
+            _ => {
+                return Err(ApplyError::ApplicationFailed(
+                    "Target type not implemented".to_string(),
+                ))
             }
-            _ => Err(ApplyError::ApplicationFailed(
-                "Target type not implemented".to_string()
-            )),
-        }
+        };
+
+        let previous = serde_json::to_string(&state.get(&key))
+            .map_err(|e| ApplyError::ApplicationFailed(e.to_string()))?;
+        let mut rollback_data = BTreeMap::new();
+        rollback_data.insert(key.clone(), previous);
+        state.set(key.clone(), StateData::Value(StateValue::String(value)));
+
+        Ok(ApplyResult {
+            patch_id: patch.id.to_string(),
+            changes_made: vec![key],
+            rollback_data,
+        })
+    }
+}
+
+/// Lower-case name of a patch status
+pub fn status_name(status: &PatchStatus) -> &'static str {
+    match status {
+        PatchStatus::Proposed => "proposed",
+        PatchStatus::Tested => "tested",
+        PatchStatus::Audited => "audited",
+        PatchStatus::Approved => "approved",
+        PatchStatus::Applied => "applied",
+        PatchStatus::Rejected { .. } => "rejected",
+        PatchStatus::RolledBack { .. } => "rolled back",
     }
 }
 
 /// Result of applying a patch
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ApplyResult {
+    /// Applied patch
     pub patch_id: String,
+    /// State keys the patch targets
     pub changes_made: Vec<String>,
+    /// Previous value of each changed key, as JSON
     pub rollback_data: BTreeMap<String, String>,
 }
 
 /// Result of rolling back a patch
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RollbackResult {
+    /// Rolled back patch
     pub patch_id: String,
+    /// State hash after the rollback
     pub restored_to: Hash,
 }
 
 /// Record of an applied patch
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AppliedPatch {
+    /// Applied patch
     pub patch_id: String,
+    /// Hash of the patch content
     pub patch_hash: Hash,
+    /// Logical time of the patch's proposal
     pub applied_at: LogicalTime,
+    /// State hash before applying
     pub before_hash: Hash,
+    /// State hash after applying
     pub after_hash: Hash,
+    /// Previous value of each changed key, as JSON
     pub rollback_data: BTreeMap<String, String>,
+    /// Value the patch wrote to each changed key, as JSON
+    #[serde(default)]
+    pub applied_data: BTreeMap<String, String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::patch::PatchId;
+    use crate::gate::DeterminismTestRunner;
+    use crate::patch::{PatchId, PatchType};
+    use crate::signature::KeyPair;
+    use oracle_omen_policy::{
+        compiler::PolicyCompiler,
+        engine::EvalContext,
+        lang::{Action, Condition, Policy, Rule, RuleKind},
+    };
+
+    fn allow_patches() -> PolicyEngine {
+        let mut policy = Policy::new("patches", "1.0.0");
+        policy.add_rule(Rule {
+            name: "allow_patches".to_string(),
+            kind: RuleKind::Patch,
+            condition: Condition::True,
+            action: Action::Allow,
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        engine
+    }
+
+    /// Submit a patch and take it through the test, audit and approval gates
+    fn approved(patch: &Patch) -> PatchEngine {
+        let kp = KeyPair::generate();
+        let mut engine = PatchEngine::new(PatchStore::new())
+            .with_policy(allow_patches())
+            .with_approvers(vec![kp.signer_id()]);
+        approve_in(&mut engine, &kp, patch);
+        engine
+    }
+
+    /// Take a patch through every gate of an engine trusting `kp`
+    fn approve_in(engine: &mut PatchEngine, kp: &KeyPair, patch: &Patch) {
+        let id = patch.id.to_string();
+        engine.submit(patch.clone()).unwrap();
+        assert!(engine
+            .test_gate(&id, &DeterminismTestRunner)
+            .unwrap()
+            .is_passed());
+        assert!(engine
+            .audit_gate(&id, &EvalContext::default())
+            .unwrap()
+            .is_passed());
+        let signature = kp.sign(patch.hash().to_hex().as_bytes());
+        engine.approve(&id, signature, kp.signer_id()).unwrap();
+    }
 
     #[test]
     fn test_patch_apply_system_prompt() {
-        let store = PatchStore::new();
-        let mut engine = PatchEngine::new(store);
         let mut state = AgentState::initial();
 
         let patch = Patch::new(
@@ -298,18 +489,17 @@ mod tests {
         )
         .with_data("prompt", "You are a helpful assistant.");
 
-        engine.submit(patch.clone()).unwrap();
-        engine.store.update_status(&patch.id.to_string(), PatchStatus::Approved).unwrap();
-
+        let mut engine = approved(&patch);
         let result = engine.apply(&patch.id.to_string(), &mut state).unwrap();
         assert!(result.changes_made.contains(&"system_prompt".to_string()));
     }
 
     #[test]
     fn test_patch_rollback() {
-        let store = PatchStore::new();
-        let mut engine = PatchEngine::new(store);
+        let value = |v: &str| StateData::Value(StateValue::String(v.to_string()));
         let mut state = AgentState::initial();
+        state.set("config.test", value("7"));
+        let before = state.clone();
 
         let patch = Patch::new(
             PatchId::new(1, 0),
@@ -319,11 +509,164 @@ mod tests {
         )
         .with_data("value", "42");
 
+        let mut engine = approved(&patch);
+        let id = patch.id.to_string();
+        engine.apply(&id, &mut state).unwrap();
+        assert_eq!(state.get("config.test"), Some(&value("42")));
+
+        let result = engine.rollback(&id, "regressed", &mut state).unwrap();
+        assert_eq!(state.data, before.data);
+        assert_eq!(result.restored_to, before.hash());
+        let (_, status) = engine.store().get_patch(&id).unwrap();
+        assert_eq!(
+            status,
+            PatchStatus::RolledBack {
+                reason: "regressed".to_string()
+            }
+        );
+        assert!(engine.rollback(&id, "again", &mut state).is_err());
+    }
+
+    #[test]
+    fn test_rollback_refuses_overwritten_key() {
+        let value = |v: &str| StateData::Value(StateValue::String(v.to_string()));
+        let config = |sequence: u64, v: &str| {
+            Patch::new(
+                PatchId::new(1, sequence),
+                PatchType::Config,
+                PatchTarget::Config("test".to_string()),
+                "Test".to_string(),
+            )
+            .with_data("value", v)
+        };
+        let (first, second) = (config(0, "42"), config(1, "43"));
+        let kp = KeyPair::generate();
+        let mut engine = PatchEngine::new(PatchStore::new())
+            .with_policy(allow_patches())
+            .with_approvers(vec![kp.signer_id()]);
+        approve_in(&mut engine, &kp, &first);
+        approve_in(&mut engine, &kp, &second);
+
+        let mut state = AgentState::initial();
+        let (first_id, second_id) = (first.id.to_string(), second.id.to_string());
+        engine.apply(&first_id, &mut state).unwrap();
+        engine.apply(&second_id, &mut state).unwrap();
+
+        let err = engine
+            .rollback(&first_id, "regressed", &mut state)
+            .unwrap_err();
+        assert!(matches!(err, ApplyError::RollbackFailed(_)));
+        assert_eq!(state.get("config.test"), Some(&value("43")));
+        let (_, status) = engine.store().get_patch(&first_id).unwrap();
+        assert_eq!(status, PatchStatus::Applied);
+
+        engine
+            .rollback(&second_id, "regressed", &mut state)
+            .unwrap();
+        assert_eq!(state.get("config.test"), Some(&value("42")));
+        engine.rollback(&first_id, "regressed", &mut state).unwrap();
+        assert_eq!(state.get("config.test"), None);
+    }
+
+    #[test]
+    fn test_config_patch_needs_value() {
+        let patch = Patch::new(
+            PatchId::new(1, 0),
+            PatchType::Config,
+            PatchTarget::Config("test".to_string()),
+            "Test".to_string(),
+        );
+        let mut engine = approved(&patch);
+        let mut state = AgentState::initial();
+        let err = engine.apply(&patch.id.to_string(), &mut state).unwrap_err();
+        assert_eq!(
+            err,
+            ApplyError::ApplicationFailed("config patch missing 'value'".to_string())
+        );
+        assert!(state.data.is_empty());
+        let (_, status) = engine.store().get_patch(&patch.id.to_string()).unwrap();
+        assert_eq!(status, PatchStatus::Approved);
+    }
+
+    #[test]
+    fn test_gates_enforce_order() {
+        let patch = Patch::new(
+            PatchId::new(1, 0),
+            PatchType::Prompt,
+            PatchTarget::SystemPrompt,
+            "Update prompt".to_string(),
+        );
+        let id = patch.id.to_string();
+        let kp = KeyPair::generate();
+        let signature = kp.sign(patch.hash().to_hex().as_bytes());
+
+        let mut engine = PatchEngine::new(PatchStore::new());
+        engine.submit(patch.clone()).unwrap();
+        let err = engine
+            .approve(&id, signature.clone(), kp.signer_id())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Patch is proposed, expected audited");
+        let err = engine.apply(&id, &mut AgentState::initial()).unwrap_err();
+        assert_eq!(err, ApplyError::NotApproved);
+
+        // Without policies the audit gate denies and rejects the patch
+        engine.test_gate(&id, &DeterminismTestRunner).unwrap();
+        let result = engine.audit_gate(&id, &EvalContext::default()).unwrap();
+        assert!(!result.is_passed());
+        let (_, status) = engine.store().get_patch(&id).unwrap();
+        assert!(matches!(status, PatchStatus::Rejected { .. }));
+
+        // A signature over different bytes does not approve
+        let mut engine = PatchEngine::new(PatchStore::new())
+            .with_policy(allow_patches())
+            .with_approvers(vec![kp.signer_id()]);
         engine.submit(patch.clone()).unwrap();
-        engine.store.update_status(&patch.id.to_string(), PatchStatus::Approved).unwrap();
-        engine.apply(&patch.id.to_string(), &mut state).unwrap();
+        engine.test_gate(&id, &DeterminismTestRunner).unwrap();
+        engine.audit_gate(&id, &EvalContext::default()).unwrap();
+        let forged = kp.sign(b"something else");
+        assert_eq!(
+            engine.approve(&id, forged, kp.signer_id()),
+            Err(ApplyError::NotApproved)
+        );
+        assert!(engine.approve(&id, signature, kp.signer_id()).is_ok());
+    }
+
+    #[test]
+    fn test_untrusted_signer_refused() {
+        let patch = Patch::new(
+            PatchId::new(1, 0),
+            PatchType::Prompt,
+            PatchTarget::SystemPrompt,
+            "Update prompt".to_string(),
+        );
+        let id = patch.id.to_string();
+        let trusted = KeyPair::from_secret([1u8; 32]);
+        let stranger = KeyPair::from_secret([2u8; 32]);
+        let signature = stranger.sign(patch.hash().to_hex().as_bytes());
+
+        // A valid signature from an unknown key does not approve
+        let mut engine = PatchEngine::new(PatchStore::new())
+            .with_policy(allow_patches())
+            .with_approvers(vec![trusted.signer_id()]);
+        engine.submit(patch.clone()).unwrap();
+        engine.test_gate(&id, &DeterminismTestRunner).unwrap();
+        engine.audit_gate(&id, &EvalContext::default()).unwrap();
+        let err = engine
+            .approve(&id, signature, stranger.signer_id())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ApplyError::UntrustedSigner(stranger.signer_id().to_hex())
+        );
 
-        let result = engine.rollback(&patch.id.to_string(), &mut state);
-        assert!(result.is_ok());
+        // An approval from a key that is no longer trusted does not apply
+        let signature = trusted.sign(patch.hash().to_hex().as_bytes());
+        engine.approve(&id, signature, trusted.signer_id()).unwrap();
+        let mut engine = PatchEngine::new(engine.into_store());
+        let err = engine.apply(&id, &mut AgentState::initial()).unwrap_err();
+        assert_eq!(
+            err,
+            ApplyError::UntrustedSigner(trusted.signer_id().to_hex())
+        );
     }
 }
@@ -1,8 +1,6 @@
 //! Patch gates: test, audit, and approval.
 
 use crate::{patch::Patch, signature::SignerId};
-use oracle_omen_core::hash::Hash;
-use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 /// Gate result
@@ -13,13 +11,17 @@ pub enum GateResult {
 
     /// Patch failed the gate
     Failed {
+        /// Why the gate failed
         reason: String,
+        /// Per-check details
         details: BTreeMap<String, String>,
     },
 
     /// Gate deferred (needs more info)
     Deferred {
+        /// Why the gate could not decide
         reason: String,
+        /// Information still needed
         needs: Vec<String>,
     },
 }
@@ -64,6 +66,19 @@ impl TestGate {
         patch: &Patch,
         test_runner: &dyn TestRunner,
     ) -> GateResult {
+        let unsupported: Vec<String> = patch
+            .tests
+            .iter()
+            .filter(|test| !test_runner.supports(&test.test_type))
+            .map(|test| test.name.clone())
+            .collect();
+        if !unsupported.is_empty() {
+            return GateResult::Deferred {
+                reason: format!("No runner for tests: {}", unsupported.join(", ")),
+                needs: unsupported,
+            };
+        }
+
         let mut results = Vec::new();
         let mut failures = Vec::new();
 
@@ -105,14 +120,28 @@ impl TestGate {
 pub struct AuditGate;
 
 impl AuditGate {
-    /// Evaluate patch against audit gate
+    /// Evaluate patch against audit gate with no policies loaded
+    ///
+    /// Patch rules default to deny, so this fails every patch.
     pub fn evaluate(
         patch: &Patch,
         policy_engine: &oracle_omen_policy::engine::EvalContext,
+    ) -> GateResult {
+        Self::evaluate_with(
+            patch,
+            &oracle_omen_policy::engine::PolicyEngine::new(),
+            policy_engine,
+        )
+    }
+
+    /// Evaluate patch against audit gate using `policies`
+    pub fn evaluate_with(
+        patch: &Patch,
+        policies: &oracle_omen_policy::engine::PolicyEngine,
+        context: &oracle_omen_policy::engine::EvalContext,
     ) -> GateResult {
         // Check policy allows this patch type
-        let policy_result = oracle_omen_policy::engine::PolicyEngine::new()
-            .evaluate_patch(&format!("{:?}", patch.patch_type), policy_engine);
+        let policy_result = policies.evaluate_patch(&format!("{:?}", patch.patch_type), context);
 
         if !policy_result.allowed {
             return GateResult::failed(policy_result.reason);
@@ -170,6 +199,13 @@ impl ApprovalGate {
 pub trait TestRunner: Send + Sync {
     /// Run a single test
     fn run_test(&self, patch: &Patch, test: &crate::patch::TestRequirement) -> TestResult;
+
+    /// Whether this runner can execute tests of a type
+    ///
+    /// The test gate defers a patch with a test no runner can execute.
+    fn supports(&self, _test_type: &crate::patch::TestType) -> bool {
+        true
+    }
 }
 
 /// Result of running a test
@@ -226,10 +226,16 @@ pub enum PatchStatus {
     Applied,
 
     /// Rejected
-    Rejected { reason: String },
+    Rejected {
+        /// Why the patch was rejected
+        reason: String,
+    },
 
     /// Rolled back
-    RolledBack { reason: String },
+    RolledBack {
+        /// Why the patch was rolled back
+        reason: String,
+    },
 }
 
 /// Signed patch with signature
@@ -1,8 +1,8 @@
 //! Cryptographic signatures for patches.
 
 use serde::{Deserialize, Serialize};
-use serde_bytes::{ByteBuf as SerdeByteBuf};
 use std::fmt;
+use std::path::Path;
 
 /// Signature using Ed25519
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,25 +25,24 @@ impl Signature {
 
     /// Parse from hex
     pub fn from_hex(hex: &str) -> Result<Self, SignatureError> {
-        if hex.len() != 128 {
-            return Err(SignatureError::InvalidLength);
-        }
-
-        let mut bytes = Vec::with_capacity(64);
-        for i in 0..64 {
-            let byte_str = &hex[i * 2..i * 2 + 2];
-            bytes.push(u8::from_str_radix(byte_str, 16)
-                .map_err(|_| SignatureError::InvalidHex)?);
-        }
-
-        Ok(Self { bytes })
+        Ok(Self {
+            bytes: decode_hex(hex, 64)?,
+        })
     }
 
-    /// Verify signature
-    pub fn verify(&self, _message: &[u8], _signer: &SignerId) -> bool {
-        // In production, use ed25519_dalek
-        // For now, placeholder that checks signature format
-        self.bytes.len() == 64 && self.bytes.iter().any(|&b| b != 0)
+    /// Verify an Ed25519 signature over `message` by `signer`
+    pub fn verify(&self, message: &[u8], signer: &SignerId) -> bool {
+        let (Ok(bytes), Ok(key)) = (
+            <[u8; 64]>::try_from(self.bytes.as_slice()),
+            <[u8; 32]>::try_from(signer.public_key.as_slice()),
+        ) else {
+            return false;
+        };
+        let Ok(key) = ed25519_dalek::VerifyingKey::from_bytes(&key) else {
+            return false;
+        };
+        key.verify_strict(message, &ed25519_dalek::Signature::from_bytes(&bytes))
+            .is_ok()
     }
 }
 
@@ -68,18 +67,9 @@ impl SignerId {
 
     /// Parse from hex
     pub fn from_hex(hex: &str) -> Result<Self, SignatureError> {
-        if hex.len() != 64 {
-            return Err(SignatureError::InvalidLength);
-        }
-
-        let mut public_key = Vec::with_capacity(32);
-        for i in 0..32 {
-            let byte_str = &hex[i * 2..i * 2 + 2];
-            public_key.push(u8::from_str_radix(byte_str, 16)
-                .map_err(|_| SignatureError::InvalidHex)?);
-        }
-
-        Ok(Self { public_key })
+        Ok(Self {
+            public_key: decode_hex(hex, 32)?,
+        })
     }
 }
 
@@ -90,62 +80,114 @@ impl fmt::Display for SignerId {
     }
 }
 
+/// Load trusted approvers from a file of hex public keys
+///
+/// One key per line; blank lines and lines starting with `#` are skipped.
+/// A missing file trusts no one.
+pub fn load_approvers(path: impl AsRef<Path>) -> Result<Vec<SignerId>, SignatureError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| SignatureError::Io(format!("{}: {}", path.display(), e)))?;
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(SignerId::from_hex)
+        .collect()
+}
+
+/// Decode `len` bytes from hex digits
+///
+/// Anything but exactly `2 * len` ASCII hex digits is refused, so a
+/// multi-byte character cannot split a digit pair.
+fn decode_hex(hex: &str, len: usize) -> Result<Vec<u8>, SignatureError> {
+    if hex.len() != len * 2 {
+        return Err(SignatureError::InvalidLength);
+    }
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(SignatureError::InvalidHex);
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or(SignatureError::InvalidHex)
+        })
+        .collect()
+}
+
 /// Key pair for signing
 #[derive(Clone)]
 pub struct KeyPair {
+    /// Ed25519 secret key bytes
     pub secret_key: Vec<u8>,
+    /// Ed25519 public key bytes
     pub public_key: Vec<u8>,
 }
 
 impl KeyPair {
-    /// Generate new key pair
+    /// Generate a fixed key pair for tests and examples
+    ///
+    /// The secret is not random; use [`KeyPair::load`] for real keys.
     pub fn generate() -> Self {
-        // In production, use ed25519_dalep::SigningKey::generate
-        // For now, placeholder
-        let mut secret_key = vec![0u8; 32];
-        let mut public_key = vec![0u8; 32];
-
-        // Fill with non-zero for testing
-        for i in 0..32 {
-            secret_key[i] = i as u8;
-            public_key[i] = (i + 32) as u8;
+        let mut secret = [0u8; 32];
+        for (i, byte) in secret.iter_mut().enumerate() {
+            *byte = i as u8;
         }
+        Self::from_secret(secret)
+    }
 
+    /// Derive the key pair for an Ed25519 secret key
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        let signing = ed25519_dalek::SigningKey::from_bytes(&secret);
         Self {
-            secret_key,
-            public_key,
+            secret_key: secret.to_vec(),
+            public_key: signing.verifying_key().to_bytes().to_vec(),
         }
     }
 
+    /// Load a key file holding a hex-encoded 32-byte secret key
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SignatureError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| SignatureError::Io(format!("{}: {}", path.display(), e)))?;
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&decode_hex(text.trim(), 32)?);
+        Ok(Self::from_secret(secret))
+    }
+
     /// Get signer ID
     pub fn signer_id(&self) -> SignerId {
         SignerId { public_key: self.public_key.clone() }
     }
 
-    /// Sign a message
+    /// Sign a message with Ed25519
     pub fn sign(&self, message: &[u8]) -> Signature {
-        use sha2::Digest;
-        // In production: use ed25519_dalek::SigningKey
-        // Placeholder: compute simple hash-based signature
-        let mut sig = vec![0u8; 64];
-
-        // Simple signing for demonstration
-        let hash = sha2::Sha256::digest(message);
-        for i in 0..32 {
-            sig[i] = hash[i];
-            sig[i + 32] = self.secret_key[i];
+        use ed25519_dalek::Signer;
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(&self.secret_key);
+        let signing = ed25519_dalek::SigningKey::from_bytes(&secret);
+        Signature {
+            bytes: signing.sign(message).to_bytes().to_vec(),
         }
-
-        Signature { bytes: sig }
     }
 }
 
 /// Signature errors
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SignatureError {
+    /// Hex string has the wrong length
     InvalidLength,
+    /// Hex string has a non-hex character
     InvalidHex,
+    /// Signature does not match
     VerificationFailed,
+    /// Key file could not be read
+    Io(String),
 }
 
 impl std::fmt::Display for SignatureError {
@@ -154,6 +196,7 @@ impl std::fmt::Display for SignatureError {
             SignatureError::InvalidLength => write!(f, "Invalid signature length"),
             SignatureError::InvalidHex => write!(f, "Invalid hex encoding"),
             SignatureError::VerificationFailed => write!(f, "Signature verification failed"),
+            SignatureError::Io(msg) => write!(f, "Key file error: {}", msg),
         }
     }
 }
@@ -179,8 +222,48 @@ mod tests {
         let sig = kp.sign(message);
         let signer = kp.signer_id();
 
-        // Note: verify is a placeholder, will use real crypto in production
-        assert!(sig.bytes != [0u8; 64]);
+        assert!(sig.verify(message, &signer));
+        assert!(!sig.verify(b"other message", &signer));
+        assert!(!sig.verify(message, &KeyPair::from_secret([7u8; 32]).signer_id()));
+    }
+
+    #[test]
+    fn test_load_key_file() {
+        let path = std::env::temp_dir().join(format!("oracle-omen-key-{}", std::process::id()));
+        let hex: String = (0..32u8).map(|b| format!("{:02x}", b)).collect();
+        std::fs::write(&path, format!("{}\n", hex)).unwrap();
+        let kp = KeyPair::load(&path).unwrap();
+        assert_eq!(kp.public_key, KeyPair::generate().public_key);
+
+        std::fs::write(&path, "abcd").unwrap();
+        assert_eq!(
+            KeyPair::load(&path).err(),
+            Some(SignatureError::InvalidLength)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_approvers() {
+        let path = std::env::temp_dir().join(format!("oracle-omen-approvers-{}", std::process::id()));
+        assert_eq!(load_approvers(&path), Ok(Vec::new()));
+
+        let signer = KeyPair::generate().signer_id();
+        std::fs::write(&path, format!("# release team\n\n{}\n", signer.to_hex())).unwrap();
+        assert_eq!(load_approvers(&path), Ok(vec![signer]));
+
+        std::fs::write(&path, "not a key\n").unwrap();
+        assert!(load_approvers(&path).is_err());
+
+        // Right byte length, but a two-byte character straddles a digit pair
+        let split = format!("a\u{e9}{}", "a".repeat(61));
+        assert_eq!(split.len(), 64);
+        std::fs::write(&path, &split).unwrap();
+        assert_eq!(load_approvers(&path), Err(SignatureError::InvalidHex));
+        assert_eq!(KeyPair::load(&path).err(), Some(SignatureError::InvalidHex));
+        let signature = format!("a\u{e9}{}", "a".repeat(125));
+        assert_eq!(Signature::from_hex(&signature), Err(SignatureError::InvalidHex));
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
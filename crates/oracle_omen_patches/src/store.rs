@@ -1,16 +1,32 @@
 //! Storage for patches.
 
 use crate::{
+    apply::AppliedPatch,
     patch::{Patch, PatchStatus, SignedPatch},
     signature::{Signature, SignerId},
 };
-use oracle_omen_core::hash::Hash;
+use oracle_omen_core::{hash::Hash, state::AgentState};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
 
 /// Storage for patches and their status
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct PatchStore {
     patches: BTreeMap<String, (Patch, PatchStatus)>,
+
+    /// Approval signatures by patch ID
+    #[serde(default)]
+    approvals: BTreeMap<String, (Signature, SignerId)>,
+
+    /// Applied patches that can still be rolled back
+    #[serde(default)]
+    applied: BTreeMap<String, AppliedPatch>,
+
+    /// State left by the latest apply or rollback, by run ID
+    #[serde(default)]
+    states: BTreeMap<u64, AgentState>,
 }
 
 impl PatchStore {
@@ -19,6 +35,34 @@ impl PatchStore {
         Self::default()
     }
 
+    /// Load a store from a JSON file
+    ///
+    /// A missing file yields an empty store.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, StoreError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let bytes = fs::read(path).map_err(|e| StoreError::Io(e.to_string()))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| StoreError::Corrupted(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Write the store to a JSON file
+    ///
+    /// The file is written next to the target and renamed into place.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StoreError> {
+        let path = path.as_ref();
+        let io = |e: std::io::Error| StoreError::Io(e.to_string());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(io)?;
+        }
+        let json = serde_json::to_vec_pretty(self).map_err(|e| StoreError::Io(e.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json).map_err(io)?;
+        fs::rename(&tmp, path).map_err(io)
+    }
+
     /// Add a patch
     pub fn add_patch(
         &mut self,
@@ -56,6 +100,98 @@ impl PatchStore {
             .collect()
     }
 
+    /// Record the approval signature for a patch
+    pub fn add_approval(
+        &mut self,
+        id: &str,
+        signature: Signature,
+        signer: SignerId,
+    ) -> Result<(), StoreError> {
+        if !self.patches.contains_key(id) {
+            return Err(StoreError::NotFound(id.to_string()));
+        }
+        self.approvals.insert(id.to_string(), (signature, signer));
+        Ok(())
+    }
+
+    /// Get a patch with its approval signature
+    pub fn approval(&self, id: &str) -> Option<SignedPatch> {
+        let (patch, _) = self.patches.get(id)?;
+        let (signature, signer) = self.approvals.get(id)?;
+        Some(SignedPatch::new(
+            patch.clone(),
+            signature.clone(),
+            signer.clone(),
+        ))
+    }
+
+    /// Record an applied patch
+    pub fn record_applied(&mut self, applied: AppliedPatch) {
+        self.applied.insert(applied.patch_id.clone(), applied);
+    }
+
+    /// Get the application record of a patch
+    pub fn applied(&self, id: &str) -> Option<&AppliedPatch> {
+        self.applied.get(id)
+    }
+
+    /// Remove and return the application record of a patch
+    pub fn take_applied(&mut self, id: &str) -> Option<AppliedPatch> {
+        self.applied.remove(id)
+    }
+
+    /// State of a run as left by its latest apply or rollback
+    pub fn state(&self, run_id: u64) -> Option<&AgentState> {
+        self.states.get(&run_id)
+    }
+
+    /// Record the state of a run after an apply or rollback
+    pub fn set_state(&mut self, run_id: u64, state: AgentState) {
+        self.states.insert(run_id, state);
+    }
+
+    /// Patches, approvals, applied records and state of one run
+    ///
+    /// A patch belongs to the run named by its `<run_id>:<sequence>` ID.
+    pub fn for_run(&self, run_id: u64) -> Self {
+        let prefix = format!("{}:", run_id);
+        Self {
+            patches: owned_by(&self.patches, &prefix),
+            approvals: owned_by(&self.approvals, &prefix),
+            applied: owned_by(&self.applied, &prefix),
+            states: self
+                .states
+                .get_key_value(&run_id)
+                .map(|(id, state)| (*id, state.clone()))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Whether the store holds no patches
+    pub fn is_empty(&self) -> bool {
+        self.patches.is_empty()
+    }
+
+    /// Add the entries of another store
+    ///
+    /// Entries present in both stores must be identical; on a conflict
+    /// nothing is added.
+    pub fn merge(&mut self, other: Self) -> Result<(), StoreError> {
+        if let Some(id) = conflict(&self.patches, &other.patches)
+            .or_else(|| conflict(&self.approvals, &other.approvals))
+            .or_else(|| conflict(&self.applied, &other.applied))
+            .or_else(|| conflict(&self.states, &other.states))
+        {
+            return Err(StoreError::AlreadyExists(id));
+        }
+        self.patches.extend(other.patches);
+        self.approvals.extend(other.approvals);
+        self.applied.extend(other.applied);
+        self.states.extend(other.states);
+        Ok(())
+    }
+
     /// Get patches by status
     pub fn get_by_status(&self, status: PatchStatus) -> Vec<Patch> {
         use PatchStatus::*;
@@ -79,6 +215,25 @@ impl PatchStore {
     }
 }
 
+/// Entries whose key starts with `prefix`
+fn owned_by<V: Clone>(map: &BTreeMap<String, V>, prefix: &str) -> BTreeMap<String, V> {
+    map.iter()
+        .filter(|(id, _)| id.starts_with(prefix))
+        .map(|(id, v)| (id.clone(), v.clone()))
+        .collect()
+}
+
+/// First key present in both maps with different values
+fn conflict<K: Ord + ToString, V: PartialEq>(
+    ours: &BTreeMap<K, V>,
+    theirs: &BTreeMap<K, V>,
+) -> Option<String> {
+    theirs
+        .iter()
+        .find(|(id, v)| ours.get(*id).is_some_and(|ours| ours != *v))
+        .map(|(id, _)| id.to_string())
+}
+
 /// Storage for signed patches
 #[derive(Clone, Default)]
 pub struct SignedPatchStore {
@@ -122,9 +277,14 @@ impl SignedPatchStore {
 /// Store errors
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum StoreError {
+    /// Entry with this ID already exists
     AlreadyExists(String),
+    /// No entry with this ID
     NotFound(String),
+    /// Store file could not be parsed
     Corrupted(String),
+    /// Reading or writing the store file failed
+    Io(String),
 }
 
 impl std::fmt::Display for StoreError {
@@ -133,6 +293,7 @@ impl std::fmt::Display for StoreError {
             StoreError::AlreadyExists(id) => write!(f, "Already exists: {}", id),
             StoreError::NotFound(id) => write!(f, "Not found: {}", id),
             StoreError::Corrupted(msg) => write!(f, "Corrupted: {}", msg),
+            StoreError::Io(msg) => write!(f, "IO error: {}", msg),
         }
     }
 }
@@ -203,4 +364,77 @@ mod tests {
         let retrieved = store.get(&signed.hash()).unwrap();
         assert_eq!(retrieved.patch.id, signed.patch.id);
     }
+
+    #[test]
+    fn test_patch_store_persistence() {
+        let dir = std::env::temp_dir().join(format!("oracle-omen-patches-{}", std::process::id()));
+        let path = dir.join("patches.json");
+        assert_eq!(PatchStore::load(&path).unwrap().list_patches().len(), 0);
+
+        let mut store = PatchStore::new();
+        let patch = Patch::new(
+            PatchId::new(1, 0),
+            PatchType::Prompt,
+            PatchTarget::SystemPrompt,
+            "test".to_string(),
+        );
+        let id = patch.id.to_string();
+        store
+            .add_patch(id.clone(), patch.clone(), PatchStatus::Audited)
+            .unwrap();
+        let kp = crate::signature::KeyPair::generate();
+        let sig = kp.sign(patch.hash().to_hex().as_bytes());
+        store.add_approval(&id, sig, kp.signer_id()).unwrap();
+        store.save(&path).unwrap();
+
+        let mut loaded = PatchStore::load(&path).unwrap();
+        let (_, status) = loaded.get_patch(&id).unwrap();
+        assert_eq!(status, PatchStatus::Audited);
+        assert!(loaded.approval(&id).unwrap().verify());
+        let missing = loaded.add_approval("2:0", Signature::from_bytes([1u8; 64]), kp.signer_id());
+        assert_eq!(missing, Err(StoreError::NotFound("2:0".to_string())));
+
+        std::fs::write(&path, "{").unwrap();
+        assert!(matches!(
+            PatchStore::load(&path),
+            Err(StoreError::Corrupted(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_for_run_and_merge() {
+        let patch = |run_id, sequence| {
+            Patch::new(
+                PatchId::new(run_id, sequence),
+                PatchType::Prompt,
+                PatchTarget::SystemPrompt,
+                "test".to_string(),
+            )
+        };
+        let mut store = PatchStore::new();
+        for p in [patch(1, 0), patch(1, 4), patch(12, 0)] {
+            store
+                .add_patch(p.id.to_string(), p, PatchStatus::Proposed)
+                .unwrap();
+        }
+
+        let run = store.for_run(1);
+        assert_eq!(run.list_patches().len(), 2);
+        assert!(run.get_patch("12:0").is_none());
+
+        // Merging is idempotent, and conflicting entries are refused whole
+        let mut merged = store.for_run(12);
+        merged.merge(run.clone()).unwrap();
+        merged.merge(run.clone()).unwrap();
+        assert_eq!(merged.list_patches().len(), 3);
+
+        let mut changed = run;
+        changed.update_status("1:4", PatchStatus::Tested).unwrap();
+        assert_eq!(
+            merged.merge(changed),
+            Err(StoreError::AlreadyExists("1:4".to_string()))
+        );
+        assert_eq!(merged.get_patch("1:4").unwrap().1, PatchStatus::Proposed);
+    }
 }
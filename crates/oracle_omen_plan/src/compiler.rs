@@ -154,6 +154,6 @@ mod tests {
 
         let dag = PlanCompiler::compile(&plan).unwrap();
         assert_eq!(dag.len(), 2);
-        assert_eq!(dag.dependencies("b"), &["a".to_string()].into());
+        assert_eq!(dag.dependencies("b"), Some(&["a".to_string()].into()));
     }
 }
@@ -2,6 +2,7 @@
 //!
 //! Compiled form of a Plan with validated dependencies.
 
+use oracle_omen_core::tool::SideEffect;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 /// A DAG representing a compiled plan
@@ -13,10 +14,10 @@ pub struct Dag {
     /// DAG nodes
     nodes: BTreeMap<String, DagNode>,
 
-    /// Edges: node -> dependencies
+    /// Edges: node -> dependents
     edges: BTreeMap<String, BTreeSet<String>>,
 
-    /// Reverse edges: node -> dependents
+    /// Reverse edges: node -> dependencies
     reverse_edges: BTreeMap<String, BTreeSet<String>>,
 }
 
@@ -44,6 +45,8 @@ impl Dag {
     }
 
     /// Add an edge (dependency) between nodes
+    ///
+    /// `to` depends on `from`: `from` must complete before `to` starts.
     pub fn add_edge(&mut self, from: String, to: String) -> Result<(), DagError> {
         if !self.nodes.contains_key(&from) {
             return Err(DagError::NodeNotFound(from));
@@ -87,38 +90,38 @@ impl Dag {
     }
 
     /// Get topological ordering of nodes
+    ///
+    /// Among nodes whose dependencies are satisfied, the smallest ID comes first.
     pub fn topological_order(&self) -> Result<Vec<String>, DagError> {
         let mut in_degree: BTreeMap<String, usize> = BTreeMap::new();
         for node in self.nodes.keys() {
             in_degree.insert(node.clone(), 0);
         }
-        for deps in self.edges.values() {
-            for dep in deps {
-                *in_degree.entry(dep.clone()).or_insert(0) += 1;
+        for dependents in self.edges.values() {
+            for dependent in dependents {
+                *in_degree.entry(dependent.clone()).or_insert(0) += 1;
             }
         }
 
-        let mut queue: Vec<String> = in_degree
+        let mut queue: BTreeSet<String> = in_degree
             .iter()
             .filter(|(_, &d)| d == 0)
             .map(|(n, _)| n.clone())
             .collect();
-        queue.sort(); // Ensure deterministic ordering
 
         let mut result = Vec::new();
-        while let Some(node) = queue.pop() {
-            result.push(node.clone());
-            if let Some(deps) = self.reverse_edges.get(&node) {
-                for dependent in deps {
+        while let Some(node) = queue.pop_first() {
+            if let Some(dependents) = self.edges.get(&node) {
+                for dependent in dependents {
                     if let Some(degree) = in_degree.get_mut(dependent) {
                         *degree -= 1;
                         if *degree == 0 {
-                            queue.push(dependent.clone());
+                            queue.insert(dependent.clone());
                         }
                     }
                 }
-                queue.sort();
             }
+            result.push(node);
         }
 
         if result.len() != self.nodes.len() {
@@ -131,6 +134,27 @@ impl Dag {
         Ok(result)
     }
 
+    /// Get the topological rank of every node
+    ///
+    /// Roots have rank 0; any other node ranks one above its highest-ranked
+    /// dependency. Ordering by `(rank, id)` is a valid execution order.
+    pub fn ranks(&self) -> Result<BTreeMap<String, usize>, DagError> {
+        let mut ranks: BTreeMap<String, usize> = BTreeMap::new();
+        for node in self.topological_order()? {
+            let rank = self
+                .reverse_edges
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| ranks.get(dep))
+                .map(|r| r + 1)
+                .max()
+                .unwrap_or(0);
+            ranks.insert(node, rank);
+        }
+        Ok(ranks)
+    }
+
     /// Get node by ID
     pub fn node(&self, id: &str) -> Option<&DagNode> {
         self.nodes.get(id)
@@ -143,12 +167,12 @@ impl Dag {
 
     /// Get dependencies for a node
     pub fn dependencies(&self, id: &str) -> Option<&BTreeSet<String>> {
-        self.edges.get(id)
+        self.reverse_edges.get(id)
     }
 
     /// Get dependents of a node
     pub fn dependents(&self, id: &str) -> Option<&BTreeSet<String>> {
-        self.reverse_edges.get(id)
+        self.edges.get(id)
     }
 
     /// Get node count
@@ -167,7 +191,7 @@ impl Dag {
         self.topological_order()?;
 
         // Check all dependencies exist
-        for (node, deps) in &self.edges {
+        for (node, deps) in &self.reverse_edges {
             for dep in deps {
                 if !self.nodes.contains_key(dep) {
                     return Err(DagError::DependencyNotFound {
@@ -208,11 +232,28 @@ pub struct DagNode {
 
     /// Node metadata
     pub metadata: BTreeMap<String, String>,
+
+    /// Side effects of running the node
+    #[serde(default = "impure")]
+    pub side_effect: SideEffect,
+}
+
+fn impure() -> SideEffect {
+    SideEffect::Impure
 }
 
 impl DagNode {
     /// Create a new DAG node
+    ///
+    /// Tool and custom nodes are impure; observations, decisions and waits
+    /// are pure.
     pub fn new(id: impl Into<String>, node_type: DagNodeType) -> Self {
+        let side_effect = match node_type {
+            DagNodeType::Tool { .. } | DagNodeType::Custom { .. } => SideEffect::Impure,
+            DagNodeType::Observation { .. }
+            | DagNodeType::Decision { .. }
+            | DagNodeType::Wait { .. } => SideEffect::Pure,
+        };
         Self {
             id: id.into(),
             node_type,
@@ -222,8 +263,15 @@ impl DagNode {
             retry_policy: RetryPolicy::default(),
             timeout_policy: TimeoutPolicy::default(),
             metadata: BTreeMap::new(),
+            side_effect,
         }
     }
+
+    /// Declare the node's side effects
+    pub fn with_side_effect(mut self, side_effect: SideEffect) -> Self {
+        self.side_effect = side_effect;
+        self
+    }
 }
 
 /// Type of DAG node
@@ -354,4 +402,24 @@ mod tests {
         let order = dag.topological_order().unwrap();
         assert_eq!(order, vec!["a", "b", "c"]);
     }
+
+    #[test]
+    fn test_ranks() {
+        let mut dag = Dag::new("test");
+        for id in ["a", "b", "c", "d"] {
+            dag.add_node(DagNode::new(id, DagNodeType::Wait { duration_ms: 0 }))
+                .unwrap();
+        }
+        dag.add_edge("a".to_string(), "c".to_string()).unwrap();
+        dag.add_edge("b".to_string(), "c".to_string()).unwrap();
+        dag.add_edge("a".to_string(), "d".to_string()).unwrap();
+        dag.add_edge("c".to_string(), "d".to_string()).unwrap();
+
+        let ranks = dag.ranks().unwrap();
+        assert_eq!(ranks["a"], 0);
+        assert_eq!(ranks["b"], 0);
+        assert_eq!(ranks["c"], 1);
+        assert_eq!(ranks["d"], 2);
+        assert_eq!(dag.dependencies("c"), Some(&["a".to_string(), "b".to_string()].into()));
+    }
 }
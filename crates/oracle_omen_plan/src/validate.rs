@@ -1,6 +1,9 @@
 //! Plan validation utilities.
 
-use crate::{dag::Dag, dsl::Plan};
+use crate::{
+    dag::Dag,
+    dsl::{FailurePolicy, Plan},
+};
 
 /// Validation result
 pub type ValidationResult<T> = Result<T, ValidationError>;
@@ -42,12 +45,36 @@ impl PlanValidator {
             }
         }
 
+        for step in &plan.steps {
+            Self::check_failure_policy(&step.id, &step.failure_policy)?;
+        }
+
         Ok(())
     }
 
     /// Validate a DAG
     pub fn validate_dag(dag: &Dag) -> ValidationResult<()> {
-        dag.validate().map_err(|e| ValidationError::DagError(e.to_string()))
+        dag.validate().map_err(|e| ValidationError::DagError(e.to_string()))?;
+        for node in dag.nodes().values() {
+            Self::check_failure_policy(&node.id, &node.failure_policy)?;
+        }
+        Ok(())
+    }
+
+    /// Reject failure policies the executor does not implement
+    ///
+    /// Only `Stop` and `Continue` are carried out; accepting the others would
+    /// silently run them as `Stop`.
+    fn check_failure_policy(step: &str, policy: &FailurePolicy) -> ValidationResult<()> {
+        match policy {
+            FailurePolicy::Stop | FailurePolicy::Continue => Ok(()),
+            FailurePolicy::Retry
+            | FailurePolicy::Compensate { .. }
+            | FailurePolicy::Fallback { .. } => Err(ValidationError::UnsupportedFailurePolicy {
+                step: step.to_string(),
+                policy: format!("{:?}", policy),
+            }),
+        }
     }
 }
 
@@ -58,13 +85,33 @@ pub enum ValidationError {
     DuplicateStep(String),
 
     /// Dependency not found
-    DependencyNotFound { step: String, dependency: String },
+    DependencyNotFound {
+        /// Step declaring the dependency
+        step: String,
+        /// Missing step ID
+        dependency: String,
+    },
 
     /// Invalid resource specification
-    InvalidResource { step: String, resource: String, reason: String },
+    InvalidResource {
+        /// Step declaring the resource
+        step: String,
+        /// Resource field
+        resource: String,
+        /// Why it is invalid
+        reason: String,
+    },
 
     /// DAG error
     DagError(String),
+
+    /// Failure policy the executor cannot carry out
+    UnsupportedFailurePolicy {
+        /// Step declaring the policy
+        step: String,
+        /// The rejected policy
+        policy: String,
+    },
 }
 
 impl std::fmt::Display for ValidationError {
@@ -78,6 +125,9 @@ impl std::fmt::Display for ValidationError {
                 write!(f, "Invalid resource '{}' in step '{}': {}", resource, step, reason)
             }
             ValidationError::DagError(msg) => write!(f, "DAG error: {}", msg),
+            ValidationError::UnsupportedFailurePolicy { step, policy } => {
+                write!(f, "Step '{}' uses unsupported failure policy {}", step, policy)
+            }
         }
     }
 }
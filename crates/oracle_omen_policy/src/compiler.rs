@@ -69,6 +69,14 @@ impl PolicyCompiler {
             crate::lang::Condition::ToolEquals(tool) => {
                 Ok(crate::schema::CompiledCondition::ToolEquals(tool.clone()))
             }
+            crate::lang::Condition::KeyMatches(pattern) => {
+                if pattern.is_empty() {
+                    return Err(CompileError::InvalidCondition(
+                        "KeyMatches pattern must not be empty".to_string(),
+                    ));
+                }
+                Ok(crate::schema::CompiledCondition::KeyMatches(pattern.clone()))
+            }
             crate::lang::Condition::Compare { field, op, value } => {
                 Ok(crate::schema::CompiledCondition::Compare {
                     field: field.clone(),
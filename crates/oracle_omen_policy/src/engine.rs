@@ -20,6 +20,9 @@ pub struct EvalContext {
     /// Memory key being accessed
     pub memory_key: Option<String>,
 
+    /// Memory operation being performed
+    pub memory_operation: Option<MemoryOperation>,
+
     /// Patch being proposed
     pub patch_type: Option<String>,
 
@@ -34,6 +37,7 @@ impl EvalContext {
             capabilities: BTreeSet::new(),
             tool: None,
             memory_key: None,
+            memory_operation: None,
             patch_type: None,
             state: BTreeMap::new(),
         }
@@ -55,7 +59,95 @@ impl Default for EvalContext {
     }
 }
 
+/// Memory operation subject to policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryOperation {
+    /// Read a key
+    Read,
+
+    /// Write a key
+    Write,
+
+    /// Delete a key
+    Delete,
+}
+
+impl MemoryOperation {
+    /// Get operation name as used in `memory.operation` comparisons
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryOperation::Read => "read",
+            MemoryOperation::Write => "write",
+            MemoryOperation::Delete => "delete",
+        }
+    }
+}
+
+impl std::fmt::Display for MemoryOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Resource request subject to policy
+///
+/// Each requested amount is exposed to `Compare` conditions as
+/// `resource.<name>` (e.g. `resource.timeout_ms`). Unset amounts are absent.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceRequest {
+    /// Requested execution timeout in milliseconds
+    pub timeout_ms: Option<u64>,
+
+    /// Requested memory in bytes
+    pub memory_bytes: Option<u64>,
+
+    /// Requested WASM fuel
+    pub fuel: Option<u64>,
+
+    /// Requested number of concurrent tasks
+    pub concurrency: Option<u64>,
+
+    /// Requested CPU units
+    pub cpu_units: Option<u64>,
+}
+
+impl ResourceRequest {
+    /// Create an empty request
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get requested amounts as comparable state fields
+    fn fields(&self) -> Vec<(&'static str, u64)> {
+        [
+            ("resource.timeout_ms", self.timeout_ms),
+            ("resource.memory_bytes", self.memory_bytes),
+            ("resource.fuel", self.fuel),
+            ("resource.concurrency", self.concurrency),
+            ("resource.cpu_units", self.cpu_units),
+        ]
+        .into_iter()
+        .filter_map(|(name, amount)| amount.map(|a| (name, a)))
+        .collect()
+    }
+
+    /// Human-readable summary for evaluation reasons
+    fn describe(&self) -> String {
+        let parts: Vec<String> = self
+            .fields()
+            .iter()
+            .map(|(name, amount)| format!("{}={}", name.trim_start_matches("resource."), amount))
+            .collect();
+        if parts.is_empty() {
+            "none".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
 /// Policy engine
+#[derive(Clone)]
 pub struct PolicyEngine {
     policies: Vec<CompiledPolicy>,
 }
@@ -148,6 +240,67 @@ impl PolicyEngine {
         self.resolve_results(results, &format!("patch: {}", patch_type))
     }
 
+    /// Evaluate a memory access against policies
+    ///
+    /// Only `Memory` rules apply. The key is available to `KeyMatches`
+    /// conditions and, with the operation, to `Compare` conditions as
+    /// `memory.key` and `memory.operation`.
+    pub fn evaluate_memory(
+        &self,
+        operation: MemoryOperation,
+        key: &str,
+        context: &EvalContext,
+    ) -> EvaluationResult {
+        let mut ctx = context.clone();
+        ctx.memory_key = Some(key.to_string());
+        ctx.memory_operation = Some(operation);
+        ctx.state
+            .insert("memory.key".to_string(), Value::String(key.to_string()));
+        ctx.state.insert(
+            "memory.operation".to_string(),
+            Value::String(operation.as_str().to_string()),
+        );
+
+        let results = self.matching_rules(RuleKind::Memory, &ctx);
+        self.resolve_results(results, &format!("memory {}: {}", operation, key))
+    }
+
+    /// Evaluate a resource request against policies
+    ///
+    /// Only `Resource` rules apply. Requested amounts are compared through
+    /// `resource.*` state fields.
+    pub fn evaluate_resource(
+        &self,
+        request: &ResourceRequest,
+        context: &EvalContext,
+    ) -> EvaluationResult {
+        let mut ctx = context.clone();
+        for (name, amount) in request.fields() {
+            let value = i64::try_from(amount).unwrap_or(i64::MAX);
+            ctx.state.insert(name.to_string(), Value::Integer(value));
+        }
+
+        let results = self.matching_rules(RuleKind::Resource, &ctx);
+        self.resolve_results(results, &format!("resources: {}", request.describe()))
+    }
+
+    /// Collect rules of a kind whose condition holds, in policy order
+    fn matching_rules(
+        &self,
+        kind: RuleKind,
+        ctx: &EvalContext,
+    ) -> Vec<(crate::lang::PolicyId, CompiledRule)> {
+        let mut results = Vec::new();
+        for policy in &self.policies {
+            for rule in &policy.rules {
+                if rule.kind == kind && self.evaluate_condition(&rule.condition, ctx) {
+                    results.push((policy.id.clone(), rule.clone()));
+                }
+            }
+        }
+        results
+    }
+
     /// Evaluate a condition
     fn evaluate_condition(&self, cond: &CompiledCondition, ctx: &EvalContext) -> bool {
         match cond {
@@ -162,6 +315,10 @@ impl PolicyEngine {
             CompiledCondition::Not(inner) => !self.evaluate_condition(inner, ctx),
             CompiledCondition::HasCapability(cap) => ctx.has_capability(cap),
             CompiledCondition::ToolEquals(tool) => ctx.tool.as_ref() == Some(tool),
+            CompiledCondition::KeyMatches(pattern) => ctx
+                .memory_key
+                .as_deref()
+                .is_some_and(|key| glob_match(pattern, key)),
             CompiledCondition::Compare { field, op, value } => {
                 if let Some(state_val) = ctx.state.get(field) {
                     self.compare_values(state_val, op, value)
@@ -236,6 +393,29 @@ impl Default for PolicyEngine {
     }
 }
 
+/// Match a key against a glob pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, key: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == key;
+    }
+
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+    if !key.starts_with(first) || key.len() < first.len() + last.len() || !key.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &key[first.len()..key.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -323,7 +503,88 @@ mod tests {
         let mut ctx = EvalContext::new();
         ctx.state.insert("iterations".to_string(), Value::Integer(50));
 
-        let result = engine.evaluate_capability("any", &ctx);
+        let result = engine.evaluate_resource(&ResourceRequest::new(), &ctx);
         assert!(result.allowed);
     }
+
+    #[test]
+    fn test_engine_memory_key_pattern() {
+        let mut policy = Policy::new("test", "1.0.0");
+        policy.add_rule(Rule {
+            name: "deny_secret_writes".to_string(),
+            kind: RuleKind::Memory,
+            condition: Condition::And(vec![
+                Condition::KeyMatches("secrets/*".to_string()),
+                Condition::Compare {
+                    field: "memory.operation".to_string(),
+                    op: CompareOp::NotEqual,
+                    value: Value::String("read".to_string()),
+                },
+            ]),
+            action: Action::Deny {
+                reason: "Secrets are read-only".to_string(),
+            },
+        });
+        policy.add_rule(Rule {
+            name: "allow_memory".to_string(),
+            kind: RuleKind::Memory,
+            condition: Condition::True,
+            action: Action::Allow,
+        });
+
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        let ctx = EvalContext::new();
+
+        assert!(engine.evaluate_memory(MemoryOperation::Read, "secrets/key", &ctx).allowed);
+        assert!(!engine.evaluate_memory(MemoryOperation::Write, "secrets/key", &ctx).allowed);
+        assert!(!engine.evaluate_memory(MemoryOperation::Delete, "secrets/key", &ctx).allowed);
+        assert!(engine.evaluate_memory(MemoryOperation::Write, "notes/key", &ctx).allowed);
+    }
+
+    #[test]
+    fn test_engine_resource_limits() {
+        let mut policy = Policy::new("test", "1.0.0");
+        policy.add_rule(Rule {
+            name: "cap_timeout".to_string(),
+            kind: RuleKind::Resource,
+            condition: Condition::Compare {
+                field: "resource.timeout_ms".to_string(),
+                op: CompareOp::Greater,
+                value: Value::Integer(60_000),
+            },
+            action: Action::Deny {
+                reason: "Timeout above 60s".to_string(),
+            },
+        });
+        policy.add_rule(Rule {
+            name: "allow_resources".to_string(),
+            kind: RuleKind::Resource,
+            condition: Condition::True,
+            action: Action::Allow,
+        });
+
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        let ctx = EvalContext::new();
+
+        let mut request = ResourceRequest::new();
+        request.timeout_ms = Some(30_000);
+        assert!(engine.evaluate_resource(&request, &ctx).allowed);
+
+        request.timeout_ms = Some(120_000);
+        let result = engine.evaluate_resource(&request, &ctx);
+        assert!(!result.allowed);
+        assert_eq!(result.reason, "Timeout above 60s");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("secrets/*", "secrets/a"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("a*c*e", "abcde"));
+        assert!(!glob_match("a*c*e", "abcd"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exact2"));
+    }
 }
@@ -115,6 +115,9 @@ pub enum Condition {
     /// Tool check
     ToolEquals(String),
 
+    /// Memory key matches a glob pattern (`*` matches any run of characters)
+    KeyMatches(String),
+
     /// Value comparison
     Compare {
         field: String,
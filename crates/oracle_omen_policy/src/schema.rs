@@ -32,6 +32,7 @@ pub enum CompiledCondition {
     Not(Box<CompiledCondition>),
     HasCapability(String),
     ToolEquals(String),
+    KeyMatches(String),
     Compare {
         field: String,
         op: CompareOp,
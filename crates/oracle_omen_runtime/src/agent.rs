@@ -0,0 +1,819 @@
+//! Agent loop driver.
+//!
+//! Runs the loop: Observation → StateMachine → Decision → Policy → Runtime →
+//! Events → State. Every step is logged:
+//!
+//! ```text
+//! agent_init
+//! observation
+//!   state_transition   state_hash_before/after set
+//!     decision
+//!       tool_request → tool_response      allowed tool call
+//!       error (policy_denied)             denied tool call
+//!       patch_proposal | patch_rejected   patch proposal
+//! ```
+//!
+//! Tool responses are fed to the next transition. After an observation the
+//! agent keeps stepping on `tool_responses` observations until it stops
+//! calling tools or a limit is reached.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use oracle_omen_core::{
+    capability::CapabilitySet,
+    event::{
+        AgentInitPayload, DecisionPayload, ErrorPayload, Event, EventId, EventKind, EventLog,
+        EventPayload, ObservationPayload, PatchPayload, PatchRejectedPayload,
+        StateTransitionPayload,
+    },
+    hash::Hash,
+    serde_utils::StableMap,
+    state::{
+        self, AgentState, Decision, Observation, PatchProposal, StateData, StateError,
+        StateMachine, StateValue, ToolCall,
+    },
+    tool::ToolId,
+};
+use oracle_omen_policy::engine::{EvalContext, PolicyEngine};
+
+use crate::{ToolInvoker, ToolRegistry};
+
+/// Agent result
+pub type AgentResult<T> = Result<T, AgentError>;
+
+/// Agent loop errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AgentError {
+    /// `run` or `step` called before `start`
+    NotStarted,
+
+    /// `start` called twice
+    AlreadyStarted,
+
+    /// The state machine failed
+    State(StateError),
+
+    /// An event could not be appended
+    Log(String),
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentError::NotStarted => write!(f, "Agent not started"),
+            AgentError::AlreadyStarted => write!(f, "Agent already started"),
+            AgentError::State(e) => write!(f, "State machine error: {}", e),
+            AgentError::Log(msg) => write!(f, "Event log error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AgentError {}
+
+/// Limits that stop a run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AgentLimits {
+    /// Maximum state transitions
+    pub max_iterations: u64,
+
+    /// Maximum tool calls, allowed or denied
+    pub max_tool_calls: u64,
+
+    /// Maximum events in the log
+    pub max_events: Option<u64>,
+}
+
+impl Default for AgentLimits {
+    fn default() -> Self {
+        Self {
+            max_iterations: 100,
+            max_tool_calls: 100,
+            max_events: None,
+        }
+    }
+}
+
+/// Agent identity and configuration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AgentConfig {
+    /// Agent type
+    pub agent_type: String,
+
+    /// Agent version
+    pub agent_version: String,
+
+    /// Configuration recorded in `AgentInit`
+    pub config: BTreeMap<String, String>,
+
+    /// Seed passed to the state machine and tools
+    pub seed: Option<u64>,
+
+    /// Run limits
+    pub limits: AgentLimits,
+}
+
+impl AgentConfig {
+    /// Create a config with default limits
+    pub fn new(agent_type: impl Into<String>, agent_version: impl Into<String>) -> Self {
+        Self {
+            agent_type: agent_type.into(),
+            agent_version: agent_version.into(),
+            config: BTreeMap::new(),
+            seed: None,
+            limits: AgentLimits::default(),
+        }
+    }
+
+    /// Record a configuration entry
+    pub fn with_entry(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the limits
+    pub fn with_limits(mut self, limits: AgentLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Why a run stopped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every observation was handled and no tool responses are pending
+    Idle,
+
+    /// `max_iterations` reached
+    MaxIterations,
+
+    /// `max_tool_calls` reached
+    MaxToolCalls,
+
+    /// `max_events` reached
+    MaxEvents,
+}
+
+/// Summary of a run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Why the run stopped
+    pub stop: StopReason,
+
+    /// State transitions taken
+    pub iterations: u64,
+
+    /// Tool calls made, allowed or denied
+    pub tool_calls: u64,
+
+    /// Final state hash
+    pub state_hash: Hash,
+
+    /// Patch proposals that passed policy, for the patch gates
+    pub proposals: Vec<PatchProposal>,
+}
+
+/// Drives a [`StateMachine`] through policy-checked tool calls
+pub struct Agent {
+    config: AgentConfig,
+    machine: Arc<dyn StateMachine>,
+    policy: PolicyEngine,
+    invoker: ToolInvoker,
+    capabilities: CapabilitySet,
+    state: AgentState,
+    pending: Vec<state::ToolResponse>,
+    last: Option<EventId>,
+    iterations: u64,
+    tool_calls: u64,
+    proposals: Vec<PatchProposal>,
+}
+
+impl Agent {
+    /// Create an agent
+    ///
+    /// Tool calls must pass both `policy` and the capability check against
+    /// `capabilities`.
+    pub fn new(
+        config: AgentConfig,
+        machine: Arc<dyn StateMachine>,
+        policy: PolicyEngine,
+        registry: ToolRegistry,
+        capabilities: CapabilitySet,
+    ) -> Self {
+        let mut invoker = ToolInvoker::new(registry, capabilities.clone());
+        if let Some(seed) = config.seed {
+            invoker = invoker.with_seed(seed);
+        }
+        Self {
+            state: machine.initial_state(),
+            config,
+            machine,
+            policy,
+            invoker,
+            capabilities,
+            pending: Vec::new(),
+            last: None,
+            iterations: 0,
+            tool_calls: 0,
+            proposals: Vec::new(),
+        }
+    }
+
+    /// Replace the tool invoker, e.g. with one in replay mode
+    pub fn with_invoker(mut self, invoker: ToolInvoker) -> Self {
+        self.invoker = invoker;
+        self
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> &AgentState {
+        &self.state
+    }
+
+    /// Get the invoker
+    pub fn invoker(&self) -> &ToolInvoker {
+        &self.invoker
+    }
+
+    /// Log `AgentInit`
+    pub fn start(&mut self, log: &mut EventLog) -> AgentResult<EventId> {
+        if self.last.is_some() {
+            return Err(AgentError::AlreadyStarted);
+        }
+        let payload = EventPayload::AgentInit(AgentInitPayload {
+            agent_type: self.config.agent_type.clone(),
+            agent_version: self.config.agent_version.clone(),
+            config: self.config.config.clone(),
+        });
+        let id = append(log, EventKind::AgentInit, payload, None)?;
+        self.last = Some(id);
+        Ok(id)
+    }
+
+    /// Handle observations until idle or a limit is reached
+    pub fn run(
+        &mut self,
+        log: &mut EventLog,
+        observations: impl IntoIterator<Item = Observation>,
+    ) -> AgentResult<RunSummary> {
+        for observation in observations {
+            if let Some(stop) = self.step(log, observation)? {
+                return Ok(self.summary(stop));
+            }
+            while !self.pending.is_empty() {
+                let follow_up = Observation::new("tool_responses", log.next_id().sequence);
+                if let Some(stop) = self.step(log, follow_up)? {
+                    return Ok(self.summary(stop));
+                }
+            }
+        }
+        Ok(self.summary(StopReason::Idle))
+    }
+
+    /// Take one transition on `observation` and act on its decision
+    ///
+    /// Returns the stop reason if a limit was reached.
+    pub fn step(
+        &mut self,
+        log: &mut EventLog,
+        observation: Observation,
+    ) -> AgentResult<Option<StopReason>> {
+        let parent = self.last.ok_or(AgentError::NotStarted)?;
+        if let Some(stop) = self.limit_reached(log) {
+            return Ok(Some(stop));
+        }
+
+        let observed = append(
+            log,
+            EventKind::Observation,
+            EventPayload::Observation(ObservationPayload {
+                obs_type: observation.kind.clone(),
+                data: observation
+                    .data
+                    .iter()
+                    .map(|(k, v)| (k.clone(), value_string(v)))
+                    .collect(),
+                source: self.config.agent_type.clone(),
+            }),
+            Some(parent),
+        )?;
+
+        let context = state::ExecutionContext {
+            logical_time: observed.sequence,
+            run_id: log.run_id,
+            seed: self.config.seed,
+        };
+        let responses = std::mem::take(&mut self.pending);
+        let transition =
+            match self
+                .machine
+                .transition(&self.state, &observation, &responses, &context)
+            {
+                Ok(transition) => transition,
+                Err(e) => {
+                    let payload = EventPayload::Error(ErrorPayload {
+                        error_type: "transition_failed".to_string(),
+                        message: e.to_string(),
+                        component: self.config.agent_type.clone(),
+                        recoverable: false,
+                    });
+                    append(log, EventKind::Error, payload, Some(observed))?;
+                    return Err(AgentError::State(e));
+                }
+            };
+
+        let before = self.state.hash();
+        let after = transition.state.hash();
+        let id = log.next_id();
+        let event = Event::with_parent(
+            id,
+            observed,
+            EventKind::StateTransition,
+            id.to_logical_time(),
+            EventPayload::StateTransition(StateTransitionPayload {
+                from_hash: before,
+                to_hash: after,
+                transition_type: decision_type(&transition.decision).to_string(),
+            }),
+        )
+        .with_state_hashes(before, after);
+        log.append(event)
+            .map_err(|e| AgentError::Log(e.to_string()))?;
+        self.state = transition.state;
+        self.iterations += 1;
+        self.last = Some(id);
+
+        let decided = append(
+            log,
+            EventKind::Decision,
+            EventPayload::Decision(decision_payload(&transition.decision)),
+            Some(id),
+        )?;
+        self.act(log, transition.decision, decided)
+    }
+
+    /// Carry out a decision
+    fn act(
+        &mut self,
+        log: &mut EventLog,
+        decision: Decision,
+        parent: EventId,
+    ) -> AgentResult<Option<StopReason>> {
+        match decision {
+            Decision::None => Ok(None),
+            Decision::ToolCall(call) => self.call_tool(log, call, parent),
+            Decision::PatchProposal(proposal) => {
+                self.propose_patch(log, proposal, parent)?;
+                Ok(None)
+            }
+            Decision::Multiple(decisions) => {
+                for decision in decisions {
+                    if let Some(stop) = self.act(log, decision, parent)? {
+                        return Ok(Some(stop));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Policy-check and invoke a tool
+    fn call_tool(
+        &mut self,
+        log: &mut EventLog,
+        call: ToolCall,
+        parent: EventId,
+    ) -> AgentResult<Option<StopReason>> {
+        if self.tool_calls >= self.config.limits.max_tool_calls {
+            return Ok(Some(StopReason::MaxToolCalls));
+        }
+        self.tool_calls += 1;
+
+        let result = self
+            .policy
+            .evaluate_tool(&call.tool_name, &self.eval_context());
+        if !result.allowed {
+            let payload = EventPayload::Error(ErrorPayload {
+                error_type: "policy_denied".to_string(),
+                message: result.reason.clone(),
+                component: format!("tool:{}", call.tool_name),
+                recoverable: true,
+            });
+            append(log, EventKind::Error, payload, Some(parent))?;
+            self.pending
+                .push(state::ToolResponse::error(call.tool_name, result.reason));
+            return Ok(None);
+        }
+
+        let tool_id = ToolId::new(&call.tool_name, &call.tool_version);
+        let response = match self
+            .invoker
+            .invoke(log, &tool_id, &call.input, Some(parent))
+        {
+            Ok(response) => state::ToolResponse::success(
+                call.tool_name,
+                StateData::Value(StateValue::String(response.data)),
+            ),
+            Err(e) => state::ToolResponse::error(call.tool_name, e.to_string()),
+        };
+        self.pending.push(response);
+        Ok(None)
+    }
+
+    /// Policy-check a patch proposal and log the outcome
+    fn propose_patch(
+        &mut self,
+        log: &mut EventLog,
+        proposal: PatchProposal,
+        parent: EventId,
+    ) -> AgentResult<()> {
+        let patch_type = patch_type_name(&proposal.patch_type);
+        let patch_hash = Hash::from_canonical(&proposal.patch);
+        let result = self
+            .policy
+            .evaluate_patch(&patch_type, &self.eval_context());
+        if result.allowed {
+            let payload = EventPayload::PatchProposal(PatchPayload {
+                patch_type,
+                target: proposal.target.clone(),
+                patch_hash,
+                reasoning: proposal.reasoning.clone(),
+            });
+            append(log, EventKind::PatchProposal, payload, Some(parent))?;
+            self.proposals.push(proposal);
+        } else {
+            let payload = EventPayload::PatchRejected(PatchRejectedPayload {
+                patch_hash,
+                reason: result.reason,
+                stage: "policy".to_string(),
+            });
+            append(log, EventKind::PatchRejected, payload, Some(parent))?;
+        }
+        Ok(())
+    }
+
+    /// Policy context holding the granted capabilities
+    fn eval_context(&self) -> EvalContext {
+        let mut ctx = EvalContext::new();
+        ctx.capabilities = self
+            .capabilities
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        ctx
+    }
+
+    /// Check the iteration and event limits
+    fn limit_reached(&self, log: &EventLog) -> Option<StopReason> {
+        let limits = &self.config.limits;
+        if self.iterations >= limits.max_iterations {
+            Some(StopReason::MaxIterations)
+        } else if limits.max_events.is_some_and(|max| log.len() as u64 >= max) {
+            Some(StopReason::MaxEvents)
+        } else {
+            None
+        }
+    }
+
+    fn summary(&self, stop: StopReason) -> RunSummary {
+        RunSummary {
+            stop,
+            iterations: self.iterations,
+            tool_calls: self.tool_calls,
+            state_hash: self.state.hash(),
+            proposals: self.proposals.clone(),
+        }
+    }
+}
+
+/// Append an event to the log
+fn append(
+    log: &mut EventLog,
+    kind: EventKind,
+    payload: EventPayload,
+    parent: Option<EventId>,
+) -> AgentResult<EventId> {
+    log.append_next(kind, payload, parent)
+        .map_err(|e| AgentError::Log(e.to_string()))
+}
+
+/// Name of a decision variant
+fn decision_type(decision: &Decision) -> &'static str {
+    match decision {
+        Decision::None => "none",
+        Decision::ToolCall(_) => "tool_call",
+        Decision::PatchProposal(_) => "patch_proposal",
+        Decision::Multiple(_) => "multiple",
+    }
+}
+
+/// Decision event payload
+fn decision_payload(decision: &Decision) -> DecisionPayload {
+    let mut data = StableMap::new();
+    match decision {
+        Decision::None => {}
+        Decision::ToolCall(call) => {
+            data.insert(
+                "tool".to_string(),
+                format!("{}@{}", call.tool_name, call.tool_version),
+            );
+            data.insert(
+                "input_hash".to_string(),
+                Hash::from_canonical(&call.input).to_hex(),
+            );
+        }
+        Decision::PatchProposal(proposal) => {
+            data.insert(
+                "patch_type".to_string(),
+                patch_type_name(&proposal.patch_type),
+            );
+            data.insert("target".to_string(), proposal.target.clone());
+        }
+        Decision::Multiple(decisions) => {
+            data.insert("count".to_string(), decisions.len().to_string());
+        }
+    }
+    let reasoning = match decision {
+        Decision::PatchProposal(proposal) => Some(proposal.reasoning.clone()),
+        _ => None,
+    };
+    DecisionPayload {
+        decision_type: decision_type(decision).to_string(),
+        data,
+        reasoning,
+    }
+}
+
+/// Name policies use for a patch type
+fn patch_type_name(patch_type: &state::PatchType) -> String {
+    match patch_type {
+        state::PatchType::Prompt => "prompt".to_string(),
+        state::PatchType::Policy => "policy".to_string(),
+        state::PatchType::Routing => "routing".to_string(),
+        state::PatchType::Config => "config".to_string(),
+        state::PatchType::Tools => "tools".to_string(),
+        state::PatchType::Other(name) => name.clone(),
+    }
+}
+
+/// Render an observation value for the log
+fn value_string(value: &StateValue) -> String {
+    match value {
+        StateValue::String(s) => s.clone(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EchoTool;
+    use oracle_omen_core::{
+        capability::Capability,
+        state::{PatchType, StateResult, Transition},
+    };
+    use oracle_omen_policy::{
+        compiler::PolicyCompiler,
+        lang::{Action, Condition, Policy, Rule, RuleKind},
+    };
+
+    /// Echoes each `ask` observation through the echo tool and stores replies
+    struct EchoAgent;
+
+    impl StateMachine for EchoAgent {
+        fn transition(
+            &self,
+            state: &AgentState,
+            observation: &Observation,
+            tool_responses: &[state::ToolResponse],
+            _context: &state::ExecutionContext,
+        ) -> StateResult<Transition> {
+            let mut next = state.clone();
+            for response in tool_responses {
+                let value = match (&response.data, &response.error) {
+                    (StateData::Value(StateValue::String(s)), None) => s.clone(),
+                    (_, Some(error)) => format!("error: {}", error),
+                    _ => String::new(),
+                };
+                next.set("last_reply", StateData::Value(StateValue::String(value)));
+            }
+            let decision =
+                match observation.data.get("text") {
+                    Some(StateValue::String(text)) if observation.kind == "ask" => {
+                        Decision::ToolCall(ToolCall::new("echo", "1.0.0", format!("\"{}\"", text)))
+                    }
+                    _ if observation.kind == "improve" => Decision::PatchProposal(
+                        PatchProposal::new(PatchType::Prompt, "system", "be terse", "shorter"),
+                    ),
+                    _ => Decision::None,
+                };
+            Ok(Transition::new(next, decision))
+        }
+
+        fn initial_state(&self) -> AgentState {
+            AgentState::initial()
+        }
+    }
+
+    /// Always calls the echo tool
+    struct LoopingAgent;
+
+    impl StateMachine for LoopingAgent {
+        fn transition(
+            &self,
+            state: &AgentState,
+            _observation: &Observation,
+            _tool_responses: &[state::ToolResponse],
+            _context: &state::ExecutionContext,
+        ) -> StateResult<Transition> {
+            let mut next = state.clone();
+            next.set(
+                "steps",
+                StateData::Value(StateValue::U64(state.version + 1)),
+            );
+            let call = ToolCall::new("echo", "1.0.0", "\"again\"");
+            Ok(Transition::new(next, Decision::ToolCall(call)))
+        }
+
+        fn initial_state(&self) -> AgentState {
+            AgentState::initial()
+        }
+    }
+
+    fn policy(kind: RuleKind, action: Action) -> PolicyEngine {
+        let mut policy = Policy::new("agent", "1.0.0");
+        policy.add_rule(Rule {
+            name: "rule".to_string(),
+            kind,
+            condition: Condition::True,
+            action,
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+        engine
+    }
+
+    fn new_agent(
+        machine: Arc<dyn StateMachine>,
+        policy: PolicyEngine,
+        limits: AgentLimits,
+    ) -> Agent {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).unwrap();
+        let config = AgentConfig::new("echo_agent", "1.0.0")
+            .with_seed(3)
+            .with_limits(limits);
+        Agent::new(config, machine, policy, registry, CapabilitySet::empty())
+    }
+
+    fn ask(text: &str) -> Observation {
+        Observation::new("ask", 0).with_field("text", StateValue::String(text.to_string()))
+    }
+
+    #[test]
+    fn test_loop_logs_every_step() {
+        let mut agent = new_agent(
+            Arc::new(EchoAgent),
+            policy(RuleKind::Tool, Action::Allow),
+            AgentLimits::default(),
+        );
+        let mut log = EventLog::new(1);
+        assert_eq!(agent.step(&mut log, ask("x")), Err(AgentError::NotStarted));
+        agent.start(&mut log).unwrap();
+
+        let summary = agent.run(&mut log, [ask("hello")]).unwrap();
+        assert_eq!(summary.stop, StopReason::Idle);
+        assert_eq!(summary.iterations, 2);
+        assert_eq!(summary.tool_calls, 1);
+        assert_eq!(
+            agent.state().get("last_reply"),
+            Some(&StateData::Value(StateValue::String(
+                "\"hello\"".to_string()
+            )))
+        );
+
+        let kinds: Vec<EventKind> = log.events().iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::AgentInit,
+                EventKind::Observation,
+                EventKind::StateTransition,
+                EventKind::Decision,
+                EventKind::ToolRequest,
+                EventKind::ToolResponse,
+                EventKind::Observation,
+                EventKind::StateTransition,
+                EventKind::Decision,
+            ]
+        );
+        let transitions: Vec<&Event> = log
+            .events()
+            .iter()
+            .filter(|e| e.kind == EventKind::StateTransition)
+            .collect();
+        assert_eq!(transitions[0].state_hash_before, Some(Hash::zero()));
+        assert_eq!(
+            transitions[0].state_hash_after,
+            transitions[1].state_hash_before
+        );
+        assert_eq!(transitions[1].state_hash_after, Some(summary.state_hash));
+    }
+
+    #[test]
+    fn test_policy_denials_are_logged() {
+        let mut agent = new_agent(
+            Arc::new(EchoAgent),
+            policy(RuleKind::Patch, Action::Allow),
+            AgentLimits::default(),
+        );
+        let mut log = EventLog::new(1);
+        agent.start(&mut log).unwrap();
+
+        let summary = agent
+            .run(&mut log, [ask("hello"), Observation::new("improve", 0)])
+            .unwrap();
+        assert_eq!(summary.proposals.len(), 1);
+        assert!(log
+            .events()
+            .iter()
+            .all(|e| e.kind != EventKind::ToolRequest));
+        let denied = log
+            .events()
+            .iter()
+            .find(|e| e.kind == EventKind::Error)
+            .unwrap();
+        match &denied.payload {
+            EventPayload::Error(error) => assert_eq!(error.error_type, "policy_denied"),
+            other => panic!("expected error, got {:?}", other),
+        }
+        assert!(matches!(
+            agent.state().get("last_reply"),
+            Some(StateData::Value(StateValue::String(s))) if s.starts_with("error: ")
+        ));
+        assert!(log
+            .events()
+            .iter()
+            .any(|e| e.kind == EventKind::PatchProposal));
+
+        let mut agent = agent_with_caps(policy(RuleKind::Tool, Action::Allow));
+        let mut log = EventLog::new(1);
+        agent.start(&mut log).unwrap();
+        agent
+            .run(&mut log, [Observation::new("improve", 0)])
+            .unwrap();
+        assert_eq!(log.last().unwrap().kind, EventKind::PatchRejected);
+    }
+
+    fn agent_with_caps(policy: PolicyEngine) -> Agent {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).unwrap();
+        Agent::new(
+            AgentConfig::new("echo_agent", "1.0.0"),
+            Arc::new(EchoAgent),
+            policy,
+            registry,
+            CapabilitySet::new([Capability::new("fs:read:*")]),
+        )
+    }
+
+    #[test]
+    fn test_limits_stop_the_loop() {
+        let limits = AgentLimits {
+            max_iterations: 3,
+            ..AgentLimits::default()
+        };
+        let mut agent = new_agent(
+            Arc::new(LoopingAgent),
+            policy(RuleKind::Tool, Action::Allow),
+            limits,
+        );
+        let mut log = EventLog::new(1);
+        agent.start(&mut log).unwrap();
+        let summary = agent.run(&mut log, [ask("go")]).unwrap();
+        assert_eq!(summary.stop, StopReason::MaxIterations);
+        assert_eq!(summary.iterations, 3);
+
+        let limits = AgentLimits {
+            max_tool_calls: 2,
+            ..AgentLimits::default()
+        };
+        let mut agent = new_agent(
+            Arc::new(LoopingAgent),
+            policy(RuleKind::Tool, Action::Allow),
+            limits,
+        );
+        let mut log = EventLog::new(1);
+        agent.start(&mut log).unwrap();
+        let summary = agent.run(&mut log, [ask("go")]).unwrap();
+        assert_eq!(summary.stop, StopReason::MaxToolCalls);
+        assert_eq!(summary.tool_calls, 2);
+    }
+}
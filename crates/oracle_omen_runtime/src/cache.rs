@@ -0,0 +1,504 @@
+//! Content-addressed cache of tool responses.
+//!
+//! Entries are keyed by tool ID and a key hash. Only tools declaring
+//! `SideEffect::Pure` and `Determinism::Deterministic` are eligible, since
+//! only their output is fully determined by the request. Cached output is
+//! already normalized, so [`cache_key`] folds the normalizer configuration
+//! into the request hash.
+//!
+//! With a directory configured, entries are also written to disk:
+//!
+//! ```text
+//! <dir>/<tool name>/<tool version>/<key hash>.json
+//! ```
+//!
+//! Tool names and versions that are not a single plain path component, such
+//! as `..` or `a/b`, are rejected with [`PersistError::UnsafePath`].
+
+use crate::persist::{PersistError, PersistResult};
+use crate::tools::Tool;
+use oracle_omen_core::hash::Hash;
+use oracle_omen_core::normalize::Normalizer;
+use oracle_omen_core::tool::{Determinism, SideEffect, ToolId};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A cached tool response
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CachedResponse {
+    /// Response output
+    pub output: String,
+
+    /// Hash of the output
+    pub response_hash: Hash,
+
+    /// Duration of the execution that produced it
+    pub duration_ms: u64,
+
+    /// Hash of the output before normalization
+    #[serde(default)]
+    pub raw_hash: Option<Hash>,
+}
+
+impl CachedResponse {
+    /// Create from a tool output
+    pub fn new(output: String, duration_ms: u64) -> Self {
+        let response_hash = Hash::from_canonical(&output);
+        Self {
+            output,
+            response_hash,
+            duration_ms,
+            raw_hash: None,
+        }
+    }
+
+    /// Record the hash of the output before normalization
+    pub fn with_raw_hash(mut self, raw_hash: Hash) -> Self {
+        self.raw_hash = Some(raw_hash);
+        self
+    }
+
+    /// Size charged against the cache limit
+    pub fn size(&self) -> u64 {
+        self.output.len() as u64
+    }
+}
+
+/// Cache key for a request whose output was normalized by `normalizer`
+pub fn cache_key(request_hash: Hash, normalizer: &Normalizer) -> Hash {
+    Hash::from_canonical(&(request_hash, normalizer.config_hash()))
+}
+
+/// Cache hit and miss counters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+
+    /// Lookups that missed
+    pub misses: u64,
+
+    /// Entries evicted to stay under the size limit
+    pub evictions: u64,
+}
+
+/// Cache entry with its recency
+#[derive(Clone, Debug)]
+struct Entry {
+    response: CachedResponse,
+    last_used: u64,
+}
+
+/// Response cache for pure deterministic tools
+#[derive(Clone, Debug, Default)]
+pub struct ToolCache {
+    /// Entries by tool and request hash
+    entries: BTreeMap<(ToolId, Hash), Entry>,
+
+    /// Total size of all entries
+    size: u64,
+
+    /// Size limit, if any
+    max_bytes: Option<u64>,
+
+    /// Logical clock for least-recently-used eviction
+    clock: u64,
+
+    /// Directory entries are persisted to
+    dir: Option<PathBuf>,
+
+    /// Counters
+    stats: CacheStats,
+}
+
+impl ToolCache {
+    /// Create an unbounded in-memory cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the total size of cached outputs
+    ///
+    /// Least recently used entries are evicted first.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self.evict();
+        self
+    }
+
+    /// Persist entries under `dir`, loading any already there
+    ///
+    /// Files whose output does not match their recorded hash are skipped.
+    pub fn open(dir: impl Into<PathBuf>) -> PersistResult<Self> {
+        let dir = dir.into();
+        let mut cache = Self::new();
+        fs::create_dir_all(&dir)?;
+
+        for (tool, path) in version_dirs(&dir)? {
+            for file in sorted_entries(&path)? {
+                let Some(request_hash) = file
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| Hash::from_hex(s).ok())
+                else {
+                    continue;
+                };
+                let response: CachedResponse = match fs::read_to_string(&file)
+                    .ok()
+                    .and_then(|s| serde_json::from_str(&s).ok())
+                {
+                    Some(response) => response,
+                    None => {
+                        tracing::warn!(path = %file.display(), "skipping unreadable cache entry");
+                        continue;
+                    }
+                };
+                if Hash::from_canonical(&response.output) != response.response_hash {
+                    tracing::warn!(path = %file.display(), "skipping corrupted cache entry");
+                    continue;
+                }
+                cache.store(tool.clone(), request_hash, response);
+            }
+        }
+
+        cache.dir = Some(dir);
+        Ok(cache)
+    }
+
+    /// Check whether a tool's responses may be cached
+    pub fn is_eligible(tool: &dyn Tool) -> bool {
+        tool.side_effects() == SideEffect::Pure && tool.determinism() == Determinism::Deterministic
+    }
+
+    /// Look up a response
+    pub fn get(&mut self, tool: &ToolId, request_hash: Hash) -> Option<CachedResponse> {
+        self.clock += 1;
+        match self.entries.get_mut(&(tool.clone(), request_hash)) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.stats.hits += 1;
+                Some(entry.response.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache a response
+    ///
+    /// A response larger than the whole size limit is not cached.
+    pub fn insert(
+        &mut self,
+        tool: &ToolId,
+        request_hash: Hash,
+        response: CachedResponse,
+    ) -> PersistResult<()> {
+        if self.max_bytes.is_some_and(|max| response.size() > max) {
+            return Ok(());
+        }
+        if let Some(dir) = &self.dir {
+            write_entry(dir, tool, request_hash, &response)?;
+        }
+        self.store(tool.clone(), request_hash, response);
+        self.evict();
+        Ok(())
+    }
+
+    /// Drop every entry for one tool version
+    ///
+    /// Returns the number of entries removed.
+    pub fn invalidate_version(&mut self, tool: &ToolId) -> PersistResult<usize> {
+        let keys: Vec<(ToolId, Hash)> = self
+            .entries
+            .keys()
+            .filter(|(id, _)| id == tool)
+            .cloned()
+            .collect();
+        for key in &keys {
+            self.remove(key);
+        }
+        if let Some(dir) = &self.dir {
+            let path = tool_dir(dir, tool)?;
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            }
+        }
+        Ok(keys.len())
+    }
+
+    /// Drop every entry
+    pub fn clear(&mut self) -> PersistResult<()> {
+        self.entries.clear();
+        self.size = 0;
+        if let Some(dir) = &self.dir {
+            for (_, path) in version_dirs(dir)? {
+                fs::remove_dir_all(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of cached outputs
+    pub fn size_bytes(&self) -> u64 {
+        self.size
+    }
+
+    /// Get the counters
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Get the persistence directory
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Insert in memory, replacing any previous entry
+    fn store(&mut self, tool: ToolId, request_hash: Hash, response: CachedResponse) {
+        self.clock += 1;
+        self.size += response.size();
+        let entry = Entry {
+            response,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert((tool, request_hash), entry) {
+            self.size -= old.response.size();
+        }
+    }
+
+    /// Remove one entry from memory and disk
+    fn remove(&mut self, key: &(ToolId, Hash)) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.size -= entry.response.size();
+        }
+        if let Some(path) = self
+            .dir
+            .as_deref()
+            .and_then(|dir| entry_path(dir, &key.0, key.1).ok())
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Evict least recently used entries until under the limit
+    fn evict(&mut self) {
+        let Some(max) = self.max_bytes else {
+            return;
+        };
+        while self.size > max {
+            let Some(key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&key);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// Directory holding one tool version's entries
+fn tool_dir(dir: &Path, tool: &ToolId) -> PersistResult<PathBuf> {
+    Ok(dir
+        .join(safe_component(&tool.name)?)
+        .join(safe_component(&tool.version)?))
+}
+
+/// Path of one persisted entry
+fn entry_path(dir: &Path, tool: &ToolId, request_hash: Hash) -> PersistResult<PathBuf> {
+    Ok(tool_dir(dir, tool)?.join(format!("{}.json", request_hash)))
+}
+
+/// Check that a name stays inside the directory it is joined to
+fn safe_component(name: &str) -> PersistResult<&str> {
+    let unsafe_name = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\', '\0'])
+        || Path::new(name).is_absolute();
+    if unsafe_name {
+        Err(PersistError::UnsafePath(name.to_string()))
+    } else {
+        Ok(name)
+    }
+}
+
+/// Write one entry, renaming it into place
+fn write_entry(
+    dir: &Path,
+    tool: &ToolId,
+    request_hash: Hash,
+    response: &CachedResponse,
+) -> PersistResult<()> {
+    let path = entry_path(dir, tool, request_hash)?;
+    fs::create_dir_all(tool_dir(dir, tool)?)?;
+    let json = serde_json::to_string(response).map_err(|e| PersistError::Io(e.to_string()))?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// Tool version directories under the cache root, in sorted order
+fn version_dirs(dir: &Path) -> PersistResult<Vec<(ToolId, PathBuf)>> {
+    let mut dirs = Vec::new();
+    for name_dir in sorted_entries(dir)? {
+        if !name_dir.is_dir() {
+            continue;
+        }
+        for version_dir in sorted_entries(&name_dir)? {
+            let (Some(name), Some(version)) = (
+                name_dir.file_name().and_then(|s| s.to_str()),
+                version_dir.file_name().and_then(|s| s.to_str()),
+            ) else {
+                continue;
+            };
+            if version_dir.is_dir() {
+                dirs.push((ToolId::new(name, version), version_dir));
+            }
+        }
+    }
+    Ok(dirs)
+}
+
+/// Directory entries in sorted order
+fn sorted_entries(dir: &Path) -> PersistResult<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oracle-omen-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_lru_eviction_by_size() {
+        let tool = ToolId::new("echo", "1.0.0");
+        let (a, b, c) = (
+            Hash::from_str("a"),
+            Hash::from_str("b"),
+            Hash::from_str("c"),
+        );
+        let mut cache = ToolCache::new().with_max_bytes(8);
+
+        cache
+            .insert(&tool, a, CachedResponse::new("aaaa".into(), 1))
+            .unwrap();
+        cache
+            .insert(&tool, b, CachedResponse::new("bbbb".into(), 1))
+            .unwrap();
+        assert!(cache.get(&tool, a).is_some());
+        cache
+            .insert(&tool, c, CachedResponse::new("cccc".into(), 1))
+            .unwrap();
+
+        // b was least recently used
+        assert!(cache.get(&tool, b).is_none());
+        assert!(cache.get(&tool, a).is_some());
+        assert_eq!(cache.size_bytes(), 8);
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Too large to ever fit
+        let d = Hash::from_str("d");
+        cache
+            .insert(&tool, d, CachedResponse::new("x".repeat(9), 1))
+            .unwrap();
+        assert!(cache.get(&tool, d).is_none());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_invalidate_version() {
+        let v1 = ToolId::new("hash", "1.0.0");
+        let v2 = ToolId::new("hash", "2.0.0");
+        let request = Hash::from_str("input");
+        let mut cache = ToolCache::new();
+        cache
+            .insert(&v1, request, CachedResponse::new("one".into(), 1))
+            .unwrap();
+        cache
+            .insert(&v2, request, CachedResponse::new("two".into(), 1))
+            .unwrap();
+
+        assert_eq!(cache.invalidate_version(&v1).unwrap(), 1);
+        assert!(cache.get(&v1, request).is_none());
+        assert_eq!(cache.get(&v2, request).unwrap().output, "two");
+    }
+
+    #[test]
+    fn test_disk_persistence() {
+        let dir = temp_dir("disk");
+        let tool = ToolId::new("echo", "1.0.0");
+        let request = Hash::from_str("hi");
+
+        let mut cache = ToolCache::open(&dir).unwrap();
+        cache
+            .insert(&tool, request, CachedResponse::new("hi".into(), 3))
+            .unwrap();
+
+        let mut reopened = ToolCache::open(&dir).unwrap();
+        assert_eq!(
+            reopened.get(&tool, request).unwrap(),
+            CachedResponse::new("hi".into(), 3)
+        );
+
+        reopened.invalidate_version(&tool).unwrap();
+        assert!(ToolCache::open(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unsafe_tool_ids_stay_inside_dir() {
+        let dir = temp_dir("unsafe");
+        let outside = dir.with_extension("outside");
+        let _ = fs::remove_dir_all(&outside);
+        fs::create_dir_all(&outside).unwrap();
+        let mut cache = ToolCache::open(&dir).unwrap();
+        let request = Hash::from_str("x");
+
+        for tool in [
+            ToolId::new("..", "1.0.0"),
+            ToolId::new("echo", ".."),
+            ToolId::new("a/b", "1.0.0"),
+            ToolId::new("/abs", "1.0.0"),
+        ] {
+            assert!(matches!(
+                cache.insert(&tool, request, CachedResponse::new("x".into(), 1)),
+                Err(PersistError::UnsafePath(_))
+            ));
+            assert!(matches!(
+                cache.invalidate_version(&tool),
+                Err(PersistError::UnsafePath(_))
+            ));
+        }
+        assert!(outside.is_dir());
+        assert!(cache.is_empty());
+        let _ = fs::remove_dir_all(&outside);
+    }
+}
@@ -15,8 +15,11 @@ impl CapabilityChecker {
     }
 
     /// Check if a capability is granted
+    ///
+    /// A grant ending in `*` covers every capability it matches, so
+    /// `process:exec:*` grants `process:exec:/bin/ls`.
     pub fn check(&self, capability: &Capability) -> CheckResult {
-        if self.granted.has(capability) {
+        if self.covers(capability) {
             CheckResult::Granted
         } else {
             CheckResult::Denied {
@@ -29,7 +32,7 @@ impl CapabilityChecker {
     /// Check if all required capabilities are granted
     pub fn check_all(&self, required: &[Capability]) -> CheckResult {
         for cap in required {
-            if !self.granted.has(cap) {
+            if !self.covers(cap) {
                 return CheckResult::Denied {
                     capability: cap.clone(),
                     reason: "Required capability not granted".to_string(),
@@ -43,6 +46,14 @@ impl CapabilityChecker {
     pub fn granted(&self) -> &CapabilitySet {
         &self.granted
     }
+
+    /// Check a capability against exact and wildcard grants
+    ///
+    /// Wildcards count only in the grant, so a broad requirement such as
+    /// `network:*` is never satisfied by a narrower grant.
+    fn covers(&self, capability: &Capability) -> bool {
+        self.granted.has(capability) || self.granted.iter().any(|grant| grant.covers(capability))
+    }
 }
 
 /// Result of a capability check
@@ -90,6 +101,21 @@ mod tests {
 
         assert!(checker.check(&Capability::new("fs:read:*")).is_granted());
         assert!(checker.check(&Capability::new("fs:write:*")).is_denied());
+        assert!(checker.check(&Capability::new("fs:read:/tmp")).is_granted());
+        assert!(checker.check(&Capability::new("network:http:post")).is_denied());
+    }
+
+    #[test]
+    fn test_broad_requirement_needs_broad_grant() {
+        let checker =
+            CapabilityChecker::new(CapabilitySet::new([Capability::new("network:https:*")]));
+
+        let api = Capability::new("network:https:api.example.com");
+        assert!(checker.check(&api).is_granted());
+        assert!(checker.check(&Capability::new("network:*")).is_denied());
+        assert!(checker.check(&Capability::new("network:*:*")).is_denied());
+        let required = [api, Capability::new("network:*")];
+        assert!(checker.check_all(&required).is_denied());
     }
 
     #[test]
@@ -0,0 +1,888 @@
+//! Run configuration file.
+//!
+//! `oracle-omen run <config>` reads a versioned TOML file:
+//!
+//! ```toml
+//! version = 1
+//!
+//! [agent]
+//! type = "echo_agent"
+//! version = "1.0.0"
+//!
+//! [run]
+//! run_id = 42
+//! seed = 7
+//!
+//! [capabilities]
+//! grant = ["fs:read:/data", "network:https:get:api.example.com"]
+//!
+//! [policy]
+//! files = ["policies/default.policy"]
+//!
+//! [[tools]]
+//! name = "echo"
+//! version = "1.0.0"
+//! kind = "native"
+//!
+//! [[tools]]
+//! name = "summarize"
+//! version = "0.2.0"
+//! kind = "wasm"
+//! module = "tools/summarize.wasm"
+//! capabilities = ["fs:read:/data"]
+//!
+//! [memory]
+//! backend = "file"
+//! path = "memory.json"
+//!
+//! [snapshots]
+//! every = 100
+//! keep = 3
+//!
+//! [limits]
+//! max_iterations = 100
+//! max_tool_calls = 50
+//! max_events = 10000
+//! timeout_ms = 30000
+//! max_memory_bytes = 67108864
+//! ```
+//!
+//! Unknown keys are errors. Every error carries the line and column it
+//! refers to. Relative paths resolve against the config file's directory.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use oracle_omen_core::{
+    capability::{Capability, CapabilitySet},
+    hash::Hash,
+    tool::ResourceBounds,
+    version::Version,
+};
+use toml_edit::{Document, Item, TableLike};
+
+use crate::{AgentConfig, AgentLimits, CapabilityChecker, CheckResult};
+
+/// Config format version this loader reads
+pub const CONFIG_VERSION: u64 = 1;
+
+/// Config result
+pub type ConfigResult<T> = Result<T, ConfigError>;
+
+/// Position in a config file, 1-based
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// Line
+    pub line: usize,
+
+    /// Column, in characters
+    pub column: usize,
+}
+
+impl Location {
+    /// Location of a byte offset in `source`
+    pub fn at(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Config errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file could not be read
+    Io {
+        /// Path of the config file
+        path: String,
+        /// Underlying IO error
+        reason: String,
+    },
+
+    /// The file is not valid TOML
+    Parse {
+        /// Where parsing failed
+        location: Location,
+        /// TOML parser error
+        message: String,
+    },
+
+    /// A key is missing, unknown or has an invalid value
+    Invalid {
+        /// Path of the key, e.g. `tools[0].version`
+        key: String,
+        /// Where the key or its table appears
+        location: Location,
+        /// What is wrong with it
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, reason } => write!(f, "{}: {}", path, reason),
+            ConfigError::Parse { location, message } => write!(f, "{}: {}", location, message),
+            ConfigError::Invalid {
+                key,
+                location,
+                message,
+            } => write!(f, "{}: {}: {}", location, key, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Kind of tool implementation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    /// Built into the binary
+    Native,
+
+    /// Loaded from a WASM module
+    Wasm,
+}
+
+/// A tool the run registers
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ToolManifest {
+    /// Tool name
+    pub name: String,
+
+    /// Tool version
+    pub version: String,
+
+    /// Implementation kind
+    pub kind: ToolKind,
+
+    /// WASM module, required for `wasm` tools
+    pub module: Option<PathBuf>,
+
+    /// Capabilities the tool needs; each must be granted
+    pub capabilities: BTreeSet<String>,
+}
+
+/// Where memory documents are kept
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case", tag = "backend")]
+pub enum MemoryBackend {
+    /// Lost when the run ends
+    InMemory,
+
+    /// Persisted to a file
+    File {
+        /// File the documents are kept in
+        path: PathBuf,
+    },
+}
+
+/// When state snapshots are taken
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SnapshotPolicy {
+    /// Snapshot every N events; `None` disables snapshots
+    pub every: Option<u64>,
+
+    /// Snapshots to keep
+    pub keep: u64,
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        Self {
+            every: None,
+            keep: 1,
+        }
+    }
+}
+
+/// Resource limits for the run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RunLimits {
+    /// Maximum state transitions
+    pub max_iterations: u64,
+
+    /// Maximum tool calls
+    pub max_tool_calls: u64,
+
+    /// Maximum events in the log
+    pub max_events: Option<u64>,
+
+    /// Default tool timeout
+    pub timeout_ms: u64,
+
+    /// Default tool memory limit
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl Default for RunLimits {
+    fn default() -> Self {
+        let agent = AgentLimits::default();
+        let tool = ResourceBounds::default();
+        Self {
+            max_iterations: agent.max_iterations,
+            max_tool_calls: agent.max_tool_calls,
+            max_events: agent.max_events,
+            timeout_ms: tool.timeout_ms,
+            max_memory_bytes: tool.max_memory_bytes,
+        }
+    }
+}
+
+/// A validated run configuration
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct RunConfig {
+    /// Format version
+    pub version: u64,
+
+    /// Agent type
+    pub agent_type: String,
+
+    /// Agent version
+    pub agent_version: String,
+
+    /// Run ID
+    pub run_id: u64,
+
+    /// Seed for the state machine and tools
+    pub seed: Option<u64>,
+
+    /// Granted capabilities
+    pub capabilities: BTreeSet<String>,
+
+    /// Policy files, in load order
+    pub policy_files: Vec<PathBuf>,
+
+    /// Tools to register
+    pub tools: Vec<ToolManifest>,
+
+    /// Memory backend
+    pub memory: MemoryBackend,
+
+    /// Snapshot policy
+    pub snapshots: SnapshotPolicy,
+
+    /// Resource limits
+    pub limits: RunLimits,
+
+    /// Directory relative paths resolve against
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+impl RunConfig {
+    /// Read and validate a config file
+    pub fn load(path: &Path) -> ConfigResult<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| ConfigError::Io {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        let mut config = Self::parse(&source)?;
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Validate config text
+    pub fn parse(source: &str) -> ConfigResult<Self> {
+        let document = Document::parse(source).map_err(|e| ConfigError::Parse {
+            location: Location::at(source, e.span().map_or(0, |s| s.start)),
+            message: e.message().to_string(),
+        })?;
+        let root = Section {
+            source,
+            table: document.as_table(),
+            path: String::new(),
+            span: None,
+        };
+        root.deny_unknown(&[
+            "version",
+            "agent",
+            "run",
+            "capabilities",
+            "policy",
+            "tools",
+            "memory",
+            "snapshots",
+            "limits",
+        ])?;
+
+        let version = root.required_u64("version")?;
+        if version != CONFIG_VERSION {
+            return Err(root.invalid(
+                "version",
+                format!(
+                    "unsupported config version {}, expected {}",
+                    version, CONFIG_VERSION
+                ),
+            ));
+        }
+
+        let agent = root.required_section("agent")?;
+        agent.deny_unknown(&["type", "version"])?;
+        let agent_type = agent.required_str("type")?;
+        let agent_version = agent.required_str("version")?;
+        agent.check_version("version", &agent_version)?;
+
+        let run = root.required_section("run")?;
+        run.deny_unknown(&["run_id", "seed"])?;
+        let run_id = run.required_u64("run_id")?;
+        let seed = run.u64("seed")?;
+
+        let mut capabilities = BTreeSet::new();
+        if let Some(section) = root.section("capabilities")? {
+            section.deny_unknown(&["grant"])?;
+            capabilities = section.capabilities("grant")?;
+        }
+        let checker = CapabilityChecker::new(CapabilitySet::new(
+            capabilities.iter().map(|c| Capability::new(c.as_str())),
+        ));
+
+        let mut policy_files = Vec::new();
+        if let Some(section) = root.section("policy")? {
+            section.deny_unknown(&["files"])?;
+            policy_files = section
+                .str_list("files")?
+                .into_iter()
+                .map(PathBuf::from)
+                .collect();
+        }
+
+        let mut tools: Vec<ToolManifest> = Vec::new();
+        for section in root.sections("tools")? {
+            let tool = section.tool(&checker)?;
+            if tools
+                .iter()
+                .any(|t| t.name == tool.name && t.version == tool.version)
+            {
+                return Err(section.invalid(
+                    "name",
+                    format!("tool {}@{} is listed twice", tool.name, tool.version),
+                ));
+            }
+            tools.push(tool);
+        }
+
+        let memory = match root.section("memory")? {
+            None => MemoryBackend::InMemory,
+            Some(section) => section.memory()?,
+        };
+
+        let mut snapshots = SnapshotPolicy::default();
+        if let Some(section) = root.section("snapshots")? {
+            section.deny_unknown(&["every", "keep"])?;
+            snapshots.every = section.positive("every")?;
+            snapshots.keep = section.positive("keep")?.unwrap_or(snapshots.keep);
+        }
+
+        let mut limits = RunLimits::default();
+        if let Some(section) = root.section("limits")? {
+            section.deny_unknown(&[
+                "max_iterations",
+                "max_tool_calls",
+                "max_events",
+                "timeout_ms",
+                "max_memory_bytes",
+            ])?;
+            limits.max_iterations = section
+                .positive("max_iterations")?
+                .unwrap_or(limits.max_iterations);
+            limits.max_tool_calls = section
+                .u64("max_tool_calls")?
+                .unwrap_or(limits.max_tool_calls);
+            limits.max_events = section.positive("max_events")?.or(limits.max_events);
+            limits.timeout_ms = section.positive("timeout_ms")?.unwrap_or(limits.timeout_ms);
+            limits.max_memory_bytes = section
+                .positive("max_memory_bytes")?
+                .or(limits.max_memory_bytes);
+        }
+
+        Ok(Self {
+            version,
+            agent_type,
+            agent_version,
+            run_id,
+            seed,
+            capabilities,
+            policy_files,
+            tools,
+            memory,
+            snapshots,
+            limits,
+            base_dir: PathBuf::new(),
+        })
+    }
+
+    /// Canonical hash of the validated config
+    ///
+    /// Formatting, comments and key order in the file do not affect it.
+    pub fn hash(&self) -> Hash {
+        Hash::from_canonical(self)
+    }
+
+    /// Resolve a path from the config against its directory
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        self.base_dir.join(path)
+    }
+
+    /// Granted capabilities
+    pub fn capability_set(&self) -> CapabilitySet {
+        CapabilitySet::new(
+            self.capabilities
+                .iter()
+                .map(|c| Capability::new(c.as_str())),
+        )
+    }
+
+    /// Default resource bounds for tools
+    pub fn tool_bounds(&self) -> ResourceBounds {
+        ResourceBounds {
+            timeout_ms: self.limits.timeout_ms,
+            max_memory_bytes: self.limits.max_memory_bytes,
+            max_fuel: None,
+        }
+    }
+
+    /// Agent config with the config hash recorded for `AgentInit`
+    pub fn agent_config(&self) -> AgentConfig {
+        let mut config = AgentConfig::new(&self.agent_type, &self.agent_version)
+            .with_entry("config_hash", self.hash().to_hex())
+            .with_entry("config_version", self.version.to_string())
+            .with_limits(AgentLimits {
+                max_iterations: self.limits.max_iterations,
+                max_tool_calls: self.limits.max_tool_calls,
+                max_events: self.limits.max_events,
+            });
+        if let Some(seed) = self.seed {
+            config = config.with_seed(seed);
+        }
+        config
+    }
+}
+
+/// A table in the config, with its key path for errors
+struct Section<'a> {
+    source: &'a str,
+    table: &'a dyn TableLike,
+    path: String,
+    span: Option<Range<usize>>,
+}
+
+impl<'a> Section<'a> {
+    fn key_path(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", self.path, key)
+        }
+    }
+
+    fn error(&self, key: String, span: Option<Range<usize>>, message: String) -> ConfigError {
+        ConfigError::Invalid {
+            key,
+            location: Location::at(self.source, span.map_or(0, |s| s.start)),
+            message,
+        }
+    }
+
+    /// Error at a key's value, or at the table when the key is missing
+    fn invalid(&self, key: &str, message: impl Into<String>) -> ConfigError {
+        let span = self
+            .table
+            .get(key)
+            .and_then(Item::span)
+            .or_else(|| self.span.clone());
+        self.error(self.key_path(key), span, message.into())
+    }
+
+    fn deny_unknown(&self, known: &[&str]) -> ConfigResult<()> {
+        for (key, _) in self.table.iter() {
+            if !known.contains(&key) {
+                let span = self.table.key(key).and_then(|k| k.span());
+                return Err(self.error(
+                    self.key_path(key),
+                    span,
+                    format!("unknown key, expected one of: {}", known.join(", ")),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn section(&self, key: &str) -> ConfigResult<Option<Section<'a>>> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(None);
+        };
+        let table = item.as_table_like().ok_or_else(|| {
+            self.invalid(key, format!("expected a table, found {}", item.type_name()))
+        })?;
+        Ok(Some(Section {
+            source: self.source,
+            table,
+            path: self.key_path(key),
+            span: item.span(),
+        }))
+    }
+
+    fn required_section(&self, key: &str) -> ConfigResult<Section<'a>> {
+        self.section(key)?
+            .ok_or_else(|| self.invalid(key, format!("missing [{}] table", key)))
+    }
+
+    /// An array of tables, `[[key]]` or `key = [{ ... }]`
+    fn sections(&self, key: &str) -> ConfigResult<Vec<Section<'a>>> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(Vec::new());
+        };
+        let path = |i: usize| format!("{}[{}]", self.key_path(key), i);
+        if let Some(tables) = item.as_array_of_tables() {
+            return Ok(tables
+                .iter()
+                .enumerate()
+                .map(|(i, table)| Section {
+                    source: self.source,
+                    table,
+                    path: path(i),
+                    span: table.span(),
+                })
+                .collect());
+        }
+        let array = item
+            .as_array()
+            .ok_or_else(|| self.invalid(key, "expected an array of tables"))?;
+        array
+            .iter()
+            .enumerate()
+            .map(|(i, value)| match value.as_inline_table() {
+                Some(table) => Ok(Section {
+                    source: self.source,
+                    table,
+                    path: path(i),
+                    span: value.span(),
+                }),
+                None => Err(self.error(
+                    path(i),
+                    value.span(),
+                    format!("expected a table, found {}", value.type_name()),
+                )),
+            })
+            .collect()
+    }
+
+    fn str(&self, key: &str) -> ConfigResult<Option<String>> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(None);
+        };
+        match item.as_str() {
+            Some(s) if !s.is_empty() => Ok(Some(s.to_string())),
+            Some(_) => Err(self.invalid(key, "must not be empty")),
+            None => Err(self.invalid(
+                key,
+                format!("expected a string, found {}", item.type_name()),
+            )),
+        }
+    }
+
+    fn required_str(&self, key: &str) -> ConfigResult<String> {
+        self.str(key)?
+            .ok_or_else(|| self.invalid(key, "missing required key"))
+    }
+
+    fn u64(&self, key: &str) -> ConfigResult<Option<u64>> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(None);
+        };
+        let value = item.as_integer().ok_or_else(|| {
+            self.invalid(
+                key,
+                format!("expected an integer, found {}", item.type_name()),
+            )
+        })?;
+        u64::try_from(value)
+            .map(Some)
+            .map_err(|_| self.invalid(key, "must not be negative"))
+    }
+
+    fn required_u64(&self, key: &str) -> ConfigResult<u64> {
+        self.u64(key)?
+            .ok_or_else(|| self.invalid(key, "missing required key"))
+    }
+
+    fn positive(&self, key: &str) -> ConfigResult<Option<u64>> {
+        match self.u64(key)? {
+            Some(0) => Err(self.invalid(key, "must be at least 1")),
+            value => Ok(value),
+        }
+    }
+
+    fn str_list(&self, key: &str) -> ConfigResult<Vec<String>> {
+        let Some(item) = self.table.get(key) else {
+            return Ok(Vec::new());
+        };
+        let array = item.as_array().ok_or_else(|| {
+            self.invalid(
+                key,
+                format!("expected an array, found {}", item.type_name()),
+            )
+        })?;
+        array
+            .iter()
+            .enumerate()
+            .map(|(i, value)| match value.as_str() {
+                Some(s) if !s.is_empty() => Ok(s.to_string()),
+                _ => Err(self.error(
+                    format!("{}[{}]", self.key_path(key), i),
+                    value.span(),
+                    "expected a non-empty string".to_string(),
+                )),
+            })
+            .collect()
+    }
+
+    /// Capability names, with empty segments and whitespace rejected
+    fn capabilities(&self, key: &str) -> ConfigResult<BTreeSet<String>> {
+        let names = self.str_list(key)?;
+        for (i, name) in names.iter().enumerate() {
+            if name.split(':').any(str::is_empty) || name.contains(char::is_whitespace) {
+                let span = self
+                    .table
+                    .get(key)
+                    .and_then(Item::as_array)
+                    .and_then(|a| a.get(i))
+                    .and_then(|v| v.span());
+                return Err(self.error(
+                    format!("{}[{}]", self.key_path(key), i),
+                    span,
+                    format!("invalid capability '{}'", name),
+                ));
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn check_version(&self, key: &str, version: &str) -> ConfigResult<()> {
+        Version::parse(version)
+            .map(|_| ())
+            .map_err(|e| self.invalid(key, e.to_string()))
+    }
+
+    fn tool(&self, checker: &CapabilityChecker) -> ConfigResult<ToolManifest> {
+        self.deny_unknown(&["name", "version", "kind", "module", "capabilities"])?;
+        let name = self.required_str("name")?;
+        let version = self.required_str("version")?;
+        self.check_version("version", &version)?;
+
+        let kind = match self.required_str("kind")?.as_str() {
+            "native" => ToolKind::Native,
+            "wasm" => ToolKind::Wasm,
+            other => {
+                return Err(self.invalid(
+                    "kind",
+                    format!("unknown tool kind '{}', expected native or wasm", other),
+                ))
+            }
+        };
+        let module = self.str("module")?.map(PathBuf::from);
+        match (kind, &module) {
+            (ToolKind::Wasm, None) => {
+                return Err(self.invalid("module", "wasm tools need a module path"))
+            }
+            (ToolKind::Native, Some(_)) => {
+                return Err(self.invalid("module", "native tools do not take a module"))
+            }
+            _ => {}
+        }
+
+        let capabilities = self.capabilities("capabilities")?;
+        for (i, name) in capabilities.iter().enumerate() {
+            if let CheckResult::Denied { .. } = checker.check(&Capability::new(name.as_str())) {
+                let span = self
+                    .table
+                    .get("capabilities")
+                    .and_then(Item::as_array)
+                    .and_then(|a| a.iter().find(|v| v.as_str() == Some(name)))
+                    .and_then(|v| v.span());
+                return Err(self.error(
+                    format!("{}.capabilities[{}]", self.path, i),
+                    span,
+                    format!("capability '{}' is not granted in [capabilities]", name),
+                ));
+            }
+        }
+
+        Ok(ToolManifest {
+            name,
+            version,
+            kind,
+            module,
+            capabilities,
+        })
+    }
+
+    fn memory(&self) -> ConfigResult<MemoryBackend> {
+        self.deny_unknown(&["backend", "path"])?;
+        let path = self.str("path")?.map(PathBuf::from);
+        match (self.required_str("backend")?.as_str(), path) {
+            ("in_memory", None) => Ok(MemoryBackend::InMemory),
+            ("in_memory", Some(_)) => {
+                Err(self.invalid("path", "the in_memory backend does not take a path"))
+            }
+            ("file", Some(path)) => Ok(MemoryBackend::File { path }),
+            ("file", None) => Err(self.invalid("path", "the file backend needs a path")),
+            (other, _) => Err(self.invalid(
+                "backend",
+                format!("unknown backend '{}', expected in_memory or file", other),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = r#"
+version = 1
+
+[agent]
+type = "echo_agent"
+version = "1.0.0"
+
+[run]
+run_id = 42
+seed = 7
+
+[capabilities]
+grant = ["fs:read:*", "network:https:get:api.example.com"]
+
+[policy]
+files = ["policies/default.policy"]
+
+[[tools]]
+name = "echo"
+version = "1.0.0"
+kind = "native"
+
+[[tools]]
+name = "summarize"
+version = "0.2.0"
+kind = "wasm"
+module = "tools/summarize.wasm"
+capabilities = ["fs:read:/data"]
+
+[memory]
+backend = "file"
+path = "memory.json"
+
+[snapshots]
+every = 100
+keep = 3
+
+[limits]
+max_iterations = 10
+max_events = 500
+"#;
+
+    fn invalid(source: &str) -> (String, Location) {
+        match RunConfig::parse(source) {
+            Err(ConfigError::Invalid { key, location, .. }) => (key, location),
+            other => panic!("expected invalid config, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_full_config() {
+        let config = RunConfig::parse(FULL).unwrap();
+        assert_eq!(config.agent_type, "echo_agent");
+        assert_eq!(config.run_id, 42);
+        assert_eq!(config.seed, Some(7));
+        assert_eq!(config.tools.len(), 2);
+        assert_eq!(config.tools[1].kind, ToolKind::Wasm);
+        assert_eq!(
+            config.memory,
+            MemoryBackend::File {
+                path: PathBuf::from("memory.json")
+            }
+        );
+        assert_eq!(config.snapshots.every, Some(100));
+        assert_eq!(config.limits.max_iterations, 10);
+        assert_eq!(config.limits.max_tool_calls, 100);
+
+        let agent = config.agent_config();
+        assert_eq!(agent.seed, Some(7));
+        assert_eq!(agent.limits.max_events, Some(500));
+        assert_eq!(agent.config["config_hash"], config.hash().to_hex());
+    }
+
+    #[test]
+    fn test_hash_ignores_formatting() {
+        let reformatted = FULL
+            .replace(
+                "[agent]\ntype = \"echo_agent\"\nversion = \"1.0.0\"",
+                "agent = { version = \"1.0.0\", type = \"echo_agent\" }",
+            )
+            .replace(
+                "grant = [\"fs:read:*\", \"network:https:get:api.example.com\"]",
+                "# grants\ngrant = [\"network:https:get:api.example.com\", \"fs:read:*\"]",
+            );
+        assert_ne!(reformatted, FULL);
+        let a = RunConfig::parse(FULL).unwrap();
+        let b = RunConfig::parse(&reformatted).unwrap();
+        assert_eq!(a.hash(), b.hash());
+
+        let changed = FULL.replace("seed = 7", "seed = 8");
+        assert_ne!(RunConfig::parse(&changed).unwrap().hash(), a.hash());
+    }
+
+    #[test]
+    fn test_errors_point_at_the_key() {
+        let (key, location) = invalid(&FULL.replace("kind = \"native\"", "kind = \"python\""));
+        assert_eq!(key, "tools[0].kind");
+        assert_eq!(
+            location,
+            Location {
+                line: 21,
+                column: 8
+            }
+        );
+
+        let (key, location) = invalid(&FULL.replace("seed = 7", "seed = 7\nsed = 8"));
+        assert_eq!(key, "run.sed");
+        assert_eq!(
+            location,
+            Location {
+                line: 11,
+                column: 1
+            }
+        );
+
+        let (key, _) = invalid(&FULL.replace("[\"fs:read:/data\"]", "[\"fs:write:/data\"]"));
+        assert_eq!(key, "tools[1].capabilities[0]");
+
+        let (key, location) = invalid(&FULL.replace("version = 1\n", "version = 2\n"));
+        assert_eq!(key, "version");
+        assert_eq!(
+            location,
+            Location {
+                line: 2,
+                column: 11
+            }
+        );
+
+        match RunConfig::parse("version = = 1") {
+            Err(ConfigError::Parse { location, .. }) => assert_eq!(location.line, 1),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
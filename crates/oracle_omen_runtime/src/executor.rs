@@ -1,10 +1,32 @@
 //! DAG executor - runs compiled DAGs with capability enforcement.
 
-use oracle_omen_core::capability::{Capability, CapabilitySet};
-use oracle_omen_plan::dag::Dag;
-use std::collections::HashMap;
+use crate::persist::{PersistError, RunStore};
+use crate::scheduler::{AdmissionDecision, Commit, CommitFlow, NodeRunner, ResourcePool, Scheduler};
+use oracle_omen_core::capability::CapabilitySet;
+use oracle_omen_core::event::{Event, EventId, EventKind, EventLog, EventPayload};
+use oracle_omen_core::hash::Hash;
+use oracle_omen_core::serde_utils::StableMap;
+use oracle_omen_core::tool::SideEffect;
+use oracle_omen_plan::dag::{Dag, DagNode};
+use oracle_omen_plan::dsl::FailurePolicy;
+use oracle_omen_plan::validate::PlanValidator;
+use oracle_omen_policy::engine::{EvalContext, PolicyEngine, ResourceRequest};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+/// Event kind recorded when a node reaches the head of the commit order
+pub const NODE_STARTED: &str = "node_started";
+
+/// Event kind recorded when a node's result is committed
+pub const NODE_COMPLETED: &str = "node_completed";
+
+/// Event kind recorded when a node is skipped because a dependency failed
+pub const NODE_SKIPPED: &str = "node_skipped";
+
+/// Event kind recorded when execution pauses
+pub const CHECKPOINT: &str = "checkpoint";
+
 /// Execution result
 pub type ExecResult<T> = Result<T, ExecError>;
 
@@ -12,19 +34,50 @@ pub type ExecResult<T> = Result<T, ExecError>;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExecError {
     /// Capability denied
-    CapabilityDenied { capability: String, reason: String },
+    CapabilityDenied {
+        /// Capability that was missing
+        capability: String,
+        /// Why it was denied
+        reason: String,
+    },
 
     /// Tool not found
     ToolNotFound(String),
 
     /// Timeout
-    Timeout { node: String, duration_ms: u64 },
+    Timeout {
+        /// Node that timed out
+        node: String,
+        /// Time allowed, in milliseconds
+        duration_ms: u64,
+    },
 
     /// Execution failed
-    Failed { node: String, reason: String },
+    Failed {
+        /// Node that failed
+        node: String,
+        /// Failure message
+        reason: String,
+    },
 
     /// Resource limit exceeded
-    ResourceExceeded { node: String, limit: String },
+    ResourceExceeded {
+        /// Node whose demand exceeds the limit
+        node: String,
+        /// Limit that was exceeded
+        limit: String,
+    },
+
+    /// Denied by policy
+    PolicyDenied {
+        /// Node whose resources were denied
+        node: String,
+        /// Reason given by the policy engine
+        reason: String,
+    },
+
+    /// Persisting the event log failed
+    Persist(String),
 
     /// Invalid state
     InvalidState(String),
@@ -46,6 +99,10 @@ impl std::fmt::Display for ExecError {
             ExecError::ResourceExceeded { node, limit } => {
                 write!(f, "Node {} exceeded limit: {}", node, limit)
             }
+            ExecError::PolicyDenied { node, reason } => {
+                write!(f, "Node {} denied by policy: {}", node, reason)
+            }
+            ExecError::Persist(msg) => write!(f, "Persist failed: {}", msg),
             ExecError::InvalidState(msg) => write!(f, "Invalid state: {}", msg),
         }
     }
@@ -53,6 +110,12 @@ impl std::fmt::Display for ExecError {
 
 impl std::error::Error for ExecError {}
 
+impl From<PersistError> for ExecError {
+    fn from(e: PersistError) -> Self {
+        ExecError::Persist(e.to_string())
+    }
+}
+
 /// Execution state
 #[derive(Clone, Debug)]
 pub struct ExecState {
@@ -62,11 +125,20 @@ pub struct ExecState {
     /// Failed nodes
     pub failed: Vec<String>,
 
-    /// Current node being executed
+    /// Nodes not run because a node they depend on failed
+    pub skipped: Vec<String>,
+
+    /// Current node being executed, or in flight when recovered from a log
     pub current: Option<String>,
 
+    /// Whether execution stopped at a pause
+    pub paused: bool,
+
     /// Node results
-    pub results: HashMap<String, NodeResult>,
+    pub results: BTreeMap<String, NodeResult>,
+
+    /// Scheduler admission decisions, in the order they were made
+    pub admissions: Vec<AdmissionDecision>,
 }
 
 impl ExecState {
@@ -75,14 +147,25 @@ impl ExecState {
         Self {
             completed: Vec::new(),
             failed: Vec::new(),
+            skipped: Vec::new(),
             current: None,
-            results: HashMap::new(),
+            paused: false,
+            results: BTreeMap::new(),
+            admissions: Vec::new(),
         }
     }
 
+    /// Rebuild the state of a DAG's execution from an event log
+    ///
+    /// Only node events of the DAG named `dag` are considered. Recovered
+    /// results carry no duration, which is never logged.
+    pub fn from_log(log: &EventLog, dag: &str) -> ExecResult<Self> {
+        Ok(Recovered::scan(log, dag)?.state)
+    }
+
     /// Check if execution is complete
     pub fn is_complete(&self) -> bool {
-        !self.completed.is_empty() && self.current.is_none()
+        !self.completed.is_empty() && self.current.is_none() && !self.paused
     }
 
     /// Check if execution failed
@@ -140,13 +223,161 @@ impl NodeResult {
     }
 }
 
+/// Node progress recovered from an event log
+struct Recovered {
+    /// Rebuilt execution state
+    state: ExecState,
+
+    /// `node_started` event of the in-flight node
+    in_flight: Option<EventId>,
+
+    /// `node_completed` event of each committed node
+    completions: BTreeMap<String, EventId>,
+}
+
+impl Recovered {
+    /// Scan a log for a DAG's node events
+    fn scan(log: &EventLog, dag: &str) -> ExecResult<Self> {
+        let mut state = ExecState::new();
+        let mut started: BTreeMap<EventId, String> = BTreeMap::new();
+        let mut in_flight = None;
+        let mut completions = BTreeMap::new();
+
+        for event in log.events() {
+            let (EventKind::Custom(kind), EventPayload::Raw(data)) = (&event.kind, &event.payload)
+            else {
+                continue;
+            };
+            let field = |name: &str| data.get(name).cloned().ok_or_else(|| corrupt(event, name));
+
+            match kind.as_str() {
+                NODE_STARTED if data.get("dag").map(String::as_str) == Some(dag) => {
+                    if in_flight.is_some() {
+                        return Err(ExecError::InvalidState(format!(
+                            "{} starts a node while another is in flight",
+                            event.id
+                        )));
+                    }
+                    let node_id = field("node_id")?;
+                    started.insert(event.id, node_id.clone());
+                    in_flight = Some(event.id);
+                    state.current = Some(node_id);
+                    state.paused = false;
+                }
+                NODE_COMPLETED => {
+                    let Some(parent) = event.parent_id.filter(|p| started.contains_key(p)) else {
+                        continue;
+                    };
+                    if in_flight != Some(parent) {
+                        return Err(ExecError::InvalidState(format!(
+                            "{} completes a node that is not in flight",
+                            event.id
+                        )));
+                    }
+                    in_flight = None;
+                    state.current = None;
+
+                    let result = NodeResult {
+                        node_id: started[&parent].clone(),
+                        success: field("success")? == "true",
+                        output: data.get("output").cloned(),
+                        error: data.get("error").cloned(),
+                        duration_ms: 0,
+                    };
+                    if result.success {
+                        state.completed.push(result.node_id.clone());
+                    } else {
+                        state.failed.push(result.node_id.clone());
+                    }
+                    completions.insert(result.node_id.clone(), event.id);
+                    state.results.insert(result.node_id.clone(), result);
+                }
+                NODE_SKIPPED if data.get("dag").map(String::as_str) == Some(dag) => {
+                    state.skipped.push(field("node_id")?);
+                }
+                CHECKPOINT if data.get("dag").map(String::as_str) == Some(dag) => {
+                    state.paused = true;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            state,
+            in_flight,
+            completions,
+        })
+    }
+}
+
+/// Error for a node event missing a field
+fn corrupt(event: &Event, field: &str) -> ExecError {
+    ExecError::InvalidState(format!("{} is missing '{}'", event.id, field))
+}
+
+/// What to do with an impure node that was in flight when a run stopped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InFlightPolicy {
+    /// Never re-run it; commit it as failed
+    #[default]
+    AtMostOnce,
+
+    /// Run it again
+    AtLeastOnce,
+}
+
+/// Requests that a running executor pause
+///
+/// The executor stops at the next commit, so an impure node is never cut
+/// short, and records a checkpoint event.
+#[derive(Clone, Debug, Default)]
+pub struct PauseHandle(Arc<AtomicBool>);
+
+impl PauseHandle {
+    /// Request a pause
+    pub fn pause(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether a pause was requested
+    pub fn is_paused(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Withdraw a pause request
+    fn clear(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
 /// DAG executor
 pub struct DagExecutor {
     /// Granted capabilities
     capabilities: CapabilitySet,
 
+    /// Policy governing node resources
+    policy: Option<PolicyEngine>,
+
     /// Execution state
     state: ExecState,
+
+    /// Log receiving committed node events
+    log: EventLog,
+
+    /// Maximum nodes running at once
+    max_concurrent: usize,
+
+    /// Resource budget shared by running nodes
+    pool: ResourcePool,
+
+    /// Store each committed event is persisted to
+    store: Option<RunStore>,
+
+    /// Handling of impure nodes in flight at a crash
+    in_flight: InFlightPolicy,
+
+    /// Pause requests
+    pause: PauseHandle,
 }
 
 impl DagExecutor {
@@ -154,30 +385,215 @@ impl DagExecutor {
     pub fn new(capabilities: CapabilitySet) -> Self {
         Self {
             capabilities,
+            policy: None,
             state: ExecState::new(),
+            log: EventLog::new(0),
+            max_concurrent: 1,
+            pool: ResourcePool::new(),
+            store: None,
+            in_flight: InFlightPolicy::default(),
+            pause: PauseHandle::default(),
+        }
+    }
+
+    /// Resume a persisted run
+    ///
+    /// Loads the run's log from `store` and keeps appending to it. The next
+    /// [`execute`](Self::execute) picks up where the log stops.
+    pub fn resume(capabilities: CapabilitySet, store: RunStore, run_id: u64) -> ExecResult<Self> {
+        let log = store.load_log(run_id)?;
+        Ok(Self::new(capabilities).with_log(log).with_store(store))
+    }
+
+    /// Append node events to an existing log
+    ///
+    /// Node events already in the log for the same DAG are treated as
+    /// progress to resume from.
+    pub fn with_log(mut self, log: EventLog) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Persist each committed event to `store`
+    ///
+    /// The store's copy of the run must match the executor's log.
+    pub fn with_store(mut self, store: RunStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Choose how impure nodes in flight at a crash are handled
+    pub fn with_in_flight_policy(mut self, policy: InFlightPolicy) -> Self {
+        self.in_flight = policy;
+        self
+    }
+
+    /// Get a handle for pausing execution from another task
+    pub fn pause_handle(&self) -> PauseHandle {
+        self.pause.clone()
+    }
+
+    /// Pause at the next commit
+    pub fn pause(&self) {
+        self.pause.pause();
+    }
+
+    /// Allow up to `max_concurrent` nodes to run at once
+    ///
+    /// The committed log does not depend on this setting.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = max_concurrent;
+        self
+    }
+
+    /// Admit nodes only while their declared resources fit `pool`
+    pub fn with_resource_pool(mut self, pool: ResourcePool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Attach a policy engine that must admit each node's resources
+    pub fn with_policy(mut self, policy: PolicyEngine) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Check every node's resource annotation against policy
+    ///
+    /// Runs before any node executes so a denied annotation never takes effect.
+    pub fn check_resources(&self, dag: &Dag) -> ExecResult<()> {
+        let Some(policy) = &self.policy else {
+            return Ok(());
+        };
+
+        let mut ctx = EvalContext::new();
+        ctx.capabilities = self
+            .capabilities
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+
+        for node in dag.nodes().values() {
+            let result = policy.evaluate_resource(&self.resource_request(node), &ctx);
+            if !result.allowed {
+                return Err(ExecError::PolicyDenied {
+                    node: node.id.clone(),
+                    reason: result.reason,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Build the resource request for running a node
+    ///
+    /// Concurrency is the most nodes this executor runs alongside each other;
+    /// the node's own CPU units are requested separately.
+    fn resource_request(&self, node: &DagNode) -> ResourceRequest {
+        ResourceRequest {
+            timeout_ms: Some(node.resources.timeout_ms),
+            memory_bytes: node.resources.max_memory_bytes,
+            fuel: None,
+            concurrency: Some(self.max_concurrent.max(1) as u64),
+            cpu_units: node.resources.cpu_units,
         }
     }
 
     /// Execute a DAG
-    pub async fn execute(&mut self, _dag: &Dag) -> ExecResult<ExecState> {
-        // Get topological order
-        // let order = dag.topological_order().map_err(|e| ExecError::InvalidState(e.to_string()))?;
+    ///
+    /// Nodes run concurrently through `runner`; each committed node appends a
+    /// `node_started` and a `node_completed` event in `(rank, id)` order.
+    /// Wall-clock durations stay in [`ExecState`] and never reach the log, so
+    /// the log is byte-identical for any concurrency level. A failed node stops
+    /// execution unless its failure policy is `Continue`, in which case its
+    /// transitive dependents are recorded as `node_skipped` and independent
+    /// branches carry on. Failure policies other than `Stop` and `Continue`
+    /// are rejected before anything runs.
+    ///
+    /// If the log already holds node events for this DAG, execution resumes:
+    /// committed nodes are not run again, and a node left in flight is re-run
+    /// if pure, otherwise handled by the [`InFlightPolicy`]. Resuming clears
+    /// any pause request.
+    pub async fn execute(
+        &mut self,
+        dag: &Dag,
+        runner: Arc<dyn NodeRunner>,
+    ) -> ExecResult<ExecState> {
+        PlanValidator::validate_dag(dag).map_err(|e| ExecError::InvalidState(e.to_string()))?;
+        self.check_resources(dag)?;
+        self.pause.clear();
+
+        let recovered = Recovered::scan(&self.log, &dag.name)?;
+        self.state = recovered.state;
+        self.state.paused = false;
+
+        let in_flight_policy = self.in_flight;
+        let mut committer = Committer {
+            dag,
+            log: &mut self.log,
+            store: self.store.as_ref(),
+            state: &mut self.state,
+            pause: &self.pause,
+            started: None,
+            completions: recovered.completions,
+        };
+
+        if committer.stopped_by_failure() {
+            return Ok(self.state.clone());
+        }
 
-        // Execute each node
-        // for node_id in order {
-        //     self.execute_node(dag, &node_id).await?;
-        // }
+        let mut head_started = false;
+        if let (Some(node_id), Some(started)) = (committer.state.current.clone(), recovered.in_flight)
+        {
+            let node = dag
+                .node(&node_id)
+                .ok_or_else(|| ExecError::InvalidState(format!("Unknown node: {}", node_id)))?;
+            committer.started = Some(started);
+
+            let rerun = runner.side_effect(node) == SideEffect::Pure
+                || in_flight_policy == InFlightPolicy::AtLeastOnce;
+            if rerun {
+                head_started = true;
+            } else {
+                let result = NodeResult::failure(
+                    node_id,
+                    "interrupted before completion; not re-run (at-most-once)",
+                    0,
+                );
+                if committer.finished(result)? == CommitFlow::Stop {
+                    return Ok(self.state.clone());
+                }
+            }
+        }
+
+        let failed: BTreeSet<String> = committer.state.failed.iter().cloned().collect();
+        let committed: BTreeSet<String> = committer
+            .state
+            .completed
+            .iter()
+            .chain(&committer.state.skipped)
+            .chain(&failed)
+            .cloned()
+            .collect();
+
+        let mut scheduler = Scheduler::new(self.max_concurrent).with_pool(self.pool);
+        let result = scheduler
+            .run_from(dag, runner, &committed, &failed, head_started, |commit| {
+                committer.on_commit(commit)
+            })
+            .await;
+        if result.is_ok() && committer.state.paused {
+            committer.checkpoint()?;
+        }
 
+        self.state.admissions = scheduler.decisions().to_vec();
+        result?;
         Ok(self.state.clone())
     }
 
-    /// Execute a single node
-    async fn execute_node(&mut self, _dag: &Dag, _node_id: &str) -> ExecResult<()> {
-        // Check capabilities
-        // Check dependencies
-        // Execute
-        // Record result
-        Ok(())
+    /// Get the event log
+    pub fn log(&self) -> &EventLog {
+        &self.log
     }
 
     /// Get current execution state
@@ -191,25 +607,150 @@ impl DagExecutor {
     }
 }
 
-/// Tool registry for execution
-pub trait ToolRegistry: Send + Sync {
-    /// Check if a tool exists
-    fn has_tool(&self, name: &str) -> bool;
+/// Turns scheduler commits into node events and state updates
+struct Committer<'a> {
+    dag: &'a Dag,
+    log: &'a mut EventLog,
+    store: Option<&'a RunStore>,
+    state: &'a mut ExecState,
+    pause: &'a PauseHandle,
+
+    /// `node_started` event of the node being committed
+    started: Option<EventId>,
 
-    /// Get tool
-    fn get_tool(&self, name: &str) -> Option<Arc<dyn Tool>>;
+    /// `node_completed` event of each committed node
+    completions: BTreeMap<String, EventId>,
 }
 
-/// Tool trait for runtime execution
-pub trait Tool: Send + Sync {
-    /// Get tool name
-    fn name(&self) -> &str;
+impl Committer<'_> {
+    /// Handle one scheduler commit
+    fn on_commit(&mut self, commit: Commit) -> ExecResult<CommitFlow> {
+        match commit {
+            Commit::Started(node_id) => self.started(node_id),
+            Commit::Finished(result) => self.finished(result),
+            Commit::Skipped { node_id, cause } => self.skipped(node_id, cause),
+        }
+    }
+
+    /// Record a node reaching the head of the commit order
+    fn started(&mut self, node_id: String) -> ExecResult<CommitFlow> {
+        if self.pause.is_paused() {
+            self.state.paused = true;
+            return Ok(CommitFlow::Stop);
+        }
+
+        // Link to the latest committed dependency, else the log tail
+        let parent = self
+            .dag
+            .dependencies(&node_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| self.completions.get(dep))
+            .max()
+            .copied()
+            .or_else(|| self.log.last().map(|e| e.id));
+
+        let mut data = StableMap::new();
+        data.insert("dag".to_string(), self.dag.name.clone());
+        data.insert("node_id".to_string(), node_id.clone());
+        self.started = Some(self.record(NODE_STARTED, data, parent)?);
+        self.state.current = Some(node_id);
+        Ok(CommitFlow::Continue)
+    }
+
+    /// Record a node's result
+    fn finished(&mut self, result: NodeResult) -> ExecResult<CommitFlow> {
+        let mut data = StableMap::new();
+        data.insert("node_id".to_string(), result.node_id.clone());
+        data.insert("success".to_string(), result.success.to_string());
+        if let Some(output) = &result.output {
+            data.insert("output".to_string(), output.clone());
+            data.insert(
+                "output_hash".to_string(),
+                Hash::from_bytes(output.as_bytes()).to_hex(),
+            );
+        }
+        if let Some(error) = &result.error {
+            data.insert("error".to_string(), error.clone());
+        }
+        let parent = self.started.take();
+        let event = self.record(NODE_COMPLETED, data, parent)?;
+        self.completions.insert(result.node_id.clone(), event);
+
+        self.state.current = None;
+        if result.success {
+            self.state.completed.push(result.node_id.clone());
+        } else {
+            self.state.failed.push(result.node_id.clone());
+        }
+        self.state.results.insert(result.node_id.clone(), result);
 
-    /// Get required capabilities
-    fn capabilities(&self) -> Vec<Capability>;
+        if self.stopped_by_failure() {
+            Ok(CommitFlow::Stop)
+        } else if self.pause.is_paused() {
+            self.state.paused = true;
+            Ok(CommitFlow::Stop)
+        } else {
+            Ok(CommitFlow::Continue)
+        }
+    }
+
+    /// Record a node skipped because `cause` failed
+    fn skipped(&mut self, node_id: String, cause: String) -> ExecResult<CommitFlow> {
+        let parent = self
+            .completions
+            .get(&cause)
+            .copied()
+            .or_else(|| self.log.last().map(|e| e.id));
+
+        let mut data = StableMap::new();
+        data.insert("dag".to_string(), self.dag.name.clone());
+        data.insert("node_id".to_string(), node_id.clone());
+        data.insert("cause".to_string(), cause);
+        self.record(NODE_SKIPPED, data, parent)?;
+        self.state.skipped.push(node_id);
+        Ok(CommitFlow::Continue)
+    }
 
-    /// Execute the tool
-    fn execute(&self, input: &[u8]) -> ExecResult<Vec<u8>>;
+    /// Check whether a committed failure stops the DAG
+    fn stopped_by_failure(&self) -> bool {
+        self.state.failed.iter().any(|node_id| {
+            self.dag
+                .node(node_id)
+                .map_or(true, |n| n.failure_policy != FailurePolicy::Continue)
+        })
+    }
+
+    /// Record a checkpoint after pausing
+    fn checkpoint(&mut self) -> ExecResult<EventId> {
+        let mut data = StableMap::new();
+        data.insert("dag".to_string(), self.dag.name.clone());
+        data.insert(
+            "completed".to_string(),
+            self.state.completed.len().to_string(),
+        );
+        data.insert("failed".to_string(), self.state.failed.len().to_string());
+        data.insert("skipped".to_string(), self.state.skipped.len().to_string());
+        let parent = self.log.last().map(|e| e.id);
+        self.record(CHECKPOINT, data, parent)
+    }
+
+    /// Append a node lifecycle event, persisting it if a store is attached
+    fn record(
+        &mut self,
+        kind: &str,
+        data: StableMap<String, String>,
+        parent: Option<EventId>,
+    ) -> ExecResult<EventId> {
+        let id = self
+            .log
+            .append_next(EventKind::Custom(kind.to_string()), EventPayload::Raw(data), parent)
+            .map_err(|e| ExecError::InvalidState(e.to_string()))?;
+        if let (Some(store), Some(event)) = (self.store, self.log.get(id)) {
+            store.append_event(event)?;
+        }
+        Ok(id)
+    }
 }
 
 #[cfg(test)]
@@ -235,4 +776,365 @@ mod tests {
         let executor = DagExecutor::new(CapabilitySet::empty());
         assert_eq!(executor.capabilities().len(), 0);
     }
+
+    #[test]
+    fn test_check_resources_policy() {
+        use oracle_omen_plan::dag::{DagNode, DagNodeType};
+        use oracle_omen_policy::{
+            compiler::PolicyCompiler,
+            lang::{Action, CompareOp, Condition, Policy, Rule, RuleKind, Value},
+        };
+
+        let mut policy = Policy::new("resources", "1.0.0");
+        policy.add_rule(Rule {
+            name: "max_memory".to_string(),
+            kind: RuleKind::Resource,
+            condition: Condition::Compare {
+                field: "resource.memory_bytes".to_string(),
+                op: CompareOp::Greater,
+                value: Value::Integer(1024),
+            },
+            action: Action::Deny {
+                reason: "Too much memory".to_string(),
+            },
+        });
+        policy.add_rule(Rule {
+            name: "max_concurrency".to_string(),
+            kind: RuleKind::Resource,
+            condition: Condition::Compare {
+                field: "resource.concurrency".to_string(),
+                op: CompareOp::Greater,
+                value: Value::Integer(2),
+            },
+            action: Action::Deny {
+                reason: "Too many concurrent nodes".to_string(),
+            },
+        });
+        policy.add_rule(Rule {
+            name: "allow".to_string(),
+            kind: RuleKind::Resource,
+            condition: Condition::True,
+            action: Action::Allow,
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(PolicyCompiler::compile(&policy).unwrap());
+
+        let mut dag = Dag::new("test");
+        let mut node = DagNode::new("big", DagNodeType::Wait { duration_ms: 0 });
+        node.resources.max_memory_bytes = Some(4096);
+        dag.add_node(node).unwrap();
+
+        let executor = DagExecutor::new(CapabilitySet::empty()).with_policy(engine.clone());
+        let err = executor.check_resources(&dag).unwrap_err();
+        assert!(matches!(err, ExecError::PolicyDenied { ref node, .. } if node == "big"));
+
+        // Concurrency is the executor's, not the node's CPU units
+        let mut dag = Dag::new("test");
+        let mut node = DagNode::new("small", DagNodeType::Wait { duration_ms: 0 });
+        node.resources.cpu_units = Some(8);
+        dag.add_node(node).unwrap();
+
+        let executor = DagExecutor::new(CapabilitySet::empty())
+            .with_policy(engine.clone())
+            .with_max_concurrent(2);
+        assert!(executor.check_resources(&dag).is_ok());
+
+        let executor = DagExecutor::new(CapabilitySet::empty())
+            .with_policy(engine)
+            .with_max_concurrent(4);
+        let err = executor.check_resources(&dag).unwrap_err();
+        assert!(matches!(
+            err,
+            ExecError::PolicyDenied { ref reason, .. } if reason == "Too many concurrent nodes"
+        ));
+    }
+
+    /// Sleeps longer for earlier nodes so completions arrive out of order
+    struct SlowRunner;
+
+    #[async_trait::async_trait]
+    impl NodeRunner for SlowRunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            let index: u64 = node.id.trim_start_matches('n').parse().unwrap_or(0);
+            let delay = 40 - index * 5;
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            if node.id == "n6" {
+                NodeResult::failure(node.id.clone(), "boom", delay)
+            } else {
+                NodeResult::success(node.id.clone(), format!("out-{}", node.id), delay)
+            }
+        }
+
+        fn side_effect(&self, _node: &DagNode) -> SideEffect {
+            SideEffect::Pure
+        }
+    }
+
+    fn fan_dag(failure_policy: FailurePolicy) -> Dag {
+        use oracle_omen_plan::dag::DagNodeType;
+
+        let mut dag = Dag::new("fan");
+        for i in 0..8 {
+            let mut node = DagNode::new(format!("n{}", i), DagNodeType::Wait { duration_ms: 0 });
+            node.failure_policy = failure_policy.clone();
+            dag.add_node(node).unwrap();
+        }
+        for i in 1..4 {
+            dag.add_edge("n0".to_string(), format!("n{}", i)).unwrap();
+        }
+        for i in 4..8 {
+            dag.add_edge(format!("n{}", i - 3), format!("n{}", i)).unwrap();
+        }
+        dag
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_parallel_log_matches_serial() {
+        let dag = fan_dag(FailurePolicy::Continue);
+
+        let mut serial = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(1);
+        let serial_state = serial.execute(&dag, Arc::new(SlowRunner)).await.unwrap();
+
+        let mut parallel = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(4);
+        let parallel_state = parallel.execute(&dag, Arc::new(SlowRunner)).await.unwrap();
+
+        let serial_log = serde_json::to_vec(serial.log().events()).unwrap();
+        let parallel_log = serde_json::to_vec(parallel.log().events()).unwrap();
+        assert_eq!(serial_log, parallel_log);
+        assert_eq!(serial.log().len(), 16);
+        assert_eq!(serial_state.completed, parallel_state.completed);
+        assert_eq!(parallel_state.failed, vec!["n6".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_failure_stops_execution() {
+        let dag = fan_dag(FailurePolicy::Stop);
+
+        let mut executor = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(8);
+        let state = executor.execute(&dag, Arc::new(SlowRunner)).await.unwrap();
+
+        assert!(state.has_failed());
+        assert_eq!(state.completed.len(), 6);
+        assert!(!state.results.contains_key("n7"));
+    }
+
+    /// Fails one node and succeeds the rest
+    struct FailOneRunner(&'static str);
+
+    #[async_trait::async_trait]
+    impl NodeRunner for FailOneRunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            if node.id == self.0 {
+                NodeResult::failure(node.id.clone(), "boom", 0)
+            } else {
+                NodeResult::success(node.id.clone(), format!("out-{}", node.id), 0)
+            }
+        }
+
+        fn side_effect(&self, _node: &DagNode) -> SideEffect {
+            SideEffect::Pure
+        }
+    }
+
+    #[tokio::test]
+    async fn test_continue_skips_dependents_of_failed() {
+        let mut dag = fan_dag(FailurePolicy::Continue);
+        dag.add_edge("n4".to_string(), "n7".to_string()).unwrap();
+
+        let mut executor = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(4);
+        let state = executor.execute(&dag, Arc::new(FailOneRunner("n1"))).await.unwrap();
+
+        assert_eq!(state.failed, vec!["n1"]);
+        assert_eq!(state.skipped, vec!["n4", "n7"]);
+        assert_eq!(state.completed, vec!["n0", "n2", "n3", "n5", "n6"]);
+        assert!(!state.results.contains_key("n4"));
+
+        let recovered = ExecState::from_log(executor.log(), "fan").unwrap();
+        assert_eq!(recovered.skipped, state.skipped);
+
+        // Resuming the finished log runs nothing more
+        let mut resumed = DagExecutor::new(CapabilitySet::empty()).with_log(executor.log().clone());
+        let state = resumed.execute(&dag, Arc::new(FailOneRunner("n1"))).await.unwrap();
+        assert_eq!(state.skipped, vec!["n4", "n7"]);
+        assert_eq!(resumed.log().len(), executor.log().len());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_failure_policy_rejected() {
+        let dag = fan_dag(FailurePolicy::Retry);
+
+        let mut executor = DagExecutor::new(CapabilitySet::empty());
+        let err = executor.execute(&dag, Arc::new(SlowRunner)).await.unwrap_err();
+        assert!(matches!(err, ExecError::InvalidState(ref msg) if msg.contains("Retry")));
+        assert!(executor.log().is_empty());
+    }
+
+    /// Runner that keeps the default side effect and records peak concurrency
+    #[derive(Default)]
+    struct PeakRunner {
+        running: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl NodeRunner for PeakRunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            NodeResult::success(node.id.clone(), "ok", 20)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_default_side_effect_runs_concurrently() {
+        use oracle_omen_plan::dag::DagNodeType;
+
+        let tools = |side_effect: Option<SideEffect>| {
+            let mut dag = Dag::new("tools");
+            for i in 0..4 {
+                let node_type = DagNodeType::Tool {
+                    name: "echo".to_string(),
+                    version: "1.0.0".to_string(),
+                };
+                let mut node = DagNode::new(format!("n{}", i), node_type);
+                if let Some(side_effect) = side_effect {
+                    node = node.with_side_effect(side_effect);
+                }
+                dag.add_node(node).unwrap();
+            }
+            dag
+        };
+
+        // Nodes declared pure overlap with a runner that does not override
+        let runner = Arc::new(PeakRunner::default());
+        let mut executor = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(4);
+        let dag = tools(Some(SideEffect::Pure));
+        let state = executor.execute(&dag, runner.clone()).await.unwrap();
+        assert!(state.is_complete());
+        assert!(runner.peak.load(Ordering::SeqCst) > 1);
+
+        // Tool nodes are impure unless declared, and run one at a time
+        let runner = Arc::new(PeakRunner::default());
+        let mut executor = DagExecutor::new(CapabilitySet::empty()).with_max_concurrent(4);
+        let dag = tools(None);
+        executor.execute(&dag, runner.clone()).await.unwrap();
+        assert_eq!(runner.peak.load(Ordering::SeqCst), 1);
+    }
+
+    /// Runner that counts runs and can pause on a given node
+    #[derive(Default)]
+    struct CountingRunner {
+        runs: std::sync::Mutex<BTreeMap<String, u32>>,
+        pause_on: Option<(String, PauseHandle)>,
+    }
+
+    impl CountingRunner {
+        fn runs(&self, node_id: &str) -> u32 {
+            self.runs.lock().unwrap().get(node_id).copied().unwrap_or(0)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl NodeRunner for CountingRunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            *self.runs.lock().unwrap().entry(node.id.clone()).or_insert(0) += 1;
+            if let Some((id, handle)) = &self.pause_on {
+                if *id == node.id {
+                    handle.pause();
+                }
+            }
+            NodeResult::success(node.id.clone(), format!("out-{}", node.id), 0)
+        }
+    }
+
+    fn chain_dag() -> Dag {
+        use oracle_omen_plan::dag::DagNodeType;
+
+        let mut dag = Dag::new("chain");
+        for i in 0..4 {
+            let node = DagNode::new(format!("n{}", i), DagNodeType::Wait { duration_ms: 0 })
+                .with_side_effect(SideEffect::Impure);
+            dag.add_node(node).unwrap();
+        }
+        for i in 1..4 {
+            dag.add_edge(format!("n{}", i - 1), format!("n{}", i)).unwrap();
+        }
+        dag
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let dag = chain_dag();
+        let mut executor = DagExecutor::new(CapabilitySet::empty());
+        let runner = Arc::new(CountingRunner {
+            pause_on: Some(("n2".to_string(), executor.pause_handle())),
+            ..Default::default()
+        });
+
+        let state = executor.execute(&dag, runner.clone()).await.unwrap();
+        assert!(state.paused);
+        assert!(!state.is_complete());
+        assert_eq!(state.completed, vec!["n0", "n1", "n2"]);
+        assert_eq!(
+            executor.log().last().unwrap().kind,
+            EventKind::Custom(CHECKPOINT.to_string())
+        );
+
+        let log = executor.log().clone();
+        assert!(ExecState::from_log(&log, "chain").unwrap().paused);
+
+        let mut resumed = DagExecutor::new(CapabilitySet::empty()).with_log(log);
+        let state = resumed.execute(&dag, runner.clone()).await.unwrap();
+        assert!(state.is_complete());
+        assert_eq!(state.completed, vec!["n0", "n1", "n2", "n3"]);
+        for i in 0..4 {
+            assert_eq!(runner.runs(&format!("n{}", i)), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crash_resume_in_flight() {
+        let dag = chain_dag();
+        let store = RunStore::new(std::env::temp_dir().join(format!(
+            "oracle-omen-exec-resume-{}",
+            std::process::id()
+        )));
+        let _ = std::fs::remove_dir_all(store.root());
+
+        let mut executor = DagExecutor::new(CapabilitySet::empty())
+            .with_log(EventLog::new(9))
+            .with_store(store.clone());
+        executor.execute(&dag, Arc::new(CountingRunner::default())).await.unwrap();
+        assert_eq!(store.load_log(9).unwrap().events(), executor.log().events());
+
+        // Crash while 'n3' was in flight: drop its node_completed event
+        let mut crashed = EventLog::new(9);
+        let events = executor.log().events();
+        for event in &events[..events.len() - 1] {
+            crashed.append(event.clone()).unwrap();
+        }
+        let recovered = ExecState::from_log(&crashed, "chain").unwrap();
+        assert_eq!(recovered.current, Some("n3".to_string()));
+
+        store.save_log(&crashed).unwrap();
+        let runner = Arc::new(CountingRunner::default());
+        let mut resumed = DagExecutor::resume(CapabilitySet::empty(), store.clone(), 9).unwrap();
+        let state = resumed.execute(&dag, runner.clone()).await.unwrap();
+        assert_eq!(runner.runs("n3"), 0);
+        assert_eq!(state.failed, vec!["n3"]);
+
+        store.save_log(&crashed).unwrap();
+        let runner = Arc::new(CountingRunner::default());
+        let mut resumed = DagExecutor::resume(CapabilitySet::empty(), store.clone(), 9)
+            .unwrap()
+            .with_in_flight_policy(InFlightPolicy::AtLeastOnce);
+        let state = resumed.execute(&dag, runner.clone()).await.unwrap();
+        assert_eq!(runner.runs("n3"), 1);
+        assert_eq!(runner.runs("n0"), 0);
+        assert!(state.is_complete());
+        assert_eq!(resumed.log().len(), events.len());
+        assert_eq!(store.load_log(9).unwrap().events(), resumed.log().events());
+    }
 }
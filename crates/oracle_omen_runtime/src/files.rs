@@ -0,0 +1,539 @@
+//! Sandboxed filesystem tool.
+//!
+//! Paths are checked against the run's path scopes at call time:
+//! - `fs:read:<dir>` allows `read`, `list` and `stat` under `<dir>`
+//! - `fs:write:<dir>` allows `write` and `delete` under `<dir>`
+//! - a scope of `*` allows any path
+//!
+//! Every path is canonicalized, following symlinks, before it is checked,
+//! so a symlink pointing outside a scope is denied. `delete` removes a
+//! symlink itself, never its target, and never a scope's root directory.
+//! Paths containing `..` are rejected outright. Writes go to a temporary
+//! file that is renamed into place, and every response carries the BLAKE3
+//! hash of the file content.
+
+use std::fs::{self, File, Metadata};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use oracle_omen_core::{
+    capability::{Capability, CapabilitySet},
+    hash::Hash,
+    tool::{
+        Determinism, ExecutionContext, ResourceBounds, SideEffect, Tool, ToolError, ToolId,
+        ToolResult,
+    },
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const INPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "op": {"enum": ["read", "write", "list", "stat", "delete"]},
+        "path": {"type": "string", "minLength": 1},
+        "content": {"type": "string"}
+    },
+    "required": ["op", "path"],
+    "additionalProperties": false
+}"#;
+
+const OUTPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "path": {"type": "string"},
+        "kind": {"enum": ["file", "dir", "symlink", "other"]},
+        "size": {"type": "integer", "minimum": 0},
+        "hash": {"type": "string"},
+        "content": {"type": "string"},
+        "entries": {"type": "array"},
+        "deleted": {"type": "boolean"}
+    },
+    "required": ["path"]
+}"#;
+
+/// Filesystem operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsOp {
+    /// Read a UTF-8 file
+    Read,
+
+    /// Atomically replace a file's content
+    Write,
+
+    /// List a directory, sorted by name
+    List,
+
+    /// Describe a file or directory
+    Stat,
+
+    /// Delete a file or empty directory
+    Delete,
+}
+
+impl FsOp {
+    /// Capability action checked for this operation
+    pub fn action(self) -> &'static str {
+        match self {
+            FsOp::Read | FsOp::List | FsOp::Stat => "read",
+            FsOp::Write | FsOp::Delete => "write",
+        }
+    }
+}
+
+/// Input of one filesystem call
+#[derive(Deserialize)]
+struct FsRequest {
+    op: FsOp,
+    path: String,
+    content: Option<String>,
+}
+
+/// Filesystem tool restricted to granted path scopes
+///
+/// Input is `{"op", "path", "content"?}`; `content` is only used by `write`.
+/// Relative paths are resolved against the root directory. Files are read
+/// and written as UTF-8 text, up to `ResourceBounds::max_memory_bytes`.
+pub struct FsTool {
+    id: ToolId,
+    root: PathBuf,
+    read_scopes: Vec<String>,
+    write_scopes: Vec<String>,
+    bounds: ResourceBounds,
+}
+
+impl FsTool {
+    /// Create a tool limited to the `fs:read` and `fs:write` scopes in `granted`
+    ///
+    /// Pass the same set the invoker checks against.
+    pub fn new(id: ToolId, granted: &CapabilitySet) -> Self {
+        let scopes = |prefix: &str| -> Vec<String> {
+            granted
+                .iter()
+                .filter_map(|c| c.scope(prefix))
+                .map(str::to_string)
+                .collect()
+        };
+        Self {
+            id,
+            root: PathBuf::from("."),
+            read_scopes: scopes("fs:read"),
+            write_scopes: scopes("fs:write"),
+            bounds: ResourceBounds::default(),
+        }
+    }
+
+    /// Resolve relative paths against `root`
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Set resource bounds
+    pub fn with_resource_bounds(mut self, bounds: ResourceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Resolve a path and check it against the scopes for `op`
+    ///
+    /// Paths that do not exist yet are allowed for `write` only; their parent
+    /// directory is canonicalized instead. For `delete` only the parent is
+    /// canonicalized, so a symlink resolves to itself.
+    pub fn resolve(&self, path: &str, op: FsOp) -> ToolResult<PathBuf> {
+        let path = Path::new(path);
+        if path.components().any(|c| c == Component::ParentDir) {
+            return Err(self.invalid(format!("path must not contain '..': {}", path.display())));
+        }
+        let full: PathBuf = if path.is_absolute() {
+            path.components().collect()
+        } else {
+            self.root.join(path).components().collect()
+        };
+
+        let resolved = match fs::canonicalize(&full) {
+            Ok(_) if op == FsOp::Delete => self.in_parent(&full)?,
+            Ok(resolved) => resolved,
+            Err(_) if op == FsOp::Write && fs::symlink_metadata(&full).is_err() => {
+                self.in_parent(&full)?
+            }
+            Err(_) if op == FsOp::Delete && fs::symlink_metadata(&full).is_ok() => {
+                self.in_parent(&full)?
+            }
+            Err(e) => return Err(self.failed(format!("{}: {}", full.display(), e))),
+        };
+
+        let scopes = match op.action() {
+            "read" => &self.read_scopes,
+            _ => &self.write_scopes,
+        };
+        let roots: Vec<PathBuf> = scopes
+            .iter()
+            .filter(|scope| *scope != "*")
+            .filter_map(|scope| fs::canonicalize(scope).ok())
+            .collect();
+        let allowed = scopes.iter().any(|scope| scope == "*")
+            || roots.iter().any(|root| resolved.starts_with(root));
+        if !allowed {
+            return Err(ToolError::Denied {
+                capability: format!("fs:{}:{}", op.action(), resolved.display()),
+                reason: "path is outside the granted scopes".to_string(),
+            });
+        }
+        if op == FsOp::Delete && roots.contains(&resolved) {
+            return Err(ToolError::Denied {
+                capability: format!("fs:write:{}", resolved.display()),
+                reason: "a scope's root cannot be deleted".to_string(),
+            });
+        }
+        Ok(resolved)
+    }
+
+    /// Canonical parent directory of `full`, joined with its file name
+    fn in_parent(&self, full: &Path) -> ToolResult<PathBuf> {
+        let name = full
+            .file_name()
+            .ok_or_else(|| self.invalid(format!("not a file path: {}", full.display())))?;
+        let parent = full.parent().unwrap_or_else(|| Path::new("/"));
+        Ok(fs::canonicalize(parent)
+            .map_err(|e| self.failed(format!("{}: {}", parent.display(), e)))?
+            .join(name))
+    }
+
+    /// Largest file read or written, in bytes
+    fn max_bytes(&self) -> Option<u64> {
+        self.bounds.max_memory_bytes
+    }
+
+    fn check_size(&self, size: u64) -> ToolResult<()> {
+        match self.max_bytes() {
+            Some(max) if size > max => Err(ToolError::ResourceExceeded {
+                tool: self.id.as_str(),
+                limit: format!("file of {} bytes exceeds {} bytes", size, max),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn invalid(&self, reason: String) -> ToolError {
+        ToolError::InvalidInput {
+            tool: self.id.as_str(),
+            reason,
+        }
+    }
+
+    fn failed(&self, reason: impl ToString) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.id.as_str(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn read(&self, path: &Path) -> ToolResult<Value> {
+        let meta = fs::metadata(path).map_err(|e| self.failed(e))?;
+        self.check_size(meta.len())?;
+        let bytes = fs::read(path).map_err(|e| self.failed(e))?;
+        let hash = Hash::from_bytes(&bytes);
+        let content = String::from_utf8(bytes)
+            .map_err(|_| self.failed(format!("{} is not UTF-8", path.display())))?;
+        Ok(json!({
+            "path": path.display().to_string(),
+            "size": content.len(),
+            "hash": hash.to_hex(),
+            "content": content,
+        }))
+    }
+
+    fn write(&self, path: &Path, content: &str) -> ToolResult<Value> {
+        self.check_size(content.len() as u64)?;
+        if path.is_dir() {
+            return Err(self.failed(format!("{} is a directory", path.display())));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = path.with_file_name(format!(".{}.tmp-{}", name, std::process::id()));
+        let written = File::create(&tmp).and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(self.failed(e));
+        }
+        Ok(json!({
+            "path": path.display().to_string(),
+            "size": content.len(),
+            "hash": Hash::from_bytes(content.as_bytes()).to_hex(),
+        }))
+    }
+
+    fn list(&self, path: &Path) -> ToolResult<Value> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path).map_err(|e| self.failed(e))? {
+            let entry = entry.map_err(|e| self.failed(e))?;
+            let meta = entry.metadata().map_err(|e| self.failed(e))?;
+            entries.push(json!({
+                "name": entry.file_name().to_string_lossy(),
+                "kind": kind(&meta),
+                "size": meta.len(),
+            }));
+        }
+        entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        Ok(json!({
+            "path": path.display().to_string(),
+            "entries": entries,
+        }))
+    }
+
+    fn stat(&self, path: &Path) -> ToolResult<Value> {
+        let meta = fs::metadata(path).map_err(|e| self.failed(e))?;
+        let mut out = json!({
+            "path": path.display().to_string(),
+            "kind": kind(&meta),
+            "size": meta.len(),
+        });
+        if meta.is_file() && self.check_size(meta.len()).is_ok() {
+            let bytes = fs::read(path).map_err(|e| self.failed(e))?;
+            out["hash"] = json!(Hash::from_bytes(&bytes).to_hex());
+        }
+        Ok(out)
+    }
+
+    fn delete(&self, path: &Path) -> ToolResult<Value> {
+        let meta = fs::symlink_metadata(path).map_err(|e| self.failed(e))?;
+        let mut out = json!({
+            "path": path.display().to_string(),
+            "kind": kind(&meta),
+            "deleted": true,
+        });
+        if meta.file_type().is_symlink() {
+            fs::remove_file(path).map_err(|e| self.failed(e))?;
+        } else if meta.is_dir() {
+            fs::remove_dir(path).map_err(|e| self.failed(e))?;
+        } else {
+            let bytes = fs::read(path).map_err(|e| self.failed(e))?;
+            out["hash"] = json!(Hash::from_bytes(&bytes).to_hex());
+            fs::remove_file(path).map_err(|e| self.failed(e))?;
+        }
+        Ok(out)
+    }
+}
+
+impl Tool for FsTool {
+    fn id(&self) -> &ToolId {
+        &self.id
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        // Paths are only known per call; see `resolve`.
+        &[]
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Impure
+    }
+
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        &self.bounds
+    }
+
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        let request: FsRequest =
+            serde_json::from_slice(input).map_err(|e| self.invalid(e.to_string()))?;
+        let path = self.resolve(&request.path, request.op)?;
+        let output = match request.op {
+            FsOp::Read => self.read(&path)?,
+            FsOp::Write => {
+                let content = request
+                    .content
+                    .as_deref()
+                    .ok_or_else(|| self.invalid("write needs content".to_string()))?;
+                self.write(&path, content)?
+            }
+            FsOp::List => self.list(&path)?,
+            FsOp::Stat => self.stat(&path)?,
+            FsOp::Delete => self.delete(&path)?,
+        };
+        serde_json::to_vec(&output).map_err(|e| ToolError::SerializationFailed {
+            tool: self.id.as_str(),
+            reason: e.to_string(),
+        })
+    }
+
+    fn input_schema(&self) -> &str {
+        INPUT_SCHEMA
+    }
+
+    fn output_schema(&self) -> &str {
+        OUTPUT_SCHEMA
+    }
+}
+
+/// Kind of a filesystem entry
+fn kind(meta: &Metadata) -> &'static str {
+    if meta.is_file() {
+        "file"
+    } else if meta.is_dir() {
+        "dir"
+    } else if meta.file_type().is_symlink() {
+        "symlink"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ToolInvoker, ToolRegistry};
+    use oracle_omen_core::event::{EventKind, EventLog};
+    use std::sync::Arc;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oracle-omen-files-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::canonicalize(dir).unwrap()
+    }
+
+    fn call(tool: &FsTool, input: Value) -> ToolResult<Value> {
+        let out = tool.execute(input.to_string().as_bytes(), &ExecutionContext::new(0, 1))?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+
+    fn grants(dir: &Path) -> CapabilitySet {
+        CapabilitySet::new([
+            Capability::new(format!("fs:read:{}", dir.display())),
+            Capability::new(format!("fs:write:{}", dir.display())),
+        ])
+    }
+
+    #[test]
+    fn test_file_operations() {
+        let dir = temp_dir("ops");
+        let tool = FsTool::new(ToolId::new("fs", "1.0.0"), &grants(&dir)).with_root(&dir);
+        let hash = Hash::from_bytes(b"hello").to_hex();
+
+        let out = call(
+            &tool,
+            json!({"op": "write", "path": "a.txt", "content": "hello"}),
+        )
+        .unwrap();
+        assert_eq!(out["hash"], hash);
+        let out = call(&tool, json!({"op": "read", "path": "a.txt"})).unwrap();
+        assert_eq!(out["content"], "hello");
+        assert_eq!(out["hash"], hash);
+        let out = call(&tool, json!({"op": "stat", "path": "a.txt"})).unwrap();
+        assert_eq!(out["kind"], "file");
+        assert_eq!(out["size"], 5);
+
+        fs::create_dir(dir.join("sub")).unwrap();
+        let out = call(&tool, json!({"op": "list", "path": "."})).unwrap();
+        let names: Vec<&str> = out["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["a.txt", "sub"]);
+
+        let out = call(&tool, json!({"op": "delete", "path": "a.txt"})).unwrap();
+        assert_eq!(out["hash"], hash);
+        assert!(!dir.join("a.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_escapes_are_rejected() {
+        let dir = temp_dir("escape");
+        let inside = dir.join("inside");
+        fs::create_dir(&inside).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(dir.join("secret.txt"), inside.join("link")).unwrap();
+
+        let read_only =
+            CapabilitySet::new([Capability::new(format!("fs:read:{}", inside.display()))]);
+        let tool = FsTool::new(ToolId::new("fs", "1.0.0"), &read_only).with_root(&inside);
+
+        assert!(matches!(
+            call(&tool, json!({"op": "read", "path": "../secret.txt"})),
+            Err(ToolError::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            call(&tool, json!({"op": "read", "path": "link"})),
+            Err(ToolError::Denied { .. })
+        ));
+        assert!(matches!(
+            call(
+                &tool,
+                json!({"op": "write", "path": "new.txt", "content": "x"})
+            ),
+            Err(ToolError::Denied { .. })
+        ));
+        assert!(!inside.join("new.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_delete_keeps_symlink_targets_and_scope_root() {
+        let dir = temp_dir("delete");
+        fs::write(dir.join("target.txt"), "kept").unwrap();
+        std::os::unix::fs::symlink(dir.join("target.txt"), dir.join("link")).unwrap();
+        let tool = FsTool::new(ToolId::new("fs", "1.0.0"), &grants(&dir)).with_root(&dir);
+
+        let out = call(&tool, json!({"op": "delete", "path": "link"})).unwrap();
+        assert_eq!(out["kind"], "symlink");
+        assert!(fs::symlink_metadata(dir.join("link")).is_err());
+        assert_eq!(fs::read_to_string(dir.join("target.txt")).unwrap(), "kept");
+
+        for root in [".", dir.to_str().unwrap()] {
+            assert!(matches!(
+                call(&tool, json!({"op": "delete", "path": root})),
+                Err(ToolError::Denied { .. })
+            ));
+        }
+        assert!(dir.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_denial_is_logged() {
+        let dir = temp_dir("denied");
+        let granted = grants(&dir.join("nothing"));
+        let mut registry = ToolRegistry::new();
+        registry
+            .register(Arc::new(
+                FsTool::new(ToolId::new("fs", "1.0.0"), &granted).with_root(&dir),
+            ))
+            .unwrap();
+        let mut invoker = ToolInvoker::new(registry, granted);
+        let mut log = EventLog::new(1);
+
+        let err = invoker
+            .invoke(
+                &mut log,
+                &ToolId::new("fs", "1.0.0"),
+                r#"{"op": "list", "path": "."}"#,
+                None,
+            )
+            .unwrap_err();
+        assert!(matches!(err, ToolError::Denied { .. }));
+        let kinds: Vec<EventKind> = log.events().iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::ToolRequest,
+                EventKind::ToolResponse,
+                EventKind::CapabilityDenied
+            ]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
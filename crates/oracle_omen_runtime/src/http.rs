@@ -0,0 +1,621 @@
+//! HTTP client tool with cassette record and replay.
+//!
+//! Requests are checked against the run's network grants at call time. A
+//! request is allowed if a grant covers (`Capability::covers`)
+//! `network:<scheme>:<method>:<host>`, so wildcard grants mean the same here
+//! as everywhere else:
+//! - `network:<scheme>:<method>:<host>` allows a method on one host
+//! - `network:<scheme>:<method>` also allows that method on any host
+//! - `*` may stand for any segment; a trailing one covers the rest, so
+//!   `network:http:*`, `network:*` and `*` all allow plain HTTP calls
+//!
+//! Grants and requests are compared in lowercase. Redirects are not followed,
+//! so a response cannot lead to a host outside the grants.
+//!
+//! A [`Cassette`] records live exchanges to a JSON file and serves them back
+//! without a network, so CI can run network-using agents offline.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use oracle_omen_core::{
+    capability::{Capability, CapabilitySet},
+    tool::{
+        Determinism, ExecutionContext, ResourceBounds, SideEffect, Tool, ToolError, ToolId,
+        ToolResult,
+    },
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::persist::{PersistError, PersistResult};
+
+const INPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "method": {"enum": ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]},
+        "url": {"type": "string", "minLength": 1},
+        "headers": {"type": "object"},
+        "body": {"type": "string"}
+    },
+    "required": ["method", "url"],
+    "additionalProperties": false
+}"#;
+
+const OUTPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "status": {"type": "integer", "minimum": 100, "maximum": 599},
+        "headers": {"type": "object"},
+        "body": {"type": "string"}
+    },
+    "required": ["status", "headers", "body"],
+    "additionalProperties": false
+}"#;
+
+/// Request headers never written to a cassette or used to match one
+const REDACTED_HEADERS: [&str; 3] = ["authorization", "cookie", "proxy-authorization"];
+
+/// One HTTP request, as given to the tool
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRequest {
+    /// Method, e.g. `GET`
+    pub method: String,
+
+    /// Absolute URL
+    pub url: String,
+
+    /// Request headers
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// Request body
+    #[serde(default)]
+    pub body: String,
+}
+
+impl HttpRequest {
+    /// Request as stored in a cassette: lowercase header names, secrets removed
+    fn recorded(&self) -> Self {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_ascii_lowercase(), value.clone()))
+            .filter(|(name, _)| !REDACTED_HEADERS.contains(&name.as_str()))
+            .collect();
+        Self {
+            method: self.method.to_ascii_uppercase(),
+            url: self.url.clone(),
+            headers,
+            body: self.body.clone(),
+        }
+    }
+}
+
+/// One HTTP response, the tool's output
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpResponse {
+    /// Status code; error statuses are responses, not tool errors
+    pub status: u16,
+
+    /// Response headers with lowercase names
+    pub headers: BTreeMap<String, String>,
+
+    /// Response body, decoded as UTF-8 with invalid bytes replaced
+    pub body: String,
+}
+
+/// A recorded request and its response
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Request, as recorded
+    pub request: HttpRequest,
+
+    /// Response
+    pub response: HttpResponse,
+}
+
+/// Whether a cassette records or replays
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests and record the exchanges
+    Record,
+
+    /// Serve recorded exchanges; never touch the network
+    Replay,
+}
+
+/// File of recorded HTTP exchanges
+///
+/// Identical requests are served in recording order. The file is rewritten
+/// after every recorded exchange.
+#[derive(Clone, Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    exchanges: Vec<Exchange>,
+    used: Vec<bool>,
+}
+
+impl Cassette {
+    /// Start an empty cassette that will be written to `path`
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            mode: CassetteMode::Record,
+            exchanges: Vec::new(),
+            used: Vec::new(),
+        }
+    }
+
+    /// Load a cassette for replay
+    pub fn replay(path: impl Into<PathBuf>) -> PersistResult<Self> {
+        let path = path.into();
+        let json = fs::read_to_string(&path)?;
+        let exchanges: Vec<Exchange> =
+            serde_json::from_str(&json).map_err(|e| PersistError::Parse {
+                line: e.line(),
+                message: e.to_string(),
+            })?;
+        Ok(Self {
+            used: vec![false; exchanges.len()],
+            path,
+            mode: CassetteMode::Replay,
+            exchanges,
+        })
+    }
+
+    /// Get the mode
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// Get the file path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the exchanges
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
+
+    /// Take the first unused exchange matching `request`
+    fn take(&mut self, request: &HttpRequest) -> Option<HttpResponse> {
+        let request = request.recorded();
+        let idx = self
+            .exchanges
+            .iter()
+            .zip(&self.used)
+            .position(|(exchange, used)| !used && exchange.request == request)?;
+        self.used[idx] = true;
+        Some(self.exchanges[idx].response.clone())
+    }
+
+    /// Record an exchange and rewrite the file
+    fn push(&mut self, request: &HttpRequest, response: &HttpResponse) -> PersistResult<()> {
+        self.exchanges.push(Exchange {
+            request: request.recorded(),
+            response: response.clone(),
+        });
+        self.used.push(true);
+        let json = serde_json::to_string_pretty(&self.exchanges)
+            .map_err(|e| PersistError::Io(e.to_string()))?;
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// HTTP client tool restricted to granted methods and hosts
+///
+/// Input is an [`HttpRequest`], output an [`HttpResponse`]. Request bodies
+/// are limited to the request limit and response bodies to
+/// `ResourceBounds::max_memory_bytes`; larger ones fail with
+/// `ResourceExceeded`.
+pub struct HttpTool {
+    id: ToolId,
+    grants: Vec<Capability>,
+    bounds: ResourceBounds,
+    max_request_bytes: u64,
+    cassette: Option<Mutex<Cassette>>,
+}
+
+impl HttpTool {
+    /// Default body limit, for requests and responses
+    pub const DEFAULT_MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+    /// Create a tool limited to the grants in `granted` that cover HTTP calls
+    ///
+    /// Pass the same set the invoker checks against.
+    pub fn new(id: ToolId, granted: &CapabilitySet) -> Self {
+        Self {
+            id,
+            grants: granted
+                .iter()
+                .map(|c| Capability::new(c.name().to_ascii_lowercase()))
+                .collect(),
+            bounds: ResourceBounds::default(),
+            max_request_bytes: Self::DEFAULT_MAX_BODY_BYTES,
+            cassette: None,
+        }
+    }
+
+    /// Set resource bounds
+    pub fn with_resource_bounds(mut self, bounds: ResourceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Set the request body limit
+    pub fn with_max_request_bytes(mut self, max: u64) -> Self {
+        self.max_request_bytes = max;
+        self
+    }
+
+    /// Record to or replay from a cassette
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(Mutex::new(cassette));
+        self
+    }
+
+    /// Get a copy of the cassette, if any
+    pub fn cassette(&self) -> Option<Cassette> {
+        self.cassette
+            .as_ref()
+            .and_then(|c| c.lock().ok())
+            .map(|c| c.clone())
+    }
+
+    /// Check a request against the grants
+    pub fn check(&self, request: &HttpRequest) -> ToolResult<Url> {
+        let url = Url::parse(&request.url).map_err(|e| self.invalid(e.to_string()))?;
+        let scheme = url.scheme();
+        if scheme != "http" && scheme != "https" {
+            return Err(self.invalid(format!("unsupported scheme: {}", scheme)));
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(self.invalid("credentials in URLs are not allowed".to_string()));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| self.invalid(format!("URL has no host: {}", url)))?;
+        let method = request.method.to_ascii_lowercase();
+        let any_host = Capability::new(format!("network:{}:{}", scheme, method));
+        let required = Capability::new(format!("{}:{}", any_host, host.to_ascii_lowercase()));
+        if !self
+            .grants
+            .iter()
+            .any(|grant| grant.covers(&required) || grant.covers(&any_host))
+        {
+            return Err(ToolError::Denied {
+                capability: required.to_string(),
+                reason: "method or host not granted".to_string(),
+            });
+        }
+        Ok(url)
+    }
+
+    fn max_response_bytes(&self) -> u64 {
+        self.bounds
+            .max_memory_bytes
+            .unwrap_or(Self::DEFAULT_MAX_BODY_BYTES)
+    }
+
+    fn invalid(&self, reason: String) -> ToolError {
+        ToolError::InvalidInput {
+            tool: self.id.as_str(),
+            reason,
+        }
+    }
+
+    fn failed(&self, reason: impl ToString) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.id.as_str(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn exceeded(&self, limit: String) -> ToolError {
+        ToolError::ResourceExceeded {
+            tool: self.id.as_str(),
+            limit,
+        }
+    }
+
+    /// Serve a request from the cassette or the network
+    fn serve(&self, request: &HttpRequest, url: &Url) -> ToolResult<HttpResponse> {
+        let Some(cassette) = &self.cassette else {
+            return self.send(request, url);
+        };
+        let mut cassette = cassette
+            .lock()
+            .map_err(|_| self.failed("cassette lock poisoned"))?;
+        match cassette.mode {
+            CassetteMode::Replay => cassette.take(request).ok_or_else(|| {
+                self.failed(format!(
+                    "no recorded exchange for {} {}",
+                    request.method, request.url
+                ))
+            }),
+            CassetteMode::Record => {
+                let response = self.send(request, url)?;
+                cassette
+                    .push(request, &response)
+                    .map_err(|e| self.failed(e))?;
+                Ok(response)
+            }
+        }
+    }
+
+    /// Send a request over the network
+    fn send(&self, request: &HttpRequest, url: &Url) -> ToolResult<HttpResponse> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.bounds.timeout())
+            .redirects(0)
+            .build();
+        let mut call = agent.request_url(&request.method, url);
+        for (name, value) in &request.headers {
+            call = call.set(name, value);
+        }
+
+        let start = Instant::now();
+        let result = if request.body.is_empty() {
+            call.call()
+        } else {
+            call.send_string(&request.body)
+        };
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(ureq::Error::Transport(_)) if start.elapsed() >= self.bounds.timeout() => {
+                return Err(ToolError::Timeout {
+                    tool: self.id.as_str(),
+                    duration_ms: self.bounds.timeout_ms,
+                });
+            }
+            Err(e) => return Err(self.failed(e)),
+        };
+
+        let status = response.status();
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_string();
+                Some((name.to_ascii_lowercase(), value))
+            })
+            .collect();
+        let max = self.max_response_bytes();
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(max + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| self.failed(e))?;
+        if body.len() as u64 > max {
+            return Err(self.exceeded(format!("response body over {} bytes", max)));
+        }
+        Ok(HttpResponse {
+            status,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+}
+
+impl Tool for HttpTool {
+    fn id(&self) -> &ToolId {
+        &self.id
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        // Hosts are only known per call; see `check`.
+        &[]
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Impure
+    }
+
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        &self.bounds
+    }
+
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        let mut request: HttpRequest =
+            serde_json::from_slice(input).map_err(|e| self.invalid(e.to_string()))?;
+        request.method = request.method.to_ascii_uppercase();
+        if request.body.len() as u64 > self.max_request_bytes {
+            return Err(self.exceeded(format!(
+                "request body over {} bytes",
+                self.max_request_bytes
+            )));
+        }
+        let url = self.check(&request)?;
+        let response = self.serve(&request, &url)?;
+        serde_json::to_vec(&response).map_err(|e| ToolError::SerializationFailed {
+            tool: self.id.as_str(),
+            reason: e.to_string(),
+        })
+    }
+
+    fn input_schema(&self) -> &str {
+        INPUT_SCHEMA
+    }
+
+    fn output_schema(&self) -> &str {
+        OUTPUT_SCHEMA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve `count` requests, answering each with its request line
+    fn serve(count: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(count) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let body = request_line.trim_end();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nX-Test: yes\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn call(tool: &HttpTool, input: serde_json::Value) -> ToolResult<HttpResponse> {
+        let out = tool.execute(input.to_string().as_bytes(), &ExecutionContext::new(0, 1))?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+
+    fn grants(caps: &[&str]) -> CapabilitySet {
+        CapabilitySet::new(caps.iter().map(|c| Capability::new(*c)))
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let tool = HttpTool::new(
+            ToolId::new("http", "1.0.0"),
+            &grants(&[
+                "network:https:get:api.example.com",
+                "network:http:*:localhost",
+            ]),
+        );
+        let request = |method: &str, url: &str| HttpRequest {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: BTreeMap::new(),
+            body: String::new(),
+        };
+
+        assert!(tool
+            .check(&request("GET", "https://api.example.com/v1"))
+            .is_ok());
+        assert!(tool
+            .check(&request("POST", "http://localhost:8080/"))
+            .is_ok());
+        assert!(matches!(
+            tool.check(&request("POST", "https://api.example.com/v1")),
+            Err(ToolError::Denied { .. })
+        ));
+        assert!(matches!(
+            tool.check(&request("GET", "http://api.example.com/v1")),
+            Err(ToolError::Denied { .. })
+        ));
+        assert!(matches!(
+            tool.check(&request("GET", "https://api.example.com@evil.com/")),
+            Err(ToolError::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            tool.check(&request("GET", "file:///etc/passwd")),
+            Err(ToolError::InvalidInput { .. })
+        ));
+
+        // Broad grants cover whatever `Capability::covers` says they cover
+        for grant in ["*", "network:*", "network:https:*", "network:https:GET"] {
+            let tool = HttpTool::new(ToolId::new("http", "1.0.0"), &grants(&[grant]));
+            assert!(
+                tool.check(&request("GET", "https://example.org/")).is_ok(),
+                "{} should allow GET",
+                grant
+            );
+        }
+        let tool = HttpTool::new(ToolId::new("http", "1.0.0"), &grants(&["network:https:*"]));
+        assert!(tool.check(&request("GET", "http://example.org/")).is_err());
+        let tool = HttpTool::new(ToolId::new("http", "1.0.0"), &grants(&["fs:*"]));
+        assert!(tool.check(&request("GET", "https://example.org/")).is_err());
+    }
+
+    #[test]
+    fn test_record_then_replay_offline() {
+        let base = serve(1);
+        let path = std::env::temp_dir().join(format!(
+            "oracle-omen-http-cassette-{}.json",
+            std::process::id()
+        ));
+        let granted = grants(&["network:http:get:127.0.0.1"]);
+        let input = json!({
+            "method": "GET",
+            "url": format!("{}/items?id=1", base),
+            "headers": {"Authorization": "secret", "Accept": "text/plain"}
+        });
+
+        let recorder = HttpTool::new(ToolId::new("http", "1.0.0"), &granted)
+            .with_cassette(Cassette::record(&path));
+        let live = call(&recorder, input.clone()).unwrap();
+        assert_eq!(live.status, 200);
+        assert_eq!(live.body, "GET /items?id=1 HTTP/1.1");
+        assert_eq!(live.headers.get("x-test").map(String::as_str), Some("yes"));
+
+        let recorded = fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("secret"));
+
+        // The server only answered once, so this must come from the cassette.
+        let player = HttpTool::new(ToolId::new("http", "1.0.0"), &granted)
+            .with_cassette(Cassette::replay(&path).unwrap());
+        assert_eq!(call(&player, input.clone()).unwrap(), live);
+        assert!(matches!(
+            call(&player, input),
+            Err(ToolError::ExecutionFailed { .. })
+        ));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_body_limits() {
+        let base = serve(1);
+        let granted = grants(&["network:http:*"]);
+        let tool = HttpTool::new(ToolId::new("http", "1.0.0"), &granted)
+            .with_max_request_bytes(4)
+            .with_resource_bounds(ResourceBounds {
+                max_memory_bytes: Some(8),
+                ..ResourceBounds::with_timeout(5_000)
+            });
+
+        assert!(matches!(
+            call(
+                &tool,
+                json!({"method": "POST", "url": base, "body": "too long"})
+            ),
+            Err(ToolError::ResourceExceeded { .. })
+        ));
+        assert!(matches!(
+            call(
+                &tool,
+                json!({"method": "GET", "url": format!("{}/long/path", base)})
+            ),
+            Err(ToolError::ResourceExceeded { .. })
+        ));
+    }
+}
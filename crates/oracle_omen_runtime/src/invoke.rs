@@ -0,0 +1,882 @@
+//! Tool invocation with record and replay.
+//!
+//! Every call is logged as a `ToolRequest` event followed by a `ToolResponse`
+//! event, whichever [`ToolMode`] served it. Recorded responses are matched by
+//! request hash, in the order they were recorded. With a [`ToolCache`]
+//! attached, pure deterministic tools are served from it when possible.
+//!
+//! Inputs are validated against the tool's input schema before it runs, and
+//! outputs against its output schema after. A malformed output is logged as
+//! an `Error` event under the response. Valid outputs then pass through the
+//! invoker's [`Normalizer`] before they are hashed; the hash of the raw output
+//! is kept in `ToolResponsePayload::raw_hash`.
+
+use crate::cache::{cache_key, CachedResponse, ToolCache};
+use crate::capabilities::{CapabilityChecker, CheckResult};
+use crate::tools::{Tool, ToolRegistry};
+use oracle_omen_core::capability::{Capability, CapabilitySet};
+use oracle_omen_core::event::{
+    CapabilityDeniedPayload, ErrorPayload, EventId, EventKind, EventLog, EventPayload,
+    ToolRequestPayload, ToolResponsePayload,
+};
+use oracle_omen_core::hash::Hash;
+use oracle_omen_core::normalize::{Normalized, Normalizer};
+use oracle_omen_core::schema::{Schema, SchemaError};
+use oracle_omen_core::tool::{
+    Determinism, ExecutionContext, ResponseSource, SideEffect, ToolError, ToolId, ToolResponse,
+    ToolResponseMetadata, ToolResult,
+};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// How tool calls are served
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToolMode {
+    /// Execute every tool and record the responses
+    #[default]
+    Live,
+
+    /// Serve recorded responses only; a miss is an error
+    Replay,
+
+    /// Replay pure deterministic tools, execute the rest and diff them
+    Hybrid,
+}
+
+/// A re-executed tool whose output differs from the recording
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToolDivergence {
+    /// Tool that diverged
+    pub tool: ToolId,
+
+    /// Request that was re-executed
+    pub request_hash: Hash,
+
+    /// Hash of the recorded response
+    pub recorded: Hash,
+
+    /// Hash of the live response
+    pub live: Hash,
+}
+
+/// Parsed schemas of one tool
+struct ToolSchemas {
+    input: Schema,
+    output: Schema,
+}
+
+/// Outcome of serving one request
+struct Served {
+    output: Result<String, ToolError>,
+    source: ResponseSource,
+    duration_ms: u64,
+    /// Raw output that failed output validation
+    malformed: Option<(String, SchemaError)>,
+    /// Normalization applied to a live output
+    normalized: Option<Normalized>,
+    /// Hash of the output before normalization
+    raw_hash: Option<Hash>,
+}
+
+impl Served {
+    /// A request that failed before reaching a tool
+    fn error(error: ToolError) -> Self {
+        Self {
+            output: Err(error),
+            source: ResponseSource::Error,
+            duration_ms: 0,
+            malformed: None,
+            normalized: None,
+            raw_hash: None,
+        }
+    }
+}
+
+/// Invokes tools and logs every call
+pub struct ToolInvoker {
+    /// Registered tools
+    registry: ToolRegistry,
+
+    /// Granted capabilities
+    checker: CapabilityChecker,
+
+    /// How calls are served
+    mode: ToolMode,
+
+    /// Recorded responses by request hash, in recording order
+    recorded: BTreeMap<Hash, VecDeque<ToolResponsePayload>>,
+
+    /// Divergences found in hybrid mode
+    divergences: Vec<ToolDivergence>,
+
+    /// Seed passed to tools
+    seed: Option<u64>,
+
+    /// Response cache for pure deterministic tools
+    cache: Option<ToolCache>,
+
+    /// Parsed schemas by tool
+    schemas: BTreeMap<ToolId, Arc<ToolSchemas>>,
+
+    /// Normalization applied to live outputs before hashing
+    normalizer: Normalizer,
+}
+
+impl ToolInvoker {
+    /// Create a live invoker
+    pub fn new(registry: ToolRegistry, capabilities: CapabilitySet) -> Self {
+        Self {
+            registry,
+            checker: CapabilityChecker::new(capabilities),
+            mode: ToolMode::Live,
+            recorded: BTreeMap::new(),
+            divergences: Vec::new(),
+            seed: None,
+            cache: None,
+            schemas: BTreeMap::new(),
+            normalizer: Normalizer::default(),
+        }
+    }
+
+    /// Set the mode
+    pub fn with_mode(mut self, mode: ToolMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Load recorded responses from a log
+    pub fn with_recording(mut self, log: &EventLog) -> Self {
+        for event in log.events() {
+            if let EventPayload::ToolResponse(response) = &event.payload {
+                self.recorded
+                    .entry(response.request_hash)
+                    .or_default()
+                    .push_back(response.clone());
+            }
+        }
+        self
+    }
+
+    /// Pass a seed to tools
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Serve pure deterministic tools from a cache
+    ///
+    /// The cache is not consulted in replay mode.
+    pub fn with_cache(mut self, cache: ToolCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Set how live outputs are normalized
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Get the seed passed to tools
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Get the cache
+    pub fn cache(&self) -> Option<&ToolCache> {
+        self.cache.as_ref()
+    }
+
+    /// Get the cache mutably, e.g. to invalidate a tool version
+    pub fn cache_mut(&mut self) -> Option<&mut ToolCache> {
+        self.cache.as_mut()
+    }
+
+    /// Get the mode
+    pub fn mode(&self) -> ToolMode {
+        self.mode
+    }
+
+    /// Get divergences found so far
+    pub fn divergences(&self) -> &[ToolDivergence] {
+        &self.divergences
+    }
+
+    /// Invoke a tool with a JSON input
+    ///
+    /// Logs the request under `parent` and the response under the request.
+    /// A missing capability is logged as `CapabilityDenied` instead, and so is
+    /// a `Denied` error returned by the tool itself, under the response. Input
+    /// that does not match the tool's schema fails with `InvalidInput`.
+    pub fn invoke(
+        &mut self,
+        log: &mut EventLog,
+        tool_id: &ToolId,
+        input: &str,
+        parent: Option<EventId>,
+    ) -> ToolResult<ToolResponse<String>> {
+        let tool = self.registry.get(tool_id);
+        if tool.is_none() && self.mode != ToolMode::Replay {
+            return Err(ToolError::NotFound(tool_id.as_str()));
+        }
+
+        let required: Vec<Capability> = tool
+            .as_ref()
+            .map(|t| t.required_capabilities().to_vec())
+            .unwrap_or_default();
+        if let CheckResult::Denied { capability, reason } = self.checker.check_all(&required) {
+            let payload = EventPayload::CapabilityDenied(CapabilityDeniedPayload {
+                capability: capability.clone(),
+                tool_name: tool_id.name.clone(),
+                reason: reason.clone(),
+            });
+            append(log, EventKind::CapabilityDenied, payload, parent)?;
+            return Err(ToolError::Denied {
+                capability: capability.to_string(),
+                reason,
+            });
+        }
+
+        let request_hash = tool_id.request_hash(input);
+        let request = append(
+            log,
+            EventKind::ToolRequest,
+            EventPayload::ToolRequest(ToolRequestPayload {
+                tool_name: tool_id.name.clone(),
+                tool_version: tool_id.version.clone(),
+                request_hash,
+                capabilities: required,
+                input: input.to_string(),
+            }),
+            parent,
+        )?;
+
+        let context = ExecutionContext {
+            logical_time: request.sequence,
+            run_id: log.run_id,
+            random_seed: self.seed,
+        };
+        let served = match self.check_input(tool.as_deref(), tool_id, input) {
+            Ok(schemas) => self.serve(
+                tool,
+                tool_id,
+                input,
+                request_hash,
+                &context,
+                schemas.as_deref(),
+            ),
+            Err(e) => Served::error(e),
+        };
+
+        let output = match (&served.output, &served.malformed) {
+            (Ok(output), _) | (Err(_), Some((output, _))) => output.clone(),
+            (Err(_), None) => String::new(),
+        };
+        let response_hash = Hash::from_canonical(&output);
+        let response = append(
+            log,
+            EventKind::ToolResponse,
+            EventPayload::ToolResponse(ToolResponsePayload {
+                tool_name: tool_id.name.clone(),
+                request_hash,
+                response_hash,
+                output: output.clone(),
+                success: served.output.is_ok(),
+                error: served.output.as_ref().err().map(|e| e.to_string()),
+                duration_ms: served.duration_ms,
+                source: served.source.clone(),
+                raw_hash: served.raw_hash,
+            }),
+            Some(request),
+        )?;
+
+        // Tools that check paths or hosts at run time report denials as errors.
+        if let Err(ToolError::Denied { capability, reason }) = &served.output {
+            let payload = EventPayload::CapabilityDenied(CapabilityDeniedPayload {
+                capability: Capability::new(capability.clone()),
+                tool_name: tool_id.name.clone(),
+                reason: reason.clone(),
+            });
+            append(log, EventKind::CapabilityDenied, payload, Some(response))?;
+        }
+
+        if let Some((_, error)) = &served.malformed {
+            append(
+                log,
+                EventKind::Error,
+                EventPayload::Error(ErrorPayload {
+                    error_type: "malformed_output".to_string(),
+                    message: error.to_string(),
+                    component: tool_id.as_str(),
+                    recoverable: false,
+                }),
+                Some(response),
+            )?;
+        }
+
+        let mut metadata = match served.source {
+            ResponseSource::Cache => ToolResponseMetadata::cached(served.duration_ms),
+            source => ToolResponseMetadata {
+                source,
+                normalized: served.normalized.is_some(),
+                duration_ms: served.duration_ms,
+                ..ToolResponseMetadata::default()
+            },
+        };
+        if let Some(raw_hash) = served.raw_hash {
+            metadata
+                .extra
+                .push(("raw_hash".to_string(), raw_hash.to_hex()));
+        }
+        if let Some(normalized) = &served.normalized {
+            for path in &normalized.stripped {
+                metadata.extra.push(("stripped".to_string(), path.clone()));
+            }
+            if normalized.truncated {
+                metadata
+                    .extra
+                    .push(("truncated".to_string(), "true".to_string()));
+            }
+        }
+        served
+            .output
+            .map(|data| ToolResponse::with_metadata(data, metadata))
+    }
+
+    /// Validate input against the tool's schema
+    ///
+    /// Returns the tool's schemas, or `None` when no tool will run.
+    fn check_input(
+        &mut self,
+        tool: Option<&dyn Tool>,
+        tool_id: &ToolId,
+        input: &str,
+    ) -> ToolResult<Option<Arc<ToolSchemas>>> {
+        let Some(tool) = tool.filter(|_| self.mode != ToolMode::Replay) else {
+            return Ok(None);
+        };
+        let schemas = match self.schemas.get(tool_id) {
+            Some(schemas) => schemas.clone(),
+            None => {
+                let parse = |schema: &str| {
+                    Schema::parse(schema).map_err(|e| ToolError::ExecutionFailed {
+                        tool: tool_id.as_str(),
+                        reason: e.to_string(),
+                    })
+                };
+                let schemas = Arc::new(ToolSchemas {
+                    input: parse(tool.input_schema())?,
+                    output: parse(tool.output_schema())?,
+                });
+                self.schemas.insert(tool_id.clone(), schemas.clone());
+                schemas
+            }
+        };
+        schemas
+            .input
+            .validate_str(input)
+            .map_err(|e| ToolError::InvalidInput {
+                tool: tool_id.as_str(),
+                reason: e.to_string(),
+            })?;
+        Ok(Some(schemas))
+    }
+
+    /// Serve a request according to the mode
+    fn serve(
+        &mut self,
+        tool: Option<Arc<dyn Tool>>,
+        tool_id: &ToolId,
+        input: &str,
+        request_hash: Hash,
+        context: &ExecutionContext,
+        schemas: Option<&ToolSchemas>,
+    ) -> Served {
+        let recorded = self
+            .recorded
+            .get_mut(&request_hash)
+            .and_then(|queue| queue.pop_front());
+
+        let tool = match (self.mode, tool) {
+            (ToolMode::Live, Some(tool)) => tool,
+            (ToolMode::Replay, _) => {
+                return match recorded {
+                    Some(response) => replayed(tool_id, response),
+                    None => Served::error(ToolError::ExecutionFailed {
+                        tool: tool_id.as_str(),
+                        reason: format!("no recorded response for request {}", request_hash),
+                    }),
+                };
+            }
+            (ToolMode::Hybrid, Some(tool)) => {
+                let replayable = tool.side_effects() == SideEffect::Pure
+                    && tool.determinism() == Determinism::Deterministic;
+                match recorded {
+                    Some(response) if replayable => return replayed(tool_id, response),
+                    Some(response) => {
+                        let served = execute(
+                            tool.as_ref(),
+                            tool_id,
+                            input,
+                            context,
+                            schemas,
+                            &self.normalizer,
+                        );
+                        let live = Hash::from_canonical(
+                            &served.output.as_ref().cloned().unwrap_or_default(),
+                        );
+                        if live != response.response_hash {
+                            tracing::warn!(tool = %tool_id, %request_hash, "tool output diverged from recording");
+                            self.divergences.push(ToolDivergence {
+                                tool: tool_id.clone(),
+                                request_hash,
+                                recorded: response.response_hash,
+                                live,
+                            });
+                        }
+                        return served;
+                    }
+                    None => tool,
+                }
+            }
+            (_, None) => return Served::error(ToolError::NotFound(tool_id.as_str())),
+        };
+
+        self.execute_cached(
+            tool.as_ref(),
+            tool_id,
+            input,
+            request_hash,
+            context,
+            schemas,
+        )
+    }
+
+    /// Execute a tool, going through the cache when it is eligible
+    fn execute_cached(
+        &mut self,
+        tool: &dyn Tool,
+        tool_id: &ToolId,
+        input: &str,
+        request_hash: Hash,
+        context: &ExecutionContext,
+        schemas: Option<&ToolSchemas>,
+    ) -> Served {
+        let Some(cache) = self.cache.as_mut().filter(|_| ToolCache::is_eligible(tool)) else {
+            return execute(tool, tool_id, input, context, schemas, &self.normalizer);
+        };
+
+        let key = cache_key(request_hash, &self.normalizer);
+        if let Some(cached) = cache.get(tool_id, key) {
+            return Served {
+                output: Ok(cached.output),
+                source: ResponseSource::Cache,
+                duration_ms: cached.duration_ms,
+                malformed: None,
+                normalized: None,
+                raw_hash: cached.raw_hash,
+            };
+        }
+
+        let served = execute(tool, tool_id, input, context, schemas, &self.normalizer);
+        if let Ok(output) = &served.output {
+            let mut response = CachedResponse::new(output.clone(), served.duration_ms);
+            if let Some(raw_hash) = served.raw_hash {
+                response = response.with_raw_hash(raw_hash);
+            }
+            if let Err(e) = cache.insert(tool_id, key, response) {
+                tracing::warn!(tool = %tool_id, error = %e, "failed to cache tool response");
+            }
+        }
+        served
+    }
+}
+
+/// Execute a tool live
+///
+/// The output is validated if schemas are given, then normalized.
+fn execute(
+    tool: &dyn Tool,
+    tool_id: &ToolId,
+    input: &str,
+    context: &ExecutionContext,
+    schemas: Option<&ToolSchemas>,
+    normalizer: &Normalizer,
+) -> Served {
+    let start = Instant::now();
+    let mut output = tool.execute(input.as_bytes(), context).and_then(|bytes| {
+        String::from_utf8(bytes).map_err(|e| ToolError::SerializationFailed {
+            tool: tool_id.as_str(),
+            reason: e.to_string(),
+        })
+    });
+    let duration_ms = start.elapsed().as_millis() as u64;
+
+    let mut malformed = None;
+    if let (Ok(raw), Some(schemas)) = (&output, schemas) {
+        if let Err(e) = schemas.output.validate_str(raw) {
+            tracing::warn!(tool = %tool_id, error = %e, "tool output failed schema validation");
+            malformed = Some((raw.clone(), e.clone()));
+            output = Err(ToolError::SerializationFailed {
+                tool: tool_id.as_str(),
+                reason: e.to_string(),
+            });
+        }
+    }
+
+    let mut normalized = None;
+    if let Ok(raw) = &mut output {
+        let result = normalizer.normalize(raw);
+        if !result.stripped.is_empty() || result.truncated {
+            tracing::debug!(tool = %tool_id, stripped = ?result.stripped, truncated = result.truncated, "normalized tool output");
+        }
+        *raw = result.output.clone();
+        normalized = Some(result);
+    }
+
+    let source = if output.is_ok() {
+        ResponseSource::Tool
+    } else {
+        ResponseSource::Error
+    };
+    Served {
+        output,
+        source,
+        duration_ms,
+        malformed,
+        raw_hash: normalized.as_ref().map(|n| n.raw_hash),
+        normalized,
+    }
+}
+
+/// Serve a recorded response
+fn replayed(tool_id: &ToolId, response: ToolResponsePayload) -> Served {
+    let output = if response.success {
+        Ok(response.output)
+    } else {
+        Err(ToolError::ExecutionFailed {
+            tool: tool_id.as_str(),
+            reason: response.error.unwrap_or_default(),
+        })
+    };
+    Served {
+        output,
+        source: ResponseSource::Mock,
+        duration_ms: response.duration_ms,
+        malformed: None,
+        normalized: None,
+        raw_hash: response.raw_hash,
+    }
+}
+
+/// Append an event to the log
+fn append(
+    log: &mut EventLog,
+    kind: EventKind,
+    payload: EventPayload,
+    parent: Option<EventId>,
+) -> ToolResult<EventId> {
+    log.append_next(kind, payload, parent)
+        .map_err(|e| ToolError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{DynTool, EchoTool, ToolMetadata};
+    use oracle_omen_core::tool::ResourceBounds;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Impure tool returning how often it ran
+    #[derive(Default)]
+    struct CounterTool {
+        calls: AtomicU64,
+    }
+
+    impl DynTool for CounterTool {
+        fn id(&self) -> &ToolId {
+            use std::sync::OnceLock;
+            static ID: OnceLock<ToolId> = OnceLock::new();
+            ID.get_or_init(|| ToolId::new("counter", "1.0.0"))
+        }
+
+        fn capabilities(&self) -> Vec<String> {
+            vec![]
+        }
+
+        fn side_effects(&self) -> SideEffect {
+            SideEffect::Impure
+        }
+
+        fn resource_bounds(&self) -> &ResourceBounds {
+            static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
+            &BOUNDS
+        }
+
+        fn execute(&self, _input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(calls.to_string().into_bytes())
+        }
+
+        fn input_schema(&self) -> &str {
+            "{}"
+        }
+
+        fn output_schema(&self) -> &str {
+            r#"{"type": "integer"}"#
+        }
+    }
+
+    fn setup() -> (Arc<CounterTool>, ToolRegistry) {
+        let counter = Arc::new(CounterTool::default());
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).unwrap();
+        registry.register_dyn(counter.clone()).unwrap();
+        (counter, registry)
+    }
+
+    fn last_source(log: &EventLog) -> ResponseSource {
+        match &log.last().unwrap().payload {
+            EventPayload::ToolResponse(response) => response.source.clone(),
+            other => panic!("expected tool response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_replay_serves_recording() {
+        let (counter, registry) = setup();
+        let counter_id = ToolId::new("counter", "1.0.0");
+
+        let mut recording = EventLog::new(1);
+        let mut live = ToolInvoker::new(registry.clone(), CapabilitySet::empty());
+        let first = live
+            .invoke(&mut recording, &counter_id, "{}", None)
+            .unwrap();
+        assert_eq!(first.metadata.source, ResponseSource::Tool);
+        assert_eq!(recording.len(), 2);
+
+        let mut replay_log = EventLog::new(2);
+        let mut replay = ToolInvoker::new(registry, CapabilitySet::empty())
+            .with_mode(ToolMode::Replay)
+            .with_recording(&recording);
+        let replayed = replay
+            .invoke(&mut replay_log, &counter_id, "{}", None)
+            .unwrap();
+        assert_eq!(replayed.data, first.data);
+        assert_eq!(replayed.metadata.source, ResponseSource::Mock);
+        assert_eq!(last_source(&replay_log), ResponseSource::Mock);
+        assert_eq!(counter.calls.load(Ordering::SeqCst), 1);
+
+        // The single recording is used up
+        let miss = replay.invoke(&mut replay_log, &counter_id, "{}", None);
+        assert!(matches!(miss, Err(ToolError::ExecutionFailed { .. })));
+        assert_eq!(last_source(&replay_log), ResponseSource::Error);
+    }
+
+    #[test]
+    fn test_hybrid_diffs_impure_tools() {
+        let (counter, registry) = setup();
+        let echo_id = ToolId::new("echo", "1.0.0");
+        let counter_id = ToolId::new("counter", "1.0.0");
+
+        let mut recording = EventLog::new(1);
+        let mut live = ToolInvoker::new(registry.clone(), CapabilitySet::empty());
+        live.invoke(&mut recording, &echo_id, "\"hi\"", None)
+            .unwrap();
+        live.invoke(&mut recording, &counter_id, "{}", None)
+            .unwrap();
+
+        let mut log = EventLog::new(2);
+        let mut hybrid = ToolInvoker::new(registry, CapabilitySet::empty())
+            .with_mode(ToolMode::Hybrid)
+            .with_recording(&recording);
+        let echo = hybrid.invoke(&mut log, &echo_id, "\"hi\"", None).unwrap();
+        assert_eq!(echo.metadata.source, ResponseSource::Mock);
+
+        let count = hybrid.invoke(&mut log, &counter_id, "{}", None).unwrap();
+        assert_eq!(count.metadata.source, ResponseSource::Tool);
+        assert_eq!(count.data, "2");
+        assert_eq!(counter.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(hybrid.divergences().len(), 1);
+        assert_eq!(hybrid.divergences()[0].tool, counter_id);
+    }
+
+    #[test]
+    fn test_cache_serves_pure_tools() {
+        let (counter, registry) = setup();
+        let echo_id = ToolId::new("echo", "1.0.0");
+        let counter_id = ToolId::new("counter", "1.0.0");
+        let mut invoker =
+            ToolInvoker::new(registry, CapabilitySet::empty()).with_cache(ToolCache::new());
+        let mut log = EventLog::new(1);
+
+        let first = invoker.invoke(&mut log, &echo_id, "\"hi\"", None).unwrap();
+        assert_eq!(first.metadata.source, ResponseSource::Tool);
+        let second = invoker.invoke(&mut log, &echo_id, "\"hi\"", None).unwrap();
+        assert_eq!(second.metadata.source, ResponseSource::Cache);
+        assert_eq!(second.metadata.duration_ms, first.metadata.duration_ms);
+        // The raw hash recorded on the miss is kept on the hit
+        assert_eq!(second.metadata.extra, first.metadata.extra);
+        assert_eq!(second.response_hash, first.response_hash);
+        assert_eq!(last_source(&log), ResponseSource::Cache);
+        assert_eq!(log.len(), 4);
+
+        // A different normalizer does not reuse output normalized by the old one
+        invoker = invoker.with_normalizer(Normalizer::new().with_max_string_chars(1));
+        let truncated = invoker.invoke(&mut log, &echo_id, "\"hi\"", None).unwrap();
+        assert_eq!(truncated.metadata.source, ResponseSource::Tool);
+        assert_eq!(truncated.data, "\"h\"");
+        invoker = invoker.with_normalizer(Normalizer::new());
+
+        // Impure tools always run
+        invoker.invoke(&mut log, &counter_id, "{}", None).unwrap();
+        invoker.invoke(&mut log, &counter_id, "{}", None).unwrap();
+        assert_eq!(counter.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(invoker.cache().unwrap().len(), 2);
+
+        invoker
+            .cache_mut()
+            .unwrap()
+            .invalidate_version(&echo_id)
+            .unwrap();
+        let third = invoker.invoke(&mut log, &echo_id, "\"hi\"", None).unwrap();
+        assert_eq!(third.metadata.source, ResponseSource::Tool);
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        /// Tool whose output never matches its schema
+        struct BadOutputTool;
+        impl DynTool for BadOutputTool {
+            fn id(&self) -> &ToolId {
+                use std::sync::OnceLock;
+                static ID: OnceLock<ToolId> = OnceLock::new();
+                ID.get_or_init(|| ToolId::new("bad", "1.0.0"))
+            }
+            fn capabilities(&self) -> Vec<String> {
+                vec![]
+            }
+            fn side_effects(&self) -> SideEffect {
+                SideEffect::Pure
+            }
+            fn resource_bounds(&self) -> &ResourceBounds {
+                static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
+                &BOUNDS
+            }
+            fn execute(&self, _input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+                Ok(b"\"three\"".to_vec())
+            }
+            fn input_schema(&self) -> &str {
+                "{}"
+            }
+            fn output_schema(&self) -> &str {
+                r#"{"type": "integer"}"#
+            }
+        }
+
+        let (_, mut registry) = setup();
+        registry.register_dyn(Arc::new(BadOutputTool)).unwrap();
+        let mut invoker = ToolInvoker::new(registry, CapabilitySet::empty());
+        let mut log = EventLog::new(1);
+
+        let err = invoker
+            .invoke(&mut log, &ToolId::new("echo", "1.0.0"), "42", None)
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidInput { .. }));
+        assert_eq!(last_source(&log), ResponseSource::Error);
+
+        let err = invoker
+            .invoke(&mut log, &ToolId::new("bad", "1.0.0"), "{}", None)
+            .unwrap_err();
+        assert!(matches!(err, ToolError::SerializationFailed { .. }));
+        let error = log.last().unwrap();
+        assert_eq!(error.kind, EventKind::Error);
+        match &error.payload {
+            EventPayload::Error(payload) => assert_eq!(payload.error_type, "malformed_output"),
+            other => panic!("expected error payload, got {:?}", other),
+        }
+        let response = log.get(error.parent_id.unwrap()).unwrap();
+        match &response.payload {
+            EventPayload::ToolResponse(payload) => {
+                assert!(!payload.success);
+                assert_eq!(payload.output, "\"three\"");
+            }
+            other => panic!("expected tool response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_outputs_are_normalized_before_hashing() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool)).unwrap();
+        let echo_id = ToolId::new("echo", "1.0.0");
+        let mut invoker = ToolInvoker::new(registry, CapabilitySet::empty()).with_normalizer(
+            Normalizer::new()
+                .with_line_endings(true)
+                .with_max_string_chars(3),
+        );
+        let mut log = EventLog::new(1);
+
+        let raw = "\"ab\\r\\ncd\"";
+        let response = invoker.invoke(&mut log, &echo_id, raw, None).unwrap();
+        assert_eq!(response.data, "\"ab\\n\"");
+        assert!(response.metadata.normalized);
+        assert!(response
+            .metadata
+            .extra
+            .contains(&("truncated".to_string(), "true".to_string())));
+
+        match &log.last().unwrap().payload {
+            EventPayload::ToolResponse(payload) => {
+                assert_eq!(payload.output, response.data);
+                assert_eq!(payload.response_hash, Hash::from_canonical(&response.data));
+                assert_eq!(payload.raw_hash, Some(Hash::from_canonical(&raw)));
+            }
+            other => panic!("expected tool response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_capability_denied_is_logged() {
+        struct NetTool;
+        impl DynTool for NetTool {
+            fn id(&self) -> &ToolId {
+                use std::sync::OnceLock;
+                static ID: OnceLock<ToolId> = OnceLock::new();
+                ID.get_or_init(|| ToolId::new("net", "1.0.0"))
+            }
+            fn capabilities(&self) -> Vec<String> {
+                vec!["network:http:get".to_string()]
+            }
+            fn side_effects(&self) -> SideEffect {
+                SideEffect::Impure
+            }
+            fn resource_bounds(&self) -> &ResourceBounds {
+                static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
+                &BOUNDS
+            }
+            fn execute(&self, _input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+                Ok(Vec::new())
+            }
+            fn input_schema(&self) -> &str {
+                "{}"
+            }
+            fn output_schema(&self) -> &str {
+                "{}"
+            }
+        }
+
+        let mut registry = ToolRegistry::new();
+        registry.register_dyn(Arc::new(NetTool)).unwrap();
+        let mut invoker = ToolInvoker::new(registry, CapabilitySet::empty());
+        let mut log = EventLog::new(1);
+
+        let err = invoker
+            .invoke(&mut log, &ToolId::new("net", "1.0.0"), "{}", None)
+            .unwrap_err();
+        assert!(matches!(err, ToolError::Denied { .. }));
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.last().unwrap().kind, EventKind::CapabilityDenied);
+    }
+}
@@ -2,9 +2,16 @@
 //
 // Provides:
 // - Tool execution runtime
+// - Record and replay of tool calls
+// - Response cache for pure deterministic tools
+// - Subprocess, sandboxed filesystem and HTTP tools
+// - Model providers for LLM calls
 // - Capability checking
+// - Agent loop driver and run config files
 // - Scheduler for DAG execution
 // - Backpressure and resource management
+// - Run persistence
+// - OTLP-JSON trace export
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
@@ -13,8 +20,28 @@ pub mod executor;
 pub mod scheduler;
 pub mod capabilities;
 pub mod tools;
+pub mod persist;
+pub mod invoke;
+pub mod cache;
+pub mod process;
+pub mod files;
+pub mod http;
+pub mod model;
+pub mod agent;
+pub mod config;
+pub mod otlp;
 
 pub use executor::*;
 pub use scheduler::*;
 pub use capabilities::*;
 pub use tools::*;
+pub use persist::*;
+pub use invoke::*;
+pub use cache::*;
+pub use process::*;
+pub use files::*;
+pub use http::*;
+pub use model::*;
+pub use agent::*;
+pub use config::*;
+pub use otlp::*;
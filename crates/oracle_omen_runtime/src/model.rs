@@ -0,0 +1,841 @@
+//! Model providers for LLM calls.
+//!
+//! A [`ModelProvider`] answers completion and chat requests. Wrap one in a
+//! [`ModelTool`] and call it through the `ToolInvoker`: the model name, prompt
+//! and sampling parameters are logged as the tool request and the reply as
+//! the tool response, so `ToolMode::Replay` reruns an LLM-driven agent exactly
+//! without calling the model. Requests are [resolved](ModelRequest::resolve)
+//! before invoking, so the logged request is the one the model receives.
+//!
+//! Providers:
+//! - [`OpenAiCompatible`]: local servers speaking the OpenAI HTTP API
+//!   (llama.cpp, vLLM, Ollama, ...)
+//! - [`MockProvider`]: deterministic scripted replies for tests
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+
+use oracle_omen_core::{
+    capability::Capability,
+    hash::Hash,
+    tool::{
+        Determinism, ExecutionContext, ResourceBounds, SideEffect, Tool, ToolError, ToolId,
+        ToolResult,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+const INPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "model": {"type": "string", "minLength": 1},
+        "prompt": {"type": "string"},
+        "messages": {
+            "type": "array",
+            "minItems": 1,
+            "items": {
+                "type": "object",
+                "properties": {
+                    "role": {"enum": ["system", "user", "assistant"]},
+                    "content": {"type": "string"}
+                },
+                "required": ["role", "content"],
+                "additionalProperties": false
+            }
+        },
+        "params": {"type": "object"}
+    },
+    "required": ["model"],
+    "additionalProperties": false
+}"#;
+
+const OUTPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "text": {"type": "string"},
+        "finish_reason": {"type": ["string", "null"]},
+        "usage": {"type": ["object", "null"]}
+    },
+    "required": ["text"],
+    "additionalProperties": false
+}"#;
+
+/// Model call result
+pub type ModelResult<T> = Result<T, ModelError>;
+
+/// Model call errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelError {
+    /// Request could not be sent or the connection failed
+    Transport(String),
+
+    /// Server answered with an error status
+    Status {
+        /// HTTP status code
+        status: u16,
+        /// Response body, up to the response size limit
+        body: String,
+    },
+
+    /// Server answered with something that is not a model response
+    InvalidResponse(String),
+
+    /// Provider does not support the request
+    Unsupported(String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Transport(msg) => write!(f, "Model transport error: {}", msg),
+            ModelError::Status { status, body } => {
+                write!(f, "Model server returned {}: {}", status, body)
+            }
+            ModelError::InvalidResponse(msg) => write!(f, "Invalid model response: {}", msg),
+            ModelError::Unsupported(msg) => write!(f, "Unsupported model request: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// Role of a chat message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Instructions for the model
+    System,
+
+    /// User or agent input
+    User,
+
+    /// Earlier model output
+    Assistant,
+}
+
+/// One chat message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Who wrote the message
+    pub role: Role,
+
+    /// Message text
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Create a message
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// Sampling parameters
+///
+/// Unset fields are left to the provider's defaults. Temperature and top-p
+/// are stored in thousandths so requests hash canonically.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplingParams {
+    /// Temperature, in thousandths (700 = 0.7)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_milli: Option<u32>,
+
+    /// Nucleus sampling mass, in thousandths
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p_milli: Option<u32>,
+
+    /// Maximum tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    /// Stop sequences
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+
+    /// Sampling seed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl SamplingParams {
+    /// Greedy decoding: temperature 0
+    pub fn greedy() -> Self {
+        Self {
+            temperature_milli: Some(0),
+            ..Self::default()
+        }
+    }
+
+    /// Set the seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the token limit
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+/// Token counts reported by the provider
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Tokens in the prompt
+    pub prompt_tokens: u64,
+
+    /// Tokens generated
+    pub completion_tokens: u64,
+}
+
+/// A model reply
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelResponse {
+    /// Generated text
+    pub text: String,
+
+    /// Why generation stopped, e.g. `stop` or `length`
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+
+    /// Token usage, if reported
+    #[serde(default)]
+    pub usage: Option<Usage>,
+}
+
+/// Something that answers prompts
+pub trait ModelProvider: Send + Sync {
+    /// Model name sent with requests
+    fn model(&self) -> &str;
+
+    /// Capabilities needed to reach the model
+    fn capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
+
+    /// Whether the same request always gets the same reply
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
+    /// Whether answering reaches outside the process, e.g. over the network
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Impure
+    }
+
+    /// Complete a raw prompt, giving up after `timeout`
+    fn complete(
+        &self,
+        prompt: &str,
+        params: &SamplingParams,
+        timeout: Duration,
+    ) -> ModelResult<ModelResponse>;
+
+    /// Answer a conversation, giving up after `timeout`
+    fn chat(
+        &self,
+        messages: &[ChatMessage],
+        params: &SamplingParams,
+        timeout: Duration,
+    ) -> ModelResult<ModelResponse>;
+}
+
+/// Provider for servers speaking the OpenAI completions API
+///
+/// Sends `POST {base_url}/chat/completions` and `POST {base_url}/completions`.
+/// Reaching the server requires `network:<scheme>:post:<host>`.
+pub struct OpenAiCompatible {
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    max_response_bytes: u64,
+    capabilities: Vec<Capability>,
+}
+
+impl OpenAiCompatible {
+    /// Default request timeout of a [`ModelTool`]
+    pub const DEFAULT_TIMEOUT_MS: u64 = 120_000;
+
+    /// Largest response body read
+    pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 8 * 1024 * 1024;
+
+    /// Create a provider for `model` at `base_url`, e.g. `http://localhost:8080/v1`
+    pub fn new(base_url: &str, model: impl Into<String>) -> ModelResult<Self> {
+        let url = Url::parse(base_url).map_err(|e| ModelError::Unsupported(e.to_string()))?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| ModelError::Unsupported(format!("URL has no host: {}", base_url)))?;
+        let capability = Capability::new(format!("network:{}:post:{}", url.scheme(), host));
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.into(),
+            api_key: None,
+            max_response_bytes: Self::DEFAULT_MAX_RESPONSE_BYTES,
+            capabilities: vec![capability],
+        })
+    }
+
+    /// Send a bearer token
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// POST a JSON body and parse the JSON reply
+    fn post(&self, path: &str, body: Value, timeout: Duration) -> ModelResult<Value> {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(timeout)
+            .redirects(0)
+            .build();
+        let mut request = agent
+            .post(&format!("{}/{}", self.base_url, path))
+            .set("Content-Type", "application/json");
+        if let Some(key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", key));
+        }
+        let (status, response) = match request.send_string(&body.to_string()) {
+            Ok(response) => (response.status(), response),
+            Err(ureq::Error::Status(status, response)) => (status, response),
+            Err(e) => return Err(ModelError::Transport(e.to_string())),
+        };
+        let mut text = String::new();
+        response
+            .into_reader()
+            .take(self.max_response_bytes)
+            .read_to_string(&mut text)
+            .map_err(|e| ModelError::Transport(e.to_string()))?;
+        if !(200..300).contains(&status) {
+            return Err(ModelError::Status { status, body: text });
+        }
+        serde_json::from_str(&text).map_err(|e| ModelError::InvalidResponse(e.to_string()))
+    }
+
+    /// Request body shared by both endpoints
+    fn body(&self, params: &SamplingParams) -> Value {
+        let mut body = json!({ "model": self.model });
+        if let Some(t) = params.temperature_milli {
+            body["temperature"] = json!(f64::from(t) / 1000.0);
+        }
+        if let Some(p) = params.top_p_milli {
+            body["top_p"] = json!(f64::from(p) / 1000.0);
+        }
+        if let Some(max) = params.max_tokens {
+            body["max_tokens"] = json!(max);
+        }
+        if !params.stop.is_empty() {
+            body["stop"] = json!(params.stop);
+        }
+        if let Some(seed) = params.seed {
+            body["seed"] = json!(seed);
+        }
+        body
+    }
+}
+
+/// Read the first choice of an OpenAI-style reply
+fn first_choice(
+    reply: &Value,
+    text: impl Fn(&Value) -> Option<&str>,
+) -> ModelResult<ModelResponse> {
+    let choice = reply
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .ok_or_else(|| ModelError::InvalidResponse("no choices".to_string()))?;
+    let content = text(choice)
+        .ok_or_else(|| ModelError::InvalidResponse("choice has no text".to_string()))?;
+    let usage = reply.get("usage").and_then(|u| {
+        Some(Usage {
+            prompt_tokens: u.get("prompt_tokens")?.as_u64()?,
+            completion_tokens: u.get("completion_tokens")?.as_u64()?,
+        })
+    });
+    Ok(ModelResponse {
+        text: content.to_string(),
+        finish_reason: choice
+            .get("finish_reason")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        usage,
+    })
+}
+
+impl ModelProvider for OpenAiCompatible {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities.clone()
+    }
+
+    fn complete(
+        &self,
+        prompt: &str,
+        params: &SamplingParams,
+        timeout: Duration,
+    ) -> ModelResult<ModelResponse> {
+        let mut body = self.body(params);
+        body["prompt"] = json!(prompt);
+        let reply = self.post("completions", body, timeout)?;
+        first_choice(&reply, |c| c.get("text")?.as_str())
+    }
+
+    fn chat(
+        &self,
+        messages: &[ChatMessage],
+        params: &SamplingParams,
+        timeout: Duration,
+    ) -> ModelResult<ModelResponse> {
+        let mut body = self.body(params);
+        body["messages"] = json!(messages);
+        let reply = self.post("chat/completions", body, timeout)?;
+        first_choice(&reply, |c| c.get("message")?.get("content")?.as_str())
+    }
+}
+
+/// Deterministic provider for tests
+///
+/// Replies are looked up by prompt, or for chats by the last message. Other
+/// prompts get `fallback`, or an echo of the prompt when none is set.
+#[derive(Clone, Debug, Default)]
+pub struct MockProvider {
+    model: String,
+    replies: BTreeMap<String, String>,
+    fallback: Option<String>,
+}
+
+impl MockProvider {
+    /// Create a mock
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Reply to `prompt` with `reply`
+    pub fn with_reply(mut self, prompt: impl Into<String>, reply: impl Into<String>) -> Self {
+        self.replies.insert(prompt.into(), reply.into());
+        self
+    }
+
+    /// Reply to unknown prompts with `reply`
+    pub fn with_fallback(mut self, reply: impl Into<String>) -> Self {
+        self.fallback = Some(reply.into());
+        self
+    }
+
+    fn reply(&self, prompt: &str, params: &SamplingParams) -> ModelResponse {
+        let text = self
+            .replies
+            .get(prompt)
+            .or(self.fallback.as_ref())
+            .cloned()
+            .unwrap_or_else(|| format!("echo: {}", prompt));
+        let (text, finish_reason) = match params.max_tokens {
+            Some(max) if text.split_whitespace().count() > max as usize => {
+                let words: Vec<&str> = text.split_whitespace().take(max as usize).collect();
+                (words.join(" "), "length")
+            }
+            _ => (text, "stop"),
+        };
+        ModelResponse {
+            usage: Some(Usage {
+                prompt_tokens: prompt.split_whitespace().count() as u64,
+                completion_tokens: text.split_whitespace().count() as u64,
+            }),
+            text,
+            finish_reason: Some(finish_reason.to_string()),
+        }
+    }
+}
+
+impl ModelProvider for MockProvider {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn determinism(&self) -> Determinism {
+        Determinism::Deterministic
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Pure
+    }
+
+    fn complete(
+        &self,
+        prompt: &str,
+        params: &SamplingParams,
+        _timeout: Duration,
+    ) -> ModelResult<ModelResponse> {
+        Ok(self.reply(prompt, params))
+    }
+
+    fn chat(
+        &self,
+        messages: &[ChatMessage],
+        params: &SamplingParams,
+        _timeout: Duration,
+    ) -> ModelResult<ModelResponse> {
+        let last = messages
+            .last()
+            .ok_or_else(|| ModelError::Unsupported("empty conversation".to_string()))?;
+        Ok(self.reply(&last.content, params))
+    }
+}
+
+/// Input of a model call
+///
+/// Exactly one of `prompt` and `messages` is set. `model` must name the
+/// provider's model; [`ModelRequest::resolve`] fills it in with the seed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelRequest {
+    /// Model the request is sent to
+    #[serde(default)]
+    pub model: String,
+
+    /// Raw completion prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+
+    /// Chat conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<ChatMessage>>,
+
+    /// Sampling parameters
+    #[serde(default)]
+    pub params: SamplingParams,
+}
+
+impl ModelRequest {
+    /// Completion request
+    pub fn completion(prompt: impl Into<String>, params: SamplingParams) -> Self {
+        Self {
+            model: String::new(),
+            prompt: Some(prompt.into()),
+            messages: None,
+            params,
+        }
+    }
+
+    /// Chat request
+    pub fn chat(messages: Vec<ChatMessage>, params: SamplingParams) -> Self {
+        Self {
+            model: String::new(),
+            prompt: None,
+            messages: Some(messages),
+            params,
+        }
+    }
+
+    /// Name the model and give an unseeded request `seed`
+    ///
+    /// Pass the invoker's seed, so the logged request carries the seed the
+    /// model is called with.
+    pub fn resolve(mut self, model: impl Into<String>, seed: Option<u64>) -> Self {
+        self.model = model.into();
+        self.params.seed = self.params.seed.or(seed);
+        self
+    }
+
+    /// Canonical JSON, the tool input
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Hash of the request, for linking prompts to outcomes
+    pub fn hash(&self) -> Hash {
+        Hash::from_canonical(self)
+    }
+}
+
+/// Runs a [`ModelProvider`] as a [`Tool`]
+///
+/// Input is a [`ModelRequest`], output a [`ModelResponse`]. Requests for
+/// another model are rejected, and so are unseeded requests in a seeded run:
+/// [`ModelRequest::resolve`] them first. The timeout in the resource bounds
+/// is passed to the provider.
+pub struct ModelTool {
+    id: ToolId,
+    provider: Arc<dyn ModelProvider>,
+    capabilities: Vec<Capability>,
+    bounds: ResourceBounds,
+}
+
+impl ModelTool {
+    /// Wrap a provider under the given tool ID
+    pub fn new(id: ToolId, provider: Arc<dyn ModelProvider>) -> Self {
+        let capabilities = provider.capabilities();
+        Self {
+            id,
+            provider,
+            capabilities,
+            bounds: ResourceBounds::with_timeout(OpenAiCompatible::DEFAULT_TIMEOUT_MS),
+        }
+    }
+
+    /// Set resource bounds
+    pub fn with_resource_bounds(mut self, bounds: ResourceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Model the provider serves
+    pub fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    fn invalid(&self, reason: String) -> ToolError {
+        ToolError::InvalidInput {
+            tool: self.id.as_str(),
+            reason,
+        }
+    }
+
+    fn failed(&self, reason: impl ToString) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.id.as_str(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Tool for ModelTool {
+    fn id(&self) -> &ToolId {
+        &self.id
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        self.provider.side_effects()
+    }
+
+    fn determinism(&self) -> Determinism {
+        self.provider.determinism()
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        &self.bounds
+    }
+
+    fn execute(&self, input: &[u8], context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        let request: ModelRequest =
+            serde_json::from_slice(input).map_err(|e| self.invalid(e.to_string()))?;
+        if request.model != self.model() {
+            return Err(self.invalid(format!(
+                "request is for model '{}', tool serves '{}'",
+                request.model,
+                self.model()
+            )));
+        }
+        if let (None, Some(seed)) = (request.params.seed, context.random_seed) {
+            return Err(self.invalid(format!("request has no seed in a run seeded with {}", seed)));
+        }
+        let timeout = Duration::from_millis(self.bounds.timeout_ms);
+        let response = match (&request.prompt, &request.messages) {
+            (Some(prompt), None) => self.provider.complete(prompt, &request.params, timeout),
+            (None, Some(messages)) => self.provider.chat(messages, &request.params, timeout),
+            _ => {
+                return Err(
+                    self.invalid("exactly one of prompt and messages is required".to_string())
+                )
+            }
+        }
+        .map_err(|e| self.failed(e))?;
+        serde_json::to_vec(&response).map_err(|e| ToolError::SerializationFailed {
+            tool: self.id.as_str(),
+            reason: e.to_string(),
+        })
+    }
+
+    fn input_schema(&self) -> &str {
+        INPUT_SCHEMA
+    }
+
+    fn output_schema(&self) -> &str {
+        OUTPUT_SCHEMA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ToolInvoker, ToolMode, ToolRegistry};
+    use oracle_omen_core::{
+        capability::CapabilitySet,
+        event::{EventKind, EventLog, EventPayload},
+    };
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_mock_provider() {
+        let mock = MockProvider::new("mock").with_reply("2+2?", "four");
+        let params = SamplingParams::greedy();
+        let timeout = Duration::from_secs(1);
+        assert_eq!(
+            mock.complete("2+2?", &params, timeout).unwrap().text,
+            "four"
+        );
+        assert_eq!(
+            mock.complete("hi", &params, timeout).unwrap().text,
+            "echo: hi"
+        );
+
+        let chat = [
+            ChatMessage::new(Role::System, "be brief"),
+            ChatMessage::new(Role::User, "2+2?"),
+        ];
+        assert_eq!(mock.chat(&chat, &params, timeout).unwrap().text, "four");
+
+        let cut = mock
+            .complete(
+                "a b c",
+                &SamplingParams::default().with_max_tokens(2),
+                timeout,
+            )
+            .unwrap();
+        assert_eq!(cut.text, "echo: a");
+        assert_eq!(cut.finish_reason.as_deref(), Some("length"));
+
+        let tool = ModelTool::new(ToolId::new("llm", "1.0.0"), Arc::new(mock));
+        assert_eq!(tool.side_effects(), SideEffect::Pure);
+    }
+
+    #[test]
+    fn test_replay_skips_the_model() {
+        let id = ToolId::new("llm", "1.0.0");
+        let mut registry = ToolRegistry::new();
+        let mock = MockProvider::new("mock").with_fallback("call search");
+        registry
+            .register(Arc::new(ModelTool::new(id.clone(), Arc::new(mock))))
+            .unwrap();
+        let mut invoker = ToolInvoker::new(registry, CapabilitySet::empty()).with_seed(7);
+        let mut log = EventLog::new(1);
+
+        let unresolved = ModelRequest::chat(
+            vec![ChatMessage::new(Role::User, "find rust docs")],
+            SamplingParams::greedy(),
+        );
+        // Missing model, then missing seed in a seeded run
+        assert!(invoker
+            .invoke(&mut log, &id, &unresolved.to_json(), None)
+            .is_err());
+        let unseeded = unresolved.clone().resolve("mock", None);
+        assert!(matches!(
+            invoker.invoke(&mut log, &id, &unseeded.to_json(), None),
+            Err(ToolError::InvalidInput { .. })
+        ));
+        let other_model = unresolved.clone().resolve("other", invoker.seed());
+        assert!(invoker
+            .invoke(&mut log, &id, &other_model.to_json(), None)
+            .is_err());
+
+        let mut log = EventLog::new(1);
+        let request = unresolved.resolve("mock", invoker.seed());
+        assert_eq!(request.params.seed, Some(7));
+        let live = invoker
+            .invoke(&mut log, &id, &request.to_json(), None)
+            .unwrap();
+        let response: ModelResponse = serde_json::from_str(&live.data).unwrap();
+        assert_eq!(response.text, "call search");
+        match &log.events()[0].payload {
+            EventPayload::ToolRequest(payload) => {
+                assert_eq!(payload.input, request.to_json());
+                assert!(payload.input.contains(r#""model":"mock""#));
+                assert!(payload.input.contains(r#""seed":7"#));
+            }
+            other => panic!("expected tool request, got {:?}", other),
+        }
+
+        // No provider is registered for the replay.
+        let mut replay = ToolInvoker::new(ToolRegistry::new(), CapabilitySet::empty())
+            .with_mode(ToolMode::Replay)
+            .with_recording(&log);
+        let mut replay_log = EventLog::new(1);
+        let replayed = replay
+            .invoke(&mut replay_log, &id, &request.to_json(), None)
+            .unwrap();
+        assert_eq!(replayed.data, live.data);
+        assert_eq!(replay_log.last().unwrap().kind, EventKind::ToolResponse);
+    }
+
+    #[test]
+    fn test_openai_compatible_chat() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        let (sent, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut stream = listener.incoming().next().unwrap().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = Vec::new();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                head.push(line.trim_end().to_string());
+                line.clear();
+            }
+            let length: usize = head
+                .iter()
+                .find_map(|h| {
+                    h.to_ascii_lowercase()
+                        .strip_prefix("content-length: ")
+                        .map(str::to_string)
+                })
+                .unwrap()
+                .parse()
+                .unwrap();
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            sent.send((head[0].clone(), String::from_utf8(body).unwrap()))
+                .unwrap();
+
+            let reply = r#"{"choices":[{"message":{"role":"assistant","content":"hello"},"finish_reason":"stop"}],"usage":{"prompt_tokens":3,"completion_tokens":1}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        });
+
+        let provider = OpenAiCompatible::new(&base, "local-model").unwrap();
+        assert_eq!(
+            provider.capabilities(),
+            vec![Capability::new("network:http:post:127.0.0.1")]
+        );
+        assert_eq!(provider.side_effects(), SideEffect::Impure);
+        let response = provider
+            .chat(
+                &[ChatMessage::new(Role::User, "hi")],
+                &SamplingParams::greedy().with_seed(42),
+                Duration::from_secs(5),
+            )
+            .unwrap();
+        assert_eq!(response.text, "hello");
+        assert_eq!(response.usage.map(|u| u.completion_tokens), Some(1));
+
+        let (request_line, body) = received.recv().unwrap();
+        assert_eq!(request_line, "POST /v1/chat/completions HTTP/1.1");
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["model"], "local-model");
+        assert_eq!(body["seed"], 42);
+        assert_eq!(body["temperature"], 0.0);
+        assert_eq!(body["messages"][0]["content"], "hi");
+    }
+}
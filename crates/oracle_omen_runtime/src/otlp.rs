@@ -0,0 +1,689 @@
+//! OTLP-JSON trace export for run logs.
+//!
+//! A run becomes one trace: the run is the root span, and each DAG node
+//! (`node_started` .. `node_completed`) and tool call (`tool_request` ..
+//! `tool_response`) is a span whose parent follows `parent_id`. DAG nodes sit
+//! under the root and link to the dependency that started them. Capability
+//! denials, errors and all other events become span events.
+//!
+//! Logs carry logical time only, so timestamps are synthetic: each event is
+//! one tick after the previous one, and a tool span lasts exactly the
+//! response's `duration_ms`. Trace and span IDs are derived from the run ID
+//! and event sequence, so exporting the same log twice gives the same file.
+
+use crate::executor::{NODE_COMPLETED, NODE_STARTED};
+use crate::persist::{PersistError, PersistResult};
+use oracle_omen_core::event::{Event, EventKind, EventLog, EventPayload};
+use oracle_omen_core::hash::Hash;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// Attribute value
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttrValue {
+    /// String value
+    String(String),
+
+    /// Integer value
+    Int(i64),
+
+    /// Boolean value
+    Bool(bool),
+}
+
+impl AttrValue {
+    /// OTLP `AnyValue` encoding; 64-bit integers are strings
+    fn to_json(&self) -> Value {
+        match self {
+            Self::String(s) => json!({ "stringValue": s }),
+            Self::Int(i) => json!({ "intValue": i.to_string() }),
+            Self::Bool(b) => json!({ "boolValue": b }),
+        }
+    }
+}
+
+impl From<&str> for AttrValue {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<Hash> for AttrValue {
+    fn from(hash: Hash) -> Self {
+        Self::String(hash.to_hex())
+    }
+}
+
+impl From<u64> for AttrValue {
+    fn from(n: u64) -> Self {
+        Self::Int(i64::try_from(n).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<bool> for AttrValue {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+/// Span attributes, sorted by key
+pub type Attributes = BTreeMap<String, AttrValue>;
+
+/// Span kind
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanKind {
+    /// Run or DAG node
+    Internal,
+
+    /// Tool call
+    Client,
+}
+
+/// Span status
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpanStatus {
+    /// No outcome recorded
+    Unset,
+
+    /// Completed successfully
+    Ok,
+
+    /// Failed
+    Error(String),
+}
+
+/// Timestamped event within a span
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpanEvent {
+    /// Event name, e.g. `capability_denied`
+    pub name: String,
+
+    /// Synthetic time in Unix nanoseconds
+    pub time_unix_nano: u64,
+
+    /// Event attributes
+    pub attributes: Attributes,
+}
+
+/// One span of a run trace
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// 32 hex digit trace ID
+    pub trace_id: String,
+
+    /// 16 hex digit span ID
+    pub span_id: String,
+
+    /// Parent span ID; `None` for the root span
+    pub parent_span_id: Option<String>,
+
+    /// Span name
+    pub name: String,
+
+    /// Span kind
+    pub kind: SpanKind,
+
+    /// Synthetic start time in Unix nanoseconds
+    pub start_unix_nano: u64,
+
+    /// Synthetic end time in Unix nanoseconds
+    pub end_unix_nano: u64,
+
+    /// Span attributes
+    pub attributes: Attributes,
+
+    /// Span events
+    pub events: Vec<SpanEvent>,
+
+    /// Spans this span is causally linked to
+    pub links: Vec<String>,
+
+    /// Outcome
+    pub status: SpanStatus,
+}
+
+impl Span {
+    /// OTLP-JSON encoding of the span
+    pub fn to_json(&self) -> Value {
+        let mut span = json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": match self.kind {
+                SpanKind::Internal => 1,
+                SpanKind::Client => 3,
+            },
+            "startTimeUnixNano": self.start_unix_nano.to_string(),
+            "endTimeUnixNano": self.end_unix_nano.to_string(),
+            "attributes": attributes_json(&self.attributes),
+            "events": self.events.iter().map(|e| json!({
+                "timeUnixNano": e.time_unix_nano.to_string(),
+                "name": e.name,
+                "attributes": attributes_json(&e.attributes),
+            })).collect::<Vec<_>>(),
+            "links": self.links.iter().map(|span_id| json!({
+                "traceId": self.trace_id,
+                "spanId": span_id,
+            })).collect::<Vec<_>>(),
+            "status": match &self.status {
+                SpanStatus::Unset => json!({}),
+                SpanStatus::Ok => json!({ "code": 1 }),
+                SpanStatus::Error(message) => json!({ "code": 2, "message": message }),
+            },
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = json!(parent);
+        }
+        span
+    }
+}
+
+/// OTLP `KeyValue` list
+fn attributes_json(attributes: &Attributes) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "value": value.to_json() }))
+        .collect()
+}
+
+/// Exports run logs as OTLP-JSON traces
+#[derive(Clone, Debug)]
+pub struct OtlpExporter {
+    /// `service.name` resource attribute
+    service_name: String,
+
+    /// Time of the first event in Unix nanoseconds
+    start_unix_nano: u64,
+
+    /// Time between consecutive events in nanoseconds
+    tick_nanos: u64,
+}
+
+impl Default for OtlpExporter {
+    fn default() -> Self {
+        Self {
+            service_name: "oracle-omen".to_string(),
+            start_unix_nano: 0,
+            tick_nanos: 1_000_000,
+        }
+    }
+}
+
+impl OtlpExporter {
+    /// Create an exporter starting at the Unix epoch with 1ms ticks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `service.name` resource attribute
+    pub fn with_service_name(mut self, name: impl Into<String>) -> Self {
+        self.service_name = name.into();
+        self
+    }
+
+    /// Set the time of the first event
+    pub fn with_start_time(mut self, unix_nano: u64) -> Self {
+        self.start_unix_nano = unix_nano;
+        self
+    }
+
+    /// Set the time between consecutive events
+    pub fn with_tick(mut self, nanos: u64) -> Self {
+        self.tick_nanos = nanos;
+        self
+    }
+
+    /// Build the spans of a run, root first, then in order of their first event
+    pub fn spans(&self, log: &EventLog) -> Vec<Span> {
+        SpanBuilder::new(self, log).build(log)
+    }
+
+    /// OTLP-JSON `TracesData` document for a run
+    pub fn to_json(&self, log: &EventLog) -> Value {
+        let spans: Vec<Value> = self.spans(log).iter().map(Span::to_json).collect();
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [
+                        { "key": "service.name", "value": { "stringValue": self.service_name } },
+                        { "key": "oracle.run_id", "value": { "intValue": log.run_id.to_string() } },
+                    ],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "oracle_omen", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Write a run's trace to a file
+    ///
+    /// The file is written next to the target and renamed into place.
+    pub fn write(&self, log: &EventLog, path: &Path) -> PersistResult<()> {
+        let bytes = serde_json::to_vec_pretty(&self.to_json(log))
+            .map_err(|e| PersistError::Io(e.to_string()))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Trace ID of a run
+fn trace_id(run_id: u64) -> String {
+    Hash::from_canonical(&("otlp_trace", run_id)).to_hex()[..32].to_string()
+}
+
+/// Span ID of the span opened by event `sequence`, or the root span
+fn span_id(run_id: u64, sequence: Option<u64>) -> String {
+    Hash::from_canonical(&("otlp_span", run_id, sequence)).to_hex()[..16].to_string()
+}
+
+/// Single pass over a log, assigning events to spans
+struct SpanBuilder {
+    trace_id: String,
+    run_id: u64,
+    spans: Vec<Span>,
+
+    /// Span of each event, by sequence; index 0 is the root
+    span_of: BTreeMap<u64, usize>,
+
+    /// Spans whose closing event has not been seen
+    open: BTreeSet<usize>,
+
+    /// Synthetic time of each event
+    times: BTreeMap<u64, u64>,
+}
+
+impl SpanBuilder {
+    fn new(exporter: &OtlpExporter, log: &EventLog) -> Self {
+        let trace_id = trace_id(log.run_id);
+        let root = Span {
+            trace_id: trace_id.clone(),
+            span_id: span_id(log.run_id, None),
+            parent_span_id: None,
+            name: format!("run {}", log.run_id),
+            kind: SpanKind::Internal,
+            start_unix_nano: exporter.start_unix_nano,
+            end_unix_nano: exporter.start_unix_nano,
+            attributes: Attributes::new(),
+            events: Vec::new(),
+            links: Vec::new(),
+            status: SpanStatus::Unset,
+        };
+
+        // A tool response ends `duration_ms` after its request
+        let mut times: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut clock = exporter.start_unix_nano;
+        for (i, event) in log.events().iter().enumerate() {
+            if i > 0 {
+                clock = clock.saturating_add(exporter.tick_nanos);
+            }
+            if let (EventPayload::ToolResponse(response), Some(request)) =
+                (&event.payload, event.parent_id)
+            {
+                if let Some(start) = times.get(&request.sequence) {
+                    let end = start.saturating_add(response.duration_ms.saturating_mul(1_000_000));
+                    clock = clock.max(end);
+                }
+            }
+            times.insert(event.id.sequence, clock);
+        }
+
+        Self {
+            trace_id,
+            run_id: log.run_id,
+            spans: vec![root],
+            span_of: BTreeMap::new(),
+            open: BTreeSet::new(),
+            times,
+        }
+    }
+
+    fn build(mut self, log: &EventLog) -> Vec<Span> {
+        for event in log.events() {
+            self.add(event);
+        }
+
+        let end = self
+            .times
+            .values()
+            .max()
+            .copied()
+            .unwrap_or(self.spans[0].start_unix_nano);
+        for &idx in &self.open {
+            let span = &mut self.spans[idx];
+            span.end_unix_nano = end;
+            span.attributes
+                .insert("oracle.incomplete".to_string(), true.into());
+        }
+
+        let root = &mut self.spans[0];
+        root.end_unix_nano = end;
+        root.attributes
+            .insert("oracle.run_id".to_string(), log.run_id.into());
+        root.attributes
+            .insert("oracle.events".to_string(), (log.len() as u64).into());
+        if let Some(hash) = log.events().iter().rev().find_map(|e| e.state_hash_after) {
+            root.attributes
+                .insert("oracle.final_state_hash".to_string(), hash.into());
+        }
+        self.spans
+    }
+
+    fn add(&mut self, event: &Event) {
+        let seq = event.id.sequence;
+        let time = self.times.get(&seq).copied().unwrap_or_default();
+        let containing = event
+            .parent_id
+            .and_then(|p| self.span_of.get(&p.sequence))
+            .copied()
+            .unwrap_or(0);
+
+        match (&event.kind, &event.payload) {
+            (EventKind::AgentInit, EventPayload::AgentInit(init)) => {
+                let root = &mut self.spans[0];
+                root.name = format!("{} run {}", init.agent_type, self.run_id);
+                root.attributes
+                    .insert("agent.type".to_string(), init.agent_type.as_str().into());
+                root.attributes.insert(
+                    "agent.version".to_string(),
+                    init.agent_version.as_str().into(),
+                );
+                self.span_of.insert(seq, 0);
+            }
+            (EventKind::Custom(kind), EventPayload::Raw(data)) if kind == NODE_STARTED => {
+                let node_id = data.get("node_id").map_or("?", String::as_str);
+                let mut attributes = Attributes::new();
+                attributes.insert("node.id".to_string(), node_id.into());
+                if let Some(dag) = data.get("dag") {
+                    attributes.insert("dag.name".to_string(), dag.as_str().into());
+                }
+                let links = if containing == 0 {
+                    Vec::new()
+                } else {
+                    vec![self.spans[containing].span_id.clone()]
+                };
+                let idx = self.open_span(event, time, 0, format!("node {}", node_id), attributes);
+                self.spans[idx].kind = SpanKind::Internal;
+                self.spans[idx].links = links;
+            }
+            (EventKind::Custom(kind), EventPayload::Raw(data))
+                if kind == NODE_COMPLETED && self.open.contains(&containing) =>
+            {
+                let span = &mut self.spans[containing];
+                if let Some(hash) = data.get("output_hash") {
+                    span.attributes
+                        .insert("node.output_hash".to_string(), hash.as_str().into());
+                }
+                span.status = if data.get("success").map(String::as_str) == Some("true") {
+                    SpanStatus::Ok
+                } else {
+                    SpanStatus::Error(data.get("error").cloned().unwrap_or_default())
+                };
+                self.close_span(containing, seq, time);
+            }
+            (EventKind::ToolRequest, EventPayload::ToolRequest(request)) => {
+                let mut attributes = Attributes::new();
+                attributes.insert("tool.name".to_string(), request.tool_name.as_str().into());
+                attributes.insert(
+                    "tool.version".to_string(),
+                    request.tool_version.as_str().into(),
+                );
+                attributes.insert("tool.request_hash".to_string(), request.request_hash.into());
+                let name = format!("tool {}", request.tool_name);
+                let idx = self.open_span(event, time, containing, name, attributes);
+                self.spans[idx].kind = SpanKind::Client;
+            }
+            (EventKind::ToolResponse, EventPayload::ToolResponse(response))
+                if self.open.contains(&containing) =>
+            {
+                let span = &mut self.spans[containing];
+                span.attributes.insert(
+                    "tool.response_hash".to_string(),
+                    response.response_hash.into(),
+                );
+                span.attributes
+                    .insert("tool.success".to_string(), response.success.into());
+                span.attributes
+                    .insert("tool.duration_ms".to_string(), response.duration_ms.into());
+                span.attributes.insert(
+                    "tool.source".to_string(),
+                    format!("{:?}", response.source).to_lowercase().into(),
+                );
+                span.status = if response.success {
+                    SpanStatus::Ok
+                } else {
+                    SpanStatus::Error(response.error.clone().unwrap_or_default())
+                };
+                self.close_span(containing, seq, time);
+            }
+            (_, EventPayload::CapabilityDenied(denied)) => {
+                let mut attributes = event_attributes(event);
+                attributes.insert(
+                    "capability".to_string(),
+                    denied.capability.to_string().into(),
+                );
+                attributes.insert("tool.name".to_string(), denied.tool_name.as_str().into());
+                attributes.insert("reason".to_string(), denied.reason.as_str().into());
+                self.add_event(containing, seq, time, attributes, event);
+            }
+            (_, EventPayload::Error(error)) => {
+                let mut attributes = event_attributes(event);
+                attributes.insert("error.type".to_string(), error.error_type.as_str().into());
+                attributes.insert("error.message".to_string(), error.message.as_str().into());
+                attributes.insert(
+                    "error.component".to_string(),
+                    error.component.as_str().into(),
+                );
+                attributes.insert("error.recoverable".to_string(), error.recoverable.into());
+                self.add_event(containing, seq, time, attributes, event);
+            }
+            _ => {
+                let attributes = event_attributes(event);
+                self.add_event(containing, seq, time, attributes, event);
+            }
+        }
+    }
+
+    /// Open a span at `event` under the span at `parent`
+    fn open_span(
+        &mut self,
+        event: &Event,
+        time: u64,
+        parent: usize,
+        name: String,
+        mut attributes: Attributes,
+    ) -> usize {
+        attributes.extend(event_attributes(event));
+        let idx = self.spans.len();
+        self.spans.push(Span {
+            trace_id: self.trace_id.clone(),
+            span_id: span_id(self.run_id, Some(event.id.sequence)),
+            parent_span_id: Some(self.spans[parent].span_id.clone()),
+            name,
+            kind: SpanKind::Internal,
+            start_unix_nano: time,
+            end_unix_nano: time,
+            attributes,
+            events: Vec::new(),
+            links: Vec::new(),
+            status: SpanStatus::Unset,
+        });
+        self.span_of.insert(event.id.sequence, idx);
+        self.open.insert(idx);
+        idx
+    }
+
+    /// Close a span at event `seq`
+    fn close_span(&mut self, idx: usize, seq: u64, time: u64) {
+        self.spans[idx].end_unix_nano = time;
+        self.span_of.insert(seq, idx);
+        self.open.remove(&idx);
+    }
+
+    /// Record `event` as a span event on the span at `idx`
+    fn add_event(
+        &mut self,
+        idx: usize,
+        seq: u64,
+        time: u64,
+        attributes: Attributes,
+        event: &Event,
+    ) {
+        self.spans[idx].events.push(SpanEvent {
+            name: event.kind.as_str().to_string(),
+            time_unix_nano: time,
+            attributes,
+        });
+        self.span_of.insert(seq, idx);
+    }
+}
+
+/// Sequence and hashes of an event
+fn event_attributes(event: &Event) -> Attributes {
+    let mut attributes = Attributes::new();
+    attributes.insert("oracle.sequence".to_string(), event.id.sequence.into());
+    attributes.insert("oracle.event_hash".to_string(), event.content_hash().into());
+    attributes.insert("oracle.payload_hash".to_string(), event.payload_hash.into());
+    if let Some(hash) = event.state_hash_after {
+        attributes.insert("oracle.state_hash_after".to_string(), hash.into());
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::capability::Capability;
+    use oracle_omen_core::event::{
+        CapabilityDeniedPayload, ToolRequestPayload, ToolResponsePayload,
+    };
+    use oracle_omen_core::serde_utils::StableMap;
+    use oracle_omen_core::tool::ResponseSource;
+
+    fn raw(pairs: &[(&str, &str)]) -> EventPayload {
+        let mut data = StableMap::new();
+        for (k, v) in pairs {
+            data.insert(k.to_string(), v.to_string());
+        }
+        EventPayload::Raw(data)
+    }
+
+    /// A node that calls a tool, then a tool call denied up front
+    fn sample_log() -> EventLog {
+        let mut log = EventLog::new(7);
+        let started = log
+            .append_next(
+                EventKind::Custom(NODE_STARTED.to_string()),
+                raw(&[("dag", "main"), ("node_id", "fetch")]),
+                None,
+            )
+            .unwrap();
+        let request = log
+            .append_next(
+                EventKind::ToolRequest,
+                EventPayload::ToolRequest(ToolRequestPayload {
+                    tool_name: "http_get".to_string(),
+                    tool_version: "1.0.0".to_string(),
+                    request_hash: Hash::from_str("request"),
+                    capabilities: Vec::new(),
+                    input: "{}".to_string(),
+                }),
+                Some(started),
+            )
+            .unwrap();
+        log.append_next(
+            EventKind::ToolResponse,
+            EventPayload::ToolResponse(ToolResponsePayload {
+                tool_name: "http_get".to_string(),
+                request_hash: Hash::from_str("request"),
+                response_hash: Hash::from_str("response"),
+                output: "{}".to_string(),
+                success: true,
+                error: None,
+                duration_ms: 250,
+                source: ResponseSource::Tool,
+                raw_hash: None,
+            }),
+            Some(request),
+        )
+        .unwrap();
+        let completed = log
+            .append_next(
+                EventKind::Custom(NODE_COMPLETED.to_string()),
+                raw(&[("node_id", "fetch"), ("success", "true")]),
+                Some(started),
+            )
+            .unwrap();
+        log.append_next(
+            EventKind::CapabilityDenied,
+            EventPayload::CapabilityDenied(CapabilityDeniedPayload {
+                capability: Capability::new("fs:write:/etc"),
+                tool_name: "writer".to_string(),
+                reason: "not granted".to_string(),
+            }),
+            Some(completed),
+        )
+        .unwrap();
+        log
+    }
+
+    #[test]
+    fn test_spans_follow_parent_links() {
+        let spans = OtlpExporter::new().spans(&sample_log());
+        assert_eq!(spans.len(), 3);
+        let (root, node, tool) = (&spans[0], &spans[1], &spans[2]);
+
+        assert_eq!(root.parent_span_id, None);
+        assert_eq!(node.parent_span_id.as_ref(), Some(&root.span_id));
+        assert_eq!(tool.parent_span_id.as_ref(), Some(&node.span_id));
+        assert_eq!(tool.kind, SpanKind::Client);
+        assert_eq!(tool.status, SpanStatus::Ok);
+
+        // The tool span lasts exactly duration_ms
+        assert_eq!(tool.end_unix_nano - tool.start_unix_nano, 250_000_000);
+        assert!(node.end_unix_nano > tool.end_unix_nano);
+        assert_eq!(
+            tool.attributes.get("tool.response_hash"),
+            Some(&AttrValue::String(Hash::from_str("response").to_hex()))
+        );
+
+        // The denial's parent is the node completion, so it lands on the node span
+        assert_eq!(node.events.len(), 1);
+        assert_eq!(node.events[0].name, "capability_denied");
+        assert!(root.events.is_empty());
+    }
+
+    #[test]
+    fn test_export_is_deterministic() {
+        let dir = std::env::temp_dir().join(format!("oracle_omen_otlp_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("trace.json");
+
+        let exporter = OtlpExporter::new().with_start_time(1_700_000_000_000_000_000);
+        exporter.write(&sample_log(), &path).unwrap();
+        let first = fs::read(&path).unwrap();
+        exporter.write(&sample_log(), &path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), first);
+
+        let doc: Value = serde_json::from_slice(&first).unwrap();
+        let spans = &doc["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans.as_array().map(Vec::len), Some(3));
+        assert_eq!(spans[0]["traceId"].as_str().map(str::len), Some(32));
+        assert_eq!(spans[1]["spanId"].as_str().map(str::len), Some(16));
+        assert_eq!(spans[0]["startTimeUnixNano"], "1700000000000000000");
+        assert!(spans[0].get("parentSpanId").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
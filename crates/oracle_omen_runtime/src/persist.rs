@@ -0,0 +1,317 @@
+//! Run persistence - event logs on disk.
+//!
+//! Layout under the data directory:
+//!
+//! ```text
+//! runs/<run_id>/events.jsonl                  one JSON event per line
+//! runs/<run_id>/snapshots/<position>.json     state after `position` events
+//! ```
+//!
+//! Loading re-appends every event to a fresh [`EventLog`], so sequence,
+//! parent and payload hash checks apply to persisted logs too.
+
+use oracle_omen_core::event::{Event, EventLog};
+use oracle_omen_core::replay::{Snapshot, SnapshotManager};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Persistence result
+pub type PersistResult<T> = Result<T, PersistError>;
+
+/// Persistence errors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PersistError {
+    /// Run does not exist
+    RunNotFound(u64),
+
+    /// IO error
+    Io(String),
+
+    /// Line could not be parsed as an event
+    Parse {
+        /// One-based line number (1 for snapshot files)
+        line: usize,
+        /// Parser error
+        message: String,
+    },
+
+    /// Events parsed but do not form a valid log
+    Corrupted {
+        /// One-based line of the event that failed
+        line: usize,
+        /// Why the event was rejected
+        message: String,
+    },
+
+    /// Name that cannot be used as a single path component
+    UnsafePath(String),
+}
+
+impl std::fmt::Display for PersistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistError::RunNotFound(run_id) => write!(f, "Run not found: {}", run_id),
+            PersistError::Io(msg) => write!(f, "IO error: {}", msg),
+            PersistError::Parse { line, message } => {
+                write!(f, "Invalid event at line {}: {}", line, message)
+            }
+            PersistError::Corrupted { line, message } => {
+                write!(f, "Corrupted log at line {}: {}", line, message)
+            }
+            PersistError::UnsafePath(name) => write!(f, "Unsafe path component: {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<std::io::Error> for PersistError {
+    fn from(e: std::io::Error) -> Self {
+        PersistError::Io(e.to_string())
+    }
+}
+
+/// Store of persisted runs under a data directory
+#[derive(Clone, Debug)]
+pub struct RunStore {
+    /// Data directory root
+    root: PathBuf,
+}
+
+impl RunStore {
+    /// Create a store rooted at `data_dir`
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            root: data_dir.into(),
+        }
+    }
+
+    /// Get the data directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding a run's files
+    pub fn run_dir(&self, run_id: u64) -> PathBuf {
+        self.root.join("runs").join(run_id.to_string())
+    }
+
+    /// Path of a run's event log
+    pub fn events_path(&self, run_id: u64) -> PathBuf {
+        self.run_dir(run_id).join("events.jsonl")
+    }
+
+    /// Check whether a run has a persisted log
+    pub fn exists(&self, run_id: u64) -> bool {
+        self.events_path(run_id).is_file()
+    }
+
+    /// List persisted run IDs in ascending order
+    pub fn list_runs(&self) -> PersistResult<Vec<u64>> {
+        let runs_dir = self.root.join("runs");
+        if !runs_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut runs: Vec<u64> = fs::read_dir(runs_dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .filter(|run_id| self.exists(*run_id))
+            .collect();
+        runs.sort_unstable();
+        Ok(runs)
+    }
+
+    /// Write a whole log, replacing any existing one
+    ///
+    /// The file is written next to the target and renamed into place.
+    pub fn save_log(&self, log: &EventLog) -> PersistResult<()> {
+        let path = self.events_path(log.run_id);
+        fs::create_dir_all(self.run_dir(log.run_id))?;
+
+        let tmp = path.with_extension("jsonl.tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            for event in log.events() {
+                writeln!(file, "{}", encode(event)?)?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Append one event to a run's log
+    pub fn append_event(&self, event: &Event) -> PersistResult<()> {
+        fs::create_dir_all(self.run_dir(event.id.run_id))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.events_path(event.id.run_id))?;
+        writeln!(file, "{}", encode(event)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Load and verify a run's log
+    pub fn load_log(&self, run_id: u64) -> PersistResult<EventLog> {
+        let path = self.events_path(run_id);
+        if !path.is_file() {
+            return Err(PersistError::RunNotFound(run_id));
+        }
+
+        let mut log = EventLog::new(run_id);
+        for (idx, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Event = serde_json::from_str(&line).map_err(|e| PersistError::Parse {
+                line: idx + 1,
+                message: e.to_string(),
+            })?;
+            log.append(event).map_err(|e| PersistError::Corrupted {
+                line: idx + 1,
+                message: e.to_string(),
+            })?;
+        }
+        Ok(log)
+    }
+
+    /// Directory holding a run's snapshots
+    pub fn snapshots_dir(&self, run_id: u64) -> PathBuf {
+        self.run_dir(run_id).join("snapshots")
+    }
+
+    /// Write a snapshot, replacing any at the same position
+    pub fn save_snapshot(&self, snapshot: &Snapshot) -> PersistResult<()> {
+        let dir = self.snapshots_dir(snapshot.run_id);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.json", snapshot.position));
+        let tmp = path.with_extension("json.tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            let json =
+                serde_json::to_vec(snapshot).map_err(|e| PersistError::Io(e.to_string()))?;
+            file.write_all(&json)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Load a run's snapshots
+    ///
+    /// A run without snapshots yields an empty manager.
+    pub fn load_snapshots(&self, run_id: u64) -> PersistResult<SnapshotManager> {
+        let mut manager = SnapshotManager::new();
+        let dir = self.snapshots_dir(run_id);
+        if !dir.is_dir() {
+            return Ok(manager);
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let snapshot: Snapshot = serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+                PersistError::Parse {
+                    line: 1,
+                    message: format!("{}: {}", path.display(), e),
+                }
+            })?;
+            manager.add(snapshot);
+        }
+        Ok(manager)
+    }
+}
+
+/// Encode an event as a single JSON line
+fn encode(event: &Event) -> PersistResult<String> {
+    serde_json::to_string(event).map_err(|e| PersistError::Io(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oracle_omen_core::event::{EventKind, EventPayload};
+    use oracle_omen_core::serde_utils::StableMap;
+
+    fn temp_store(name: &str) -> RunStore {
+        let dir = std::env::temp_dir().join(format!(
+            "oracle-omen-persist-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        RunStore::new(dir)
+    }
+
+    fn sample_log(run_id: u64, len: usize) -> EventLog {
+        let mut log = EventLog::new(run_id);
+        for i in 0..len {
+            let mut data = StableMap::new();
+            data.insert("i".to_string(), i.to_string());
+            let parent = log.last().map(|e| e.id);
+            log.append_next(
+                EventKind::Custom("test".to_string()),
+                EventPayload::Raw(data),
+                parent,
+            )
+            .unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn test_save_and_load_log() {
+        let store = temp_store("save");
+        let log = sample_log(3, 4);
+        store.save_log(&log).unwrap();
+
+        let loaded = store.load_log(3).unwrap();
+        assert_eq!(loaded.events(), log.events());
+        assert_eq!(store.list_runs().unwrap(), vec![3]);
+        assert_eq!(store.load_log(9).unwrap_err(), PersistError::RunNotFound(9));
+    }
+
+    #[test]
+    fn test_append_event() {
+        let store = temp_store("append");
+        let log = sample_log(5, 3);
+        for event in log.events() {
+            store.append_event(event).unwrap();
+        }
+        assert_eq!(store.load_log(5).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_load_reports_line() {
+        let store = temp_store("corrupt");
+        store.save_log(&sample_log(1, 2)).unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(store.events_path(1))
+            .unwrap();
+        writeln!(file, "not json").unwrap();
+
+        let err = store.load_log(1).unwrap_err();
+        assert!(matches!(err, PersistError::Parse { line: 3, .. }));
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let store = temp_store("snapshots");
+        assert!(store.load_snapshots(2).unwrap().positions().is_empty());
+
+        let state = oracle_omen_core::state::AgentState::with_run_id(2);
+        store.save_snapshot(&Snapshot::new("s10", 2, 10, state.clone())).unwrap();
+        store.save_snapshot(&Snapshot::new("s4", 2, 4, state)).unwrap();
+
+        let snapshots = store.load_snapshots(2).unwrap();
+        assert_eq!(snapshots.positions(), vec![4, 10]);
+        assert_eq!(snapshots.get_snapshot_before(9).map(|s| s.id.as_str()), Some("s4"));
+    }
+}
@@ -0,0 +1,529 @@
+//! Subprocess tool.
+//!
+//! Runs one whitelisted binary per tool. The binary, argv template, working
+//! directory and passed-through environment are fixed when the tool is built;
+//! a call only fills template placeholders and supplies stdin.
+//!
+//! Required capabilities:
+//! - `process:exec:<binary>` to run the binary (`process:exec:*` grants all)
+//! - `env:read:<VAR>` for every environment variable passed through
+//!
+//! The child starts with an empty environment. Calls that run longer than
+//! `ResourceBounds::timeout_ms` are killed, and stdout and stderr are each
+//! cut at `ResourceBounds::max_memory_bytes`. Output still open at the
+//! deadline, e.g. held by a background grandchild, is returned as read so
+//! far and marked truncated.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use oracle_omen_core::{
+    capability::Capability,
+    tool::{
+        Determinism, ExecutionContext, ResourceBounds, SideEffect, Tool, ToolError, ToolId,
+        ToolResult,
+    },
+};
+use serde_json::{json, Map, Value};
+
+/// Output schema shared by all process tools
+const OUTPUT_SCHEMA: &str = r#"{
+    "type": "object",
+    "properties": {
+        "exit_code": {"type": ["integer", "null"]},
+        "success": {"type": "boolean"},
+        "stdout": {"type": "string"},
+        "stderr": {"type": "string"},
+        "truncated": {"type": "boolean"}
+    },
+    "required": ["exit_code", "success", "stdout", "stderr", "truncated"],
+    "additionalProperties": false
+}"#;
+
+/// How often a running child is polled for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Runs a local binary as a tool
+///
+/// Argument templates may contain `{field}` placeholders, which are replaced
+/// with the matching top-level field of the JSON input. Each template becomes
+/// exactly one argument; nothing is passed through a shell. The optional
+/// `stdin` input field is written to the child's stdin.
+///
+/// Output is `{"exit_code", "success", "stdout", "stderr", "truncated"}`.
+/// `exit_code` is null if the child was killed by a signal.
+pub struct ProcessTool {
+    id: ToolId,
+    binary: PathBuf,
+    args: Vec<String>,
+    env: BTreeMap<String, Option<String>>,
+    working_dir: Option<PathBuf>,
+    bounds: ResourceBounds,
+    capabilities: Vec<Capability>,
+    input_schema: String,
+}
+
+impl ProcessTool {
+    /// Default per-stream output cap when the bounds set none
+    pub const DEFAULT_MAX_OUTPUT_BYTES: u64 = 1024 * 1024;
+
+    /// Create a tool running `binary` with no arguments
+    pub fn new(id: ToolId, binary: impl Into<PathBuf>) -> Self {
+        let mut tool = Self {
+            id,
+            binary: binary.into(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            working_dir: None,
+            bounds: ResourceBounds::default(),
+            capabilities: Vec::new(),
+            input_schema: String::new(),
+        };
+        tool.refresh();
+        tool
+    }
+
+    /// Set the argument templates
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self.refresh();
+        self
+    }
+
+    /// Pass an environment variable through, requiring `env:read:<name>`
+    pub fn with_env(mut self, name: impl Into<String>) -> Self {
+        self.env.insert(name.into(), None);
+        self.refresh();
+        self
+    }
+
+    /// Set an environment variable for the child instead of passing the
+    /// host's value, requiring `env:read:<name>`
+    pub fn with_env_value(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(name.into(), Some(value.into()));
+        self.refresh();
+        self
+    }
+
+    /// Set the working directory
+    pub fn with_working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Set resource bounds
+    pub fn with_resource_bounds(mut self, bounds: ResourceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Get the binary path
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Capability needed to run `binary`
+    pub fn exec_capability(binary: &Path) -> Capability {
+        Capability::new(format!("process:exec:{}", binary.display()))
+    }
+
+    /// Rebuild capabilities and input schema after a builder change
+    fn refresh(&mut self) {
+        self.capabilities = std::iter::once(Self::exec_capability(&self.binary))
+            .chain(
+                self.env
+                    .keys()
+                    .map(|name| Capability::new(format!("env:read:{}", name))),
+            )
+            .collect();
+
+        let fields: BTreeSet<&str> = self.args.iter().flat_map(|a| placeholders(a)).collect();
+        let mut properties = Map::new();
+        for field in &fields {
+            properties.insert(
+                field.to_string(),
+                json!({"type": ["string", "number", "boolean"]}),
+            );
+        }
+        properties.insert("stdin".to_string(), json!({"type": "string"}));
+        self.input_schema = json!({
+            "type": "object",
+            "properties": properties,
+            "required": fields,
+            "additionalProperties": false,
+        })
+        .to_string();
+    }
+
+    /// Largest number of bytes kept per stream
+    fn max_output(&self) -> u64 {
+        self.bounds
+            .max_memory_bytes
+            .unwrap_or(Self::DEFAULT_MAX_OUTPUT_BYTES)
+    }
+
+    /// Build argv from the templates and the input fields
+    fn render_args(&self, input: &Map<String, Value>) -> ToolResult<Vec<String>> {
+        self.args
+            .iter()
+            .map(|template| render(template, input))
+            .collect::<Result<_, _>>()
+            .map_err(|reason| ToolError::InvalidInput {
+                tool: self.id.as_str(),
+                reason,
+            })
+    }
+
+    fn failed(&self, reason: impl ToString) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.id.as_str(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Wait for the child, killing it at the deadline
+    fn wait(&self, child: &mut Child, deadline: Instant) -> ToolResult<ExitStatus> {
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => return Ok(status),
+                Ok(None) if Instant::now() >= deadline => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(ToolError::Timeout {
+                        tool: self.id.as_str(),
+                        duration_ms: self.bounds.timeout_ms,
+                    });
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(self.failed(e)),
+            }
+        }
+    }
+}
+
+impl Tool for ProcessTool {
+    fn id(&self) -> &ToolId {
+        &self.id
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Impure
+    }
+
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        &self.bounds
+    }
+
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        let invalid = |reason: String| ToolError::InvalidInput {
+            tool: self.id.as_str(),
+            reason,
+        };
+        let input = match serde_json::from_slice::<Value>(input) {
+            Ok(Value::Object(obj)) => obj,
+            Ok(_) => return Err(invalid("expected a JSON object".to_string())),
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        let args = self.render_args(&input)?;
+        let stdin = match input.get("stdin") {
+            Some(Value::String(s)) => s.clone().into_bytes(),
+            Some(_) => return Err(invalid("stdin must be a string".to_string())),
+            None => Vec::new(),
+        };
+
+        let mut command = Command::new(&self.binary);
+        command
+            .args(&args)
+            .env_clear()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (name, value) in &self.env {
+            match value {
+                Some(value) => {
+                    command.env(name, value);
+                }
+                None => {
+                    if let Some(value) = std::env::var_os(name) {
+                        command.env(name, value);
+                    }
+                }
+            }
+        }
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        let deadline = Instant::now() + self.bounds.timeout();
+        let mut child = command.spawn().map_err(|e| self.failed(e))?;
+        if let Some(mut pipe) = child.stdin.take() {
+            thread::spawn(move || {
+                // The child may exit without reading; a broken pipe is fine.
+                let _ = pipe.write_all(&stdin);
+            });
+        }
+        let max = self.max_output();
+        let stdout = child.stdout.take().map(|pipe| capture(pipe, max));
+        let stderr = child.stderr.take().map(|pipe| capture(pipe, max));
+
+        // The writer and readers are never waited on past the deadline: a
+        // grandchild may hold the pipes open after the child exits.
+        let status = self.wait(&mut child, deadline)?;
+        let (stdout, stdout_cut) = collect(stdout, deadline);
+        let (stderr, stderr_cut) = collect(stderr, deadline);
+
+        let output = json!({
+            "exit_code": status.code(),
+            "success": status.success(),
+            "stdout": String::from_utf8_lossy(&stdout),
+            "stderr": String::from_utf8_lossy(&stderr),
+            "truncated": stdout_cut || stderr_cut,
+        });
+        serde_json::to_vec(&output).map_err(|e| ToolError::SerializationFailed {
+            tool: self.id.as_str(),
+            reason: e.to_string(),
+        })
+    }
+
+    fn input_schema(&self) -> &str {
+        &self.input_schema
+    }
+
+    fn output_schema(&self) -> &str {
+        OUTPUT_SCHEMA
+    }
+}
+
+/// Placeholder names in an argument template
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                names.push(&after[..end]);
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+/// Fill the placeholders of one template
+///
+/// The template is scanned once, left to right, so placeholder text inside
+/// an input value is copied as is.
+fn render(template: &str, input: &Map<String, Value>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                out.push_str(&rest[..start]);
+                out.push_str(&field(input, &after[..end])?);
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Argument text for one input field
+fn field(input: &Map<String, Value>, name: &str) -> Result<String, String> {
+    match input.get(name) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(v @ (Value::Number(_) | Value::Bool(_))) => Ok(v.to_string()),
+        Some(_) => Err(format!(
+            "field {} must be a string, number or boolean",
+            name
+        )),
+        None => Err(format!("missing field {}", name)),
+    }
+}
+
+/// Bytes read from a stream so far, and whether any were dropped
+type Captured = Arc<Mutex<(Vec<u8>, bool)>>;
+
+/// A stream being read on its own thread
+struct Capture {
+    output: Captured,
+    done: Receiver<()>,
+}
+
+/// Read a stream on a thread, keeping at most `max` bytes
+///
+/// The rest is drained so the child never blocks on a full pipe.
+fn capture(mut pipe: impl Read + Send + 'static, max: u64) -> Capture {
+    let output = Captured::default();
+    let (finished, done) = mpsc::channel();
+    let shared = Arc::clone(&output);
+    thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            let n = match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            let mut out = shared.lock().unwrap_or_else(PoisonError::into_inner);
+            let room = max.saturating_sub(out.0.len() as u64).min(n as u64) as usize;
+            out.0.extend_from_slice(&chunk[..room]);
+            out.1 |= room < n;
+        }
+        let _ = finished.send(());
+    });
+    Capture { output, done }
+}
+
+/// Collect a captured stream, waiting for its end until `deadline`
+///
+/// A stream still open at the deadline is cut at what was read.
+fn collect(capture: Option<Capture>, deadline: Instant) -> (Vec<u8>, bool) {
+    let Some(capture) = capture else {
+        return Default::default();
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    let ended = capture.done.recv_timeout(remaining).is_ok();
+    let mut out = capture
+        .output
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    (std::mem::take(&mut out.0), out.1 || !ended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ToolInvoker, ToolRegistry};
+    use oracle_omen_core::{
+        capability::CapabilitySet,
+        event::{EventKind, EventLog},
+    };
+    use std::sync::Arc;
+
+    fn run(tool: &ProcessTool, input: &str) -> ToolResult<Value> {
+        let out = tool.execute(input.as_bytes(), &ExecutionContext::new(0, 1))?;
+        Ok(serde_json::from_slice(&out).unwrap())
+    }
+
+    #[test]
+    fn test_args_stdin_and_env() {
+        // HOME is not passed through, so the child sees it unset
+        let tool = ProcessTool::new(ToolId::new("sh", "1.0.0"), "/bin/sh")
+            .with_args([
+                "-c",
+                "echo \"$0 $ORACLE_OMEN_PROCESS_TEST $HOME\"; cat; echo err >&2; exit 3",
+                "{name}",
+            ])
+            .with_env_value("ORACLE_OMEN_PROCESS_TEST", "visible");
+        assert_eq!(
+            tool.required_capabilities(),
+            &[
+                Capability::new("process:exec:/bin/sh"),
+                Capability::new("env:read:ORACLE_OMEN_PROCESS_TEST"),
+            ]
+        );
+
+        let out = run(&tool, r#"{"name": "hi", "stdin": "piped"}"#).unwrap();
+        assert_eq!(out["stdout"], "hi visible \npiped");
+        assert_eq!(out["stderr"], "err\n");
+        assert_eq!(out["exit_code"], 3);
+        assert_eq!(out["success"], false);
+        assert_eq!(out["truncated"], false);
+
+        assert!(matches!(
+            run(&tool, "{}"),
+            Err(ToolError::InvalidInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_input_placeholders_are_not_rendered() {
+        let tool = ProcessTool::new(ToolId::new("echo", "1.0.0"), "/bin/echo")
+            .with_args(["{a}-{b}", "{b}"]);
+        let out = run(&tool, r#"{"a": "{b}", "b": "x"}"#).unwrap();
+        assert_eq!(out["stdout"], "{b}-x x\n");
+    }
+
+    #[test]
+    fn test_timeout_and_output_cap() {
+        let tool = ProcessTool::new(ToolId::new("sleep", "1.0.0"), "/bin/sh")
+            .with_args(["-c", "sleep 5"])
+            .with_resource_bounds(ResourceBounds::with_timeout(100));
+        let start = Instant::now();
+        assert!(matches!(run(&tool, "{}"), Err(ToolError::Timeout { .. })));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let bounds = ResourceBounds {
+            max_memory_bytes: Some(4),
+            ..ResourceBounds::with_timeout(5_000)
+        };
+        let tool = ProcessTool::new(ToolId::new("echo", "1.0.0"), "/bin/echo")
+            .with_args(["abcdefgh"])
+            .with_resource_bounds(bounds);
+        let out = run(&tool, "{}").unwrap();
+        assert_eq!(out["stdout"], "abcd");
+        assert_eq!(out["truncated"], true);
+    }
+
+    #[test]
+    fn test_background_grandchild_does_not_block() {
+        let tool = ProcessTool::new(ToolId::new("sh", "1.0.0"), "/bin/sh")
+            .with_args(["-c", "sleep 30 & echo hi"])
+            .with_resource_bounds(ResourceBounds::with_timeout(300));
+        let start = Instant::now();
+        let out = run(&tool, "{}").unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(out["stdout"], "hi\n");
+        assert_eq!(out["success"], true);
+        assert_eq!(out["truncated"], true);
+    }
+
+    #[test]
+    fn test_missing_grant_is_denied() {
+        let tool = ProcessTool::new(ToolId::new("echo", "1.0.0"), "/bin/echo").with_args(["hi"]);
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(tool)).unwrap();
+        let id = ToolId::new("echo", "1.0.0");
+
+        let mut invoker = ToolInvoker::new(registry.clone(), CapabilitySet::empty());
+        let mut log = EventLog::new(1);
+        assert!(matches!(
+            invoker.invoke(&mut log, &id, "{}", None),
+            Err(ToolError::Denied { .. })
+        ));
+        assert_eq!(log.last().unwrap().kind, EventKind::CapabilityDenied);
+
+        let grants = CapabilitySet::new([Capability::new("process:exec:*")]);
+        let mut invoker = ToolInvoker::new(registry, grants);
+        let mut log = EventLog::new(1);
+        let response = invoker.invoke(&mut log, &id, "{}", None).unwrap();
+        let out: Value = serde_json::from_str(&response.data).unwrap();
+        assert_eq!(out["stdout"], "hi\n");
+        assert_eq!(log.last().unwrap().kind, EventKind::ToolResponse);
+    }
+}
@@ -1,100 +1,603 @@
 //! Scheduler for DAG execution with backpressure.
+//!
+//! Nodes run concurrently up to `max_concurrent`, but results are committed
+//! in a fixed order: by topological rank, then node ID. The committed
+//! sequence is therefore identical for any concurrency level.
+//!
+//! Admission is resource-aware: a node starts only when its declared CPU
+//! units and memory fit the remaining [`ResourcePool`], and exclusive nodes
+//! run alone. Impure nodes start only at the head of the commit order, after
+//! their `Started` commit, so a crash never hides a side effect.
+//!
+//! A failed node's transitive dependents never run. They are committed as
+//! [`Commit::Skipped`] in their place in the order, so independent branches
+//! carry on.
 
-use crate::executor::{ExecResult, ExecError};
-use oracle_omen_plan::dag::Dag;
-use std::collections::{HashMap, VecDeque};
+use crate::executor::{ExecError, ExecResult, NodeResult};
+use async_trait::async_trait;
+use oracle_omen_core::tool::SideEffect;
+use oracle_omen_plan::dag::{Dag, DagNode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Default number of times a blocked node may be overtaken
+pub const DEFAULT_MAX_BYPASS: u32 = 4;
+
+/// Executes individual DAG nodes on behalf of the scheduler
+#[async_trait]
+pub trait NodeRunner: Send + Sync {
+    /// Run a node to completion
+    async fn run(&self, node: &DagNode) -> NodeResult;
+
+    /// Side effects of running a node
+    ///
+    /// Defaults to the node's declared `side_effect`. Impure nodes never
+    /// overlap a node with uncommitted predecessors.
+    fn side_effect(&self, node: &DagNode) -> SideEffect {
+        node.side_effect
+    }
+}
+
+/// A step in the committed execution order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Commit {
+    /// Node reached the head of the commit order
+    Started(String),
+
+    /// Node finished and its result is committed
+    Finished(NodeResult),
+
+    /// Node was not run because a node it depends on failed
+    Skipped {
+        /// Skipped node
+        node_id: String,
+        /// Failed node it transitively depends on
+        cause: String,
+    },
+}
+
+/// Whether to keep going after a commit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitFlow {
+    /// Continue committing and dispatching
+    Continue,
+
+    /// Stop; results not yet committed are discarded
+    Stop,
+}
+
+/// Resource budget shared by running nodes
+///
+/// `None` means the resource is not limited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourcePool {
+    /// Total CPU units
+    pub cpu_units: Option<u64>,
+
+    /// Total memory in bytes
+    pub memory_bytes: Option<u64>,
+}
+
+impl ResourcePool {
+    /// Create an unlimited pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit total CPU units
+    pub fn with_cpu_units(mut self, cpu_units: u64) -> Self {
+        self.cpu_units = Some(cpu_units);
+        self
+    }
+
+    /// Limit total memory
+    pub fn with_memory_bytes(mut self, memory_bytes: u64) -> Self {
+        self.memory_bytes = Some(memory_bytes);
+        self
+    }
+}
+
+/// Resources a node holds while running
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Demand {
+    cpu_units: u64,
+    memory_bytes: u64,
+    exclusive: bool,
+    impure: bool,
+}
+
+impl Demand {
+    /// Undeclared CPU and memory are not charged
+    fn of(node: &DagNode) -> Self {
+        Self {
+            cpu_units: node.resources.cpu_units.unwrap_or(0),
+            memory_bytes: node.resources.max_memory_bytes.unwrap_or(0),
+            exclusive: node.resources.exclusive,
+            impure: false,
+        }
+    }
+}
+
+/// Why a ready node was not started
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WaitReason {
+    /// All concurrency slots are taken
+    Concurrency {
+        /// Maximum concurrent nodes
+        limit: usize,
+    },
+
+    /// An exclusive node is running
+    ExclusiveRunning {
+        /// The running exclusive node
+        node: String,
+    },
+
+    /// Node is exclusive and waits for running nodes to drain
+    NeedsExclusive {
+        /// Nodes still running
+        running: usize,
+    },
+
+    /// Not enough CPU units left
+    Cpu {
+        /// CPU units the node declares
+        requested: u64,
+        /// CPU units left in the pool
+        available: u64,
+    },
+
+    /// Not enough memory left
+    Memory {
+        /// Bytes the node declares
+        requested: u64,
+        /// Bytes left in the pool
+        available: u64,
+    },
+
+    /// Held back so an earlier, overtaken node can start
+    Reserved {
+        /// The overtaken node the resources are held for
+        for_node: String,
+    },
+
+    /// Impure node waiting for earlier nodes to commit
+    AwaitingCommit {
+        /// Node at the head of the commit order
+        head: String,
+    },
+}
+
+impl fmt::Display for WaitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitReason::Concurrency { limit } => {
+                write!(f, "all {} slots busy", limit)
+            }
+            WaitReason::ExclusiveRunning { node } => {
+                write!(f, "exclusive node {} is running", node)
+            }
+            WaitReason::NeedsExclusive { running } => {
+                write!(f, "exclusive, waiting for {} running nodes", running)
+            }
+            WaitReason::Cpu {
+                requested,
+                available,
+            } => {
+                write!(f, "needs {} cpu units, {} available", requested, available)
+            }
+            WaitReason::Memory {
+                requested,
+                available,
+            } => {
+                write!(f, "needs {} bytes, {} available", requested, available)
+            }
+            WaitReason::Reserved { for_node } => {
+                write!(f, "resources reserved for {}", for_node)
+            }
+            WaitReason::AwaitingCommit { head } => {
+                write!(f, "impure, waiting for {} to commit", head)
+            }
+        }
+    }
+}
+
+/// Outcome of an admission check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Admission {
+    /// Node was started
+    Admitted,
+
+    /// Node stays ready but was not started
+    Waiting(WaitReason),
+}
+
+/// An admission decision, in the order it was made
+///
+/// Decisions depend on completion timing, so they are kept out of the event
+/// log and reported through `tracing` and [`Scheduler::decisions`] instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdmissionDecision {
+    /// Node the decision applies to
+    pub node_id: String,
+
+    /// What was decided
+    pub admission: Admission,
+}
 
 /// Scheduler for DAG execution
 pub struct Scheduler {
-    /// Ready to execute (dependencies satisfied)
-    ready: VecDeque<String>,
+    /// Commit order: nodes sorted by (rank, id)
+    order: Vec<String>,
+
+    /// Position of each node in the commit order
+    position: BTreeMap<String, usize>,
+
+    /// Position of the next node to commit
+    head: usize,
+
+    /// Ready to execute (dependencies satisfied), by commit position
+    ready: BTreeSet<usize>,
 
     /// Pending (waiting for dependencies)
-    pending: HashMap<String, Vec<String>>,
+    pending: BTreeMap<String, BTreeSet<String>>,
+
+    /// Dependents of each node
+    dependents: BTreeMap<String, BTreeSet<String>>,
 
     /// Currently executing
-    running: HashMap<String, RunningTask>,
+    running: BTreeMap<String, RunningTask>,
+
+    /// Dependents of failed nodes, with the failed node
+    skipped: BTreeMap<String, String>,
+
+    /// Number of nodes dispatched so far
+    dispatched: u64,
 
     /// Maximum concurrent tasks
     max_concurrent: usize,
+
+    /// Resource budget
+    pool: ResourcePool,
+
+    /// Declared demand of each node
+    demand: BTreeMap<String, Demand>,
+
+    /// Resources held by running nodes
+    in_use: Demand,
+
+    /// Times each blocked node has been overtaken
+    bypassed: BTreeMap<String, u32>,
+
+    /// Overtakes allowed before a blocked node reserves the pool
+    max_bypass: u32,
+
+    /// Last wait reason recorded per node
+    waiting: BTreeMap<String, WaitReason>,
+
+    /// Admission decisions in order
+    decisions: Vec<AdmissionDecision>,
 }
 
 impl Scheduler {
     /// Create a new scheduler
+    ///
+    /// A `max_concurrent` of zero is treated as one.
     pub fn new(max_concurrent: usize) -> Self {
         Self {
-            ready: VecDeque::new(),
-            pending: HashMap::new(),
-            running: HashMap::new(),
-            max_concurrent,
+            order: Vec::new(),
+            position: BTreeMap::new(),
+            head: 0,
+            ready: BTreeSet::new(),
+            pending: BTreeMap::new(),
+            dependents: BTreeMap::new(),
+            running: BTreeMap::new(),
+            skipped: BTreeMap::new(),
+            dispatched: 0,
+            max_concurrent: max_concurrent.max(1),
+            pool: ResourcePool::new(),
+            demand: BTreeMap::new(),
+            in_use: Demand::default(),
+            bypassed: BTreeMap::new(),
+            max_bypass: DEFAULT_MAX_BYPASS,
+            waiting: BTreeMap::new(),
+            decisions: Vec::new(),
         }
     }
 
+    /// Limit running nodes to a resource budget
+    pub fn with_pool(mut self, pool: ResourcePool) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Set how often a blocked node may be overtaken by later ready nodes
+    ///
+    /// Once exceeded, nothing else starts until the blocked node fits.
+    pub fn with_max_bypass(mut self, max_bypass: u32) -> Self {
+        self.max_bypass = max_bypass;
+        self
+    }
+
     /// Initialize scheduler with a DAG
     pub fn initialize(&mut self, dag: &Dag) -> ExecResult<()> {
-        let order = dag
-            .topological_order()
+        let ranks = dag
+            .ranks()
             .map_err(|e| ExecError::InvalidState(e.to_string()))?;
 
-        // Track remaining dependencies for each node
-        let mut remaining_deps: HashMap<String, usize> = HashMap::new();
+        let mut order: Vec<(usize, String)> =
+            ranks.into_iter().map(|(id, rank)| (rank, id)).collect();
+        order.sort();
 
-        for node_id in &order {
-            let deps = dag.dependencies(node_id);
-            let dep_count = deps.as_ref().map_or(0, |d| d.len());
-            remaining_deps.insert(node_id.clone(), dep_count);
+        *self = Self::new(self.max_concurrent)
+            .with_pool(self.pool)
+            .with_max_bypass(self.max_bypass);
+        self.order = order.into_iter().map(|(_, id)| id).collect();
 
-            if let Some(dep_set) = deps {
-                if dep_set.is_empty() {
-                    self.ready.push_back(node_id.clone());
-                } else {
-                    self.pending.insert(node_id.clone(), dep_set.iter().cloned().collect());
-                }
+        for (pos, node_id) in self.order.iter().enumerate() {
+            self.position.insert(node_id.clone(), pos);
+
+            let demand = dag.node(node_id).map(Demand::of).unwrap_or_default();
+            self.check_fits_pool(node_id, &demand)?;
+            self.demand.insert(node_id.clone(), demand);
+
+            let deps = dag.dependencies(node_id).cloned().unwrap_or_default();
+            if deps.is_empty() {
+                self.ready.insert(pos);
             } else {
-                // No dependencies
-                self.ready.push_back(node_id.clone());
+                self.pending.insert(node_id.clone(), deps);
             }
+
+            let dependents = dag.dependents(node_id).cloned().unwrap_or_default();
+            self.dependents.insert(node_id.clone(), dependents);
         }
 
         Ok(())
     }
 
     /// Get next task to execute
+    ///
+    /// Ready nodes are considered in commit order. The first one whose demand
+    /// fits is started; it occupies a concurrency slot and its resources until
+    /// completed. A later node may overtake a blocked earlier one at most
+    /// `max_bypass` times, after which nothing else starts until it fits.
     pub fn next(&mut self) -> Option<String> {
+        let mut candidates = Vec::new();
+        for pos in self.ready.clone() {
+            let node_id = self.order[pos].clone();
+            let impure = self.demand.get(&node_id).is_some_and(|d| d.impure);
+            if impure && pos != self.head {
+                let head = self.order[self.head].clone();
+                self.note_wait(&node_id, WaitReason::AwaitingCommit { head });
+            } else {
+                candidates.push(pos);
+            }
+        }
+        let head = self.order.get(*candidates.first()?)?.clone();
+
         if self.running.len() >= self.max_concurrent {
-            None
-        } else {
-            self.ready.pop_front()
+            let limit = self.max_concurrent;
+            self.note_wait(&head, WaitReason::Concurrency { limit });
+            return None;
+        }
+
+        for &pos in &candidates {
+            let node_id = self.order[pos].clone();
+            match self.admission(&node_id) {
+                Ok(()) => {
+                    if node_id != head {
+                        *self.bypassed.entry(head).or_insert(0) += 1;
+                    }
+                    self.admit(pos, node_id.clone());
+                    return Some(node_id);
+                }
+                Err(reason) => {
+                    self.note_wait(&node_id, reason);
+                    let overtaken = self.bypassed.get(&head).copied().unwrap_or(0);
+                    if overtaken >= self.max_bypass {
+                        let held: Vec<String> = candidates
+                            .iter()
+                            .skip(1)
+                            .map(|&p| self.order[p].clone())
+                            .collect();
+                        for later in held {
+                            let for_node = head.clone();
+                            self.note_wait(&later, WaitReason::Reserved { for_node });
+                        }
+                        return None;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Check whether a node fits alongside the running nodes
+    fn admission(&self, node_id: &str) -> Result<(), WaitReason> {
+        if let Some(exclusive) = self
+            .running
+            .keys()
+            .find(|id| self.demand.get(*id).is_some_and(|d| d.exclusive))
+        {
+            return Err(WaitReason::ExclusiveRunning {
+                node: exclusive.clone(),
+            });
+        }
+
+        let demand = self.demand.get(node_id).copied().unwrap_or_default();
+        if demand.exclusive && !self.running.is_empty() {
+            return Err(WaitReason::NeedsExclusive {
+                running: self.running.len(),
+            });
+        }
+        if let Some(total) = self.pool.cpu_units {
+            let available = total.saturating_sub(self.in_use.cpu_units);
+            if demand.cpu_units > available {
+                return Err(WaitReason::Cpu {
+                    requested: demand.cpu_units,
+                    available,
+                });
+            }
+        }
+        if let Some(total) = self.pool.memory_bytes {
+            let available = total.saturating_sub(self.in_use.memory_bytes);
+            if demand.memory_bytes > available {
+                return Err(WaitReason::Memory {
+                    requested: demand.memory_bytes,
+                    available,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a ready node and charge its resources
+    fn admit(&mut self, pos: usize, node_id: String) {
+        self.ready.remove(&pos);
+        self.bypassed.remove(&node_id);
+        self.waiting.remove(&node_id);
+
+        let demand = self.demand.get(&node_id).copied().unwrap_or_default();
+        self.in_use.cpu_units += demand.cpu_units;
+        self.in_use.memory_bytes += demand.memory_bytes;
+
+        tracing::debug!(node = %node_id, "admitted");
+        self.decisions.push(AdmissionDecision {
+            node_id: node_id.clone(),
+            admission: Admission::Admitted,
+        });
+        self.running
+            .insert(node_id.clone(), RunningTask::new(node_id, self.dispatched));
+        self.dispatched += 1;
+    }
+
+    /// Record why a node waits, once per change of reason
+    fn note_wait(&mut self, node_id: &str, reason: WaitReason) {
+        if self.waiting.get(node_id) == Some(&reason) {
+            return;
+        }
+        tracing::debug!(node = %node_id, %reason, "waiting");
+        self.waiting.insert(node_id.to_string(), reason.clone());
+        self.decisions.push(AdmissionDecision {
+            node_id: node_id.to_string(),
+            admission: Admission::Waiting(reason),
+        });
+    }
+
+    /// Reject nodes that could never fit the pool
+    fn check_fits_pool(&self, node_id: &str, demand: &Demand) -> ExecResult<()> {
+        let too_big = |requested: u64, total: Option<u64>, unit: &str| {
+            total
+                .filter(|&t| requested > t)
+                .map(|t| ExecError::ResourceExceeded {
+                    node: node_id.to_string(),
+                    limit: format!("{} {} exceeds pool of {}", requested, unit, t),
+                })
+        };
+        match too_big(demand.cpu_units, self.pool.cpu_units, "cpu units")
+            .or_else(|| too_big(demand.memory_bytes, self.pool.memory_bytes, "bytes"))
+        {
+            Some(err) => Err(err),
+            None => Ok(()),
         }
     }
 
     /// Mark a task as started
-    pub fn start(&mut self, node_id: String, _task: RunningTask) {
-        self.running.insert(node_id, _task);
+    pub fn start(&mut self, node_id: String, task: RunningTask) {
+        self.running.insert(node_id, task);
     }
 
     /// Mark a task as complete
     pub fn complete(&mut self, node_id: &str) -> ExecResult<()> {
-        self.running.remove(node_id);
-
-        // Update pending tasks that depend on this one
-        let mut newly_ready = Vec::new();
-        for (pending_id, deps) in self.pending.iter_mut() {
-            if let Some(pos) = deps.iter().position(|d| d == node_id) {
-                deps.remove(pos);
-                if deps.is_empty() {
-                    newly_ready.push(pending_id.clone());
-                }
+        self.finish(node_id)?;
+        self.release(node_id);
+        Ok(())
+    }
+
+    /// Mark a task as failed and skip its transitive dependents
+    pub fn fail(&mut self, node_id: &str) -> ExecResult<()> {
+        self.finish(node_id)?;
+        self.skip_dependents(node_id);
+        Ok(())
+    }
+
+    /// Stop tracking a running task and free its resources
+    fn finish(&mut self, node_id: &str) -> ExecResult<()> {
+        if self.running.remove(node_id).is_none() {
+            return Err(ExecError::InvalidState(format!(
+                "Node {} completed but was not running",
+                node_id
+            )));
+        }
+
+        let demand = self.demand.get(node_id).copied().unwrap_or_default();
+        self.in_use.cpu_units = self.in_use.cpu_units.saturating_sub(demand.cpu_units);
+        self.in_use.memory_bytes = self.in_use.memory_bytes.saturating_sub(demand.memory_bytes);
+        Ok(())
+    }
+
+    /// Mark already-committed nodes as done without running them
+    ///
+    /// `committed` must be exactly a prefix of the commit order. Dependents
+    /// of the `failed` ones are skipped rather than released.
+    pub fn restore(
+        &mut self,
+        committed: &BTreeSet<String>,
+        failed: &BTreeSet<String>,
+    ) -> ExecResult<()> {
+        let prefix: Vec<String> = self.order.iter().take(committed.len()).cloned().collect();
+        if prefix.len() != committed.len() || prefix.iter().any(|id| !committed.contains(id)) {
+            return Err(ExecError::InvalidState(
+                "Committed nodes are not a prefix of the commit order".to_string(),
+            ));
+        }
+        for node_id in prefix {
+            self.ready.remove(&self.position[&node_id]);
+            if failed.contains(&node_id) {
+                self.skip_dependents(&node_id);
+            } else if !self.skipped.contains_key(&node_id) {
+                self.release(&node_id);
             }
         }
+        self.head = committed.len();
+        Ok(())
+    }
 
-        // Remove newly ready from pending and add to ready
-        for id in newly_ready {
-            self.pending.remove(&id);
-            self.ready.push_back(id);
+    /// Unblock dependents of a finished node
+    fn release(&mut self, node_id: &str) {
+        let dependents = self.dependents.get(node_id).cloned().unwrap_or_default();
+        for dependent in dependents {
+            let Some(deps) = self.pending.get_mut(&dependent) else {
+                continue;
+            };
+            deps.remove(node_id);
+            if deps.is_empty() {
+                self.pending.remove(&dependent);
+                self.ready.insert(self.position[&dependent]);
+            }
         }
+    }
 
-        Ok(())
+    /// Skip every node that transitively depends on a failed one
+    fn skip_dependents(&mut self, failed: &str) {
+        let mut stack: Vec<String> = self
+            .dependents
+            .get(failed)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        while let Some(node_id) = stack.pop() {
+            if self.skipped.contains_key(&node_id) {
+                continue;
+            }
+            self.pending.remove(&node_id);
+            self.ready.remove(&self.position[&node_id]);
+            stack.extend(self.dependents.get(&node_id).into_iter().flatten().cloned());
+            self.skipped.insert(node_id, failed.to_string());
+        }
     }
 
     /// Check if scheduling is complete
@@ -111,6 +614,148 @@ impl Scheduler {
     pub fn ready_count(&self) -> usize {
         self.ready.len()
     }
+
+    /// Get the commit order
+    pub fn commit_order(&self) -> &[String] {
+        &self.order
+    }
+
+    /// Get admission decisions made so far
+    pub fn decisions(&self) -> &[AdmissionDecision] {
+        &self.decisions
+    }
+
+    /// Run a DAG to completion
+    ///
+    /// Nodes are dispatched to `runner` on the tokio runtime as soon as they
+    /// are ready and a slot is free. Finished results are buffered and handed
+    /// to `commit` strictly in commit order, each preceded by
+    /// [`Commit::Started`]; dependents of failed nodes are committed as
+    /// [`Commit::Skipped`] instead. Returning [`CommitFlow::Stop`] aborts the
+    /// remaining tasks.
+    pub async fn run<F>(
+        &mut self,
+        dag: &Dag,
+        runner: Arc<dyn NodeRunner>,
+        commit: F,
+    ) -> ExecResult<()>
+    where
+        F: FnMut(Commit) -> ExecResult<CommitFlow>,
+    {
+        self.run_from(
+            dag,
+            runner,
+            &BTreeSet::new(),
+            &BTreeSet::new(),
+            false,
+            commit,
+        )
+        .await
+    }
+
+    /// Resume a DAG whose `committed` nodes are already done
+    ///
+    /// `failed` are the committed nodes that failed. With `head_started`,
+    /// the first uncommitted node's `Started` commit already happened and is
+    /// not repeated.
+    pub async fn run_from<F>(
+        &mut self,
+        dag: &Dag,
+        runner: Arc<dyn NodeRunner>,
+        committed: &BTreeSet<String>,
+        failed: &BTreeSet<String>,
+        head_started: bool,
+        mut commit: F,
+    ) -> ExecResult<()>
+    where
+        F: FnMut(Commit) -> ExecResult<CommitFlow>,
+    {
+        self.initialize(dag)?;
+        for node in dag.nodes().values() {
+            if runner.side_effect(node) == SideEffect::Impure {
+                if let Some(demand) = self.demand.get_mut(&node.id) {
+                    demand.impure = true;
+                }
+            }
+        }
+        self.restore(committed, failed)?;
+
+        let mut tasks = JoinSet::new();
+        let mut finished: BTreeMap<String, NodeResult> = BTreeMap::new();
+        let mut head_started = head_started;
+
+        loop {
+            // Commit everything that is in order
+            while let Some(node_id) = self.order.get(self.head) {
+                if let Some(cause) = self.skipped.get(node_id) {
+                    let skipped = Commit::Skipped {
+                        node_id: node_id.clone(),
+                        cause: cause.clone(),
+                    };
+                    self.head += 1;
+                    if commit(skipped)? == CommitFlow::Stop {
+                        tasks.abort_all();
+                        return Ok(());
+                    }
+                    continue;
+                }
+                if !head_started {
+                    head_started = true;
+                    if commit(Commit::Started(node_id.clone()))? == CommitFlow::Stop {
+                        tasks.abort_all();
+                        return Ok(());
+                    }
+                }
+                let Some(result) = finished.remove(node_id) else {
+                    break;
+                };
+                self.head += 1;
+                head_started = false;
+                if commit(Commit::Finished(result))? == CommitFlow::Stop {
+                    tasks.abort_all();
+                    return Ok(());
+                }
+            }
+
+            if self.head == self.order.len() {
+                return Ok(());
+            }
+
+            // Dispatch ready nodes
+            while let Some(node_id) = self.next() {
+                let node = dag
+                    .node(&node_id)
+                    .cloned()
+                    .ok_or_else(|| ExecError::InvalidState(format!("Unknown node: {}", node_id)))?;
+                let runner = Arc::clone(&runner);
+                tasks.spawn(async move {
+                    let result = runner.run(&node).await;
+                    (node.id, result)
+                });
+            }
+
+            // Wait for the next completion
+            match tasks.join_next().await {
+                Some(Ok((node_id, result))) => {
+                    if result.success {
+                        self.complete(&node_id)?;
+                    } else {
+                        self.fail(&node_id)?;
+                    }
+                    finished.insert(node_id, result);
+                }
+                Some(Err(e)) => {
+                    tasks.abort_all();
+                    return Err(ExecError::InvalidState(format!("Node task failed: {}", e)));
+                }
+                None => {
+                    return Err(ExecError::InvalidState(
+                        "Scheduler stalled with nodes outstanding".to_string(),
+                    ));
+                }
+            }
+        }
+    }
 }
 
 /// A running task
@@ -141,15 +786,21 @@ mod tests {
     #[test]
     fn test_scheduler_initialization() {
         let mut dag = Dag::new("test");
-        dag.add_node(DagNode::new("a", DagNodeType::Tool {
-            name: "tool1".to_string(),
-            version: "1.0.0".to_string(),
-        }))
+        dag.add_node(DagNode::new(
+            "a",
+            DagNodeType::Tool {
+                name: "tool1".to_string(),
+                version: "1.0.0".to_string(),
+            },
+        ))
         .unwrap();
-        dag.add_node(DagNode::new("b", DagNodeType::Tool {
-            name: "tool2".to_string(),
-            version: "1.0.0".to_string(),
-        }))
+        dag.add_node(DagNode::new(
+            "b",
+            DagNodeType::Tool {
+                name: "tool2".to_string(),
+                version: "1.0.0".to_string(),
+            },
+        ))
         .unwrap();
 
         let mut scheduler = Scheduler::new(2);
@@ -162,15 +813,21 @@ mod tests {
     #[test]
     fn test_scheduler_with_dependencies() {
         let mut dag = Dag::new("test");
-        dag.add_node(DagNode::new("a", DagNodeType::Tool {
-            name: "tool1".to_string(),
-            version: "1.0.0".to_string(),
-        }))
+        dag.add_node(DagNode::new(
+            "a",
+            DagNodeType::Tool {
+                name: "tool1".to_string(),
+                version: "1.0.0".to_string(),
+            },
+        ))
         .unwrap();
-        dag.add_node(DagNode::new("b", DagNodeType::Tool {
-            name: "tool2".to_string(),
-            version: "1.0.0".to_string(),
-        }))
+        dag.add_node(DagNode::new(
+            "b",
+            DagNodeType::Tool {
+                name: "tool2".to_string(),
+                version: "1.0.0".to_string(),
+            },
+        ))
         .unwrap();
         dag.add_edge("a".to_string(), "b".to_string()).unwrap();
 
@@ -211,4 +868,254 @@ mod tests {
         assert!(scheduler.next().is_some());
         assert!(scheduler.next().is_none()); // Backpressure
     }
+
+    fn resource_dag(nodes: &[(&str, u64, bool)]) -> Dag {
+        let mut dag = Dag::new("resources");
+        for &(id, cpu_units, exclusive) in nodes {
+            let mut node = DagNode::new(id, DagNodeType::Wait { duration_ms: 0 });
+            node.resources.cpu_units = Some(cpu_units);
+            node.resources.exclusive = exclusive;
+            dag.add_node(node).unwrap();
+        }
+        dag
+    }
+
+    fn last_wait(scheduler: &Scheduler, node_id: &str) -> Option<WaitReason> {
+        scheduler
+            .decisions()
+            .iter()
+            .rev()
+            .find(|d| d.node_id == node_id)
+            .and_then(|d| match &d.admission {
+                Admission::Waiting(reason) => Some(reason.clone()),
+                Admission::Admitted => None,
+            })
+    }
+
+    #[test]
+    fn test_scheduler_resource_pool() {
+        let dag = resource_dag(&[("a", 3, false), ("b", 2, false), ("c", 1, false)]);
+        let mut scheduler = Scheduler::new(8).with_pool(ResourcePool::new().with_cpu_units(4));
+        scheduler.initialize(&dag).unwrap();
+
+        assert_eq!(scheduler.next(), Some("a".to_string()));
+        // 'b' does not fit, 'c' does
+        assert_eq!(scheduler.next(), Some("c".to_string()));
+        assert_eq!(
+            last_wait(&scheduler, "b"),
+            Some(WaitReason::Cpu {
+                requested: 2,
+                available: 1
+            })
+        );
+        assert_eq!(scheduler.next(), None);
+
+        scheduler.complete("a").unwrap();
+        assert_eq!(scheduler.next(), Some("b".to_string()));
+
+        let oversized = resource_dag(&[("huge", 8, false)]);
+        let err = scheduler.initialize(&oversized).unwrap_err();
+        assert!(matches!(err, ExecError::ResourceExceeded { ref node, .. } if node == "huge"));
+    }
+
+    #[test]
+    fn test_scheduler_exclusive() {
+        let dag = resource_dag(&[("a", 0, false), ("b", 0, true), ("c", 0, false)]);
+        let mut scheduler = Scheduler::new(8).with_max_bypass(0);
+        scheduler.initialize(&dag).unwrap();
+
+        assert_eq!(scheduler.next(), Some("a".to_string()));
+        // 'b' waits for 'a' to drain and holds back 'c'
+        assert_eq!(scheduler.next(), None);
+        assert_eq!(
+            last_wait(&scheduler, "b"),
+            Some(WaitReason::NeedsExclusive { running: 1 })
+        );
+        assert_eq!(
+            last_wait(&scheduler, "c"),
+            Some(WaitReason::Reserved {
+                for_node: "b".to_string()
+            })
+        );
+
+        scheduler.complete("a").unwrap();
+        assert_eq!(scheduler.next(), Some("b".to_string()));
+        assert_eq!(scheduler.next(), None);
+        assert_eq!(
+            last_wait(&scheduler, "c"),
+            Some(WaitReason::ExclusiveRunning {
+                node: "b".to_string()
+            })
+        );
+
+        scheduler.complete("b").unwrap();
+        assert_eq!(scheduler.next(), Some("c".to_string()));
+    }
+
+    #[test]
+    fn test_scheduler_bounded_bypass() {
+        let dag = resource_dag(&[
+            ("a", 2, false),
+            ("b", 4, false),
+            ("c", 1, false),
+            ("d", 1, false),
+        ]);
+        let mut scheduler = Scheduler::new(8)
+            .with_pool(ResourcePool::new().with_cpu_units(4))
+            .with_max_bypass(1);
+        scheduler.initialize(&dag).unwrap();
+
+        assert_eq!(scheduler.next(), Some("a".to_string()));
+        // 'c' may overtake 'b' once; then 'd' must wait for 'b'
+        assert_eq!(scheduler.next(), Some("c".to_string()));
+        assert_eq!(scheduler.next(), None);
+        assert_eq!(
+            last_wait(&scheduler, "d"),
+            Some(WaitReason::Reserved {
+                for_node: "b".to_string()
+            })
+        );
+
+        scheduler.complete("a").unwrap();
+        assert_eq!(scheduler.next(), None);
+        scheduler.complete("c").unwrap();
+        assert_eq!(scheduler.next(), Some("b".to_string()));
+    }
+
+    #[test]
+    fn test_scheduler_impure_waits_for_commit() {
+        let dag = resource_dag(&[("a", 0, false), ("b", 0, false)]);
+        let mut scheduler = Scheduler::new(8);
+        scheduler.initialize(&dag).unwrap();
+        scheduler.demand.get_mut("b").unwrap().impure = true;
+
+        assert_eq!(scheduler.next(), Some("a".to_string()));
+        assert_eq!(scheduler.next(), None);
+        assert_eq!(
+            last_wait(&scheduler, "b"),
+            Some(WaitReason::AwaitingCommit {
+                head: "a".to_string()
+            })
+        );
+
+        // 'a' finishing is not enough; it must be committed
+        scheduler.complete("a").unwrap();
+        assert_eq!(scheduler.next(), None);
+        scheduler.head = 1;
+        assert_eq!(scheduler.next(), Some("b".to_string()));
+    }
+
+    /// Finishes nodes in reverse ID order regardless of dispatch order
+    struct ReverseRunner;
+
+    #[async_trait]
+    impl NodeRunner for ReverseRunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            let delay = match node.id.as_str() {
+                "a" => 30,
+                "b" => 20,
+                "c" => 10,
+                _ => 0,
+            };
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            NodeResult::success(node.id.clone(), node.id.clone(), delay)
+        }
+
+        fn side_effect(&self, _node: &DagNode) -> SideEffect {
+            SideEffect::Pure
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_commits_in_rank_order() {
+        let mut dag = Dag::new("test");
+        for id in ["a", "b", "c", "d"] {
+            dag.add_node(DagNode::new(id, DagNodeType::Wait { duration_ms: 0 }))
+                .unwrap();
+        }
+        dag.add_edge("c".to_string(), "d".to_string()).unwrap();
+
+        let mut commits = Vec::new();
+        let mut scheduler = Scheduler::new(4);
+        scheduler
+            .run(&dag, Arc::new(ReverseRunner), |commit| {
+                let label = match commit {
+                    Commit::Started(id) => format!("start:{}", id),
+                    Commit::Finished(result) => format!("done:{}", result.node_id),
+                    Commit::Skipped { node_id, .. } => format!("skip:{}", node_id),
+                };
+                commits.push(label);
+                Ok(CommitFlow::Continue)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            commits,
+            vec![
+                "start:a", "done:a", "start:b", "done:b", "start:c", "done:c", "start:d", "done:d"
+            ]
+        );
+        assert!(scheduler.is_complete());
+    }
+
+    /// Fails node 'a' and succeeds everything else
+    struct FailARunner;
+
+    #[async_trait]
+    impl NodeRunner for FailARunner {
+        async fn run(&self, node: &DagNode) -> NodeResult {
+            if node.id == "a" {
+                NodeResult::failure(node.id.clone(), "boom", 0)
+            } else {
+                NodeResult::success(node.id.clone(), node.id.clone(), 0)
+            }
+        }
+
+        fn side_effect(&self, _node: &DagNode) -> SideEffect {
+            SideEffect::Pure
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_skips_dependents_of_failed() {
+        let mut dag = Dag::new("test");
+        for id in ["a", "b", "c", "d", "e"] {
+            dag.add_node(DagNode::new(id, DagNodeType::Wait { duration_ms: 0 }))
+                .unwrap();
+        }
+        dag.add_edge("a".to_string(), "b".to_string()).unwrap();
+        dag.add_edge("b".to_string(), "c".to_string()).unwrap();
+        dag.add_edge("d".to_string(), "e".to_string()).unwrap();
+
+        let mut commits = Vec::new();
+        let mut scheduler = Scheduler::new(4);
+        scheduler
+            .run(&dag, Arc::new(FailARunner), |commit| {
+                let label = match commit {
+                    Commit::Started(id) => format!("start:{}", id),
+                    Commit::Finished(result) => format!("done:{}", result.node_id),
+                    Commit::Skipped { node_id, cause } => format!("skip:{}<-{}", node_id, cause),
+                };
+                commits.push(label);
+                Ok(CommitFlow::Continue)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            commits,
+            vec![
+                "start:a",
+                "done:a",
+                "start:d",
+                "done:d",
+                "skip:b<-a",
+                "start:e",
+                "done:e",
+                "skip:c<-a"
+            ]
+        );
+        assert!(scheduler.is_complete());
+    }
 }
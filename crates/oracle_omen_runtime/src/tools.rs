@@ -1,43 +1,116 @@
 //! Built-in tools for the runtime.
+//!
+//! [`Tool`] from `oracle_omen_core` is the one tool trait: the registry stores
+//! it and the [`ToolInvoker`](crate::invoke::ToolInvoker) calls it. Tools
+//! written against the older [`DynTool`] or [`NamedTool`] traits are wrapped
+//! by [`DynToolAdapter`] and [`NamedToolAdapter`].
 
+pub use oracle_omen_core::tool::Tool;
+
+use crate::executor::{ExecError, ExecResult};
 use oracle_omen_core::{
+    capability::Capability,
     hash::Hash,
-    tool::{ResourceBounds, SideEffect, ToolError, ToolId, ToolResult},
+    tool::{
+        Determinism, ExecutionContext, ResourceBounds, SideEffect, ToolError, ToolId, ToolResult,
+    },
+    version::{compatible_with, Version, VersionReq},
 };
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 /// Tool registry
+///
+/// Tools are keyed by name and semver version, so several versions of a
+/// tool can be registered side by side.
 #[derive(Clone)]
 pub struct ToolRegistry {
-    tools: std::collections::HashMap<String, Arc<dyn DynTool>>,
+    tools: BTreeMap<String, BTreeMap<Version, Arc<dyn Tool>>>,
 }
 
 impl ToolRegistry {
     /// Create a new empty registry
     pub fn new() -> Self {
         Self {
-            tools: std::collections::HashMap::new(),
+            tools: BTreeMap::new(),
         }
     }
 
     /// Register a tool
-    pub fn register(&mut self, tool: Arc<dyn DynTool>) -> ToolResult<()> {
-        let key = tool.id().as_str();
-        if self.tools.contains_key(&key) {
-            return Err(ToolError::NotFound(format!("Tool {} already registered", key)));
+    pub fn register(&mut self, tool: Arc<dyn Tool>) -> ToolResult<()> {
+        let id = tool.id().clone();
+        let version = id.semver()?;
+        let versions = self.tools.entry(id.name.clone()).or_default();
+        if versions.contains_key(&version) {
+            return Err(ToolError::AlreadyRegistered(id.as_str()));
         }
-        self.tools.insert(key, tool);
+        versions.insert(version, tool);
         Ok(())
     }
 
-    /// Get a tool
-    pub fn get(&self, id: &ToolId) -> Option<Arc<dyn DynTool>> {
-        self.tools.get(&id.as_str()).cloned()
+    /// Register a tool implementing the older [`DynTool`] trait
+    pub fn register_dyn(&mut self, tool: Arc<dyn DynTool>) -> ToolResult<()> {
+        self.register(Arc::new(DynToolAdapter::new(tool)))
+    }
+
+    /// Get a tool by exact ID
+    pub fn get(&self, id: &ToolId) -> Option<Arc<dyn Tool>> {
+        let version = Version::parse(&id.version).ok()?;
+        self.tools.get(&id.name)?.get(&version).cloned()
+    }
+
+    /// Get the highest version of a tool matching a requirement, e.g. `^1.2`
+    pub fn resolve(&self, name: &str, requirement: &str) -> ToolResult<Arc<dyn Tool>> {
+        let req = VersionReq::parse(requirement).map_err(|e| ToolError::InvalidVersion {
+            tool: name.to_string(),
+            reason: e.to_string(),
+        })?;
+        self.highest(name, &req)
+            .ok_or_else(|| ToolError::NotFound(format!("{}@{}", name, requirement)))
+    }
+
+    /// Get the highest version compatible with `id` under caret rules
+    pub fn latest_compatible(&self, id: &ToolId) -> ToolResult<Arc<dyn Tool>> {
+        let req = compatible_with(&id.semver()?);
+        self.highest(&id.name, &req)
+            .ok_or_else(|| ToolError::NotFound(id.as_str()))
+    }
+
+    /// Get the highest release version of a tool
+    pub fn latest(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.highest(name, &VersionReq::STAR)
+    }
+
+    /// Check if any version of a tool is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools.contains_key(name)
+    }
+
+    /// Registered versions of a tool, in ascending order
+    pub fn versions(&self, name: &str) -> Vec<Version> {
+        self.tools
+            .get(name)
+            .map(|versions| versions.keys().cloned().collect())
+            .unwrap_or_default()
     }
 
-    /// List all tools
+    /// List all tools, ordered by name then version
     pub fn list(&self) -> Vec<ToolId> {
-        self.tools.values().map(|t| t.id().clone()).collect()
+        self.tools
+            .values()
+            .flat_map(|versions| versions.values())
+            .map(|t| t.id().clone())
+            .collect()
+    }
+
+    /// Highest version matching a requirement
+    fn highest(&self, name: &str, req: &VersionReq) -> Option<Arc<dyn Tool>> {
+        self.tools
+            .get(name)?
+            .iter()
+            .rev()
+            .find(|(version, _)| req.matches(version))
+            .map(|(_, tool)| tool.clone())
     }
 }
 
@@ -48,6 +121,9 @@ impl Default for ToolRegistry {
 }
 
 /// Dynamic tool trait for runtime registration
+///
+/// Older form of [`Tool`]; register implementations with
+/// [`ToolRegistry::register_dyn`].
 pub trait DynTool: Send + Sync {
     /// Get tool ID
     fn id(&self) -> &ToolId;
@@ -58,6 +134,13 @@ pub trait DynTool: Send + Sync {
     /// Get side effect type
     fn side_effects(&self) -> SideEffect;
 
+    /// Get determinism declaration
+    ///
+    /// Tools that do not declare it are never replayed in place of a live call.
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
     /// Get resource bounds
     fn resource_bounds(&self) -> &ResourceBounds;
 
@@ -84,11 +167,169 @@ pub struct ToolMetadata {
     pub seed: Option<u64>,
 }
 
+impl From<&ExecutionContext> for ToolMetadata {
+    fn from(context: &ExecutionContext) -> Self {
+        Self {
+            logical_time: context.logical_time,
+            run_id: context.run_id,
+            seed: context.random_seed,
+        }
+    }
+}
+
+/// Runs a [`DynTool`] as a [`Tool`]
+pub struct DynToolAdapter {
+    tool: Arc<dyn DynTool>,
+    capabilities: Vec<Capability>,
+}
+
+impl DynToolAdapter {
+    /// Wrap a tool
+    pub fn new(tool: Arc<dyn DynTool>) -> Self {
+        let capabilities = tool
+            .capabilities()
+            .into_iter()
+            .map(Capability::new)
+            .collect();
+        Self { tool, capabilities }
+    }
+}
+
+impl Tool for DynToolAdapter {
+    fn id(&self) -> &ToolId {
+        self.tool.id()
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        self.tool.side_effects()
+    }
+
+    fn determinism(&self) -> Determinism {
+        self.tool.determinism()
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        self.tool.resource_bounds()
+    }
+
+    fn execute(&self, input: &[u8], context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        self.tool.execute(input, &ToolMetadata::from(context))
+    }
+
+    fn input_schema(&self) -> &str {
+        self.tool.input_schema()
+    }
+
+    fn output_schema(&self) -> &str {
+        self.tool.output_schema()
+    }
+}
+
+/// Minimal name-only tool, formerly `executor::Tool`
+///
+/// Wrap implementations in [`NamedToolAdapter`] to register them.
+pub trait NamedTool: Send + Sync {
+    /// Get tool name
+    fn name(&self) -> &str;
+
+    /// Get required capabilities
+    fn capabilities(&self) -> Vec<Capability>;
+
+    /// Execute the tool
+    fn execute(&self, input: &[u8]) -> ExecResult<Vec<u8>>;
+}
+
+/// Runs a [`NamedTool`] as a [`Tool`]
+///
+/// A named tool declares nothing about itself, so it is treated as impure
+/// and non-deterministic, and accepts any JSON input.
+pub struct NamedToolAdapter {
+    tool: Arc<dyn NamedTool>,
+    id: ToolId,
+    capabilities: Vec<Capability>,
+    bounds: ResourceBounds,
+}
+
+impl NamedToolAdapter {
+    /// Default timeout for named tools
+    pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+    /// Wrap a tool under the given version
+    pub fn new(tool: Arc<dyn NamedTool>, version: impl Into<String>) -> Self {
+        let id = ToolId::new(tool.name(), version);
+        let capabilities = tool.capabilities();
+        Self {
+            tool,
+            id,
+            capabilities,
+            bounds: ResourceBounds::with_timeout(Self::DEFAULT_TIMEOUT_MS),
+        }
+    }
+
+    /// Set resource bounds
+    pub fn with_resource_bounds(mut self, bounds: ResourceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+}
+
+impl Tool for NamedToolAdapter {
+    fn id(&self) -> &ToolId {
+        &self.id
+    }
+
+    fn required_capabilities(&self) -> &[Capability] {
+        &self.capabilities
+    }
+
+    fn side_effects(&self) -> SideEffect {
+        SideEffect::Impure
+    }
+
+    fn determinism(&self) -> Determinism {
+        Determinism::NonDeterministic
+    }
+
+    fn resource_bounds(&self) -> &ResourceBounds {
+        &self.bounds
+    }
+
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
+        let tool = self.id.as_str();
+        self.tool.execute(input).map_err(|e| match e {
+            ExecError::CapabilityDenied { capability, reason } => {
+                ToolError::Denied { capability, reason }
+            }
+            ExecError::ToolNotFound(name) => ToolError::NotFound(name),
+            ExecError::Timeout { duration_ms, .. } => ToolError::Timeout { tool, duration_ms },
+            ExecError::ResourceExceeded { limit, .. } => {
+                ToolError::ResourceExceeded { tool, limit }
+            }
+            other => ToolError::ExecutionFailed {
+                tool,
+                reason: other.to_string(),
+            },
+        })
+    }
+
+    fn input_schema(&self) -> &str {
+        "{}"
+    }
+
+    fn output_schema(&self) -> &str {
+        "{}"
+    }
+}
+
 /// Example: Echo tool (deterministic, no side effects)
 #[derive(Clone)]
 pub struct EchoTool;
 
-impl DynTool for EchoTool {
+impl Tool for EchoTool {
     fn id(&self) -> &ToolId {
         use std::sync::OnceLock;
         static ID: OnceLock<ToolId> = OnceLock::new();
@@ -98,20 +339,24 @@ impl DynTool for EchoTool {
         })
     }
 
-    fn capabilities(&self) -> Vec<String> {
-        vec![]
+    fn required_capabilities(&self) -> &[Capability] {
+        &[]
     }
 
     fn side_effects(&self) -> SideEffect {
         SideEffect::Pure
     }
 
+    fn determinism(&self) -> Determinism {
+        Determinism::Deterministic
+    }
+
     fn resource_bounds(&self) -> &ResourceBounds {
         static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
         &BOUNDS
     }
 
-    fn execute(&self, input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
         Ok(input.to_vec())
     }
 
@@ -128,7 +373,7 @@ impl DynTool for EchoTool {
 #[derive(Clone)]
 pub struct HashTool;
 
-impl DynTool for HashTool {
+impl Tool for HashTool {
     fn id(&self) -> &ToolId {
         use std::sync::OnceLock;
         static ID: OnceLock<ToolId> = OnceLock::new();
@@ -138,22 +383,29 @@ impl DynTool for HashTool {
         })
     }
 
-    fn capabilities(&self) -> Vec<String> {
-        vec![]
+    fn required_capabilities(&self) -> &[Capability] {
+        &[]
     }
 
     fn side_effects(&self) -> SideEffect {
         SideEffect::Pure
     }
 
+    fn determinism(&self) -> Determinism {
+        Determinism::Deterministic
+    }
+
     fn resource_bounds(&self) -> &ResourceBounds {
         static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
         &BOUNDS
     }
 
-    fn execute(&self, input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+    fn execute(&self, input: &[u8], _context: &ExecutionContext) -> ToolResult<Vec<u8>> {
         let hash = Hash::from_bytes(input);
-        Ok(hash.to_hex().into_bytes())
+        serde_json::to_vec(&hash.to_hex()).map_err(|e| ToolError::SerializationFailed {
+            tool: self.id().as_str(),
+            reason: e.to_string(),
+        })
     }
 
     fn input_schema(&self) -> &str {
@@ -169,6 +421,50 @@ impl DynTool for HashTool {
 mod tests {
     use super::*;
 
+    /// Legacy tool reporting its version in the output
+    struct Versioned(ToolId);
+
+    impl DynTool for Versioned {
+        fn id(&self) -> &ToolId {
+            &self.0
+        }
+
+        fn capabilities(&self) -> Vec<String> {
+            vec!["fs:read:/tmp".to_string()]
+        }
+
+        fn side_effects(&self) -> SideEffect {
+            SideEffect::Pure
+        }
+
+        fn resource_bounds(&self) -> &ResourceBounds {
+            static BOUNDS: ResourceBounds = ResourceBounds::with_timeout(1000);
+            &BOUNDS
+        }
+
+        fn execute(&self, _input: &[u8], _metadata: &ToolMetadata) -> ToolResult<Vec<u8>> {
+            Ok(self.0.version.clone().into_bytes())
+        }
+
+        fn input_schema(&self) -> &str {
+            "{}"
+        }
+
+        fn output_schema(&self) -> &str {
+            "{}"
+        }
+    }
+
+    fn versioned_registry(versions: &[&str]) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        for version in versions {
+            registry
+                .register_dyn(Arc::new(Versioned(ToolId::new("fetch", *version))))
+                .unwrap();
+        }
+        registry
+    }
+
     #[test]
     fn test_tool_registry() {
         let mut registry = ToolRegistry::new();
@@ -176,34 +472,102 @@ mod tests {
         registry.register(Arc::new(HashTool)).unwrap();
 
         assert_eq!(registry.list().len(), 2);
+        assert_eq!(
+            registry.register(Arc::new(EchoTool)).unwrap_err(),
+            ToolError::AlreadyRegistered("echo@1.0.0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_versioned_lookup() {
+        let registry = versioned_registry(&["1.0.0", "1.4.2", "2.0.0", "2.1.0-beta"]);
+        let version = |tool: Arc<dyn Tool>| tool.id().version.clone();
+
+        assert_eq!(version(registry.resolve("fetch", "^1.2").unwrap()), "1.4.2");
+        assert_eq!(version(registry.resolve("fetch", "~1.0").unwrap()), "1.0.0");
+        assert_eq!(version(registry.latest("fetch").unwrap()), "2.0.0");
+        let compatible = registry
+            .latest_compatible(&ToolId::new("fetch", "1.0.0"))
+            .unwrap();
+        assert_eq!(version(compatible), "1.4.2");
+        assert!(registry.get(&ToolId::new("fetch", "2.1.0-beta")).is_some());
+
+        assert!(matches!(
+            registry.resolve("fetch", "^3"),
+            Err(ToolError::NotFound(_))
+        ));
+        assert!(matches!(
+            registry.latest_compatible(&ToolId::new("fetch", "2.1.0")),
+            Err(ToolError::NotFound(_))
+        ));
+        assert!(matches!(
+            registry.resolve("fetch", "latest"),
+            Err(ToolError::InvalidVersion { .. })
+        ));
+        let mut registry = ToolRegistry::new();
+        assert!(matches!(
+            registry.register_dyn(Arc::new(Versioned(ToolId::new("x", "v1")))),
+            Err(ToolError::InvalidVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_adapters() {
+        let adapter = DynToolAdapter::new(Arc::new(Versioned(ToolId::new("fetch", "1.0.0"))));
+        assert_eq!(
+            adapter.required_capabilities(),
+            &[Capability::new("fs:read:/tmp")]
+        );
+        assert_eq!(adapter.determinism(), Determinism::NonDeterministic);
+        let output = adapter
+            .execute(b"{}", &ExecutionContext::new(0, 1))
+            .unwrap();
+        assert_eq!(output, b"1.0.0");
+
+        struct Failing;
+        impl NamedTool for Failing {
+            fn name(&self) -> &str {
+                "failing"
+            }
+            fn capabilities(&self) -> Vec<Capability> {
+                vec![]
+            }
+            fn execute(&self, _input: &[u8]) -> ExecResult<Vec<u8>> {
+                Err(ExecError::Timeout {
+                    node: "n".to_string(),
+                    duration_ms: 5,
+                })
+            }
+        }
+        let adapter = NamedToolAdapter::new(Arc::new(Failing), "0.1.0");
+        assert_eq!(adapter.id(), &ToolId::new("failing", "0.1.0"));
+        assert_eq!(
+            adapter.execute(b"{}", &ExecutionContext::new(0, 1)),
+            Err(ToolError::Timeout {
+                tool: "failing@0.1.0".to_string(),
+                duration_ms: 5
+            })
+        );
     }
 
     #[test]
     fn test_echo_tool() {
         let tool = EchoTool;
-        let metadata = ToolMetadata {
-            logical_time: 0,
-            run_id: 1,
-            seed: None,
-        };
+        let context = ExecutionContext::new(0, 1);
 
         let input = b"hello";
-        let output = tool.execute(input, &metadata).unwrap();
+        let output = tool.execute(input, &context).unwrap();
         assert_eq!(output, b"hello");
     }
 
     #[test]
     fn test_hash_tool() {
         let tool = HashTool;
-        let metadata = ToolMetadata {
-            logical_time: 0,
-            run_id: 1,
-            seed: None,
-        };
+        let context = ExecutionContext::new(0, 1);
 
         let input = b"test";
-        let output = tool.execute(input, &metadata).unwrap();
-        let hash_str = String::from_utf8(output).unwrap();
+        let output = tool.execute(input, &context).unwrap();
+        let hash_str: String = serde_json::from_slice(&output).unwrap();
         assert_eq!(hash_str.len(), 64); // BLAKE3 is 32 bytes = 64 hex chars
     }
 }
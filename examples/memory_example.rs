@@ -2,32 +2,56 @@
 //!
 //! Demonstrates:
 //! - CRDT document storage
+//! - Merging replicas with concurrent writes
+//! - Structured CRDTs declared per key prefix
 //! - Provenance tracking
 //! - Temporal queries
 //! - Deterministic retrieval
 
 use oracle_omen_memory::{
+    crdt::{CrdtKind, MemorySchema},
     document::{Document, DocumentValue},
     provenance::{Operation, ProvenanceRecord, ProvenanceTracker},
-    query::{QueryBuilder, QueryOrder},
+    query::{DeterministicQuery, QueryBuilder, QueryOrder},
     store::MemoryStore,
 };
+use oracle_omen_policy::{
+    compiler::PolicyCompiler,
+    engine::{EvalContext, PolicyEngine},
+    lang::{Action, Condition, Policy, Rule, RuleKind},
+};
+
+/// Memory policy allowing every read, write and delete
+fn allow_all_memory() -> PolicyEngine {
+    let mut policy = Policy::new("example-memory", "1.0.0");
+    policy.add_rule(Rule {
+        name: "allow_all".to_string(),
+        kind: RuleKind::Memory,
+        condition: Condition::True,
+        action: Action::Allow,
+    });
+    let mut engine = PolicyEngine::new();
+    engine.add_policy(PolicyCompiler::compile(&policy).expect("valid policy"));
+    engine
+}
 
 fn main() {
     println!("Oracle Omen - Memory Store Example");
     println!("==================================\n");
 
+    let policy = allow_all_memory();
+    let ctx = EvalContext::new();
     let mut store = MemoryStore::new();
     let mut tracker = ProvenanceTracker::new();
 
     // Write documents
-    for i in 1..=5 {
+    for i in 1..=5u64 {
         let doc = Document::new(
             format!("key_{}", i),
-            DocumentValue::Integer(i * 10),
+            DocumentValue::Integer(i as i64 * 10),
             i, // causal_event
         );
-        store.write(doc);
+        store.write_checked(doc, &policy, &ctx).expect("write allowed");
 
         tracker.record(ProvenanceRecord::new(i, Operation::Write, format!("key_{}", i)));
     }
@@ -36,7 +60,7 @@ fn main() {
     println!("Store hash: {}\n", store.hash());
 
     // Read a document
-    if let Some(doc) = store.read("key_3") {
+    if let Ok(Some(doc)) = store.read_checked("key_3", &policy, &ctx) {
         println!("Document 'key_3':");
         println!("  Value: {:?}", doc.value);
         println!("  Causal event: {}", doc.causal_event);
@@ -76,4 +100,45 @@ fn main() {
     println!("Snapshot:");
     println!("  Documents: {}", snapshot.document_hashes.len());
     println!("  Store hash: {}", snapshot.store_hash);
+    println!();
+
+    // Replicas: two branches write the same key without seeing each other
+    let mut left = MemoryStore::new().with_replica("left");
+    let mut right = MemoryStore::new().with_replica("right");
+    let fetch = Document::new("plan", DocumentValue::from("fetch first"), 6);
+    let parse = Document::new("plan", DocumentValue::from("parse first"), 6);
+    left.write_checked(fetch, &policy, &ctx).expect("write allowed");
+    right.write_checked(parse, &policy, &ctx).expect("write allowed");
+
+    let report = left.merge_checked(&right, &policy, &ctx).expect("merge allowed");
+    right.merge_checked(&left, &policy, &ctx).expect("merge allowed");
+    println!("Merged replicas:");
+    println!("  Concurrent keys: {:?}", report.concurrent);
+    if let Ok(Some(doc)) = left.read_checked("plan", &policy, &ctx) {
+        println!("  Value: {:?} (from {})", doc.value, doc.replica);
+        println!("  Concurrent writes kept: {}", doc.concurrent.len());
+    }
+    println!("  Replicas agree: {}", left.hash() == right.hash());
+    println!();
+
+    // Structured keys: both branches append a step, and both steps survive
+    let schema = MemorySchema::new().with_prefix("steps", CrdtKind::Sequence);
+    let mut left = MemoryStore::new()
+        .with_replica("left")
+        .with_schema(schema.clone());
+    let mut right = MemoryStore::new().with_replica("right").with_schema(schema);
+    let steps = |names: &[&str]| DocumentValue::Vec(names.iter().map(|&n| n.into()).collect());
+    let doc = Document::new("steps", steps(&["fetch"]), 7);
+    left.write_checked(doc, &policy, &ctx).expect("write allowed");
+    right.merge_checked(&left, &policy, &ctx).expect("merge allowed");
+    let parse = Document::new("steps", steps(&["fetch", "parse"]), 8);
+    let summarize = Document::new("steps", steps(&["fetch", "summarize"]), 8);
+    left.write_checked(parse, &policy, &ctx).expect("write allowed");
+    right.write_checked(summarize, &policy, &ctx).expect("write allowed");
+
+    left.merge_checked(&right, &policy, &ctx).expect("merge allowed");
+    println!("Merged sequence:");
+    if let Ok(Some(doc)) = left.read_checked("steps", &policy, &ctx) {
+        println!("  Value: {:?}", doc.value);
+    }
 }
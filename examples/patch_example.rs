@@ -4,11 +4,15 @@ use oracle_omen_core::{hash::Hash, state::AgentState};
 use oracle_omen_patches::{
     apply::PatchEngine,
     gate::{ApprovalGate, DeterminismTestRunner, GateResult},
-    patch::{Patch, PatchId, PatchStatus, PatchType, PatchTarget},
-    signature::{KeyPair, Signature, SignedPatch, SignerId},
-    signature,
+    patch::{Patch, PatchId, PatchStatus, PatchType, PatchTarget, SignedPatch},
+    signature::KeyPair,
     store::PatchStore,
 };
+use oracle_omen_policy::{
+    compiler::PolicyCompiler,
+    engine::{EvalContext, PolicyEngine},
+    lang::{Action, Condition, Policy, Rule, RuleKind},
+};
 
 fn main() {
     println!("Oracle Omen - Patch Example");
@@ -43,7 +47,7 @@ fn main() {
     let signature = keypair.sign(&patch.hash().to_hex().into_bytes());
 
     // Create signed patch
-    let signed = SignedPatch::new(patch.clone(), signature, signer.clone());
+    let signed = SignedPatch::new(patch.clone(), signature.clone(), signer.clone());
     println!("Signature verified: {}\n", signed.verify());
 
     // Create patch store
@@ -52,16 +56,33 @@ fn main() {
         .add_patch(patch_id.to_string(), patch.clone(), PatchStatus::Proposed)
         .unwrap();
 
-    // Create patch engine
-    let mut engine = PatchEngine::new(store);
+    // Create patch engine with a policy that allows patches
+    let mut policy = Policy::new("patches", "1.0.0");
+    policy.add_rule(Rule {
+        name: "allow_patches".to_string(),
+        kind: RuleKind::Patch,
+        condition: Condition::True,
+        action: Action::Allow,
+    });
+    let mut policies = PolicyEngine::new();
+    policies.add_policy(PolicyCompiler::compile(&policy).unwrap());
+    let mut engine = PatchEngine::new(store)
+        .with_policy(policies)
+        .with_approvers(vec![signer.clone()]);
 
     // Run test gate
     let test_runner = DeterminismTestRunner;
     let test_result = engine.test_gate(&patch_id.to_string(), &test_runner).unwrap();
     println!("Test gate: {}", if test_result.is_passed() { "PASSED" } else { "FAILED" });
 
+    // Run audit gate
+    let audit_result = engine
+        .audit_gate(&patch_id.to_string(), &EvalContext::default())
+        .unwrap();
+    println!("Audit gate: {}", if audit_result.is_passed() { "PASSED" } else { "FAILED" });
+
     // Approve the patch
-    let approval_gate = ApprovalGate::new(vec![signer]);
+    let approval_gate = ApprovalGate::new(vec![signer.clone()]);
     let approval_result = approval_gate.evaluate(&signature, &signer);
     println!("Approval gate: {}", if approval_result.is_passed() { "GRANTED" } else { "DENIED" });
 